@@ -0,0 +1,65 @@
+//! Generate an svd for a chip using the library api instead of the `header2svd` binary.
+//!
+//! Parses the headers with a custom field naming scheme, corrects a register with a fixup,
+//! drops anything that fails validation and writes the result to `custom_chip.svd`.
+//!
+//! ```text
+//! $ cargo run --example custom_chip -- path/to/include/soc/
+//! ```
+
+use header2svd::{
+    fixup::Fixups,
+    parse_idf_with_options,
+    source::Model,
+    svd::{create_svd_with_options, write_svd, SvdOptions},
+    validate::{validate, ValidateOptions},
+    ParseOptions, Peripheral, Register,
+};
+
+use std::fs::File;
+use std::io::BufWriter;
+
+/// Strip the peripheral name from field names, `UART_RXFIFO_CNT` becomes `RXFIFO_CNT`
+fn short_field_name(p: &Peripheral, _r: &Register, name: &str) -> String {
+    let prefix = format!("{}_", p.description);
+    name.trim_start_matches(prefix.as_str()).to_string()
+}
+
+/// The headers define `UART_DATE` without fields, its value is the version of the peripheral
+fn uart_version(model: &mut Model) {
+    let registers = model.values_mut().flat_map(|p| p.registers.iter_mut());
+    for r in registers.filter(|r| r.name == "UART_DATE") {
+        r.description = "Version of the UART".to_string();
+    }
+}
+
+fn main() {
+    let path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "esp-idf/components/soc/esp32/include/soc/".to_string());
+
+    let options = ParseOptions {
+        field_namer: Some(short_field_name),
+        ..Default::default()
+    };
+    let mut peripherals = parse_idf_with_options(&path, &options);
+
+    // before validation, so it checks the corrected model
+    let mut fixups = Fixups::new();
+    fixups.register("UART version", uart_version);
+    fixups.apply(&mut peripherals);
+
+    let validate_options = ValidateOptions {
+        fix: true,
+        ..Default::default()
+    };
+    for issue in validate(&mut peripherals, &validate_options) {
+        println!("Dropped: {}", issue);
+    }
+
+    let device = create_svd_with_options(peripherals, &SvdOptions::default())
+        .expect("Failed to build the svd device");
+
+    let out = BufWriter::new(File::create("custom_chip.svd").unwrap());
+    write_svd(&device, out).unwrap();
+}
@@ -0,0 +1,94 @@
+//! Registers defined under several names at the same offset, such as the read and write views
+//! of a FIFO. One of them is kept as the register and the others are emitted as its alternate
+//! registers, instead of being reported and dropped as duplicates.
+
+use crate::Peripheral;
+use std::collections::BTreeMap;
+
+/// Make every register sharing its offset with a differently named register an alternate of
+/// the one with the most fields, the first defined on a tie. Registers already alternates,
+/// e.g. byte lanes, are left alone. Returns the number of linked registers
+pub fn link_aliases(peripherals: &mut BTreeMap<String, Peripheral>) -> usize {
+    let mut linked = 0;
+    for p in peripherals.values_mut() {
+        let mut by_address: BTreeMap<u32, Vec<usize>> = BTreeMap::new();
+        for (i, r) in p.registers.iter().enumerate() {
+            if r.alternate_register.is_none() {
+                by_address.entry(r.address).or_default().push(i);
+            }
+        }
+        for indices in by_address.values().filter(|i| i.len() > 1) {
+            // the first with the most fields
+            let primary = indices
+                .iter()
+                .rev()
+                .max_by_key(|i| p.registers[**i].bit_fields.len())
+                .copied()
+                .unwrap();
+            let name = p.registers[primary].name.clone();
+            for &i in indices {
+                let r = &mut p.registers[i];
+                // the same name twice is a duplicate, not an alias
+                if i != primary && r.name != name {
+                    r.alternate_register = Some(name.clone());
+                    linked += 1;
+                }
+            }
+        }
+    }
+    linked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BitField, Register};
+
+    #[test]
+    fn test_link_aliases() {
+        let register = |name: &str, address, fields: usize| Register {
+            name: name.to_string(),
+            address,
+            bit_fields: vec![BitField::default(); fields],
+            ..Default::default()
+        };
+        let mut peripherals = BTreeMap::new();
+        peripherals.insert(
+            "UART".to_string(),
+            Peripheral {
+                registers: vec![
+                    register("UART_FIFO_RD", 0x0, 1),
+                    register("UART_FIFO_WR", 0x0, 1),
+                    register("UART_INT_RAW", 0x4, 0),
+                    register("UART_INT_RAW_BITS", 0x4, 3),
+                    register("UART_CONF0", 0x20, 0),
+                    register("UART_CONF0", 0x20, 0),
+                    Register {
+                        alternate_register: Some("UART_CONF0".to_string()),
+                        ..register("UART_CONF0_BYTE0", 0x20, 0)
+                    },
+                ],
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(link_aliases(&mut peripherals), 2);
+        let alternates: Vec<Option<&str>> = peripherals["UART"]
+            .registers
+            .iter()
+            .map(|r| r.alternate_register.as_deref())
+            .collect();
+        assert_eq!(
+            alternates,
+            vec![
+                None,
+                Some("UART_FIFO_RD"),
+                Some("UART_INT_RAW_BITS"),
+                None,
+                None,
+                None,
+                Some("UART_CONF0"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,205 @@
+//! Reading a previously generated svd back into the model, so a chip can be documented
+//! incrementally over several runs with different sources instead of being regenerated
+//! from scratch.
+
+use crate::diff::{child_text, child_value, children, name_of, read_field};
+use crate::{BitField, Bits, Interrupt, Peripheral, Provenance, Register, Type};
+use std::collections::BTreeMap;
+use std::path::Path;
+use xmltree::Element;
+
+fn access(text: &str) -> Result<Type, String> {
+    Ok(match text {
+        "read-only" => Type::ReadOnly,
+        "read-write" => Type::ReadWrite,
+        "write-only" => Type::WriteOnly,
+        _ => return Err(format!("unsupported access {}", text)),
+    })
+}
+
+fn description(element: &Element) -> Option<String> {
+    child_text(element, "description").map(str::to_string)
+}
+
+fn read_register(
+    element: &Element,
+    name: String,
+    offset: u64,
+    provenance: &Provenance,
+) -> Result<Register, String> {
+    let mut bit_fields = vec![];
+    for field in children(element, "fields") {
+        let fname = name_of(field)?;
+        let summary = read_field(field).map_err(|e| format!("{}.{}: {}", name, fname, e))?;
+        let msb = summary.lsb + summary.width - 1;
+        if msb > 31 {
+            return Err(format!("{}.{}: bits beyond 31", name, fname));
+        }
+        bit_fields.push(BitField {
+            bits: if summary.width == 1 {
+                Bits::Single(summary.lsb as u8)
+            } else {
+                Bits::Range(summary.lsb as u8..=msb as u8)
+            },
+            type_: match &summary.access {
+                Some(a) => access(a).map_err(|e| format!("{}.{}: {}", name, fname, e))?,
+                None => Type::default(),
+            },
+            description: description(field).unwrap_or_default(),
+            name: fname,
+            provenance: vec![provenance.clone()],
+            ..Default::default()
+        });
+    }
+    bit_fields.sort_by_key(|f| f.bits.lsb());
+
+    Ok(Register {
+        description: description(element).unwrap_or_else(|| name.clone()),
+        address: offset as u32,
+        width: child_value(element, "size")?.unwrap_or(0) as u8,
+        reset_value: child_value(element, "resetValue")?.unwrap_or(0),
+        reset_mask: child_value(element, "resetMask")?.map(|mask| mask as u32),
+        bit_fields,
+        provenance: vec![provenance.clone()],
+        alternate_register: child_text(element, "alternateRegister").map(str::to_string),
+        name,
+        ..Default::default()
+    })
+}
+
+/// Add the registers of a `registers` or `cluster` element, cluster members are flattened
+/// into `CLUSTER_REGISTER`
+fn read_registers(
+    registers: &mut Vec<Register>,
+    parent: &Element,
+    prefix: &str,
+    base: u64,
+    provenance: &Provenance,
+) -> Result<(), String> {
+    for element in &parent.children {
+        let name = format!("{}{}", prefix, name_of(element)?);
+        let offset = base + child_value(element, "addressOffset")?.unwrap_or(0);
+        match element.name.as_str() {
+            "register" => registers.push(read_register(element, name, offset, provenance)?),
+            "cluster" => read_registers(
+                registers,
+                element,
+                &format!("{}_", name),
+                offset,
+                provenance,
+            )?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Read the peripherals of an SVD document into the model, `file` is recorded as their
+/// provenance. Peripherals derived from another one become its further instances
+pub fn parse_svd_model(xml: &str, file: &str) -> Result<BTreeMap<String, Peripheral>, String> {
+    let device = Element::parse(xml.as_bytes()).map_err(|e| e.to_string())?;
+    let provenance = Provenance::Svd {
+        file: file.to_string(),
+    };
+    let mut peripherals = BTreeMap::new();
+    let mut derived = vec![];
+    for element in children(&device, "peripherals") {
+        let name = name_of(element)?;
+        let address = child_value(element, "baseAddress")?.unwrap_or(0) as u32;
+        if let Some(base) = element.attributes.get("derivedFrom") {
+            derived.push((name, base.clone(), address));
+            continue;
+        }
+
+        let mut registers = vec![];
+        if let Some(list) = element.get_child("registers") {
+            read_registers(&mut registers, list, "", 0, &provenance)
+                .map_err(|e| format!("{}: {}", name, e))?;
+        }
+        registers.sort_by_key(|r| r.address);
+
+        let mut interrupts = vec![];
+        for i in element.children.iter().filter(|c| c.name == "interrupt") {
+            interrupts.push(Interrupt {
+                name: name_of(i)?,
+                description: description(i),
+                value: child_value(i, "value")?.unwrap_or(0) as u32,
+            });
+        }
+
+        let p = Peripheral {
+            description: description(element).unwrap_or_else(|| name.clone()),
+            version: child_text(element, "version").map(str::to_string),
+            group_name: child_text(element, "groupName").map(str::to_string),
+            header_struct_name: child_text(element, "headerStructName").map(str::to_string),
+            address,
+            registers,
+            interrupts,
+            provenance: vec![provenance.clone()],
+            ..Default::default()
+        };
+        peripherals.insert(name, p);
+    }
+
+    for (name, base, address) in derived {
+        match peripherals.get_mut(&base) {
+            Some(p) => p.instance_addresses.push(address),
+            None => return Err(format!("{} is derived from unknown {}", name, base)),
+        }
+    }
+    Ok(peripherals)
+}
+
+/// Load an SVD file into the model
+pub fn read_svd_model(path: &Path) -> Result<BTreeMap<String, Peripheral>, String> {
+    let xml = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    parse_svd_model(&xml, &path.display().to_string())
+        .map_err(|e| format!("Invalid svd {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_svd_model() {
+        let xml = r#"<device><peripherals>
+            <peripheral>
+                <name>UART</name>
+                <baseAddress>0x3FF40000</baseAddress>
+                <interrupt><name>UART0</name><value>34</value></interrupt>
+                <registers>
+                    <register>
+                        <name>UART_CONF0</name>
+                        <description>Configuration</description>
+                        <addressOffset>0x20</addressOffset>
+                        <resetValue>0x800001C</resetValue>
+                        <fields>
+                            <field><name>TICK_REF_ALWAYS_ON</name><bitOffset>27</bitOffset><bitWidth>1</bitWidth></field>
+                            <field><name>PARITY</name><bitRange>[1:0]</bitRange><access>read-only</access></field>
+                        </fields>
+                    </register>
+                </registers>
+            </peripheral>
+            <peripheral derivedFrom="UART">
+                <name>UART1</name>
+                <baseAddress>0x3FF50000</baseAddress>
+            </peripheral>
+        </peripherals></device>"#;
+        let peripherals = parse_svd_model(xml, "old.svd").unwrap();
+        assert_eq!(peripherals.len(), 1);
+
+        let uart = &peripherals["UART"];
+        assert_eq!(uart.address, 0x3ff4_0000);
+        assert_eq!(uart.instance_addresses, vec![0x3ff5_0000]);
+        assert_eq!(uart.interrupts[0].value, 34);
+
+        let r = &uart.registers[0];
+        assert_eq!((r.address, r.reset_value), (0x20, 0x800_001c));
+        assert_eq!(r.bit_fields[0].name, "PARITY");
+        assert_eq!(r.bit_fields[0].bits.msb(), 1);
+        assert_eq!(r.bit_fields[0].type_, Type::ReadOnly);
+        assert_eq!(r.bit_fields[1].bits.lsb(), 27);
+    }
+}
@@ -0,0 +1,213 @@
+//! Parser for the `esp8266_peri.h` header of the ESP8266 Arduino core, which describes many
+//! registers missing from the SDK headers with `ESP8266_REG(addr)` style macros followed by
+//! bit position constants, or by masks with their `_S` shift.
+
+use crate::nonos::mask_bits;
+use crate::{
+    expr, peripheral_for_address, BitField, Bits, Origin, Peripheral, Provenance, Register,
+};
+use regex::Regex;
+use std::collections::{BTreeMap, HashMap};
+
+/// Source name recorded on the merged registers
+pub const SOURCE: &str = "esp8266_peri.h";
+
+/// A register defined through one of the `*_REG(addr)` base macros
+#[derive(Debug, Clone)]
+pub struct PeriRegister {
+    pub name: String,
+    /// First word of the preceding section comment, e.g. `GPIO` for `//GPIO (0-15) Control Registers`
+    pub section: String,
+    /// Absolute address
+    pub address: u32,
+    pub description: String,
+    /// Bit position constants directly following the register
+    pub bit_fields: Vec<BitField>,
+    /// 1-based line of the register define
+    pub line: usize,
+}
+
+/// Parse the registers of an `esp8266_peri.h` style header, indexed registers such as
+/// `GPC(p)` are taken at index 0
+pub fn parse_peri(text: &str) -> Vec<PeriRegister> {
+    let re_base = Regex::new(
+        r"^\s*#define\s+(\w+)\(addr\)\s+\*\(\(volatile\s+uint32_t\s*\*\)\(\s*(0x[0-9a-fA-F]+)\s*\+\s*\(?addr\)?\s*\)\)",
+    )
+    .unwrap();
+    let re_reg =
+        Regex::new(r"^\s*#define\s+(\w+)(?:\(\w+\))?\s+(\w+)\((.*?)\)\s*(?://\s*(.*?))?\s*$")
+            .unwrap();
+    let re_bit = Regex::new(r"^\s*#define\s+(\w+)\s+([0-9]+)\s*(?://\s*(.*?))?\s*$").unwrap();
+    let re_mask =
+        Regex::new(r"^\s*#define\s+(\w+)\s+\(?(0x[0-9a-fA-F]+)\)?\s*(?://\s*(.*?))?\s*$").unwrap();
+    let re_section = Regex::new(r"^\s*//\s*([A-Za-z][A-Za-z0-9]*)").unwrap();
+
+    let mut bases: HashMap<String, u32> = HashMap::new();
+    let mut registers: Vec<PeriRegister> = vec![];
+    let mut unparsed = vec![];
+    let mut section = String::new();
+    // whether bit constants on the following lines belong to the last register
+    let mut in_register = false;
+    // unshifted masks and their descriptions, completed into fields by their `_S` shift
+    let mut masks: HashMap<String, (u32, String)> = HashMap::new();
+
+    for (i, line) in text.lines().enumerate() {
+        if let Some(c) = re_base.captures(line) {
+            if let Ok(base) = expr::parse_number(&c[2]) {
+                bases.insert(c[1].to_string(), base as u32);
+            }
+            in_register = false;
+        } else if let Some(base) = re_reg
+            .captures(line)
+            .and_then(|c| bases.get(&c[2]).map(|base| (c, *base)))
+        {
+            let (c, base) = base;
+            // index parameters of indexed registers are evaluated as 0
+            match expr::eval(&c[3], &|_| Some(0)) {
+                Ok(offset) => {
+                    registers.push(PeriRegister {
+                        name: c[1].to_string(),
+                        section: section.clone(),
+                        address: base.wrapping_add(offset as u32),
+                        description: c.get(4).map_or("", |d| d.as_str()).to_string(),
+                        bit_fields: vec![],
+                        line: i + 1,
+                    });
+                    in_register = true;
+                    masks.clear();
+                }
+                Err(_) => {
+                    unparsed.push(c[1].to_string());
+                    in_register = false;
+                }
+            }
+        } else if let Some(c) = re_mask.captures(line) {
+            match expr::parse_number(&c[2]) {
+                Ok(mask) if in_register => {
+                    let description = c.get(3).map_or("", |d| d.as_str()).to_string();
+                    masks.insert(c[1].to_string(), (mask as u32, description));
+                }
+                _ => in_register = false,
+            }
+        } else if let Some(c) = re_bit.captures(line) {
+            let value: u32 = c[2].parse().unwrap_or(u32::MAX);
+            // a `_S` define is the shift of a mask and never a bit of its own, not even a
+            // shift of 0
+            let field = match c[1].strip_suffix("_S") {
+                Some(name) if value < 32 => masks.get(name).and_then(|(mask, description)| {
+                    let bits = mask_bits(*mask, value).filter(|bits| bits.msb() < 32)?;
+                    Some((name, bits, description.as_str(), Origin::HeaderMask))
+                }),
+                Some(_) => None,
+                None if value < 32 => Some((
+                    &c[1],
+                    Bits::Single(value as u8),
+                    c.get(3).map_or("", |d| d.as_str()),
+                    Origin::HeaderBit,
+                )),
+                None => None,
+            };
+            match (registers.last_mut(), field) {
+                (Some(r), Some((name, bits, description, origin))) if in_register => {
+                    r.bit_fields.push(BitField {
+                        name: name.to_string(),
+                        bits,
+                        description: description.to_string(),
+                        origin,
+                        provenance: vec![Provenance::Header {
+                            file: SOURCE.to_string(),
+                            line: Some(i + 1),
+                        }],
+                        ..Default::default()
+                    })
+                }
+                // the shift of an unknown mask
+                (Some(_), None) if in_register && c[1].ends_with("_S") => {}
+                _ => in_register = false,
+            }
+        } else if let Some(c) = re_section.captures(line) {
+            section = c[1].to_uppercase();
+            in_register = false;
+        } else {
+            in_register = false;
+        }
+    }
+
+    if !unparsed.is_empty() {
+        println!(
+            "The following esp8266_peri.h registers failed to parse {:?}",
+            unparsed
+        );
+    }
+
+    registers
+}
+
+/// Add the registers of `esp8266_peri.h` to the model, registers already known at the same
+/// address are kept as is. Returns the number of added registers
+pub fn merge_peri(
+    peripherals: &mut BTreeMap<String, Peripheral>,
+    registers: Vec<PeriRegister>,
+) -> usize {
+    let mut added = 0;
+    for r in registers {
+        let hint = if r.section.is_empty() {
+            r.name.clone()
+        } else {
+            r.section.clone()
+        };
+        let pname = peripheral_for_address(peripherals, &hint, r.address);
+        let p = peripherals.get_mut(&pname).unwrap();
+        let offset = r.address - p.address;
+        if p.registers.iter().any(|known| known.address == offset) {
+            continue;
+        }
+        p.registers.push(Register {
+            description: if r.description.is_empty() {
+                r.name.clone()
+            } else {
+                r.description
+            },
+            name: r.name,
+            address: offset,
+            bit_fields: r.bit_fields,
+            provenance: vec![Provenance::Header {
+                file: SOURCE.to_string(),
+                line: Some(r.line),
+            }],
+            ..Default::default()
+        });
+        added += 1;
+    }
+
+    for p in peripherals.values_mut() {
+        p.registers.sort_by_key(|r| r.address);
+        for r in p.registers.iter_mut() {
+            r.bit_fields.sort_by_key(|f| f.bits.lsb());
+        }
+    }
+    added
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zero_shift() {
+        let text = "#define ESP8266_REG(addr) *((volatile uint32_t *)(0x60000000+(addr)))\n\
+                    #define UCD ESP8266_REG(0x014) //UART CLKDIV\n\
+                    #define UCDIV 0xFFFFF //clock divider\n\
+                    #define UCDIV_S 0\n\
+                    #define UCFRAC_S 0\n\
+                    #define UCFRAC 20 //fraction\n";
+        let registers = parse_peri(text);
+        let fields: Vec<(&str, u8, u8)> = registers[0]
+            .bit_fields
+            .iter()
+            .map(|f| (f.name.as_str(), f.bits.lsb(), f.bits.msb()))
+            .collect();
+        assert_eq!(fields, vec![("UCDIV", 0, 19), ("UCFRAC", 20, 20)]);
+        assert_eq!(registers[0].bit_fields[0].description, "clock divider");
+    }
+}
@@ -0,0 +1,85 @@
+//! Detection of channelised registers and fields, e.g. `RMT_CH0CONF0` to `RMT_CH7CONF0`,
+//! which are emitted as separate elements but form an array in the hardware.
+
+use regex::Regex;
+use std::collections::BTreeMap;
+
+/// Consecutively numbered elements that only differ in one index
+#[derive(Debug, Clone, PartialEq)]
+pub struct Run {
+    /// Name with the index replaced by `%s`
+    pub pattern: String,
+    /// Letters directly in front of the index, e.g. `CH` for `RMT_CH%sCONF0`
+    pub segment: String,
+    /// First index of the run
+    pub start: u32,
+    pub len: u32,
+    /// Distance between the offsets (or bit positions) of two elements, if constant
+    pub stride: Option<u32>,
+}
+
+/// Find all runs of at least two elements among `(name, position)` pairs
+pub fn runs<'a>(elements: impl IntoIterator<Item = (&'a str, u32)>) -> Vec<Run> {
+    let re_index = Regex::new(r"[0-9]+").unwrap();
+    let mut groups: BTreeMap<(String, String), Vec<(u32, u32)>> = BTreeMap::new();
+
+    for (name, position) in elements {
+        for m in re_index.find_iter(name) {
+            let index = match m.as_str().parse() {
+                Ok(index) => index,
+                Err(_) => continue,
+            };
+            let pattern = format!("{}%s{}", &name[..m.start()], &name[m.end()..]);
+            let segment = name[..m.start()]
+                .rsplit(|c: char| !c.is_ascii_alphabetic())
+                .next()
+                .unwrap_or_default()
+                .to_string();
+            groups
+                .entry((pattern, segment))
+                .or_default()
+                .push((index, position));
+        }
+    }
+
+    groups
+        .into_iter()
+        .filter_map(|((pattern, segment), mut elements)| {
+            if elements.len() < 2 {
+                return None;
+            }
+            elements.sort();
+            let start = elements[0].0;
+            let consecutive = elements
+                .iter()
+                .enumerate()
+                .all(|(i, (index, _))| *index == start + i as u32);
+            if !consecutive {
+                return None;
+            }
+            let stride = elements[1].1.checked_sub(elements[0].1);
+            let stride = stride.filter(|stride| {
+                elements
+                    .windows(2)
+                    .all(|w| w[1].1.checked_sub(w[0].1) == Some(*stride))
+            });
+            Some(Run {
+                pattern,
+                segment,
+                start,
+                len: elements.len() as u32,
+                stride,
+            })
+        })
+        .collect()
+}
+
+/// Whether the count macro `name` (e.g. `RMT_CHANNEL_NUM`) counts the elements of `run`
+pub fn counts_run(name: &str, run: &Run) -> bool {
+    let word = name
+        .trim_end_matches("_NUM")
+        .rsplit('_')
+        .next()
+        .unwrap_or_default();
+    !run.segment.is_empty() && word.starts_with(run.segment.as_str())
+}
@@ -0,0 +1,128 @@
+//! Regression check of a generated svd against a known good one, failing when peripherals or
+//! registers went missing or moved. Added registers and field changes are left to review of the
+//! [`crate::diff`], intended removals and moves are listed in the `[baseline]` of the config.
+
+use crate::diff::{diff, Change, SvdSummary};
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BaselineConfig {
+    /// Dotted paths of peripherals and registers whose removal or move is intended, a
+    /// peripheral also allows the changes of its registers
+    #[serde(default)]
+    pub allow: Vec<String>,
+}
+
+/// Dotted path of a change the check fails on, `None` for additions and field changes
+fn regression_path(change: &Change) -> Option<String> {
+    match change {
+        Change::PeripheralRemoved { peripheral }
+        | Change::BaseAddressChanged { peripheral, .. } => Some(peripheral.clone()),
+        Change::RegisterRemoved {
+            peripheral,
+            register,
+        }
+        | Change::RegisterMoved {
+            peripheral,
+            register,
+            ..
+        } => Some(format!("{}.{}", peripheral, register)),
+        _ => None,
+    }
+}
+
+fn allowed(path: &str, allow: &[String]) -> bool {
+    allow
+        .iter()
+        .any(|a| path == a || path.starts_with(&format!("{}.", a)))
+}
+
+/// Removed and moved peripherals and registers of `new` compared to `baseline`, without the
+/// ones `config` allows
+pub fn check_baseline(
+    baseline: &SvdSummary,
+    new: &SvdSummary,
+    config: &BaselineConfig,
+) -> Vec<Change> {
+    diff(baseline, new)
+        .into_iter()
+        .filter(|c| match regression_path(c) {
+            Some(path) => !allowed(&path, &config.allow),
+            None => false,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::parse_svd;
+
+    fn svd(peripherals: &str) -> SvdSummary {
+        parse_svd(&format!(
+            "<device><name>esp8266</name><peripherals>{}</peripherals></device>",
+            peripherals
+        ))
+        .unwrap()
+    }
+
+    fn uart(base: &str, registers: &[(&str, &str)]) -> String {
+        let registers: String = registers
+            .iter()
+            .map(|(name, offset)| {
+                format!(
+                    "<register><name>{}</name><addressOffset>{}</addressOffset></register>",
+                    name, offset
+                )
+            })
+            .collect();
+        format!(
+            "<peripheral><name>UART</name><baseAddress>{}</baseAddress>\
+             <registers>{}</registers></peripheral>",
+            base, registers
+        )
+    }
+
+    #[test]
+    fn test_check_baseline() {
+        let rtc = "<peripheral><name>RTC</name><baseAddress>0x60000700</baseAddress></peripheral>";
+        let baseline = svd(&format!(
+            "{}{}",
+            uart(
+                "0x60000000",
+                &[("FIFO", "0x0"), ("CONF0", "0x20"), ("CONF1", "0x24")]
+            ),
+            rtc
+        ));
+        let new = svd(&uart(
+            "0x60000000",
+            &[("FIFO", "0x0"), ("CONF0", "0x28"), ("STATUS", "0x1c")],
+        ));
+
+        let regressions: Vec<String> = check_baseline(&baseline, &new, &Default::default())
+            .iter()
+            .map(Change::to_string)
+            .collect();
+        assert_eq!(
+            regressions,
+            vec![
+                "removed RTC",
+                "UART.CONF0 moved from offset 0x20 to 0x28",
+                "removed UART.CONF1",
+            ]
+        );
+
+        let config = BaselineConfig {
+            allow: vec!["RTC".to_string(), "UART.CONF1".to_string()],
+        };
+        let regressions = check_baseline(&baseline, &new, &config);
+        assert_eq!(regressions.len(), 1);
+
+        let moved = svd(&uart("0x60000f00", &[("FIFO", "0x0")]));
+        let config = BaselineConfig {
+            allow: vec!["UART".to_string(), "RTC".to_string()],
+        };
+        assert!(check_baseline(&baseline, &moved, &config).is_empty());
+    }
+}
@@ -0,0 +1,224 @@
+//! Self-contained HTML page to browse the register map without an svd viewer: a section per
+//! peripheral with a sortable register table whose rows expand into the fields.
+
+use crate::{BitField, Peripheral, Register, Type};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+const STYLE: &str = r#"
+body { font-family: sans-serif; margin: 0; display: flex; }
+nav { position: sticky; top: 0; height: 100vh; overflow-y: auto; padding: 1em; border-right: 1px solid #ccc; }
+nav ul { list-style: none; padding: 0; }
+main { padding: 1em 2em; flex: 1; }
+table { border-collapse: collapse; margin: 0.5em 0; }
+th, td { border: 1px solid #ccc; padding: 0.2em 0.5em; text-align: left; vertical-align: top; }
+table.registers > thead th { cursor: pointer; background: #eee; }
+table.registers > thead th[data-order=asc]::after { content: " \25B2"; }
+table.registers > thead th[data-order=desc]::after { content: " \25BC"; }
+code, .address { font-family: monospace; }
+summary { cursor: pointer; font-family: monospace; }
+.safety { color: #b00; }
+"#;
+
+const SCRIPT: &str = r#"
+document.querySelectorAll("table.registers > thead th").forEach(function (th, column) {
+  th.addEventListener("click", function () {
+    var tbody = th.closest("table").tBodies[0];
+    var ascending = th.dataset.order !== "asc";
+    th.parentNode.querySelectorAll("th").forEach(function (other) { delete other.dataset.order; });
+    th.dataset.order = ascending ? "asc" : "desc";
+    var rows = Array.prototype.slice.call(tbody.rows);
+    rows.sort(function (a, b) {
+      var x = a.cells[column].dataset.sort, y = b.cells[column].dataset.sort;
+      return (x < y ? -1 : x > y ? 1 : 0) * (ascending ? 1 : -1);
+    });
+    rows.forEach(function (row) { tbody.appendChild(row); });
+  });
+});
+"#;
+
+/// Escape text for use in html content and attribute values
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn access(type_: Type) -> &'static str {
+    match type_ {
+        Type::ReadOnly => "RO",
+        Type::ReadWrite => "R/W",
+        Type::WriteOnly => "WO",
+    }
+}
+
+fn field_rows(out: &mut String, fields: &[BitField]) {
+    let mut fields: Vec<&BitField> = fields.iter().collect();
+    fields.sort_by_key(|f| std::cmp::Reverse(f.bits.msb()));
+    for f in fields {
+        let bits = if f.bits.msb() == f.bits.lsb() {
+            f.bits.lsb().to_string()
+        } else {
+            format!("{}:{}", f.bits.msb(), f.bits.lsb())
+        };
+        writeln!(
+            out,
+            "<tr><td>{}</td><td><code>{}</code></td><td>{}</td><td class=\"address\">{:#X}</td><td>{}</td></tr>",
+            bits,
+            escape(&f.name),
+            access(f.type_),
+            f.reset_value,
+            escape(f.description.trim())
+        )
+        .unwrap();
+    }
+}
+
+fn register_row(out: &mut String, base: u32, r: &Register) {
+    writeln!(out, "<tr>").unwrap();
+    writeln!(
+        out,
+        "<td class=\"address\" data-sort=\"{0:08X}\">{0:#06X}</td>",
+        r.address
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "<td class=\"address\" data-sort=\"{0:08X}\">{0:#010X}</td>",
+        base + r.address
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "<td data-sort=\"{}\"><details><summary>{}</summary>",
+        escape(&r.name.to_lowercase()),
+        escape(&r.name)
+    )
+    .unwrap();
+    if let Some(safety) = r.safety {
+        writeln!(out, "<p class=\"safety\">{}</p>", safety.note()).unwrap();
+    }
+    if let Some(detailed) = &r.detailed_description {
+        writeln!(out, "<p>{}</p>", escape(detailed.trim())).unwrap();
+    }
+    writeln!(
+        out,
+        "<table class=\"fields\"><thead><tr><th>Bits</th><th>Field</th><th>Access</th><th>Reset</th><th>Description</th></tr></thead><tbody>"
+    )
+    .unwrap();
+    field_rows(out, &r.bit_fields);
+    writeln!(out, "</tbody></table></details></td>").unwrap();
+    writeln!(
+        out,
+        "<td class=\"address\" data-sort=\"{0:016X}\">{0:#010X}</td>",
+        r.reset_value
+    )
+    .unwrap();
+    let description = if r.description == r.name {
+        ""
+    } else {
+        r.description.trim()
+    };
+    writeln!(out, "<td data-sort=\"{0}\">{0}</td>", escape(description)).unwrap();
+    writeln!(out, "</tr>").unwrap();
+}
+
+/// The html page, with `banner` as a comment at the top
+pub fn browser_page(peripherals: &BTreeMap<String, Peripheral>, banner: &str) -> String {
+    let mut out = String::new();
+    writeln!(out, "<!DOCTYPE html>").unwrap();
+    out.push_str(banner);
+    writeln!(out, "<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">").unwrap();
+    writeln!(out, "<title>Register map</title>\n<style>{}</style>", STYLE).unwrap();
+    writeln!(out, "</head>\n<body>\n<nav>\n<h2>Peripherals</h2>\n<ul>").unwrap();
+    for name in peripherals.keys() {
+        writeln!(out, "<li><a href=\"#{0}\">{0}</a></li>", escape(name)).unwrap();
+    }
+    writeln!(out, "</ul>\n</nav>\n<main>").unwrap();
+
+    for (name, p) in peripherals {
+        writeln!(out, "<section id=\"{0}\">\n<h2>{0}</h2>", escape(name)).unwrap();
+        if !p.description.is_empty() && p.description != *name {
+            writeln!(out, "<p>{}</p>", escape(p.description.trim())).unwrap();
+        }
+        let mut addresses = vec![p.address];
+        addresses.extend(&p.instance_addresses);
+        let addresses: Vec<String> = addresses.iter().map(|a| format!("{:#010X}", a)).collect();
+        writeln!(
+            out,
+            "<p>Base address: <span class=\"address\">{}</span></p>",
+            addresses.join(", ")
+        )
+        .unwrap();
+        if let Some(version) = &p.version {
+            writeln!(out, "<p>Version: {}</p>", escape(version)).unwrap();
+        }
+        if !p.interrupts.is_empty() {
+            let interrupts: Vec<String> = p
+                .interrupts
+                .iter()
+                .map(|i| format!("<code>{}</code> ({})", escape(&i.name), i.value))
+                .collect();
+            writeln!(out, "<p>Interrupts: {}</p>", interrupts.join(", ")).unwrap();
+        }
+
+        writeln!(
+            out,
+            "<table class=\"registers\"><thead><tr><th>Offset</th><th>Address</th><th>Register</th><th>Reset</th><th>Description</th></tr></thead><tbody>"
+        )
+        .unwrap();
+        let mut registers: Vec<&Register> = p.registers.iter().collect();
+        registers.sort_by_key(|r| (r.address, r.name.clone()));
+        for r in registers {
+            register_row(&mut out, p.address, r);
+        }
+        writeln!(out, "</tbody></table>\n</section>").unwrap();
+    }
+
+    writeln!(
+        out,
+        "</main>\n<script>{}</script>\n</body>\n</html>",
+        SCRIPT
+    )
+    .unwrap();
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Bits;
+
+    #[test]
+    fn test_browser_page() {
+        let register = Register {
+            name: "UART_CONF0".to_string(),
+            address: 0x20,
+            description: "Parity <enable> & stop bits".to_string(),
+            bit_fields: vec![BitField {
+                name: "PARITY_EN".to_string(),
+                bits: Bits::Single(1),
+                type_: Type::ReadWrite,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let mut peripherals = BTreeMap::new();
+        peripherals.insert(
+            "UART".to_string(),
+            Peripheral {
+                address: 0x3ff4_0000,
+                registers: vec![register],
+                ..Default::default()
+            },
+        );
+
+        let page = browser_page(&peripherals, "<!-- banner -->\n");
+        assert!(page.starts_with("<!DOCTYPE html>\n<!-- banner -->\n<html"));
+        assert!(page.contains("<li><a href=\"#UART\">UART</a></li>"));
+        assert!(page.contains("<td class=\"address\" data-sort=\"3FF40020\">0x3FF40020</td>"));
+        assert!(page.contains("Parity &lt;enable&gt; &amp; stop bits"));
+        assert!(page.contains("<td>1</td><td><code>PARITY_EN</code></td><td>R/W</td>"));
+    }
+}
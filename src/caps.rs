@@ -0,0 +1,139 @@
+//! Capability macros of `soc_caps.h`, such as `SOC_UART_NUM` or `SOC_BT_SUPPORTED`. They are
+//! emitted as a `vendorExtensions` block and their counts give the number of instances of the
+//! indexed peripherals, whose base addresses come from the `REG_<NAME>_BASE(i)` macros of
+//! `soc.h`.
+
+use crate::expr;
+use crate::preprocess::{preprocess, resolve, tokenize_define};
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use xmltree::Element;
+
+/// Capability header next to `soc.h`, present since esp-idf v4.1
+pub const CAPS_FILE: &str = "soc_caps.h";
+
+/// Peripherals whose count macro isn't named after them
+const COUNT_NAMES: &[(&str, &str)] = &[("TIMG", "TIMER_GROUPS")];
+
+/// Evaluate the `SOC_*` defines of a capability header using `defines` for its `#if` blocks.
+/// Defines which aren't an integer expression, e.g. strings, are skipped
+pub fn parse_caps(text: &str, defines: &HashMap<String, String>) -> BTreeMap<String, i64> {
+    let mut defines = defines.clone();
+    let lines = preprocess(text, &mut defines);
+    lines
+        .iter()
+        .filter_map(|(_, line)| tokenize_define(line))
+        .filter(|d| d.name.starts_with("SOC_") && d.params.is_none())
+        .filter_map(|d| Some((d.name.clone(), resolve(&defines, &d.name)?)))
+        .collect()
+}
+
+/// Number of instances of peripheral `name`, from `SOC_<NAME>_NUM` or `SOC_<NAME>_PERIPH_NUM`
+pub fn instance_count(caps: &BTreeMap<String, i64>, name: &str) -> Option<usize> {
+    let mut candidates = vec![
+        format!("SOC_{}_NUM", name),
+        format!("SOC_{}_PERIPH_NUM", name),
+    ];
+    for (peripheral, count) in COUNT_NAMES {
+        if *peripheral == name {
+            candidates.push(format!("SOC_{}", count));
+        }
+    }
+    candidates
+        .iter()
+        .find_map(|c| caps.get(c))
+        .filter(|&&count| count > 0)
+        .map(|&count| count as usize)
+}
+
+/// Base addresses of `count` instances of peripheral `name`, evaluating its
+/// `REG_<NAME>_BASE(i)` macro in `soc_h` with the `symbols` defined there
+pub fn instance_bases(
+    soc_h: &str,
+    name: &str,
+    count: usize,
+    symbols: &HashMap<String, String>,
+) -> Option<Vec<u32>> {
+    let macro_name = format!("REG_{}_BASE", name);
+    let define = soc_h
+        .lines()
+        .filter_map(tokenize_define)
+        .find(|d| d.name == macro_name)?;
+    let param = match define.params.as_deref() {
+        Some([param]) => param.clone(),
+        _ => return None,
+    };
+    (0..count as i64)
+        .map(|i| {
+            let value = expr::eval(&define.value, &|n| {
+                if n == param {
+                    Some(i)
+                } else {
+                    resolve(symbols, n)
+                }
+            })
+            .ok()?;
+            u32::try_from(value).ok()
+        })
+        .collect()
+}
+
+/// `vendorExtensions` entry listing the capabilities
+pub fn capabilities_extension(caps: &BTreeMap<String, i64>) -> Element {
+    let text = |name: &str, text: String| {
+        let mut element = Element::new(name);
+        element.text = Some(text);
+        element
+    };
+    let mut capabilities = Element::new("capabilities");
+    for (name, value) in caps {
+        let mut capability = Element::new("capability");
+        capability.children.push(text("name", name.clone()));
+        capability.children.push(text("value", value.to_string()));
+        capabilities.children.push(capability);
+    }
+    capabilities
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_caps() {
+        let caps_h = "#pragma once\n\
+            #define SOC_BT_SUPPORTED 1\n\
+            #define SOC_CPU_CORES_NUM 2\n\
+            #define SOC_UART_NUM (3)\n\
+            #define SOC_I2C_NUM (SOC_CPU_CORES_NUM)\n\
+            #define SOC_TIMER_GROUPS 2\n\
+            #ifdef CONFIG_IDF_TARGET_ESP32S2\n\
+            #define SOC_SPI_PERIPH_NUM 3\n\
+            #else\n\
+            #define SOC_SPI_PERIPH_NUM 4\n\
+            #endif\n\
+            #define SOC_CHIP_NAME \"esp32\"\n";
+        let caps = parse_caps(caps_h, &HashMap::new());
+        assert_eq!(caps.len(), 6);
+        assert_eq!(caps["SOC_I2C_NUM"], 2);
+        assert_eq!(instance_count(&caps, "SPI"), Some(4));
+        assert_eq!(instance_count(&caps, "TIMG"), Some(2));
+        assert_eq!(instance_count(&caps, "MCPWM"), None);
+
+        let soc_h = "#define DR_REG_I2C_EXT_BASE 0x3ff53000\n\
+            #define REG_I2C_BASE(i) (DR_REG_I2C_EXT_BASE + (i) * 0x14000)\n\
+            #define REG_SPI_BASE(i) (DR_REG_SPI1_BASE + (((i)>1) ? (((i)* 0x1000) + 0x20000) : (((~(i)) & 1)* 0x1000 )))\n";
+        let mut symbols = HashMap::new();
+        symbols.insert("DR_REG_I2C_EXT_BASE".to_string(), "0x3ff53000".to_string());
+        symbols.insert("DR_REG_SPI1_BASE".to_string(), "0x3ff42000".to_string());
+        assert_eq!(
+            instance_bases(soc_h, "I2C", 2, &symbols),
+            Some(vec![0x3ff5_3000, 0x3ff6_7000])
+        );
+        assert_eq!(
+            instance_bases(soc_h, "SPI", 4, &symbols),
+            Some(vec![0x3ff4_3000, 0x3ff4_2000, 0x3ff6_4000, 0x3ff6_5000])
+        );
+        assert_eq!(instance_bases(soc_h, "UHCI", 2, &symbols), None);
+    }
+}
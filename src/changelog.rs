@@ -0,0 +1,122 @@
+//! Markdown release notes drafted from the [`crate::diff`] of the previously released SVD and
+//! a regenerated one, for pasting into the release of the downstream SVD repository.
+
+use crate::diff::Change;
+use std::fmt::Write;
+
+/// Markdown fragment listing new and removed peripherals and the changes per peripheral
+pub fn changelog(changes: &[Change]) -> String {
+    let mut out = String::new();
+    if changes.is_empty() {
+        out.push_str("No changes to the peripherals, registers or fields\n");
+        return out;
+    }
+
+    let added: Vec<&str> = changes
+        .iter()
+        .filter_map(|c| match c {
+            Change::PeripheralAdded { peripheral } => Some(peripheral.as_str()),
+            _ => None,
+        })
+        .collect();
+    let removed: Vec<&str> = changes
+        .iter()
+        .filter_map(|c| match c {
+            Change::PeripheralRemoved { peripheral } => Some(peripheral.as_str()),
+            _ => None,
+        })
+        .collect();
+    let changed: Vec<&Change> = changes
+        .iter()
+        .filter(|c| {
+            !matches!(
+                c,
+                Change::PeripheralAdded { .. } | Change::PeripheralRemoved { .. }
+            )
+        })
+        .collect();
+
+    if !added.is_empty() {
+        out.push_str("### New peripherals\n\n");
+        for peripheral in added {
+            writeln!(out, "- `{}`", peripheral).unwrap();
+        }
+        out.push('\n');
+    }
+    if !removed.is_empty() {
+        out.push_str("### Removed peripherals\n\n");
+        for peripheral in removed {
+            writeln!(out, "- `{}`", peripheral).unwrap();
+        }
+        out.push('\n');
+    }
+    if !changed.is_empty() {
+        out.push_str("### Changed peripherals\n");
+        let mut current = None;
+        for change in changed {
+            if current != Some(change.peripheral()) {
+                current = Some(change.peripheral());
+                writeln!(out, "\n#### `{}`\n", change.peripheral()).unwrap();
+            }
+            writeln!(out, "- {}", change).unwrap();
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::{diff, parse_svd};
+
+    fn svd(peripherals: &str) -> crate::diff::SvdSummary {
+        parse_svd(&format!(
+            "<device><name>esp32</name><peripherals>{}</peripherals></device>",
+            peripherals
+        ))
+        .unwrap()
+    }
+
+    fn register(name: &str, offset: u32) -> String {
+        format!(
+            "<register><name>{}</name><addressOffset>{:#x}</addressOffset></register>",
+            name, offset
+        )
+    }
+
+    fn uart(registers: &[String]) -> String {
+        format!(
+            "<peripheral><name>UART</name><baseAddress>0x3FF40000</baseAddress>\
+             <registers>{}</registers></peripheral>",
+            registers.concat()
+        )
+    }
+
+    #[test]
+    fn test_changelog() {
+        let old = uart(&[
+            register("CONF0", 0x20),
+            register("CONF1", 0x24),
+            register("MEM_CONF", 0x58),
+        ]);
+        // CONF1 is renamed to CONF_1 and MEM_CONF removed
+        let new = uart(&[register("CONF0", 0x20), register("CONF_1", 0x24)]);
+        let rmt = "<peripheral><name>RMT</name><baseAddress>0x3FF56000</baseAddress></peripheral>";
+
+        let changes = diff(&svd(&format!("{}{}", old, rmt)), &svd(&new));
+        assert_eq!(
+            changelog(&changes),
+            "### Removed peripherals\n\n\
+             - `RMT`\n\n\
+             ### Changed peripherals\n\n\
+             #### `UART`\n\n\
+             - removed UART.CONF1\n\
+             - removed UART.MEM_CONF\n\
+             - added UART.CONF_1\n"
+        );
+        assert_eq!(
+            changelog(&[]),
+            "No changes to the peripherals, registers or fields\n"
+        );
+    }
+}
@@ -0,0 +1,126 @@
+//! Chip profiles: data files listing the peripherals of a chip that can't be derived from its
+//! headers, in the `[[peripheral]]` format of the config, and describing its CPU in the `[cpu]`
+//! format. The profiles in `src/chips` are built in, other chips can be described in a file of
+//! the same format.
+
+use crate::config::{CpuConfig, PeripheralSeed};
+use serde::Deserialize;
+use std::ffi::OsStr;
+use std::path::Path;
+
+/// Chip whose profile is used unless configured
+pub const DEFAULT_CHIP: &str = "esp32";
+
+/// Built in profiles by chip name
+const PROFILES: &[(&str, &str)] = &[("esp32", include_str!("chips/esp32.toml"))];
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChipProfile {
+    #[serde(default, rename = "peripheral")]
+    pub seeds: Vec<PeripheralSeed>,
+    /// The svd has no CPU description when absent
+    #[serde(default)]
+    pub cpu: Option<CpuConfig>,
+}
+
+/// Names of the built in profiles
+pub fn builtin_chips() -> Vec<&'static str> {
+    PROFILES.iter().map(|(name, _)| *name).collect()
+}
+
+fn read_profile(text: &str, source: &str) -> Result<ChipProfile, String> {
+    toml::from_str::<ChipProfile>(text)
+        .map_err(|e| format!("Invalid chip profile {}: {}", source, e))
+}
+
+/// Parse the seeds of the text of a profile, `source` names it in errors
+pub fn parse_profile(text: &str, source: &str) -> Result<Vec<PeripheralSeed>, String> {
+    read_profile(text, source).map(|profile| profile.seeds)
+}
+
+/// Seeds of `chip`, either the name of a built in profile or the path of a profile file
+pub fn chip_seeds(chip: &str) -> Result<Vec<PeripheralSeed>, String> {
+    chip_profile(chip).map(|profile| profile.seeds)
+}
+
+/// Profile of `chip`, either the name of a built in profile or the path of a profile file
+pub fn chip_profile(chip: &str) -> Result<ChipProfile, String> {
+    if let Some((_, text)) = PROFILES.iter().find(|(name, _)| *name == chip) {
+        return read_profile(text, chip);
+    }
+    let path = Path::new(chip);
+    if path.extension() == Some(OsStr::new("toml")) {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        return read_profile(&text, chip);
+    }
+    Err(format!(
+        "Unknown chip {}, expected one of {} or a .toml profile",
+        chip,
+        builtin_chips().join(", ")
+    ))
+}
+
+/// The profile seeds with `overrides` applied, a seed replaces the profile seed of the same
+/// name and is added otherwise
+pub fn merge_seeds(
+    mut seeds: Vec<PeripheralSeed>,
+    overrides: &[PeripheralSeed],
+) -> Vec<PeripheralSeed> {
+    for seed in overrides {
+        match seeds.iter_mut().find(|s| s.name == seed.name) {
+            Some(existing) => *existing = seed.clone(),
+            None => seeds.push(seed.clone()),
+        }
+    }
+    seeds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Endianness;
+
+    #[test]
+    fn test_chip_seeds() {
+        let seeds = chip_seeds(DEFAULT_CHIP).unwrap();
+        let names: Vec<&str> = seeds.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["I2C", "SPI", "TIMG", "MCPWM", "UHCI"]);
+        assert!(seeds
+            .iter()
+            .all(|s| s.instances == 1 && s.reference.is_some()));
+        assert!(chip_seeds("esp8266").is_err());
+
+        let overrides = parse_profile(
+            "[[peripheral]]\nname = \"I2C\"\ninstances = 2\nbase_addresses = [0x3ff53000, 0x3ff67000]\n\n\
+             [[peripheral]]\nname = \"RNG\"\nbase_addresses = [0x3ff75144]\n",
+            "test",
+        )
+        .unwrap();
+        let merged = merge_seeds(seeds, &overrides);
+        assert_eq!(merged.len(), 6);
+        assert_eq!(merged[0].base_addresses, vec![0x3ff5_3000, 0x3ff6_7000]);
+        assert_eq!(merged[0].reference, None);
+        assert_eq!(merged[5].name, "RNG");
+        assert!(parse_profile("[[peripheral]]\nnam = \"I2C\"\n", "typo").is_err());
+    }
+
+    #[test]
+    fn test_chip_cpu() {
+        let cpu = chip_profile(DEFAULT_CHIP).unwrap().cpu.unwrap();
+        assert_eq!(cpu.name, "Xtensa LX6");
+        assert_eq!(cpu.nvic_priority_bits, 3);
+
+        let profile = read_profile(
+            "[cpu]\nname = \"RV32IMC\"\nrevision = \"r0p3\"\nnvic_priority_bits = 4\n",
+            "test",
+        )
+        .unwrap();
+        let cpu = profile.cpu.unwrap();
+        assert_eq!(cpu.endian, Endianness::Little);
+        assert!(!cpu.fpu_present);
+        assert!(profile.seeds.is_empty());
+        assert!(read_profile("[cpu]\nname = \"RV32IMC\"\n", "partial").is_err());
+    }
+}
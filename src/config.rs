@@ -0,0 +1,378 @@
+//! Runtime configuration loaded from a TOML file
+//!
+//! ```toml
+//! # built in chip profile or path of a profile file, esp32 unless set
+//! chip = "esp32"
+//!
+//! [[replacement]]
+//! search = "RTC_STORE0 "
+//! replace = "RTC_STORE0_REG "
+//! # optional, only apply to headers with this file name
+//! file = "rtc_cntl_reg.h"
+//!
+//! [[peripheral]]
+//! name = "I2C"
+//! instances = 2
+//! base_addresses = [0x3ff53000, 0x3ff67000]
+//! # optional, where the addresses are documented
+//! reference = "ESP32 Technical Reference Manual, I2C Controller"
+//! # optional, the span of the registers unless set
+//! block_size = 0x1000
+//!
+//! [[interrupt]]
+//! name = "NMI"
+//! value = 14
+//! peripheral = "DPORT"
+//!
+//! [[merge]]
+//! peripheral = "TIMER"
+//! strategy = "prefer-header"
+//!
+//! [[merge]]
+//! peripheral = "TIMER"
+//! register = "FRC1_CTRL"
+//! strategy = "union"
+//!
+//! [[byte_lanes]]
+//! path = "UART.UART_FIFO"
+//! # optional, all four bytes when left out
+//! lanes = [0]
+//!
+//! [[write]]
+//! path = "GPIO.GPIO_STATUS_W1TC"
+//! # optional, plain writes when left out
+//! modified = "one-to-clear"
+//! # optional, access of the fields
+//! access = "WO"
+//!
+//! # applied in this order to the names after shortening
+//! [[rename]]
+//! pattern = "^SPI_FLASH_"
+//! replacement = "FLASH_"
+//! # peripheral, register or field
+//! scope = "register"
+//! # optional, only rename in this peripheral
+//! peripheral = "SPI0"
+//!
+//! [[enum]]
+//! # relative to the SDK directory
+//! header = "components/soc/include/hal/uart_types.h"
+//! type = "uart_parity_t"
+//! fields = ["UART.UART_CONF0.UART_PARITY"]
+//!
+//! [[patch]]
+//! path = "UART.UART_MEM_CONF"
+//! rename = "UART_MEM_CNF"
+//!
+//! [[patch]]
+//! path = "GPIO.GPIO_BT_SELECT"
+//! delete = true
+//!
+//! [[patch]]
+//! path = "SPI0.SPI_CMD"
+//! safety = "unsafe"
+//!
+//! # replaces the CPU of the chip profile
+//! [cpu]
+//! name = "Xtensa LX106"
+//! revision = "1"
+//! # optional, little unless set
+//! endian = "little"
+//! # optional, false unless set
+//! mpu_present = false
+//! fpu_present = false
+//! vendor_systick = false
+//! nvic_priority_bits = 1
+//!
+//! [device]
+//! name = "ESP8266EX"
+//! # optional
+//! vendor = "Espressif Systems (Shanghai) Co., Ltd."
+//! series = "ESP8266"
+//! description = "Wi-Fi SoC"
+//! license_text = "Licensed under the Apache License, Version 2.0"
+//! # optional, 1.0 and 32 unless set
+//! version = "1.0"
+//! width = 32
+//!
+//! # removed or moved peripherals and registers `--baseline` doesn't fail on
+//! [baseline]
+//! allow = ["GPIO.GPIO_BT_SELECT", "RTC"]
+//!
+//! [banner]
+//! license = "Apache-2.0"
+//! notice = ["Derived from esp-idf, Copyright 2015-2019 Espressif Systems (Shanghai) PTE LTD"]
+//! ```
+
+use crate::baseline::BaselineConfig;
+use crate::chips::{chip_profile, chip_seeds, merge_seeds, DEFAULT_CHIP};
+use crate::enums::EnumMapping;
+use crate::lanes::ByteLanes;
+use crate::merge::MergeRule;
+use crate::patch::Patch;
+use crate::rename::RenameRule;
+use crate::review::Accepted;
+use crate::writes::WriteOverride;
+use serde::Deserialize;
+use std::ffi::OsStr;
+use std::path::Path;
+
+/// Text replacement applied to header contents before parsing, used to fix malformed headers
+#[derive(Debug, Clone, Deserialize)]
+pub struct Replacement {
+    pub search: String,
+    pub replace: String,
+    /// Only apply to headers with this file name
+    #[serde(default)]
+    pub file: Option<String>,
+}
+
+impl Replacement {
+    fn applies_to(&self, path: &Path) -> bool {
+        match &self.file {
+            Some(file) => path.file_name() == Some(OsStr::new(file)),
+            None => true,
+        }
+    }
+}
+
+/// Apply all replacements that apply to the header at `path` to its contents
+pub fn apply_replacements(replacements: &[Replacement], path: &Path, text: String) -> String {
+    replacements
+        .iter()
+        .filter(|r| r.applies_to(path))
+        .fold(text, |text, r| text.replace(&r.search, &r.replace))
+}
+
+/// Peripheral whose base address can't be derived from the headers
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PeripheralSeed {
+    pub name: String,
+    /// Number of instances sharing the register block
+    #[serde(default = "one")]
+    pub instances: usize,
+    /// Base address of every instance, the registers are attached to the first one
+    #[serde(default)]
+    pub base_addresses: Vec<u32>,
+    /// Documentation the seed was taken from, e.g. a section of the technical reference manual
+    #[serde(default)]
+    pub reference: Option<String>,
+    /// Size of the address block, the span of the registers unless set
+    #[serde(default)]
+    pub block_size: Option<u32>,
+}
+
+fn one() -> usize {
+    1
+}
+
+/// The seeds of the default chip profile
+pub fn default_seeds() -> Vec<PeripheralSeed> {
+    chip_seeds(DEFAULT_CHIP).unwrap()
+}
+
+/// Interrupt missing from `soc.h`, e.g. the NMI or software interrupts used by the RTOS port
+#[derive(Debug, Clone, Deserialize)]
+pub struct FixedInterrupt {
+    pub name: String,
+    pub value: u32,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Peripheral the interrupt is emitted on
+    pub peripheral: String,
+}
+
+/// Byte order of the CPU
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Endianness {
+    #[default]
+    Little,
+    Big,
+}
+
+/// The CPU of the chip, as described in the `cpu` element of the svd
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CpuConfig {
+    /// e.g. `Xtensa LX6`, or `RV32IMC` for the RISC-V chips
+    pub name: String,
+    pub revision: String,
+    #[serde(default)]
+    pub endian: Endianness,
+    #[serde(default)]
+    pub mpu_present: bool,
+    #[serde(default)]
+    pub fpu_present: bool,
+    /// Bits needed for the interrupt priority levels
+    pub nvic_priority_bits: u32,
+    #[serde(default)]
+    pub vendor_systick: bool,
+}
+
+/// Identification of the device in the svd
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DeviceConfig {
+    pub name: String,
+    pub vendor: Option<String>,
+    pub series: Option<String>,
+    pub version: String,
+    pub description: Option<String>,
+    pub license_text: Option<String>,
+    /// Width of the bus in bits
+    pub width: u32,
+}
+
+impl Default for DeviceConfig {
+    fn default() -> Self {
+        DeviceConfig {
+            name: "Espressif".to_string(),
+            vendor: None,
+            series: None,
+            version: "1.0".to_string(),
+            description: None,
+            license_text: None,
+            width: 32,
+        }
+    }
+}
+
+impl DeviceConfig {
+    /// Set the value of `key`, named as in the config, e.g. from a `--device KEY=VALUE` option
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let value = value.to_string();
+        match key {
+            "name" => self.name = value,
+            "vendor" => self.vendor = Some(value),
+            "series" => self.series = Some(value),
+            "version" => self.version = value,
+            "description" => self.description = Some(value),
+            "license_text" => self.license_text = Some(value),
+            "width" => {
+                self.width = value
+                    .parse()
+                    .map_err(|_| format!("Invalid device width {}", value))?
+            }
+            _ => return Err(format!("Unknown device property {}", key)),
+        }
+        Ok(())
+    }
+}
+
+/// Provenance banner written at the top of every generated file
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Banner {
+    /// SPDX identifier of the license of the generated content
+    #[serde(default)]
+    pub license: Option<String>,
+    /// Lines added verbatim, e.g. the copyright of the headers the content is derived from
+    #[serde(default)]
+    pub notice: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Chip profile the seeds are loaded from, a built in chip or the path of a profile file
+    #[serde(default)]
+    pub chip: Option<String>,
+    #[serde(default, rename = "replacement")]
+    pub replacements: Vec<Replacement>,
+    /// Seeds of the chip profile, with the seeds of the config file replacing or adding to them
+    #[serde(default, rename = "peripheral")]
+    pub seeds: Vec<PeripheralSeed>,
+    #[serde(default, rename = "interrupt")]
+    pub interrupts: Vec<FixedInterrupt>,
+    /// How documentation is merged with the headers, `prefer-doc` unless configured
+    #[serde(default, rename = "merge")]
+    pub merge_rules: Vec<MergeRule>,
+    #[serde(default, rename = "patch")]
+    pub patches: Vec<Patch>,
+    /// Renames of the naming policy, applied in order
+    #[serde(default, rename = "rename")]
+    pub rename_rules: Vec<RenameRule>,
+    /// Findings of `review` that are correct as parsed
+    #[serde(default, rename = "accept")]
+    pub accepted: Vec<Accepted>,
+    /// Registers to also emit as 8-bit alternate registers, one per byte
+    #[serde(default)]
+    pub byte_lanes: Vec<ByteLanes>,
+    /// Write semantics replacing the ones found by name
+    #[serde(default, rename = "write")]
+    pub writes: Vec<WriteOverride>,
+    /// C enums of the SDK giving the enumerated values of fields
+    #[serde(default, rename = "enum")]
+    pub enums: Vec<EnumMapping>,
+    /// CPU of the chip, replacing the one of the chip profile
+    #[serde(default)]
+    pub cpu: Option<CpuConfig>,
+    #[serde(default)]
+    pub device: DeviceConfig,
+    #[serde(default)]
+    pub baseline: BaselineConfig,
+    #[serde(default)]
+    pub banner: Banner,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            chip: None,
+            replacements: vec![],
+            seeds: default_seeds(),
+            interrupts: vec![],
+            merge_rules: vec![],
+            patches: vec![],
+            rename_rules: vec![],
+            accepted: vec![],
+            byte_lanes: vec![],
+            writes: vec![],
+            enums: vec![],
+            cpu: chip_profile(DEFAULT_CHIP).unwrap().cpu,
+            device: DeviceConfig::default(),
+            baseline: BaselineConfig::default(),
+            banner: Banner::default(),
+        }
+    }
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Config, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let mut config: Config = toml::from_str(&text)
+            .map_err(|e| format!("Invalid config {}: {}", path.display(), e))?;
+        let profile = chip_profile(config.chip.as_deref().unwrap_or(DEFAULT_CHIP))?;
+        config.seeds = merge_seeds(profile.seeds, &config.seeds);
+        config.cpu = config.cpu.or(profile.cpu);
+        Ok(config)
+    }
+
+    /// Add the patches, merge rules and accepted findings of a patch file written by `review`,
+    /// its merge rules taking precedence
+    pub fn add_patch_file(&mut self, path: &Path) -> Result<(), String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let file: PatchFile = toml::from_str(&text)
+            .map_err(|e| format!("Invalid patch file {}: {}", path.display(), e))?;
+        self.patches.extend(file.patches);
+        self.merge_rules.extend(file.merge_rules);
+        self.accepted.extend(file.accepted);
+        Ok(())
+    }
+}
+
+/// The part of the config a patch file holds
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PatchFile {
+    #[serde(default, rename = "patch")]
+    patches: Vec<Patch>,
+    #[serde(default, rename = "merge")]
+    merge_rules: Vec<MergeRule>,
+    #[serde(default, rename = "accept")]
+    accepted: Vec<Accepted>,
+}
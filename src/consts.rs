@@ -0,0 +1,179 @@
+//! Rust module with raw peripheral base addresses, register offsets and field shifts and masks,
+//! for projects that don't use a PAC but want addresses that stay in sync with the svd. It only
+//! contains constants, so it can be included in `no_std` crates.
+
+use crate::{BitField, Peripheral};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+/// Format an address as `0x3FF4_0000`
+fn address(value: u32) -> String {
+    format!("0x{:04X}_{:04X}", value >> 16, value & 0xFFFF)
+}
+
+/// Mask of the bits of `f` in its register
+fn field_mask(f: &BitField) -> u32 {
+    let width = u32::from(f.bits.msb() - f.bits.lsb()) + 1;
+    (((1u64 << width) - 1) << f.bits.lsb()) as u32
+}
+
+/// Generate the source of a Rust module with `<PERIPHERAL>_BASE`, `<REGISTER>_OFFSET` and
+/// `<FIELD>_SHIFT`/`<FIELD>_MASK` constants. Fields whose name is taken by a field of another
+/// register are prefixed with their register name
+pub fn generate_consts(peripherals: &BTreeMap<String, Peripheral>) -> String {
+    let mut out = String::new();
+    let mut names = BTreeSet::new();
+
+    writeln!(
+        out,
+        "//! Peripheral addresses generated by idf2svd, do not edit"
+    )
+    .unwrap();
+
+    for (name, p) in peripherals {
+        if p.address == 0 && p.registers.is_empty() {
+            continue;
+        }
+        writeln!(out).unwrap();
+        writeln!(out, "// {}", name).unwrap();
+        writeln!(
+            out,
+            "pub const {}_BASE: u32 = {};",
+            name,
+            address(p.address)
+        )
+        .unwrap();
+        names.insert(format!("{}_BASE", name));
+        for (i, instance) in p.instance_addresses.iter().enumerate() {
+            let const_name = format!("{}{}_BASE", name, i + 1);
+            writeln!(
+                out,
+                "pub const {}: u32 = {};",
+                const_name,
+                address(*instance)
+            )
+            .unwrap();
+            names.insert(const_name);
+        }
+
+        for r in &p.registers {
+            let const_name = if r.name.starts_with(&format!("{}_", name)) {
+                format!("{}_OFFSET", r.name)
+            } else {
+                format!("{}_{}_OFFSET", name, r.name)
+            };
+            if !names.insert(const_name.clone()) {
+                writeln!(out, "// duplicate: {} = {:#X}", const_name, r.address).unwrap();
+                continue;
+            }
+            writeln!(out, "pub const {}: u32 = {:#X};", const_name, r.address).unwrap();
+
+            // a single full width value has no field worth naming
+            for f in r.bit_fields.iter().filter(|f| !f.origin.is_inferred()) {
+                let prefixed = if f.name.starts_with(&format!("{}_", name)) {
+                    f.name.clone()
+                } else {
+                    format!("{}_{}", name, f.name)
+                };
+                let candidates = [prefixed, format!("{}_{}", r.name, f.name)];
+                let field_name = candidates.iter().find(|c| {
+                    !names.contains(&format!("{}_SHIFT", c))
+                        && !names.contains(&format!("{}_MASK", c))
+                });
+                let field_name = match field_name {
+                    Some(field_name) => field_name,
+                    None => {
+                        writeln!(out, "// duplicate: {}.{}", r.name, f.name).unwrap();
+                        continue;
+                    }
+                };
+                names.insert(format!("{}_SHIFT", field_name));
+                names.insert(format!("{}_MASK", field_name));
+                writeln!(
+                    out,
+                    "pub const {}_SHIFT: u32 = {};",
+                    field_name,
+                    f.bits.lsb()
+                )
+                .unwrap();
+                writeln!(
+                    out,
+                    "pub const {}_MASK: u32 = {:#X};",
+                    field_name,
+                    field_mask(f)
+                )
+                .unwrap();
+            }
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Bits, Origin, Register};
+
+    #[test]
+    fn test_field_consts() {
+        let field = |name: &str, bits| BitField {
+            name: name.to_string(),
+            bits,
+            origin: Origin::HeaderComment,
+            ..Default::default()
+        };
+        let register = |name: &str, address, bit_fields| Register {
+            name: name.to_string(),
+            address,
+            bit_fields,
+            ..Default::default()
+        };
+        let mut peripherals = BTreeMap::new();
+        peripherals.insert(
+            "UART".to_string(),
+            Peripheral {
+                address: 0x3ff4_0000,
+                registers: vec![
+                    register(
+                        "UART_CONF0",
+                        0x20,
+                        vec![
+                            field("UART_PARITY_EN", Bits::Single(1)),
+                            field("EN", Bits::Range(4..=31)),
+                        ],
+                    ),
+                    register(
+                        "UART_CONF1",
+                        0x24,
+                        vec![field("EN", Bits::Single(0)), BitField::default()],
+                    ),
+                ],
+                ..Default::default()
+            },
+        );
+
+        let consts = generate_consts(&peripherals);
+        assert!(consts.contains(
+            "pub const UART_BASE: u32 = 0x3FF4_0000;
+"
+        ));
+        assert!(consts.contains(
+            "pub const UART_PARITY_EN_SHIFT: u32 = 1;
+"
+        ));
+        assert!(consts.contains(
+            "pub const UART_PARITY_EN_MASK: u32 = 0x2;
+"
+        ));
+        assert!(consts.contains(
+            "pub const UART_EN_MASK: u32 = 0xFFFFFFF0;
+"
+        ));
+        assert!(consts.contains(
+            "pub const UART_CONF1_EN_MASK: u32 = 0x1;
+"
+        ));
+        assert_eq!(consts.matches("_MASK").count(), 3);
+    }
+}
@@ -0,0 +1,429 @@
+//! Semantic differences between two SVD files: added, removed and moved peripherals,
+//! registers and fields, independent of formatting, element order and descriptions.
+
+use crate::tables::parse_value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use xmltree::Element;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SvdSummary {
+    pub peripherals: BTreeMap<String, PeripheralSummary>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PeripheralSummary {
+    pub base_address: u64,
+    /// Registers by name, registers in clusters are named `CLUSTER.REGISTER`
+    pub registers: BTreeMap<String, RegisterSummary>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RegisterSummary {
+    /// Offset from the peripheral base address
+    pub offset: u64,
+    pub fields: BTreeMap<String, FieldSummary>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FieldSummary {
+    pub lsb: u64,
+    pub width: u64,
+    pub access: Option<String>,
+}
+
+impl fmt::Display for FieldSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.width == 1 {
+            write!(f, "[{}]", self.lsb)
+        } else {
+            write!(f, "[{}:{}]", self.lsb + self.width - 1, self.lsb)
+        }
+    }
+}
+
+pub(crate) fn child_text<'a>(element: &'a Element, name: &str) -> Option<&'a str> {
+    element
+        .get_child(name)
+        .and_then(|c| c.text.as_deref())
+        .map(str::trim)
+}
+
+pub(crate) fn child_value(element: &Element, name: &str) -> Result<Option<u64>, String> {
+    match child_text(element, name) {
+        Some(text) => parse_value(text)
+            .map(Some)
+            .ok_or_else(|| format!("invalid {} {}", name, text)),
+        None => Ok(None),
+    }
+}
+
+pub(crate) fn children<'a>(
+    element: &'a Element,
+    name: &'a str,
+) -> impl Iterator<Item = &'a Element> {
+    element
+        .get_child(name)
+        .into_iter()
+        .flat_map(move |c| c.children.iter())
+}
+
+pub(crate) fn name_of(element: &Element) -> Result<String, String> {
+    child_text(element, "name")
+        .map(str::to_string)
+        .ok_or_else(|| format!("{} without name", element.name))
+}
+
+pub(crate) fn read_field(field: &Element) -> Result<FieldSummary, String> {
+    let (lsb, width) = if let Some(range) = child_text(field, "bitRange") {
+        let mut bits = range
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .split(':')
+            .map(|b| b.trim().parse::<u64>());
+        match (bits.next(), bits.next()) {
+            (Some(Ok(msb)), Some(Ok(lsb))) if msb >= lsb => (lsb, msb - lsb + 1),
+            _ => return Err(format!("invalid bitRange {}", range)),
+        }
+    } else if let Some(lsb) = child_value(field, "lsb")? {
+        let msb = child_value(field, "msb")?.unwrap_or(lsb);
+        (lsb, msb.saturating_sub(lsb) + 1)
+    } else {
+        (
+            child_value(field, "bitOffset")?.unwrap_or(0),
+            child_value(field, "bitWidth")?.unwrap_or(1),
+        )
+    };
+    Ok(FieldSummary {
+        lsb,
+        width,
+        access: child_text(field, "access").map(str::to_string),
+    })
+}
+
+/// Add the registers of a `registers` or `cluster` element, prefixing cluster members
+fn read_registers(
+    registers: &mut BTreeMap<String, RegisterSummary>,
+    parent: &Element,
+    prefix: &str,
+    base: u64,
+) -> Result<(), String> {
+    for element in &parent.children {
+        let name = format!("{}{}", prefix, name_of(element)?);
+        let offset = base + child_value(element, "addressOffset")?.unwrap_or(0);
+        match element.name.as_str() {
+            "register" => {
+                let mut fields = BTreeMap::new();
+                for field in children(element, "fields") {
+                    let summary = read_field(field).map_err(|e| format!("{}.{}", name, e))?;
+                    fields.insert(name_of(field)?, summary);
+                }
+                registers.insert(name, RegisterSummary { offset, fields });
+            }
+            "cluster" => read_registers(registers, element, &format!("{}.", name), offset)?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Summarize the peripherals, registers and fields of an SVD document
+pub fn parse_svd(xml: &str) -> Result<SvdSummary, String> {
+    let device = Element::parse(xml.as_bytes()).map_err(|e| e.to_string())?;
+    let mut summary = SvdSummary::default();
+    for peripheral in children(&device, "peripherals") {
+        let name = name_of(peripheral)?;
+        let mut registers = BTreeMap::new();
+        if let Some(element) = peripheral.get_child("registers") {
+            read_registers(&mut registers, element, "", 0)
+                .map_err(|e| format!("{}: {}", name, e))?;
+        }
+        let p = PeripheralSummary {
+            base_address: child_value(peripheral, "baseAddress")?.unwrap_or(0),
+            registers,
+        };
+        summary.peripherals.insert(name, p);
+    }
+    Ok(summary)
+}
+
+/// Load and summarize an SVD file
+pub fn read_svd(path: &Path) -> Result<SvdSummary, String> {
+    let xml = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    parse_svd(&xml).map_err(|e| format!("Invalid svd {}: {}", path.display(), e))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    PeripheralAdded {
+        peripheral: String,
+    },
+    PeripheralRemoved {
+        peripheral: String,
+    },
+    BaseAddressChanged {
+        peripheral: String,
+        old: u64,
+        new: u64,
+    },
+    RegisterAdded {
+        peripheral: String,
+        register: String,
+    },
+    RegisterRemoved {
+        peripheral: String,
+        register: String,
+    },
+    RegisterMoved {
+        peripheral: String,
+        register: String,
+        old: u64,
+        new: u64,
+    },
+    FieldAdded {
+        peripheral: String,
+        register: String,
+        field: String,
+        bits: FieldSummary,
+    },
+    FieldRemoved {
+        peripheral: String,
+        register: String,
+        field: String,
+    },
+    FieldChanged {
+        peripheral: String,
+        register: String,
+        field: String,
+        old: FieldSummary,
+        new: FieldSummary,
+    },
+}
+
+impl Change {
+    /// Peripheral the change belongs to
+    pub fn peripheral(&self) -> &str {
+        match self {
+            Change::PeripheralAdded { peripheral }
+            | Change::PeripheralRemoved { peripheral }
+            | Change::BaseAddressChanged { peripheral, .. }
+            | Change::RegisterAdded { peripheral, .. }
+            | Change::RegisterRemoved { peripheral, .. }
+            | Change::RegisterMoved { peripheral, .. }
+            | Change::FieldAdded { peripheral, .. }
+            | Change::FieldRemoved { peripheral, .. }
+            | Change::FieldChanged { peripheral, .. } => peripheral,
+        }
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Change::PeripheralAdded { peripheral } => write!(f, "added {}", peripheral),
+            Change::PeripheralRemoved { peripheral } => write!(f, "removed {}", peripheral),
+            Change::BaseAddressChanged {
+                peripheral,
+                old,
+                new,
+            } => write!(
+                f,
+                "base address of {} moved from {:#x} to {:#x}",
+                peripheral, old, new
+            ),
+            Change::RegisterAdded {
+                peripheral,
+                register,
+            } => write!(f, "added {}.{}", peripheral, register),
+            Change::RegisterRemoved {
+                peripheral,
+                register,
+            } => write!(f, "removed {}.{}", peripheral, register),
+            Change::RegisterMoved {
+                peripheral,
+                register,
+                old,
+                new,
+            } => write!(
+                f,
+                "{}.{} moved from offset {:#x} to {:#x}",
+                peripheral, register, old, new
+            ),
+            Change::FieldAdded {
+                peripheral,
+                register,
+                field,
+                bits,
+            } => write!(f, "added {}.{}.{} {}", peripheral, register, field, bits),
+            Change::FieldRemoved {
+                peripheral,
+                register,
+                field,
+            } => write!(f, "removed {}.{}.{}", peripheral, register, field),
+            Change::FieldChanged {
+                peripheral,
+                register,
+                field,
+                old,
+                new,
+            } => {
+                write!(f, "{}.{}.{} changed from ", peripheral, register, field)?;
+                let access = |s: &FieldSummary| s.access.clone().unwrap_or_default();
+                if old.access == new.access {
+                    write!(f, "{} to {}", old, new)
+                } else {
+                    write!(f, "{} {} to {} {}", old, access(old), new, access(new))
+                }
+            }
+        }
+    }
+}
+
+fn diff_register(
+    changes: &mut Vec<Change>,
+    peripheral: &str,
+    register: &str,
+    old: &RegisterSummary,
+    new: &RegisterSummary,
+) {
+    if old.offset != new.offset {
+        changes.push(Change::RegisterMoved {
+            peripheral: peripheral.to_string(),
+            register: register.to_string(),
+            old: old.offset,
+            new: new.offset,
+        });
+    }
+    for (field, old_field) in &old.fields {
+        match new.fields.get(field) {
+            None => changes.push(Change::FieldRemoved {
+                peripheral: peripheral.to_string(),
+                register: register.to_string(),
+                field: field.clone(),
+            }),
+            Some(new_field) if new_field != old_field => changes.push(Change::FieldChanged {
+                peripheral: peripheral.to_string(),
+                register: register.to_string(),
+                field: field.clone(),
+                old: old_field.clone(),
+                new: new_field.clone(),
+            }),
+            Some(_) => {}
+        }
+    }
+    for (field, new_field) in &new.fields {
+        if !old.fields.contains_key(field) {
+            changes.push(Change::FieldAdded {
+                peripheral: peripheral.to_string(),
+                register: register.to_string(),
+                field: field.clone(),
+                bits: new_field.clone(),
+            });
+        }
+    }
+}
+
+/// Changes between `old` and `new`, ordered by peripheral
+pub fn diff(old: &SvdSummary, new: &SvdSummary) -> Vec<Change> {
+    let mut changes = vec![];
+    let names: std::collections::BTreeSet<&String> = old
+        .peripherals
+        .keys()
+        .chain(new.peripherals.keys())
+        .collect();
+
+    for name in names {
+        let (old_p, new_p) = match (old.peripherals.get(name), new.peripherals.get(name)) {
+            (Some(old_p), Some(new_p)) => (old_p, new_p),
+            (Some(_), None) => {
+                changes.push(Change::PeripheralRemoved {
+                    peripheral: name.clone(),
+                });
+                continue;
+            }
+            _ => {
+                changes.push(Change::PeripheralAdded {
+                    peripheral: name.clone(),
+                });
+                continue;
+            }
+        };
+
+        if old_p.base_address != new_p.base_address {
+            changes.push(Change::BaseAddressChanged {
+                peripheral: name.clone(),
+                old: old_p.base_address,
+                new: new_p.base_address,
+            });
+        }
+        for (register, old_r) in &old_p.registers {
+            match new_p.registers.get(register) {
+                Some(new_r) => diff_register(&mut changes, name, register, old_r, new_r),
+                None => changes.push(Change::RegisterRemoved {
+                    peripheral: name.clone(),
+                    register: register.clone(),
+                }),
+            }
+        }
+        for register in new_p.registers.keys() {
+            if !old_p.registers.contains_key(register) {
+                changes.push(Change::RegisterAdded {
+                    peripheral: name.clone(),
+                    register: register.clone(),
+                });
+            }
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn svd(peripherals: &str) -> SvdSummary {
+        parse_svd(&format!(
+            "<device><name>esp32</name><peripherals>{}</peripherals></device>",
+            peripherals
+        ))
+        .unwrap()
+    }
+
+    const UART: &str = "<peripheral><name>UART</name><baseAddress>0x3FF40000</baseAddress>
+        <registers><register><name>CONF0</name><addressOffset>0x20</addressOffset><fields>
+        <field><name>PARITY</name><bitOffset>0</bitOffset><bitWidth>1</bitWidth></field>
+        <field><name>BIT_NUM</name><bitRange>[3:2]</bitRange><access>read-write</access></field>
+        </fields></register></registers></peripheral>";
+
+    #[test]
+    fn test_parse() {
+        let uart = &svd(UART).peripherals["UART"];
+        assert_eq!(uart.base_address, 0x3FF4_0000);
+        let conf0 = &uart.registers["CONF0"];
+        assert_eq!(conf0.offset, 0x20);
+        assert_eq!(conf0.fields["BIT_NUM"].lsb, 2);
+        assert_eq!(conf0.fields["BIT_NUM"].width, 2);
+        assert_eq!(conf0.fields["PARITY"].to_string(), "[0]");
+    }
+
+    #[test]
+    fn test_diff() {
+        let changed = UART
+            .replace("<bitRange>[3:2]</bitRange>", "<bitRange>[4:2]</bitRange>")
+            .replace("<name>PARITY</name>", "<name>PARITY_EN</name>");
+        let rmt = "<peripheral><name>RMT</name><baseAddress>0x3FF56000</baseAddress></peripheral>";
+        let changes = diff(&svd(UART), &svd(&format!("{}{}", changed, rmt)));
+        let changes: Vec<String> = changes.iter().map(Change::to_string).collect();
+        assert_eq!(
+            changes,
+            vec![
+                "added RMT",
+                "UART.CONF0.BIT_NUM changed from [3:2] to [4:2]",
+                "removed UART.CONF0.PARITY",
+                "added UART.CONF0.PARITY_EN [0]",
+            ]
+        );
+    }
+}
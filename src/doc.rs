@@ -0,0 +1,481 @@
+//! Register documentation in JSON form, for peripherals the headers don't describe (well).
+//! One file describes one peripheral
+//!
+//! ```json
+//! {
+//!   "peripheral": "TIMER",
+//!   "address": 1610614272,
+//!   "description": "FRC1 and FRC2 timers",
+//!   "version": "TRM v1.7",
+//!   "registers": [
+//!     {
+//!       "name": "FRC1_LOAD",
+//!       "offset": 0,
+//!       "description": "the load value into the counter",
+//!       "fields": [
+//!         { "name": "FRC1_LOAD_VALUE", "bits": "22:0", "access": "RW", "reset": 0 }
+//!       ]
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! Numbers can also be given as strings in hex (`"0x60000600"`), verilog (`"23'h0"`) or
+//! decimal notation.
+//!
+//! The format is described by the JSON schema in [`SCHEMA`] (`header2svd doc schema`), files
+//! are checked against it when loaded.
+
+use crate::merge::{merge_documented, MergeRule};
+use crate::safety::Safety;
+use crate::tables::parse_value;
+use crate::{BitField, Bits, Origin, Peripheral, Provenance, Register, Type};
+use serde::de::{self, Deserializer, Visitor};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// JSON schema of the documentation format
+pub const SCHEMA: &str = include_str!("doc.schema.json");
+
+struct NumberVisitor;
+
+impl<'de> Visitor<'de> for NumberVisitor {
+    type Value = u64;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a non-negative integer or a string such as \"0x15\"")
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<u64, E> {
+        Ok(v)
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<u64, E> {
+        u64::try_from(v).map_err(|_| E::custom(format!("negative number {}", v)))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<u64, E> {
+        parse_value(v).ok_or_else(|| E::custom(format!("invalid number {:?}", v)))
+    }
+}
+
+/// A number given as an integer or as a string in any notation `parse_value` understands
+fn number<'de, D: Deserializer<'de>, T: TryFrom<u64>>(d: D) -> Result<T, D::Error> {
+    let value = d.deserialize_any(NumberVisitor)?;
+    T::try_from(value).map_err(|_| de::Error::custom(format!("{:#x} is out of range", value)))
+}
+
+fn optional_number<'de, D: Deserializer<'de>, T: TryFrom<u64>>(
+    d: D,
+) -> Result<Option<T>, D::Error> {
+    number(d).map(Some)
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DocFile {
+    pub peripheral: String,
+    /// Base address, keeps the address found in the headers when absent
+    #[serde(
+        default,
+        deserialize_with = "optional_number",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub address: Option<u32>,
+    /// Description of the peripheral, replaces the header name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Documented revision of the block, e.g. of the manual describing it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    pub registers: Vec<DocRegister>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DocRegister {
+    pub name: String,
+    /// Offset from the peripheral base address
+    #[serde(deserialize_with = "number")]
+    pub offset: u32,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    #[serde(
+        default,
+        deserialize_with = "optional_number",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub reset: Option<u64>,
+    /// Bits with a defined reset value, all when absent
+    #[serde(
+        default,
+        deserialize_with = "optional_number",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub reset_mask: Option<u32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<DocField>,
+    /// The fields describe every bit of the register, reserved bits included
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub complete: bool,
+    /// Set for registers that must not be accessed by ordinary code
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub safety: Option<Safety>,
+    /// Width in bits, 8, 16 or 32, 32 when absent
+    #[serde(
+        default,
+        deserialize_with = "optional_number",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub size: Option<u8>,
+    /// `RO`, `RW` or `WO`, the access of fields without one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub access: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DocField {
+    pub name: String,
+    /// `msb:lsb` or a single bit
+    pub bits: String,
+    /// `RO`, `RW` or `WO`, read-write when absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub access: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "optional_number",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub reset: Option<u32>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+}
+
+/// Parse a `msb:lsb` or single bit position, with or without brackets
+pub fn parse_bits(bits: &str) -> Option<Bits> {
+    let bits = bits.trim().trim_start_matches('[').trim_end_matches(']');
+    let mut parts = bits.split(':').map(|b| b.trim().parse::<u8>());
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(Ok(msb)), Some(Ok(lsb)), None) if msb > lsb => Some(Bits::Range(lsb..=msb)),
+        (Some(Ok(msb)), Some(Ok(lsb)), None) if msb == lsb => Some(Bits::Single(lsb)),
+        (Some(Ok(bit)), None, None) => Some(Bits::Single(bit)),
+        _ => None,
+    }
+}
+
+impl DocFile {
+    /// Check the constraints the types don't express, returning a message per problem found,
+    /// prefixed with the register and field it concerns
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = vec![];
+        if self.peripheral.is_empty() {
+            errors.push(String::from("peripheral: name is empty"));
+        }
+        for (i, r) in self.registers.iter().enumerate() {
+            let record = format!("registers[{}] ({})", i, r.name);
+            if r.name.is_empty() {
+                errors.push(format!("{}: name is empty", record));
+            } else if self.registers[..i].iter().any(|o| o.name == r.name) {
+                errors.push(format!("{}: duplicate register name", record));
+            }
+            if r.offset % 4 != 0 {
+                errors.push(format!(
+                    "{}: offset {:#x} is not word aligned",
+                    record, r.offset
+                ));
+            }
+            if let Some(size) = r.size {
+                if ![8, 16, 32].contains(&size) {
+                    errors.push(format!("{}: size {} is not 8, 16 or 32", record, size));
+                }
+            }
+            if let Some(access) = &r.access {
+                if let Err(e) = Type::from_str(access) {
+                    errors.push(format!("{}: {}", record, e));
+                }
+            }
+            if let Some(reset) = r.reset {
+                if reset > u64::from(u32::MAX) {
+                    errors.push(format!(
+                        "{}: reset {:#x} is wider than 32 bits",
+                        record, reset
+                    ));
+                }
+            }
+
+            let mut bits_seen: Vec<(&str, Bits)> = vec![];
+            for (j, f) in r.fields.iter().enumerate() {
+                let record = format!("{}.fields[{}] ({})", record, j, f.name);
+                if f.name.is_empty() {
+                    errors.push(format!("{}: name is empty", record));
+                } else if r.fields[..j].iter().any(|o| o.name == f.name) {
+                    errors.push(format!("{}: duplicate field name", record));
+                }
+                if let Some(access) = &f.access {
+                    if let Err(e) = Type::from_str(access) {
+                        errors.push(format!("{}: {}", record, e));
+                    }
+                }
+                let bits = match parse_bits(&f.bits) {
+                    Some(bits) if u32::from(bits.msb()) < u32::from(r.size.unwrap_or(32)) => bits,
+                    _ => {
+                        errors.push(format!("{}: invalid bits {:?}", record, f.bits));
+                        continue;
+                    }
+                };
+                let width = u32::from(bits.msb() - bits.lsb()) + 1;
+                if let Some(reset) = f.reset {
+                    if width < 32 && reset >> width != 0 {
+                        errors.push(format!(
+                            "{}: reset {:#x} doesn't fit in {} bits",
+                            record, reset, width
+                        ));
+                    }
+                }
+                if let Some((other, _)) = bits_seen.iter().find(|(_, b)| b.overlaps(&bits)) {
+                    errors.push(format!("{}: bits {} overlap {}", record, f.bits, other));
+                }
+                bits_seen.push((&f.name, bits));
+            }
+        }
+        errors
+    }
+
+    /// Convert into the model, returning the peripheral name and peripheral. `file` is
+    /// recorded as the provenance of the registers and fields
+    pub fn into_peripheral(self, file: &str) -> Result<(String, Peripheral), String> {
+        let provenance = vec![Provenance::Doc {
+            file: file.to_string(),
+        }];
+        let mut registers = vec![];
+        for r in self.registers {
+            let access = match &r.access {
+                Some(access) => Some(Type::from_str(access)?),
+                None => None,
+            };
+            let mut bit_fields = vec![];
+            for f in &r.fields {
+                let bits = parse_bits(&f.bits)
+                    .ok_or_else(|| format!("{}.{}: invalid bits {}", r.name, f.name, f.bits))?;
+                let type_ = match &f.access {
+                    Some(access) => Type::from_str(access)?,
+                    None => access.unwrap_or_default(),
+                };
+                bit_fields.push(BitField {
+                    name: f.name.clone(),
+                    bits,
+                    type_,
+                    reset_value: f.reset.unwrap_or(0),
+                    description: f.description.clone(),
+                    origin: Origin::Doc,
+                    provenance: provenance.clone(),
+                    ..Default::default()
+                });
+            }
+            bit_fields.sort_by_key(|f| f.bits.lsb());
+            registers.push(Register {
+                description: if r.description.is_empty() {
+                    r.name.clone()
+                } else {
+                    r.description
+                },
+                name: r.name,
+                address: r.offset,
+                width: r.size.unwrap_or(0),
+                access,
+                reset_value: r.reset.unwrap_or(0),
+                reset_mask: r.reset_mask,
+                bit_fields,
+                complete: r.complete,
+                safety: r.safety,
+                provenance: provenance.clone(),
+                ..Default::default()
+            });
+        }
+        registers.sort_by_key(|r| r.address);
+
+        let p = Peripheral {
+            description: match self.description {
+                Some(description) => description,
+                None => self.peripheral.clone(),
+            },
+            version: self.version,
+            address: self.address.unwrap_or(0),
+            registers,
+            provenance: if self.address.is_some() {
+                provenance
+            } else {
+                vec![]
+            },
+            ..Default::default()
+        };
+        Ok((self.peripheral, p))
+    }
+}
+
+/// Load and validate a documentation file
+pub fn parse_doc(path: &Path) -> Result<DocFile, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let doc: DocFile = serde_json::from_str(&text)
+        .map_err(|e| format!("Invalid doc {}: {}", path.display(), e))?;
+    let errors = doc.validate();
+    if !errors.is_empty() {
+        return Err(format!(
+            "Invalid doc {}:\n  {}",
+            path.display(),
+            errors.join("\n  ")
+        ));
+    }
+    Ok(doc)
+}
+
+/// Merge the documented peripheral into the model, using the strategies of `rules` for
+/// registers also found in the headers. `file` is the documentation file they are recorded to
+/// come from. Returns the conflicts with the headers resolved by the strategies
+pub fn merge_doc(
+    peripherals: &mut BTreeMap<String, Peripheral>,
+    doc: DocFile,
+    file: &str,
+    rules: &[MergeRule],
+) -> Result<Vec<String>, String> {
+    let (name, p) = doc.into_peripheral(file)?;
+    match peripherals.get_mut(&name) {
+        Some(existing) => merge_documented(&name, existing, p, rules),
+        None => {
+            peripherals.insert(name, p);
+            Ok(vec![])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(name: &str, bits: &str) -> DocField {
+        DocField {
+            name: name.to_string(),
+            bits: bits.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn doc(fields: Vec<DocField>) -> DocFile {
+        DocFile {
+            peripheral: "TIMER".to_string(),
+            registers: vec![DocRegister {
+                name: "CTRL".to_string(),
+                offset: 8,
+                fields,
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_valid() {
+        let doc = doc(vec![field("ENABLE", "7"), field("DIV", "[3:2]")]);
+        assert!(doc.validate().is_empty());
+    }
+
+    #[test]
+    fn test_field_errors() {
+        let mut reset = field("DIV", "3:2");
+        reset.reset = Some(4);
+        let doc = doc(vec![field("ENABLE", "2"), reset, field("HIGH", "32")]);
+        assert_eq!(
+            doc.validate(),
+            vec![
+                "registers[0] (CTRL).fields[1] (DIV): reset 0x4 doesn't fit in 2 bits",
+                "registers[0] (CTRL).fields[1] (DIV): bits 3:2 overlap ENABLE",
+                "registers[0] (CTRL).fields[2] (HIGH): invalid bits \"32\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_register_size_and_access() {
+        let mut doc = doc(vec![field("DIV", "7:0"), field("HIGH", "15")]);
+        doc.registers[0].size = Some(8);
+        doc.registers[0].access = Some("RO".to_string());
+        assert_eq!(
+            doc.validate(),
+            vec!["registers[0] (CTRL).fields[1] (HIGH): invalid bits \"15\""]
+        );
+        doc.registers[0].fields.pop();
+        doc.registers[0].fields[0].access = None;
+        let (_, p) = doc.clone().into_peripheral("timer.json").unwrap();
+        assert_eq!(p.registers[0].width, 8);
+        assert_eq!(p.registers[0].access, Some(Type::ReadOnly));
+        assert_eq!(p.registers[0].bit_fields[0].type_, Type::ReadOnly);
+
+        doc.registers[0].size = Some(12);
+        assert_eq!(
+            doc.validate(),
+            vec!["registers[0] (CTRL): size 12 is not 8, 16 or 32"]
+        );
+    }
+
+    #[test]
+    fn test_numbers() {
+        let json = r#"{ "peripheral": "TIMER", "address": "0x60000600", "registers": [
+            { "name": "CTRL", "offset": 8, "reset": "0x15", "reset_mask": "0xff",
+              "fields": [{ "name": "DIV", "bits": "3:2", "reset": "2'b01" }] }
+        ] }"#;
+        let doc: DocFile = serde_json::from_str(json).unwrap();
+        assert_eq!(doc.address, Some(0x6000_0600));
+        assert_eq!(doc.registers[0].reset, Some(0x15));
+        assert_eq!(doc.registers[0].reset_mask, Some(0xff));
+        assert_eq!(doc.registers[0].fields[0].reset, Some(1));
+
+        let invalid = |json: &str| {
+            serde_json::from_str::<DocFile>(json)
+                .unwrap_err()
+                .to_string()
+        };
+        assert!(
+            invalid(r#"{ "peripheral": "T", "address": "0x1g", "registers": [] }"#)
+                .starts_with("invalid number \"0x1g\"")
+        );
+        assert!(
+            invalid(r#"{ "peripheral": "T", "address": 4294967296, "registers": [] }"#)
+                .starts_with("0x100000000 is out of range")
+        );
+    }
+
+    #[test]
+    fn test_peripheral_metadata() {
+        let json = r#"{ "peripheral": "TIMER", "description": "FRC1 and FRC2 timers",
+            "version": "TRM v1.7", "registers": [] }"#;
+        let doc: DocFile = serde_json::from_str(json).unwrap();
+        let mut peripherals = BTreeMap::new();
+        peripherals.insert(
+            "TIMER".to_string(),
+            Peripheral {
+                description: "TIMER".to_string(),
+                ..Default::default()
+            },
+        );
+        merge_doc(&mut peripherals, doc, "timer.json", &[]).unwrap();
+        assert_eq!(peripherals["TIMER"].description, "FRC1 and FRC2 timers");
+        assert_eq!(peripherals["TIMER"].version.as_deref(), Some("TRM v1.7"));
+
+        let doc: DocFile =
+            serde_json::from_str(r#"{ "peripheral": "TIMER", "registers": [] }"#).unwrap();
+        merge_doc(&mut peripherals, doc, "regs.json", &[]).unwrap();
+        assert_eq!(peripherals["TIMER"].description, "FRC1 and FRC2 timers");
+    }
+
+    #[test]
+    fn test_unknown_key() {
+        let json = r#"{ "peripheral": "TIMER", "registers": [], "adress": 0 }"#;
+        assert!(serde_json::from_str::<DocFile>(json).is_err());
+    }
+}
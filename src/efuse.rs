@@ -0,0 +1,139 @@
+//! The efuse read registers of the ESP8266, four words in the DPORT block which no SDK header
+//! defines. The layout is the one esptool reads the MAC address, chip id and ESP8285 flags from.
+
+use crate::{BitField, Bits, Origin, Peripheral, Provenance, Register, Type};
+
+/// Name of the efuse peripheral
+pub const PERIPHERAL: &str = "EFUSE";
+
+/// Address of the first efuse word
+pub const ADDRESS: u32 = 0x3ff0_0050;
+
+/// Address of the ESP8266 DPORT block, the efuse words are added to chips with it
+pub const DPORT_ADDRESS: u32 = 0x3ff0_0000;
+
+/// Registers: name, offset, description
+const REGISTERS: &[(&str, u32, &str)] = &[
+    ("EFUSE_DATA0", 0x0, "Efuse bits 31:0"),
+    ("EFUSE_DATA1", 0x4, "Efuse bits 63:32"),
+    ("EFUSE_DATA2", 0x8, "Efuse bits 95:64"),
+    ("EFUSE_DATA3", 0xc, "Efuse bits 127:96"),
+];
+
+/// Fields: register, field, lsb, msb, description
+const FIELDS: &[(&str, &str, u8, u8, &str)] = &[
+    (
+        "EFUSE_DATA0",
+        "EFUSE_EMBEDDED_FLASH",
+        4,
+        4,
+        "Set on the ESP8285, which has embedded flash",
+    ),
+    (
+        "EFUSE_DATA0",
+        "EFUSE_CHIP_ID_LOW",
+        24,
+        31,
+        "Bits 7:0 of the chip id, the last byte of the MAC address",
+    ),
+    (
+        "EFUSE_DATA1",
+        "EFUSE_CHIP_ID_HIGH",
+        0,
+        23,
+        "Bits 31:8 of the chip id. Bits 15:0 are the fourth and fifth byte of the MAC address, \
+         bits 23:16 select its OUI when EFUSE_OUI is zero: 0 for 18:fe:34, 1 for ac:d0:74",
+    ),
+    (
+        "EFUSE_DATA2",
+        "EFUSE_EMBEDDED_FLASH2",
+        16,
+        16,
+        "Set on the ESP8285, which has embedded flash",
+    ),
+    (
+        "EFUSE_DATA3",
+        "EFUSE_OUI",
+        0,
+        23,
+        "OUI of the MAC address, zero when selected by EFUSE_CHIP_ID_HIGH",
+    ),
+    (
+        "EFUSE_DATA3",
+        "EFUSE_FLASH_SIZE",
+        25,
+        27,
+        "Size of the embedded flash of the ESP8285",
+    ),
+];
+
+/// The efuse peripheral with its curated registers and fields
+pub fn efuse_peripheral() -> Peripheral {
+    let registers = REGISTERS
+        .iter()
+        .map(|(name, offset, description)| Register {
+            name: name.to_string(),
+            description: description.to_string(),
+            address: *offset,
+            bit_fields: FIELDS
+                .iter()
+                .filter(|(register, ..)| register == name)
+                .map(|(_, field, lsb, msb, description)| BitField {
+                    name: field.to_string(),
+                    bits: if lsb == msb {
+                        Bits::Single(*lsb)
+                    } else {
+                        Bits::Range(*lsb..=*msb)
+                    },
+                    type_: Type::ReadOnly,
+                    description: description.to_string(),
+                    origin: Origin::Doc,
+                    provenance: vec![Provenance::Curated],
+                    ..Default::default()
+                })
+                .collect(),
+            provenance: vec![Provenance::Curated],
+            ..Default::default()
+        })
+        .collect();
+    Peripheral {
+        description: "Efuse read registers".to_string(),
+        address: ADDRESS,
+        registers,
+        provenance: vec![Provenance::Curated],
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_efuse_peripheral() {
+        let p = efuse_peripheral();
+        assert_eq!(p.address, 0x3ff0_0050);
+        assert_eq!(p.registers.len(), 4);
+        assert!(FIELDS
+            .iter()
+            .all(|(register, ..)| REGISTERS.iter().any(|(name, ..)| name == register)));
+        for r in &p.registers {
+            let mut used = 0u32;
+            for f in &r.bit_fields {
+                let width = u32::from(f.bits.msb() - f.bits.lsb()) + 1;
+                let mask = (((1u64 << width) - 1) << f.bits.lsb()) as u32;
+                assert_eq!(used & mask, 0, "{} overlaps in {}", f.name, r.name);
+                used |= mask;
+            }
+        }
+        let chip_id = &p.registers[1].bit_fields[0];
+        assert_eq!(
+            (
+                chip_id.name.as_str(),
+                chip_id.bits.lsb(),
+                chip_id.bits.msb()
+            ),
+            ("EFUSE_CHIP_ID_HIGH", 0, 23)
+        );
+    }
+}
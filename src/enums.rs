@@ -0,0 +1,341 @@
+//! Enumerated values of fields from the C enums of the SDK driver headers, such as the
+//! `uart_parity_t` of `uart_types.h`, whose values are the encodings of a register field. The
+//! `[[enum]]` entries of the config link an enum to the fields taking its values.
+
+use crate::{expr, Peripheral};
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumValue {
+    pub name: String,
+    pub value: u32,
+    pub description: Option<String>,
+}
+
+/// Enum of a header, with its values in the order they are defined
+#[derive(Debug, Clone, PartialEq)]
+pub struct CEnum {
+    /// The `enum` tag and `typedef` name, whichever are given
+    pub names: Vec<String>,
+    pub values: Vec<EnumValue>,
+}
+
+/// Fields taking the values of an enum
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EnumMapping {
+    /// Header defining the enum, relative to the SDK directory
+    pub header: PathBuf,
+    /// The `typedef` name or `enum` tag
+    #[serde(rename = "type")]
+    pub type_: String,
+    /// Fields as `PERIPHERAL.REGISTER.FIELD` with the names of the generated svd
+    pub fields: Vec<String>,
+}
+
+/// Text of a trailing comment, without the doxygen `!<` or `<` markers
+fn comment_text(comment: &str) -> Option<String> {
+    let text = comment
+        .trim_start_matches(&['/', '*', '!', '<'][..])
+        .trim_end_matches("*/")
+        .trim();
+    if text.is_empty() {
+        None
+    } else {
+        Some(text.to_string())
+    }
+}
+
+/// Values of the body of an enum. Values given by expressions are evaluated with the earlier
+/// values and `defines`, an enumerator that can't be is skipped along with the implicit ones
+/// following it
+fn parse_values(name: &str, body: &str, defines: &HashMap<String, String>) -> Vec<EnumValue> {
+    let mut values: Vec<EnumValue> = vec![];
+    let mut next = Some(0i64);
+    let mut in_comment = false;
+    for line in body.lines() {
+        let line = line.trim();
+        if in_comment {
+            in_comment = !line.contains("*/");
+            continue;
+        }
+        if line.starts_with('#') {
+            continue;
+        }
+        let (code, comment) = match line.find("/*").or_else(|| line.find("//")) {
+            Some(i) => {
+                in_comment = line[i..].starts_with("/*") && !line[i..].contains("*/");
+                (&line[..i], comment_text(&line[i..]))
+            }
+            None => (line, None),
+        };
+        let enumerators: Vec<&str> = code
+            .split(',')
+            .map(str::trim)
+            .filter(|e| !e.is_empty())
+            .collect();
+        for enumerator in &enumerators {
+            let mut parts = enumerator.splitn(2, '=');
+            let enumerator = parts.next().unwrap().trim();
+            let value = match parts.next() {
+                Some(value) => {
+                    let resolve = |id: &str| {
+                        values
+                            .iter()
+                            .find(|v| v.name == id)
+                            .map(|v| i64::from(v.value))
+                            .or_else(|| {
+                                let define = defines.get(id)?;
+                                expr::eval(define, &|_| None).ok()
+                            })
+                    };
+                    match expr::eval(value, &resolve) {
+                        Ok(value) => Some(value),
+                        Err(e) => {
+                            println!("Skipping {} of enum {}: {}", enumerator, name, e);
+                            None
+                        }
+                    }
+                }
+                None => next,
+            };
+            next = value.map(|v| v + 1);
+            if let Some(value) = value {
+                values.push(EnumValue {
+                    name: enumerator.to_string(),
+                    value: value as u32,
+                    // a comment is only clearly about the value when it is alone on the line
+                    description: comment.clone().filter(|_| enumerators.len() == 1),
+                });
+            }
+        }
+    }
+    values
+}
+
+/// All enums of a header
+pub fn parse_enums(text: &str, defines: &HashMap<String, String>) -> Vec<CEnum> {
+    let re_enum =
+        Regex::new(r"(?s)(?:typedef\s+)?\benum\s*(\w+)?\s*\{(.*?)\}\s*(\w+)?\s*;").unwrap();
+    re_enum
+        .captures_iter(text)
+        .map(|c| {
+            let names: Vec<String> = [c.get(1), c.get(3)]
+                .iter()
+                .flatten()
+                .map(|m| m.as_str().to_string())
+                .collect();
+            let name = names.first().cloned().unwrap_or_default();
+            CEnum {
+                values: parse_values(&name, &c[2], defines),
+                names,
+            }
+        })
+        .collect()
+}
+
+/// Strip the `_` separated prefix all values share, e.g. `UART_PARITY_` of
+/// `UART_PARITY_DISABLE` and `UART_PARITY_EVEN`, as long as no name is left empty or starting
+/// with a digit
+fn strip_common_prefix(values: &mut [EnumValue]) {
+    if values.len() < 2 {
+        return;
+    }
+    let first = values[0].name.clone();
+    let mut prefix = first.as_str();
+    while !prefix.is_empty() {
+        prefix = match prefix[..prefix.len() - 1].rfind('_') {
+            Some(i) => &first[..=i],
+            None => "",
+        };
+        let valid = values.iter().all(|v| {
+            v.name.starts_with(prefix)
+                && matches!(v.name[prefix.len()..].chars().next(), Some(c) if !c.is_ascii_digit())
+        });
+        if valid {
+            break;
+        }
+    }
+    for v in values.iter_mut() {
+        v.name = v.name[prefix.len()..].to_string();
+    }
+}
+
+/// Give the fields of `mappings` the values of their enum, read from the headers in `sdk_dir`.
+/// Sentinels such as `UART_DATA_BITS_MAX` and values too wide for a field are left out. Returns
+/// the number of fields given values
+pub fn apply_enums(
+    peripherals: &mut BTreeMap<String, Peripheral>,
+    sdk_dir: &Path,
+    mappings: &[EnumMapping],
+    defines: &HashMap<String, String>,
+) -> Result<usize, String> {
+    let mut applied = 0;
+    for mapping in mappings {
+        let path = sdk_dir.join(&mapping.header);
+        let text = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let mut values = parse_enums(&text, defines)
+            .into_iter()
+            .find(|e| e.names.contains(&mapping.type_))
+            .ok_or_else(|| format!("No enum {} in {}", mapping.type_, path.display()))?
+            .values;
+        values.retain(|v| !v.name.ends_with("_MAX"));
+        strip_common_prefix(&mut values);
+
+        for field in &mapping.fields {
+            let parts: Vec<&str> = field.split('.').collect();
+            let f = match parts.as_slice() {
+                [p, r, f] => peripherals
+                    .get_mut(*p)
+                    .and_then(|p| p.registers.iter_mut().find(|reg| reg.name == *r))
+                    .and_then(|r| r.bit_fields.iter_mut().find(|bf| bf.name == *f)),
+                _ => return Err(format!("Invalid enum field path {}", field)),
+            }
+            .ok_or_else(|| format!("No field {} for enum {}", field, mapping.type_))?;
+            let width = u32::from(f.bits.msb() - f.bits.lsb()) + 1;
+            let (fitting, wide): (Vec<EnumValue>, Vec<EnumValue>) = values
+                .iter()
+                .cloned()
+                .partition(|v| u64::from(v.value) < 1u64 << width);
+            for v in wide {
+                println!(
+                    "Leaving {} = {} out of {}, too wide",
+                    v.name, v.value, field
+                );
+            }
+            f.enumerated_values = fitting;
+            applied += 1;
+        }
+    }
+    Ok(applied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BitField, Bits, Register};
+
+    const UART_TYPES: &str = r#"
+/**
+ * @brief UART parity constants
+ */
+typedef enum {
+    UART_PARITY_DISABLE  = 0x0,  /*!< Disable UART parity*/
+    UART_PARITY_EVEN     = 0x2,  /*!< Enable UART even parity*/
+    UART_PARITY_ODD      = 0x3   /*!< Enable UART odd parity*/
+} uart_parity_t;
+
+typedef enum uart_word_length {
+    UART_DATA_5_BITS = 0x0,    /*!< word length: 5bits*/
+    UART_DATA_6_BITS,          /*!< word length: 6bits*/
+    UART_DATA_7_BITS,          /* word length: 7bits, continued
+                                  on the next line */
+    UART_DATA_8_BITS = UART_DATA_7_BITS + 1,
+#if SOC_UART_SUPPORT_9_BITS
+    UART_DATA_9_BITS,
+#endif
+    UART_DATA_BITS_MAX = UART_BITS_MAX,
+} uart_word_length_t;
+
+enum { A, B = UNKNOWN, C };
+"#;
+
+    #[test]
+    fn test_parse_enums() {
+        let mut defines = HashMap::new();
+        defines.insert("UART_BITS_MAX".to_string(), "(0x4)".to_string());
+        let enums = parse_enums(UART_TYPES, &defines);
+        assert_eq!(enums.len(), 3);
+        assert_eq!(enums[0].names, vec!["uart_parity_t"]);
+        assert_eq!(
+            enums[0].values[1],
+            EnumValue {
+                name: "UART_PARITY_EVEN".to_string(),
+                value: 2,
+                description: Some("Enable UART even parity".to_string()),
+            }
+        );
+        assert_eq!(
+            enums[1].names,
+            vec!["uart_word_length", "uart_word_length_t"]
+        );
+        let values: Vec<(&str, u32, Option<&str>)> = enums[1]
+            .values
+            .iter()
+            .map(|v| (v.name.as_str(), v.value, v.description.as_deref()))
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                ("UART_DATA_5_BITS", 0, Some("word length: 5bits")),
+                ("UART_DATA_6_BITS", 1, Some("word length: 6bits")),
+                ("UART_DATA_7_BITS", 2, Some("word length: 7bits, continued")),
+                ("UART_DATA_8_BITS", 3, None),
+                ("UART_DATA_9_BITS", 4, None),
+                ("UART_DATA_BITS_MAX", 4, None),
+            ]
+        );
+        let values: Vec<&str> = enums[2].values.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(values, vec!["A"]);
+
+        let mut values = enums[1].values.clone();
+        strip_common_prefix(&mut values);
+        assert_eq!(values[0].name, "DATA_5_BITS");
+    }
+
+    #[test]
+    fn test_apply_enums() {
+        let dir = std::env::temp_dir().join(format!("header2svd-enums-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("uart_types.h"), UART_TYPES).unwrap();
+
+        let field = |name: &str, bits| BitField {
+            name: name.to_string(),
+            bits,
+            ..Default::default()
+        };
+        let mut peripherals = BTreeMap::new();
+        peripherals.insert(
+            "UART".to_string(),
+            Peripheral {
+                registers: vec![Register {
+                    name: "UART_CONF0".to_string(),
+                    bit_fields: vec![
+                        field("UART_PARITY", Bits::Range(0..=1)),
+                        field("UART_BIT_NUM", Bits::Range(2..=3)),
+                    ],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+        );
+        let mapping = |type_: &str, field: &str| EnumMapping {
+            header: PathBuf::from("uart_types.h"),
+            type_: type_.to_string(),
+            fields: vec![field.to_string()],
+        };
+        let mappings = vec![
+            mapping("uart_parity_t", "UART.UART_CONF0.UART_PARITY"),
+            mapping("uart_word_length_t", "UART.UART_CONF0.UART_BIT_NUM"),
+        ];
+        let applied = apply_enums(&mut peripherals, &dir, &mappings, &HashMap::new());
+        assert_eq!(applied, Ok(2));
+        let fields = &peripherals["UART"].registers[0].bit_fields;
+        let names = |f: &BitField| -> Vec<String> {
+            f.enumerated_values.iter().map(|v| v.name.clone()).collect()
+        };
+        assert_eq!(names(&fields[0]), vec!["DISABLE", "EVEN", "ODD"]);
+        assert_eq!(
+            names(&fields[1]),
+            vec!["DATA_5_BITS", "DATA_6_BITS", "DATA_7_BITS", "DATA_8_BITS"]
+        );
+
+        let missing = vec![mapping("uart_stop_bits_t", "UART.UART_CONF0.UART_PARITY")];
+        assert!(apply_enums(&mut peripherals, &dir, &missing, &HashMap::new()).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,209 @@
+//! How a single register of the generated svd was derived: the header lines it was parsed from
+//! and the regexes they matched, the doc files, seeds and patches that changed it, the renames
+//! of the sanitization pass and the validation issues found for it. Meant for tracking down
+//! why the svd disagrees with the reference manual.
+
+use crate::sanitize::Rename;
+use crate::validate::Issue;
+use crate::{
+    Bits, Peripheral, Provenance, COUNT_DEF, REG_BITS, REG_BIT_INFO, REG_DEF, REG_DEF_ABS,
+    REG_DEF_INDEX,
+};
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Regexes of the header parser a line can be explained by
+const LINE_REGEXES: &[(&str, &str)] = &[
+    ("REG_DEF", REG_DEF),
+    ("REG_DEF_INDEX", REG_DEF_INDEX),
+    ("REG_DEF_ABS", REG_DEF_ABS),
+    ("REG_BIT_INFO", REG_BIT_INFO),
+    ("REG_BITS", REG_BITS),
+    ("COUNT_DEF", COUNT_DEF),
+];
+
+/// The parser regexes matching `line`, with their capture groups
+pub fn line_matches(line: &str) -> Vec<(&'static str, Vec<String>)> {
+    LINE_REGEXES
+        .iter()
+        .filter_map(|(name, regex)| {
+            let captures = Regex::new(regex).unwrap().captures(line)?;
+            let groups = captures
+                .iter()
+                .skip(1)
+                .map(|g| g.map_or("", |g| g.as_str()).to_string())
+                .collect();
+            Some((*name, groups))
+        })
+        .collect()
+}
+
+/// Line `line` of `file`, counting from 1
+fn source_line(file: &str, line: usize) -> Option<String> {
+    let text = std::fs::read_to_string(file).ok()?;
+    text.lines().nth(line.checked_sub(1)?).map(str::to_string)
+}
+
+fn write_provenance(out: &mut String, provenance: &[Provenance], indent: &str) {
+    for p in provenance {
+        writeln!(out, "{}from {}", indent, p).unwrap();
+        if let Provenance::Header {
+            file,
+            line: Some(line),
+        } = p
+        {
+            match source_line(file, *line) {
+                Some(text) => {
+                    writeln!(out, "{}  | {}", indent, text.trim()).unwrap();
+                    for (regex, groups) in line_matches(&text) {
+                        writeln!(out, "{}  matches {} {:?}", indent, regex, groups).unwrap();
+                    }
+                }
+                None => writeln!(out, "{}  (source line not available)", indent).unwrap(),
+            }
+        }
+    }
+}
+
+/// Describe how register `path`, as `PERIPHERAL.REGISTER` with the final names, was derived
+pub fn explain(
+    peripherals: &BTreeMap<String, Peripheral>,
+    path: &str,
+    renames: &[Rename],
+    issues: &[Issue],
+) -> Result<String, String> {
+    let mut parts = path.splitn(2, '.');
+    let (pname, rname) = match (parts.next(), parts.next()) {
+        (Some(p), Some(r)) => (p, r),
+        _ => return Err(format!("{}: expected PERIPHERAL.REGISTER", path)),
+    };
+    let p = peripherals
+        .get(pname)
+        .ok_or_else(|| format!("{}: no peripheral {}", path, pname))?;
+    let r = p
+        .registers
+        .iter()
+        .find(|r| r.name == rname)
+        .ok_or_else(|| format!("{}: no register {}", path, rname))?;
+
+    let mut out = String::new();
+    writeln!(out, "{} \"{}\"", path, r.description).unwrap();
+    writeln!(
+        out,
+        "  offset {:#x}, address {:#010x}, reset value {:#x}",
+        r.address,
+        p.address + r.address,
+        r.reset_value
+    )
+    .unwrap();
+    if let Some(alternate) = &r.alternate_register {
+        writeln!(out, "  alternate view of {}", alternate).unwrap();
+    }
+    if let Some(strobes) = &r.strobes {
+        for (what, name) in [("set", &strobes.set), ("cleared", &strobes.clear)].iter() {
+            if let Some(name) = name {
+                writeln!(out, "  bits {} by writing ones to {}", what, name).unwrap();
+            }
+        }
+    }
+    write_provenance(&mut out, &r.provenance, "  ");
+    for rename in renames.iter().filter(|n| n.scope == pname && n.to == rname) {
+        writeln!(out, "  sanitized from {}", rename.from).unwrap();
+    }
+    for issue in issues.iter().map(Issue::to_string) {
+        if issue.starts_with(pname) && issue.contains(rname) {
+            writeln!(out, "  issue: {}", issue).unwrap();
+        }
+    }
+
+    for f in &r.bit_fields {
+        let bits = match &f.bits {
+            Bits::Single(b) => format!("{}", b),
+            Bits::Range(r) => format!("{}:{}", r.end(), r.start()),
+        };
+        writeln!(
+            out,
+            "  field {} [{}] {:?}, {:?} \"{}\"",
+            f.name, bits, f.type_, f.origin, f.description
+        )
+        .unwrap();
+        write_provenance(&mut out, &f.provenance, "    ");
+        let scope = format!("{}.{}", pname, rname);
+        for rename in renames
+            .iter()
+            .filter(|n| n.scope == scope && n.to == f.name)
+        {
+            writeln!(out, "    sanitized from {}", rename.from).unwrap();
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BitField, Register};
+
+    #[test]
+    fn test_explain() {
+        let dir = std::env::temp_dir().join(format!("header2svd-explain-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let header = dir.join("uart_reg.h");
+        std::fs::write(
+            &header,
+            "#define UART_CONF0_REG(i)          (REG_UART_BASE(i) + 0x20)\n\
+             /* UART_PARITY : R/W ;bitpos:[0] ;default: 1'b0 ; */\n",
+        )
+        .unwrap();
+        let file = header.display().to_string();
+
+        let conf0 = Register {
+            name: "UART_CONF0".to_string(),
+            address: 0x20,
+            bit_fields: vec![BitField {
+                name: "UART_PARITY".to_string(),
+                provenance: vec![Provenance::Header {
+                    file: file.clone(),
+                    line: Some(2),
+                }],
+                ..Default::default()
+            }],
+            provenance: vec![
+                Provenance::Header {
+                    file,
+                    line: Some(1),
+                },
+                Provenance::Doc {
+                    file: "uart.json".to_string(),
+                },
+            ],
+            ..Default::default()
+        };
+        let mut peripherals = BTreeMap::new();
+        peripherals.insert(
+            "UART".to_string(),
+            Peripheral {
+                address: 0x3ff4_0000,
+                registers: vec![conf0],
+                ..Default::default()
+            },
+        );
+        let renames = vec![Rename {
+            scope: "UART".to_string(),
+            from: "UART CONF0".to_string(),
+            to: "UART_CONF0".to_string(),
+        }];
+
+        let text = explain(&peripherals, "UART.UART_CONF0", &renames, &[]).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(text.contains("address 0x3ff40020"));
+        assert!(text.contains("matches REG_DEF_INDEX [\"UART_CONF0\", \"UART\", \"0x20\"]"));
+        assert!(text.contains("from doc uart.json"));
+        assert!(text.contains("sanitized from UART CONF0"));
+        assert!(text.contains("matches REG_BIT_INFO [\"UART_PARITY\", \"R/W\", \"0\", \"1'b0\"]"));
+
+        assert!(explain(&peripherals, "UART.UART_CONF1", &renames, &[]).is_err());
+        assert!(explain(&peripherals, "UART", &renames, &[]).is_err());
+    }
+}
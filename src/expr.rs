@@ -0,0 +1,287 @@
+//! Evaluator for the constant integer expressions found in C headers, e.g.
+//! `(0x3C + 0x4 * 2)` or `(BIT(3) | FOO)` in `#if` conditions.
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(i64),
+    Ident(String),
+    Op(&'static str),
+}
+
+const OPERATORS: &[&str] = &[
+    "<<", ">>", "<=", ">=", "==", "!=", "&&", "||", "+", "-", "*", "/", "%", "<", ">", "&", "|",
+    "^", "~", "!", "(", ")", "?", ":",
+];
+
+fn tokenize(expr: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut rest = expr.trim();
+
+    while !rest.is_empty() {
+        let c = rest.chars().next().unwrap();
+        if c.is_whitespace() {
+            rest = rest.trim_start();
+        } else if c.is_ascii_digit() {
+            let end = rest
+                .find(|c: char| !c.is_ascii_alphanumeric())
+                .unwrap_or(rest.len());
+            tokens.push(Token::Num(parse_number(&rest[..end])?));
+            rest = &rest[end..];
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Ident(rest[..end].to_string()));
+            rest = &rest[end..];
+        } else if let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(*op)) {
+            tokens.push(Token::Op(op));
+            rest = &rest[op.len()..];
+        } else {
+            return Err(format!("Unexpected character '{}' in '{}'", c, expr));
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Parse a C integer literal, including hex/octal prefixes and `U`/`L` suffixes
+pub fn parse_number(s: &str) -> Result<i64, String> {
+    let digits = s.trim_end_matches(&['u', 'U', 'l', 'L'][..]);
+    let value = if digits.starts_with("0x") || digits.starts_with("0X") {
+        u64::from_str_radix(&digits[2..], 16)
+    } else if digits.len() > 1 && digits.starts_with('0') {
+        u64::from_str_radix(&digits[1..], 8)
+    } else {
+        digits.parse()
+    };
+    value
+        .map(|v| v as i64)
+        .map_err(|_| format!("Invalid number '{}'", s))
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    resolve: &'a dyn Fn(&str) -> Option<i64>,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, op: &str) -> Result<(), String> {
+        match self.next() {
+            Some(Token::Op(o)) if o == op => Ok(()),
+            other => Err(format!("Expected '{}', found {:?}", op, other)),
+        }
+    }
+
+    /// `cond ? a : b` has the lowest precedence of the supported operators
+    fn ternary(&mut self) -> Result<i64, String> {
+        let cond = self.binary(0)?;
+        if self.peek() == Some(&Token::Op("?")) {
+            self.next();
+            let a = self.ternary()?;
+            self.expect(":")?;
+            let b = self.ternary()?;
+            Ok(if cond != 0 { a } else { b })
+        } else {
+            Ok(cond)
+        }
+    }
+
+    fn binary(&mut self, min_precedence: u8) -> Result<i64, String> {
+        let mut lhs = self.unary()?;
+        while let Some(Token::Op(op)) = self.peek() {
+            let op = *op;
+            let precedence = match precedence(op) {
+                Some(p) if p >= min_precedence => p,
+                _ => break,
+            };
+            self.next();
+            let rhs = self.binary(precedence + 1)?;
+            lhs = apply(op, lhs, rhs)?;
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<i64, String> {
+        match self.next() {
+            Some(Token::Num(n)) => Ok(n),
+            Some(Token::Op("-")) => Ok(self.unary()?.wrapping_neg()),
+            Some(Token::Op("+")) => self.unary(),
+            Some(Token::Op("~")) => Ok(!self.unary()?),
+            Some(Token::Op("!")) => Ok((self.unary()? == 0) as i64),
+            Some(Token::Op("(")) => {
+                let value = self.ternary()?;
+                self.expect(")")?;
+                Ok(value)
+            }
+            Some(Token::Ident(name)) => {
+                if self.peek() == Some(&Token::Op("(")) {
+                    self.call(&name)
+                } else {
+                    (self.resolve)(&name).ok_or_else(|| format!("Unknown symbol '{}'", name))
+                }
+            }
+            other => Err(format!("Unexpected token {:?}", other)),
+        }
+    }
+
+    /// Function like macros that are common enough to be built in
+    fn call(&mut self, name: &str) -> Result<i64, String> {
+        self.expect("(")?;
+        let arg = self.ternary()?;
+        self.expect(")")?;
+        match name {
+            "BIT" => Ok(1i64.wrapping_shl(arg as u32)),
+            _ => Err(format!("Unknown macro '{}'", name)),
+        }
+    }
+}
+
+fn precedence(op: &str) -> Option<u8> {
+    Some(match op {
+        "||" => 1,
+        "&&" => 2,
+        "|" => 3,
+        "^" => 4,
+        "&" => 5,
+        "==" | "!=" => 6,
+        "<" | ">" | "<=" | ">=" => 7,
+        "<<" | ">>" => 8,
+        "+" | "-" => 9,
+        "*" | "/" | "%" => 10,
+        _ => return None,
+    })
+}
+
+fn apply(op: &str, a: i64, b: i64) -> Result<i64, String> {
+    Ok(match op {
+        "||" => (a != 0 || b != 0) as i64,
+        "&&" => (a != 0 && b != 0) as i64,
+        "|" => a | b,
+        "^" => a ^ b,
+        "&" => a & b,
+        "==" => (a == b) as i64,
+        "!=" => (a != b) as i64,
+        "<" => (a < b) as i64,
+        ">" => (a > b) as i64,
+        "<=" => (a <= b) as i64,
+        ">=" => (a >= b) as i64,
+        "<<" => a.wrapping_shl(b as u32),
+        ">>" => a.wrapping_shr(b as u32),
+        "+" => a.wrapping_add(b),
+        "-" => a.wrapping_sub(b),
+        "*" => a.wrapping_mul(b),
+        "/" | "%" if b == 0 => return Err("Division by zero".to_string()),
+        "/" => a.wrapping_div(b),
+        "%" => a.wrapping_rem(b),
+        _ => return Err(format!("Unsupported operator '{}'", op)),
+    })
+}
+
+/// Evaluate `expr`, looking up identifiers with `resolve`
+pub fn eval(expr: &str, resolve: &dyn Fn(&str) -> Option<i64>) -> Result<i64, String> {
+    let mut parser = Parser {
+        tokens: tokenize(expr)?,
+        pos: 0,
+        resolve,
+    };
+    let value = parser.ternary()?;
+    match parser.peek() {
+        None => Ok(value),
+        Some(t) => Err(format!("Unexpected token {:?} in '{}'", t, expr)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval_plain(expr: &str) -> Result<i64, String> {
+        eval(expr, &|name| match name {
+            "FOO" => Some(0x10),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn test_precedence() {
+        assert_eq!(eval_plain("0x3C + 0x4 * 2"), Ok(0x44));
+        assert_eq!(eval_plain("(0x3C + 0x4) * 2"), Ok(0x80));
+        assert_eq!(eval_plain("1 << 2 + 1"), Ok(8));
+        assert_eq!(eval_plain("1 | 2 & 3 ^ 4"), Ok(7));
+        assert_eq!(eval_plain("1 + 2 == 3 && 4 > 5 || 6 <= 6"), Ok(1));
+        // left associative
+        assert_eq!(eval_plain("10 - 4 - 3"), Ok(3));
+        assert_eq!(eval_plain("64 / 4 / 2"), Ok(8));
+        assert_eq!(eval_plain("17 % 5 % 3"), Ok(2));
+    }
+
+    #[test]
+    fn test_ternary() {
+        assert_eq!(eval_plain("1 ? 2 : 3"), Ok(2));
+        assert_eq!(eval_plain("0 ? 2 : 3"), Ok(3));
+        assert_eq!(eval_plain("0 ? 1 : 0 ? 2 : 3"), Ok(3));
+        assert_eq!(eval_plain("FOO > 8 ? FOO : 8"), Ok(0x10));
+        assert!(eval_plain("1 ? 2").is_err());
+    }
+
+    #[test]
+    fn test_unary() {
+        assert_eq!(eval_plain("~0"), Ok(-1));
+        assert_eq!(eval_plain("~0x1 & 0xF"), Ok(0xE));
+        assert_eq!(eval_plain("!0"), Ok(1));
+        assert_eq!(eval_plain("!FOO"), Ok(0));
+        assert_eq!(eval_plain("-3 + 5"), Ok(2));
+        assert_eq!(eval_plain("- -3"), Ok(3));
+    }
+
+    #[test]
+    fn test_bit() {
+        assert_eq!(eval_plain("BIT(3)"), Ok(8));
+        assert_eq!(eval_plain("BIT(3) | FOO"), Ok(0x18));
+        assert_eq!(eval_plain("BIT(1 + 1)"), Ok(4));
+        assert!(eval_plain("BITS(3)").is_err());
+    }
+
+    #[test]
+    fn test_literals() {
+        assert_eq!(parse_number("0x3FF44000"), Ok(0x3FF4_4000));
+        assert_eq!(parse_number("0XfF"), Ok(0xFF));
+        assert_eq!(parse_number("017"), Ok(0o17));
+        assert_eq!(parse_number("0"), Ok(0));
+        assert_eq!(parse_number("42UL"), Ok(42));
+        assert_eq!(parse_number("0x10u"), Ok(0x10));
+        assert!(parse_number("0x").is_err());
+        assert!(parse_number("09").is_err());
+        assert_eq!(eval_plain("0x10UL + 010"), Ok(0x18));
+    }
+
+    #[test]
+    fn test_unknown_identifier() {
+        assert_eq!(
+            eval_plain("FOO + BAR"),
+            Err("Unknown symbol 'BAR'".to_string())
+        );
+        assert!(eval_plain("FOO FOO").is_err());
+        assert!(eval_plain("FOO @ 1").is_err());
+    }
+
+    #[test]
+    fn test_division() {
+        assert_eq!(eval_plain("1 / 0"), Err("Division by zero".to_string()));
+        assert_eq!(eval_plain("1 % 0"), Err("Division by zero".to_string()));
+        assert_eq!(parse_number("0x8000000000000000"), Ok(i64::MIN));
+        assert_eq!(eval_plain("(0x8000000000000000 / -1)"), Ok(i64::MIN));
+        assert_eq!(eval_plain("(0x8000000000000000 % -1)"), Ok(0));
+    }
+}
@@ -0,0 +1,91 @@
+//! Checking out the SDK at a pinned revision, so a generated svd can be reproduced from the
+//! commit recorded in its `vendorExtensions`.
+
+use std::path::Path;
+use std::process::Command;
+use xmltree::Element;
+
+/// Repository cloned when no other one is given
+pub const DEFAULT_REPOSITORY: &str = "https://github.com/espressif/esp-idf.git";
+
+/// Run git with `args`, in `dir` when given, returning its trimmed output
+fn git(dir: Option<&Path>, args: &[&str]) -> Result<String, String> {
+    let mut command = Command::new("git");
+    if let Some(dir) = dir {
+        command.arg("-C").arg(dir);
+    }
+    let output = command
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Clone `repository` into `dir`, or fetch the clone already there from its origin, and check
+/// out `rev` (a tag, branch or commit). Returns the commit hash that was checked out
+pub fn checkout(repository: &str, rev: &str, dir: &Path) -> Result<String, String> {
+    if dir.join(".git").exists() {
+        println!("Updating {}", dir.display());
+        git(Some(dir), &["fetch", "--quiet", "--tags", "origin"])?;
+    } else {
+        println!("Cloning {} into {}", repository, dir.display());
+        let target = dir.to_string_lossy();
+        git(
+            None,
+            &["clone", "--quiet", "--no-checkout", repository, &target],
+        )?;
+    }
+    // a branch name would resolve to the local, possibly outdated, branch
+    let commit = git(
+        Some(dir),
+        &[
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("origin/{}^{{commit}}", rev),
+        ],
+    )
+    .or_else(|_| {
+        git(
+            Some(dir),
+            &["rev-parse", "--verify", &format!("{}^{{commit}}", rev)],
+        )
+    })
+    .map_err(|_| format!("{} has no revision {}", repository, rev))?;
+    git(Some(dir), &["checkout", "--quiet", "--detach", &commit])?;
+    Ok(commit)
+}
+
+/// Repository and commit of the SDK checkout in `dir`, `None` if it isn't a git checkout
+pub fn sdk_revision(dir: &Path) -> Option<(String, String)> {
+    // a plain directory inside another checkout would report that checkout
+    if !dir.join(".git").exists() {
+        return None;
+    }
+    let commit = git(Some(dir), &["rev-parse", "HEAD"]).ok()?;
+    let repository = git(Some(dir), &["remote", "get-url", "origin"]).unwrap_or_default();
+    Some((repository, commit))
+}
+
+fn text_element(name: &str, text: &str) -> Element {
+    let mut element = Element::new(name);
+    element.text = Some(text.to_string());
+    element
+}
+
+/// `vendorExtensions` entry recording the SDK revision the svd was generated from
+pub fn sdk_extension(repository: &str, commit: &str) -> Element {
+    let mut sdk = Element::new("sdk");
+    if !repository.is_empty() {
+        sdk.children.push(text_element("repository", repository));
+    }
+    sdk.children.push(text_element("commit", commit));
+    sdk
+}
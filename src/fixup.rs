@@ -0,0 +1,98 @@
+//! Fixups of the model, run right after the sources are merged and before any pass reads the
+//! model, so projects using the library can correct their chip in code instead of forking the
+//! parser, as in `examples/custom_chip.rs`.
+
+use crate::source::Model;
+use std::fmt;
+
+/// Correction applied to the parsed model, can capture its parameters such as the config
+pub type Fixup = Box<dyn Fn(&mut Model)>;
+
+/// Fixups by name, run in the order they were registered
+#[derive(Default)]
+pub struct Fixups {
+    fixups: Vec<(String, Fixup)>,
+}
+
+impl fmt::Debug for Fixups {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
+
+impl Fixups {
+    pub fn new() -> Self {
+        Fixups::default()
+    }
+
+    /// Add `fixup`, run after the ones registered before it. The name shows up in the log
+    pub fn register<F: Fn(&mut Model) + 'static>(&mut self, name: &str, fixup: F) -> &mut Self {
+        self.fixups.push((name.to_string(), Box::new(fixup)));
+        self
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.fixups.iter().map(|(name, _)| name.as_str())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fixups.is_empty()
+    }
+
+    /// Run all fixups on `model`
+    pub fn apply(&self, model: &mut Model) {
+        for (name, fixup) in &self.fixups {
+            println!("Applying fixup {}", name);
+            fixup(model);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Peripheral, Register};
+
+    fn add_date(model: &mut Model) {
+        let uart = model.get_mut("UART").unwrap();
+        uart.registers.push(Register {
+            name: "UART_DATE".to_string(),
+            address: 0x78,
+            ..Default::default()
+        });
+    }
+
+    /// Rename `UART_DATE` to `name`
+    fn rename_date(name: &str) -> impl Fn(&mut Model) {
+        let name = name.to_string();
+        move |model: &mut Model| {
+            for r in model.values_mut().flat_map(|p| p.registers.iter_mut()) {
+                if r.name == "UART_DATE" {
+                    r.name = name.clone();
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_fixups() {
+        let mut model = Model::new();
+        model.insert("UART".to_string(), Peripheral::default());
+        let mut fixups = Fixups::new();
+        fixups
+            .register("add date", add_date)
+            .register("rename date", rename_date("UART_VERSION"));
+        assert_eq!(
+            fixups.names().collect::<Vec<_>>(),
+            vec!["add date", "rename date"]
+        );
+
+        fixups.apply(&mut model);
+        let names: Vec<&str> = model["UART"]
+            .registers
+            .iter()
+            .map(|r| r.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["UART_VERSION"]);
+    }
+}
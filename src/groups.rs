@@ -0,0 +1,168 @@
+//! Peripheral groups, e.g. `UART` for the UART and its further instances, emitted as the
+//! `groupName` of the svd. Peripherals of a group with the same register layout also get the
+//! group as their `headerStructName`, so CMSIS header generators emit one struct for them.
+
+use crate::Peripheral;
+use std::collections::BTreeMap;
+use xmltree::Element;
+
+/// Group of a peripheral, its name without the instance number
+fn group_of(name: &str) -> &str {
+    let stem = name
+        .trim_end_matches(|c: char| c.is_ascii_digit())
+        .trim_end_matches('_');
+    if stem.is_empty() {
+        name
+    } else {
+        stem
+    }
+}
+
+/// What a C struct of the peripheral is made of: the offset and width of each register and
+/// the bits of its fields
+type Layout = Vec<(u32, u8, Vec<(u8, u8)>)>;
+
+fn layout(p: &Peripheral) -> Layout {
+    p.registers
+        .iter()
+        .map(|r| {
+            let fields = r
+                .bit_fields
+                .iter()
+                .map(|f| (f.bits.lsb(), f.bits.msb()))
+                .collect();
+            (r.address, r.width, fields)
+        })
+        .collect()
+}
+
+/// Give every peripheral without a group the one of its name, and the group as header struct
+/// name to peripherals named differently whose group members all share a register layout.
+/// Returns the number of peripherals given a header struct name
+pub fn assign_groups(peripherals: &mut BTreeMap<String, Peripheral>) -> usize {
+    for (name, p) in peripherals.iter_mut() {
+        if p.group_name.is_none() {
+            p.group_name = Some(group_of(name).to_string());
+        }
+    }
+    let mut members: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (name, p) in peripherals.iter() {
+        members
+            .entry(p.group_name.clone().unwrap())
+            .or_default()
+            .push(name.clone());
+    }
+    let mut assigned = 0;
+    for (group, names) in members {
+        let first = layout(&peripherals[&names[0]]);
+        if names.iter().any(|n| layout(&peripherals[n]) != first) {
+            continue;
+        }
+        for name in names.iter().filter(|n| **n != group) {
+            peripherals.get_mut(name).unwrap().header_struct_name = Some(group.clone());
+            assigned += 1;
+        }
+    }
+    assigned
+}
+
+/// Header struct names of `peripherals` by peripheral name, for `insert_header_struct_names`
+pub fn header_struct_names(peripherals: &BTreeMap<String, Peripheral>) -> BTreeMap<String, String> {
+    peripherals
+        .iter()
+        .filter_map(|(name, p)| Some((name.clone(), p.header_struct_name.clone()?)))
+        .collect()
+}
+
+/// Add the `headerStructName` of `names` to the peripherals of an encoded svd, which svd-parser
+/// can't write itself. It goes right before the `baseAddress` as the schema orders it
+pub fn insert_header_struct_names(device: &mut Element, names: &BTreeMap<String, String>) {
+    let peripherals = match device.get_mut_child("peripherals") {
+        Some(peripherals) => peripherals,
+        None => return,
+    };
+    for peripheral in peripherals.children.iter_mut() {
+        let name = peripheral.get_child("name").and_then(|n| n.text.clone());
+        let header_struct = match name.and_then(|n| names.get(&n)) {
+            Some(header_struct) => header_struct,
+            None => continue,
+        };
+        let mut element = Element::new("headerStructName");
+        element.text = Some(header_struct.clone());
+        let at = peripheral
+            .children
+            .iter()
+            .position(|c| c.name == "baseAddress")
+            .unwrap_or(peripheral.children.len());
+        peripheral.children.insert(at, element);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Register;
+
+    #[test]
+    fn test_assign_groups() {
+        let peripheral = |addresses: &[u32]| Peripheral {
+            registers: addresses
+                .iter()
+                .map(|address| Register {
+                    address: *address,
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let mut peripherals = BTreeMap::new();
+        peripherals.insert("UART0".to_string(), peripheral(&[0x0, 0x4]));
+        peripherals.insert("UART1".to_string(), peripheral(&[0x0, 0x4]));
+        peripherals.insert("SPI".to_string(), peripheral(&[0x0]));
+        peripherals.insert("SPI1".to_string(), peripheral(&[0x0, 0x8]));
+        peripherals.insert("I2C".to_string(), peripheral(&[0x0]));
+
+        assert_eq!(assign_groups(&mut peripherals), 2);
+        let groups: Vec<(&str, &str)> = peripherals
+            .iter()
+            .map(|(name, p)| (name.as_str(), p.group_name.as_deref().unwrap()))
+            .collect();
+        assert_eq!(
+            groups,
+            vec![
+                ("I2C", "I2C"),
+                ("SPI", "SPI"),
+                ("SPI1", "SPI"),
+                ("UART0", "UART"),
+                ("UART1", "UART"),
+            ]
+        );
+        let names = header_struct_names(&peripherals);
+        assert_eq!(
+            names.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("UART0".to_string(), "UART".to_string()),
+                ("UART1".to_string(), "UART".to_string()),
+            ]
+        );
+
+        let mut device = Element::parse(
+            "<device><peripherals><peripheral><name>UART0</name><groupName>UART</groupName>\
+             <baseAddress>0</baseAddress></peripheral><peripheral><name>SPI</name>\
+             </peripheral></peripherals></device>"
+                .as_bytes(),
+        )
+        .unwrap();
+        insert_header_struct_names(&mut device, &header_struct_names(&peripherals));
+        let children: Vec<&str> = device.children[0].children[0]
+            .children
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect();
+        assert_eq!(
+            children,
+            vec!["name", "groupName", "headerStructName", "baseAddress"]
+        );
+        assert_eq!(device.children[0].children[1].children.len(), 1);
+    }
+}
@@ -0,0 +1,300 @@
+//! C register headers generated from the model, in the style of the esp-idf `*_reg.h` headers
+//! with uniform `_REG`, `_M`, `_V` and `_S` macros, plus a `soc.h` with the base addresses and
+//! interrupt sources. The set can be parsed again by idf2svd.
+
+use crate::{BitField, Peripheral, Register, Type};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use std::path::Path;
+
+/// Text usable inside a `/* */` comment on a single line
+fn comment(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace("*/", "* /")
+}
+
+fn access(type_: Type) -> &'static str {
+    match type_ {
+        Type::ReadOnly => "RO",
+        Type::ReadWrite => "R/W",
+        Type::WriteOnly => "WO",
+    }
+}
+
+fn base_define(peripheral: &str) -> String {
+    format!("DR_REG_{}_BASE", peripheral)
+}
+
+/// File name of the header of `peripheral`
+pub fn header_name(peripheral: &str) -> String {
+    format!("{}_reg.h", peripheral.to_lowercase())
+}
+
+/// `soc.h` with the base address of every peripheral, a `REG_<NAME>_BASE(i)` macro for those
+/// with more than one instance, and the interrupt sources
+pub fn soc_header(peripherals: &BTreeMap<String, Peripheral>) -> String {
+    let mut out = String::new();
+    writeln!(out, "#ifndef _IDF2SVD_SOC_H_\n#define _IDF2SVD_SOC_H_\n").unwrap();
+    writeln!(out, "#ifndef BIT\n#define BIT(nr) (1UL << (nr))\n#endif\n").unwrap();
+    for (name, p) in peripherals {
+        writeln!(out, "#define {:<40}0x{:08x}", base_define(name), p.address).unwrap();
+    }
+    for (name, p) in peripherals
+        .iter()
+        .filter(|(_, p)| !p.instance_addresses.is_empty())
+    {
+        let mut select = String::new();
+        for (i, address) in p.instance_addresses.iter().enumerate().rev() {
+            select = if select.is_empty() {
+                format!("0x{:08x}", address)
+            } else {
+                format!("(i) == {} ? 0x{:08x} : {}", i + 1, address, select)
+            };
+        }
+        writeln!(
+            out,
+            "#define REG_{}_BASE(i) ((i) == 0 ? {} : {})",
+            name,
+            base_define(name),
+            select
+        )
+        .unwrap();
+    }
+
+    let mut interrupts: Vec<_> = peripherals.values().flat_map(|p| &p.interrupts).collect();
+    interrupts.sort_by_key(|i| (i.value, i.name.clone()));
+    interrupts.dedup_by(|a, b| a.value == b.value && a.name == b.name);
+    if !interrupts.is_empty() {
+        writeln!(out).unwrap();
+    }
+    for i in interrupts {
+        let define = format!("ETS_{}_SOURCE", i.name);
+        match &i.description {
+            Some(d) => writeln!(
+                out,
+                "#define {:<40}{}/**< {}*/",
+                define,
+                i.value,
+                comment(d)
+            ),
+            None => writeln!(out, "#define {:<40}{}", define, i.value),
+        }
+        .unwrap();
+    }
+    writeln!(out, "\n#endif /* _IDF2SVD_SOC_H_ */").unwrap();
+    out
+}
+
+fn field_defines(out: &mut String, macro_name: &str, f: &BitField) {
+    let lsb = f.bits.lsb();
+    let width = u32::from(f.bits.msb() - lsb) + 1;
+    let value_mask = ((1u64 << width) - 1) as u32;
+    if width == 1 {
+        writeln!(out, "#define {}  (BIT({}))", macro_name, lsb).unwrap();
+        writeln!(out, "#define {}_M  (BIT({}))", macro_name, lsb).unwrap();
+    } else {
+        writeln!(
+            out,
+            "#define {0}  0x{1:08X}\n#define {0}_M  (({0}_V)<<({0}_S))",
+            macro_name, value_mask
+        )
+        .unwrap();
+    }
+    writeln!(out, "#define {}_V  0x{:X}", macro_name, value_mask).unwrap();
+    writeln!(out, "#define {}_S  {}", macro_name, lsb).unwrap();
+}
+
+fn register_defines(out: &mut String, pname: &str, r: &Register, unique: &BTreeSet<&str>) {
+    if !r.description.trim().is_empty() && r.description != r.name {
+        writeln!(out, "/* {} */", comment(&r.description)).unwrap();
+    }
+    writeln!(
+        out,
+        "#define {}_REG          ({} + 0x{:x})",
+        r.name,
+        base_define(pname),
+        r.address
+    )
+    .unwrap();
+    let mut fields: Vec<&BitField> = r.bit_fields.iter().collect();
+    fields.sort_by_key(|f| std::cmp::Reverse(f.bits.msb()));
+    for f in fields {
+        let bitpos = if f.bits.msb() == f.bits.lsb() {
+            f.bits.lsb().to_string()
+        } else {
+            format!("{}:{}", f.bits.msb(), f.bits.lsb())
+        };
+        let width = f.bits.msb() - f.bits.lsb() + 1;
+        writeln!(
+            out,
+            "/* {} : {} ;bitpos:[{}] ;default: {}'h{:x} ; */",
+            f.name,
+            access(f.type_),
+            bitpos,
+            width,
+            f.reset_value
+        )
+        .unwrap();
+        writeln!(out, "/*description: {}*/", comment(&f.description)).unwrap();
+        // a field name used in several registers gets the register name to stay unique
+        let macro_name = if unique.contains(f.name.as_str()) {
+            f.name.clone()
+        } else {
+            format!("{}_{}", r.name, f.name)
+        };
+        field_defines(out, &macro_name, f);
+    }
+    writeln!(out).unwrap();
+}
+
+/// The register header of peripheral `name`
+pub fn peripheral_header(name: &str, p: &Peripheral) -> String {
+    let guard = format!("_IDF2SVD_{}_REG_H_", name);
+    let mut out = String::new();
+    writeln!(out, "#ifndef {0}\n#define {0}\n", guard).unwrap();
+    writeln!(out, "#include \"soc.h\"\n").unwrap();
+
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for f in p.registers.iter().flat_map(|r| &r.bit_fields) {
+        *counts.entry(f.name.as_str()).or_default() += 1;
+    }
+    let unique: BTreeSet<&str> = counts
+        .into_iter()
+        .filter(|(_, count)| *count == 1)
+        .map(|(name, _)| name)
+        .collect();
+
+    let mut registers: Vec<&Register> = p.registers.iter().collect();
+    registers.sort_by_key(|r| (r.address, r.name.clone()));
+    for r in registers {
+        register_defines(&mut out, name, r, &unique);
+    }
+    writeln!(out, "#endif /* {} */", guard).unwrap();
+    out
+}
+
+/// Write `soc.h` and the register header of every peripheral with registers into `dir`,
+/// starting every file with `banner`
+pub fn write_c_headers(
+    dir: &Path,
+    peripherals: &BTreeMap<String, Peripheral>,
+    banner: &str,
+) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(
+        dir.join("soc.h"),
+        format!("{}{}", banner, soc_header(peripherals)),
+    )?;
+    for (name, p) in peripherals.iter().filter(|(_, p)| !p.registers.is_empty()) {
+        std::fs::write(
+            dir.join(header_name(name)),
+            format!("{}{}", banner, peripheral_header(name, p)),
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_idf_with_options, Bits, Interrupt, Origin, ParseOptions};
+
+    #[test]
+    fn test_round_trip() {
+        let field = |name: &str, bits, type_| BitField {
+            name: name.to_string(),
+            bits,
+            type_,
+            description: format!("Description of {} */", name),
+            origin: Origin::HeaderComment,
+            ..Default::default()
+        };
+        let mut peripherals = BTreeMap::new();
+        peripherals.insert(
+            "UART".to_string(),
+            Peripheral {
+                description: "UART".to_string(),
+                address: 0x3ff4_0000,
+                instance_addresses: vec![0x3ff5_0000, 0x3ff6_e000],
+                interrupts: vec![Interrupt {
+                    name: "UART0_INTR".to_string(),
+                    description: Some("interrupt of UART0, level".to_string()),
+                    value: 34,
+                }],
+                registers: vec![
+                    Register {
+                        name: "UART_CONF0".to_string(),
+                        description: "Configuration".to_string(),
+                        address: 0x20,
+                        bit_fields: vec![
+                            field("UART_PARITY_EN", Bits::Single(1), Type::ReadWrite),
+                            field("EN", Bits::Range(4..=7), Type::ReadWrite),
+                        ],
+                        ..Default::default()
+                    },
+                    Register {
+                        name: "UART_STATUS".to_string(),
+                        description: "UART_STATUS".to_string(),
+                        address: 0x1c,
+                        bit_fields: vec![field("EN", Bits::Range(0..=31), Type::ReadOnly)],
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            },
+        );
+
+        let header = peripheral_header("UART", &peripherals["UART"]);
+        assert!(header.contains("#define UART_CONF0_REG          (DR_REG_UART_BASE + 0x20)"));
+        assert!(header.contains("#define UART_PARITY_EN_M  (BIT(1))"));
+        assert!(header.contains("#define UART_CONF0_EN_M  ((UART_CONF0_EN_V)<<(UART_CONF0_EN_S))"));
+        let soc = soc_header(&peripherals);
+        assert!(soc.contains(
+            "#define REG_UART_BASE(i) ((i) == 0 ? DR_REG_UART_BASE : (i) == 1 ? 0x3ff50000 : 0x3ff6e000)"
+        ));
+
+        let dir = std::env::temp_dir().join(format!("idf2svd-headers-{}", std::process::id()));
+        write_c_headers(&dir, &peripherals, "/* banner */\n").unwrap();
+        let parsed =
+            parse_idf_with_options(&format!("{}/", dir.display()), &ParseOptions::default());
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let uart = &parsed["UART"];
+        assert_eq!(uart.address, 0x3ff4_0000);
+        assert_eq!(uart.interrupts[0].value, 34);
+        let mut registers: Vec<_> = uart.registers.iter().collect();
+        registers.sort_by_key(|r| r.address);
+        let layout: Vec<_> = registers
+            .iter()
+            .map(|r| {
+                let fields: Vec<_> = r
+                    .bit_fields
+                    .iter()
+                    .map(|f| (f.name.as_str(), f.bits.msb(), f.bits.lsb(), f.type_))
+                    .collect();
+                (r.name.as_str(), r.address, fields)
+            })
+            .collect();
+        assert_eq!(
+            layout,
+            vec![
+                ("UART_STATUS", 0x1c, vec![("EN", 31, 0, Type::ReadOnly)]),
+                (
+                    "UART_CONF0",
+                    0x20,
+                    vec![
+                        ("UART_PARITY_EN", 1, 1, Type::ReadWrite),
+                        ("EN", 7, 4, Type::ReadWrite)
+                    ]
+                ),
+            ]
+        );
+        assert_eq!(registers[1].description, "Configuration");
+        assert_eq!(
+            registers[1].bit_fields[0].description,
+            "Description of UART_PARITY_EN * /"
+        );
+    }
+}
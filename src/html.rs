@@ -0,0 +1,136 @@
+//! Extraction of register tables from saved pages of Espressif's HTML documentation into the
+//! documentation format of [`crate::doc`]. Headings and captions such as
+//! `UART_CONF0_REG (0x0020)` name the register the following field table belongs to.
+
+use crate::doc::DocFile;
+use crate::tables::{header_columns, Column, TableExtractor};
+use regex::Regex;
+
+/// Plain text of an html fragment, with tags removed, entities decoded and whitespace collapsed
+fn text(re_tag: &Regex, html: &str) -> String {
+    let text = re_tag
+        .replace_all(html, " ")
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#8217;", "'")
+        .replace("&amp;", "&");
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Find the register and field tables in the html `pages`. Addresses are made relative to
+/// `base` when given.
+pub fn extract_html<'a>(
+    pages: impl IntoIterator<Item = &'a str>,
+    peripheral: &str,
+    base: Option<u32>,
+) -> DocFile {
+    let re_block = Regex::new(
+        r"(?is)<h[1-6][^>]*>(.*?)</h[1-6]>|<p(?:\s[^>]*)?>(.*?)</p>|<table[^>]*>(.*?)</table>",
+    )
+    .unwrap();
+    let re_caption = Regex::new(r"(?is)<caption[^>]*>(.*?)</caption>").unwrap();
+    let re_row = Regex::new(r"(?is)<tr[^>]*>(.*?)</tr>").unwrap();
+    let re_cell = Regex::new(r"(?is)<t[hd][^>]*>(.*?)</t[hd]>").unwrap();
+    let re_tag = Regex::new(r"(?s)<[^>]*>").unwrap();
+
+    let mut extractor = TableExtractor::new(peripheral, base);
+    for page in pages {
+        for block in re_block.captures_iter(page) {
+            let table = match block.get(3) {
+                Some(table) => table.as_str(),
+                None => {
+                    let heading = block.get(1).or_else(|| block.get(2)).unwrap();
+                    extractor.heading(&text(&re_tag, heading.as_str()));
+                    continue;
+                }
+            };
+
+            if let Some(caption) = re_caption.captures(table) {
+                extractor.heading(&text(&re_tag, &caption[1]));
+            }
+            let mut columns: Option<Vec<Option<Column>>> = None;
+            for row in re_row.captures_iter(table) {
+                let cells: Vec<String> = re_cell
+                    .captures_iter(&row[1])
+                    .map(|c| text(&re_tag, &c[1]))
+                    .collect();
+                match &columns {
+                    Some(columns) => {
+                        let values: Vec<(Column, String)> = columns
+                            .iter()
+                            .zip(cells)
+                            .filter_map(|(column, cell)| column.map(|c| (c, cell)))
+                            .collect();
+                        extractor.row(&values);
+                    }
+                    None => {
+                        columns = header_columns(cells.iter().map(String::as_str));
+                        if let Some(columns) = &columns {
+                            extractor.start_table(columns);
+                        }
+                    }
+                }
+            }
+            extractor.end_table();
+        }
+    }
+    extractor.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"<h3>Register 26.3: UART_CONF0_REG (0x3FF40020)</h3>
+<table class="fields">
+<tr><th>Field</th><th>Bit(s)</th><th>Type</th><th>Reset</th><th>Description</th></tr>
+<tr><td>reserved</td><td>[31:4]</td><td>RO</td><td>0</td><td></td></tr>
+<tr><td>UART_BIT_NUM</td><td>[3:2]</td><td>r/w</td><td>2'h3</td><td>Length of&nbsp;data</td></tr>
+<tr><td></td><td></td><td></td><td></td><td>in <b>bits</b></td></tr>
+<tr><td>UART_PARITY_EN</td><td>1</td><td>R/W</td><td>1'b0</td><td>Parity &amp; check</td></tr>
+</table>"#;
+
+    #[test]
+    fn test_extract_html() {
+        let doc = extract_html(vec![PAGE], "UART", Some(0x3ff4_0000));
+        assert_eq!(doc.peripheral, "UART");
+        assert_eq!(doc.registers.len(), 1);
+        let conf0 = &doc.registers[0];
+        assert_eq!((conf0.name.as_str(), conf0.offset), ("UART_CONF0", 0x20));
+        let fields: Vec<(&str, &str, Option<&str>, Option<u32>, &str)> = conf0
+            .fields
+            .iter()
+            .map(|f| {
+                (
+                    f.name.as_str(),
+                    f.bits.as_str(),
+                    f.access.as_deref(),
+                    f.reset,
+                    f.description.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                (
+                    "UART_BIT_NUM",
+                    "3:2",
+                    Some("R/W"),
+                    Some(3),
+                    "Length of data in bits"
+                ),
+                (
+                    "UART_PARITY_EN",
+                    "1",
+                    Some("R/W"),
+                    Some(0),
+                    "Parity & check"
+                ),
+            ]
+        );
+    }
+}
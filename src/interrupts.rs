@@ -0,0 +1,142 @@
+//! Standalone C header with the interrupt sources of the parsed table, in the style of the
+//! `ETS_*_INTR_SOURCE` defines of `soc.h`, for projects that only need the interrupt numbers.
+
+use crate::sanitize::sanitize_siblings;
+use crate::{Interrupt, Peripheral};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+const GUARD: &str = "IDF2SVD_INTERRUPTS_H";
+
+/// Generate a C header with an `ETS_<NAME>_INTR_SOURCE` define per interrupt of the model and
+/// of `unassigned`, the sources belonging to no peripheral, ordered by number. Interrupts
+/// listed more than once are emitted once, names are sanitized and made unique like the other
+/// names of the model
+pub fn interrupt_header(
+    peripherals: &BTreeMap<String, Peripheral>,
+    unassigned: &[Interrupt],
+) -> String {
+    let mut interrupts: Vec<_> = peripherals
+        .values()
+        .flat_map(|p| p.interrupts.iter())
+        .chain(unassigned)
+        .collect();
+    interrupts.sort_by_key(|i| (i.value, i.name.clone()));
+    interrupts.dedup_by(|a, b| a.value == b.value && a.name == b.name);
+    let names = sanitize_siblings(interrupts.iter().map(|i| i.name.as_str()));
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        "/* Interrupt sources generated by idf2svd, do not edit */"
+    )
+    .unwrap();
+    writeln!(out, "#ifndef {}", GUARD).unwrap();
+    writeln!(out, "#define {}", GUARD).unwrap();
+    writeln!(out).unwrap();
+    for (i, name) in interrupts.iter().zip(names) {
+        let define = format!("ETS_{}_INTR_SOURCE", name);
+        match &i.description {
+            Some(d) => writeln!(
+                out,
+                "#define {:<40}{} /**< {} */",
+                define,
+                i.value,
+                d.trim().replace("*/", "* /")
+            ),
+            None => writeln!(out, "#define {:<40}{}", define, i.value),
+        }
+        .unwrap();
+    }
+    if let Some(last) = interrupts.last() {
+        writeln!(out).unwrap();
+        writeln!(
+            out,
+            "#define {:<40}{} /**< number of interrupt sources */",
+            "ETS_MAX_INTR_SOURCE",
+            last.value + 1
+        )
+        .unwrap();
+    }
+    writeln!(out).unwrap();
+    writeln!(out, "#endif /* {} */", GUARD).unwrap();
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scrape_interrupts;
+
+    #[test]
+    fn test_interrupt_header() {
+        let interrupt = |name: &str, value| Interrupt {
+            name: name.to_string(),
+            description: Some(format!("interrupt of {}, level", name)),
+            value,
+        };
+        let mut peripherals = BTreeMap::new();
+        peripherals.insert(
+            "UART".to_string(),
+            Peripheral {
+                interrupts: vec![interrupt("UART1", 35), interrupt("UART0", 34)],
+                ..Default::default()
+            },
+        );
+        peripherals.insert(
+            "UART0".to_string(),
+            Peripheral {
+                interrupts: vec![interrupt("UART0", 34), interrupt("TG/WDT", 2)],
+                ..Default::default()
+            },
+        );
+
+        let header = interrupt_header(&peripherals, &[interrupt("WIFI_MAC", 0)]);
+        let defines: Vec<&str> = header
+            .lines()
+            .filter(|l| l.starts_with("#define ETS_"))
+            .map(|l| l.split_whitespace().nth(1).unwrap())
+            .collect();
+        assert_eq!(
+            defines,
+            vec![
+                "ETS_WIFI_MAC_INTR_SOURCE",
+                "ETS_TG_WDT_INTR_SOURCE",
+                "ETS_UART0_INTR_SOURCE",
+                "ETS_UART1_INTR_SOURCE",
+                "ETS_MAX_INTR_SOURCE"
+            ]
+        );
+        assert!(header.contains("34 /**< interrupt of UART0, level */"));
+        assert!(header.contains("ETS_MAX_INTR_SOURCE                     36"));
+    }
+
+    #[test]
+    fn test_scrape_descriptions() {
+        let soc_h = "#define ETS_WIFI_MAC_INTR_SOURCE                0/**< interrupt of WiFi MAC, level*/\n\
+            #define ETS_TG0_T0_LEVEL_INTR_SOURCE            14 /**< interrupt of TIMER_GROUP0, TIMER0 (level-triggered); see TRM 18.2.*/\n\
+            #define ETS_CACHE_IA_INTR_SOURCE                68/**< interrupt of Cache Invalied Access, LEVEL.\n\
+             * Triggered by e.g. `0x3ff00000 + 4`. */\n";
+        let interrupts = scrape_interrupts(soc_h);
+        let descriptions: Vec<(&str, u32, &str)> = interrupts
+            .iter()
+            .map(|i| (i.name.as_str(), i.value, i.description.as_deref().unwrap()))
+            .collect();
+        assert_eq!(
+            descriptions,
+            vec![
+                ("WIFI_MAC", 0, "interrupt of WiFi MAC, level"),
+                (
+                    "TG0_T0_LEVEL",
+                    14,
+                    "interrupt of TIMER_GROUP0, TIMER0 (level-triggered); see TRM 18.2."
+                ),
+                (
+                    "CACHE_IA",
+                    68,
+                    "interrupt of Cache Invalied Access, LEVEL. Triggered by e.g. `0x3ff00000 + 4`."
+                ),
+            ]
+        );
+    }
+}
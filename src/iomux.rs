@@ -0,0 +1,283 @@
+//! The pin multiplexer of `eagle_soc.h`. Its pad registers are defined relative to
+//! `PERIPHS_IO_MUX` as `PERIPHS_IO_MUX_MTDI_U`, each followed by the `FUNC_*` numbers of the
+//! functions the pad can be switched to. The fields are shared by all pads and defined once as
+//! `PERIPHS_IO_MUX_FUNC`, `PERIPHS_IO_MUX_PULLUP` and so on, so they don't follow the register
+//! they belong to like the other NONOS SDK fields.
+
+use crate::{expr, BitField, Bits, Origin, Peripheral, Provenance, Register, Type};
+use regex::Regex;
+
+/// Name of the peripheral made from the pad registers
+pub const PERIPHERAL: &str = "IO_MUX";
+
+/// Pad register defines, the defines following one belong to the pad instead of the register
+/// the NONOS SDK parser saw before
+pub const PAD_REGISTER: &str =
+    r"^\s*#define\s+PERIPHS_IO_MUX_(\w+)_U\s+\(\s*PERIPHS_IO_MUX\s*\+\s*(0x[0-9a-fA-F]+)\s*\)";
+
+/// Registers of the pin multiplexer which aren't pads and don't get the shared fields
+const NON_PADS: &[&str] = &["CONF"];
+
+/// Runs of consecutive set bits in `mask`, as (lsb, msb)
+fn runs(mask: u32) -> Vec<(u8, u8)> {
+    let mut runs = vec![];
+    let mut rest = mask;
+    while rest != 0 {
+        let lsb = rest.trailing_zeros();
+        let width = (!(rest >> lsb)).trailing_zeros();
+        runs.push((lsb as u8, (lsb + width - 1) as u8));
+        rest &= !(((1u64 << width) - 1) << lsb) as u32;
+    }
+    runs
+}
+
+fn bits(lsb: u8, msb: u8) -> Bits {
+    if lsb == msb {
+        Bits::Single(lsb)
+    } else {
+        Bits::Range(lsb..=msb)
+    }
+}
+
+/// Fields of a mask, a mask split over several runs of bits such as the function select is a
+/// field per run, each stating which bits of the value it holds
+fn mask_fields(
+    name: &str,
+    mask: u32,
+    shift: u32,
+    origin: Origin,
+    provenance: &Provenance,
+) -> Vec<BitField> {
+    let runs = runs(mask);
+    let mut value_lsb = 0;
+    runs.iter()
+        .enumerate()
+        .map(|(i, (lsb, msb))| {
+            let width = msb - lsb + 1;
+            let field = match i {
+                0 => name.to_string(),
+                _ if runs.len() == 2 => format!("{}_HI", name),
+                _ => format!("{}{}", name, i),
+            };
+            let description = if runs.len() == 1 {
+                String::new()
+            } else if width == 1 {
+                format!("Bit {} of {}", value_lsb, name)
+            } else {
+                format!("Bits {}:{} of {}", value_lsb + width - 1, value_lsb, name)
+            };
+            value_lsb += width;
+            BitField {
+                name: field,
+                bits: bits(lsb + shift as u8, msb + shift as u8),
+                type_: Type::ReadWrite,
+                description,
+                origin,
+                provenance: vec![provenance.clone()],
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+fn number(value: &str) -> Option<u32> {
+    expr::eval(value, &|_| None).ok().map(|v| v as u32)
+}
+
+/// Mask of a `BIT(n)` or `BITn` define
+fn bit_value(value: &str) -> Option<u32> {
+    let re_bit = Regex::new(r"^\(?BIT\(?(\d+)\)?\)?$").unwrap();
+    re_bit
+        .captures(value)
+        .and_then(|c| c[1].parse::<u32>().ok())
+        .map(|bit| 1 << bit)
+}
+
+/// The `IO_MUX` peripheral of `eagle_soc.h`, with a register per pad holding the shared fields
+/// and the functions of the pad in its description. `None` for headers without the pin
+/// multiplexer
+pub fn parse_io_mux(text: &str, file: &str) -> Option<Peripheral> {
+    let re_base =
+        Regex::new(r"^\s*#define\s+PERIPHS_IO_MUX\s+\(?\s*(0x[0-9a-fA-F]+)\s*\)?\s*$").unwrap();
+    let re_pad = Regex::new(PAD_REGISTER).unwrap();
+    let re_shared = Regex::new(r"^\s*#define\s+PERIPHS_IO_MUX_(\w+)\s+(\S+)\s*$").unwrap();
+    let re_define = Regex::new(r"^\s*#define\s+(\w+)\s+(\S+)\s*(?://.*|/\*.*)?$").unwrap();
+    let here = |i: usize| Provenance::Header {
+        file: file.to_string(),
+        line: Some(i + 1),
+    };
+
+    let mut address = None;
+    let mut masks: Vec<(String, u32, usize)> = vec![];
+    let mut shifts: Vec<(String, u32)> = vec![];
+    let mut registers: Vec<Register> = vec![];
+    let mut functions: Vec<Vec<(String, u32)>> = vec![];
+    // whether the defines of the current line on belong to the last pad register
+    let mut in_pad = false;
+    for (i, line) in text.lines().enumerate() {
+        if let Some(c) = re_base.captures(line) {
+            address = number(&c[1]);
+        } else if let Some(c) = re_pad.captures(line) {
+            if let Some(offset) = number(&c[2]) {
+                registers.push(Register {
+                    name: c[1].to_string(),
+                    address: offset,
+                    provenance: vec![here(i)],
+                    ..Default::default()
+                });
+                functions.push(vec![]);
+                in_pad = true;
+            }
+        } else if let Some(c) = re_shared.captures(line) {
+            if let Some(shift) = c[1].strip_suffix("_S") {
+                shifts.extend(number(&c[2]).map(|s| (shift.to_string(), s)));
+            } else if let Some(mask) = bit_value(&c[2]).or_else(|| number(&c[2])) {
+                masks.push((c[1].to_string(), mask, i));
+            }
+        } else if let Some(c) = re_define.captures(line).filter(|_| in_pad) {
+            let (name, value) = (&c[1], &c[2]);
+            if let Some(function) = name.strip_prefix("FUNC_") {
+                if let Some(value) = number(value) {
+                    functions
+                        .last_mut()
+                        .unwrap()
+                        .push((function.to_string(), value));
+                }
+            } else if let Some(mask) = bit_value(value) {
+                // a bit only this register has, like SPI0_CLK_EQU_SYS_CLK of CONF
+                let fields = mask_fields(name, mask, 0, Origin::HeaderBit, &here(i));
+                registers.last_mut().unwrap().bit_fields.extend(fields);
+            } else {
+                in_pad = false;
+            }
+        } else if line.trim_start().starts_with("#define") {
+            in_pad = false;
+        }
+    }
+
+    let address = address?;
+    if registers.is_empty() {
+        return None;
+    }
+    for (r, functions) in registers.iter_mut().zip(functions) {
+        if NON_PADS.contains(&r.name.as_str()) {
+            r.description = format!("{} register of the pin multiplexer", r.name);
+            continue;
+        }
+        r.description = format!("Configuration of the {} pad", r.name);
+        if !functions.is_empty() {
+            let functions: Vec<String> = functions
+                .iter()
+                .map(|(name, value)| format!("{} = {}", name, value))
+                .collect();
+            r.description += &format!(". Functions: {}", functions.join(", "));
+        }
+        for (name, mask, line) in &masks {
+            let shift = shifts
+                .iter()
+                .find(|(s, _)| s == name)
+                .map_or(0, |(_, s)| *s);
+            let origin = if mask.count_ones() == 1 && shift == 0 {
+                Origin::HeaderBit
+            } else {
+                Origin::HeaderMask
+            };
+            for f in mask_fields(name, *mask, shift, origin, &here(*line)) {
+                if r.bit_fields.iter().all(|existing| existing.name != f.name) {
+                    r.bit_fields.push(f);
+                }
+            }
+        }
+        r.bit_fields.sort_by_key(|f| f.bits.lsb());
+    }
+    registers.sort_by_key(|r| r.address);
+    Some(Peripheral {
+        description: PERIPHERAL.to_string(),
+        address,
+        registers,
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EAGLE_SOC: &str = "#define PERIPHS_IO_MUX                  0x60000800
+
+//PIN Mux reg {{
+#define PERIPHS_IO_MUX_FUNC             0x13
+#define PERIPHS_IO_MUX_FUNC_S           4
+#define PERIPHS_IO_MUX_PULLUP           BIT7
+#define PERIPHS_IO_MUX_OE               BIT0
+
+#define PERIPHS_IO_MUX_CONF_U           (PERIPHS_IO_MUX + 0x00)
+#define SPI0_CLK_EQU_SYS_CLK            BIT8
+#define PERIPHS_IO_MUX_MTDO_U           (PERIPHS_IO_MUX + 0x10)
+#define FUNC_GPIO15                     3
+#define FUNC_U0RTS                      4
+#define PERIPHS_IO_MUX_GPIO0_U          (PERIPHS_IO_MUX + 0x34)
+#define FUNC_GPIO0                      0
+
+#define PIN_PULLUP_DIS(PIN_NAME)        CLEAR_PERI_REG_MASK(PIN_NAME, PERIPHS_IO_MUX_PULLUP)
+//}}
+";
+
+    #[test]
+    fn test_parse_io_mux() {
+        assert_eq!(runs(0x13), vec![(0, 1), (4, 4)]);
+
+        let p = parse_io_mux(EAGLE_SOC, "eagle_soc.h").unwrap();
+        assert_eq!(p.address, 0x6000_0800);
+        let registers: Vec<(&str, u32)> = p
+            .registers
+            .iter()
+            .map(|r| (r.name.as_str(), r.address))
+            .collect();
+        assert_eq!(
+            registers,
+            vec![("CONF", 0x00), ("MTDO", 0x10), ("GPIO0", 0x34)]
+        );
+
+        let conf = &p.registers[0];
+        assert_eq!(conf.bit_fields.len(), 1);
+        assert_eq!(conf.bit_fields[0].name, "SPI0_CLK_EQU_SYS_CLK");
+        assert_eq!(conf.bit_fields[0].bits.lsb(), 8);
+
+        let mtdo = &p.registers[1];
+        assert_eq!(
+            mtdo.description,
+            "Configuration of the MTDO pad. Functions: GPIO15 = 3, U0RTS = 4"
+        );
+        let fields: Vec<(&str, u8, u8, &str)> = mtdo
+            .bit_fields
+            .iter()
+            .map(|f| {
+                (
+                    f.name.as_str(),
+                    f.bits.lsb(),
+                    f.bits.msb(),
+                    f.description.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                ("OE", 0, 0, ""),
+                ("FUNC", 4, 5, "Bits 1:0 of FUNC"),
+                ("PULLUP", 7, 7, ""),
+                ("FUNC_HI", 8, 8, "Bit 2 of FUNC"),
+            ]
+        );
+        assert_eq!(
+            mtdo.bit_fields[1].provenance,
+            vec![Provenance::Header {
+                file: "eagle_soc.h".to_string(),
+                line: Some(4)
+            }]
+        );
+
+        assert!(parse_io_mux("#define PERIPHS_IO_MUX 0x60000800\n", "eagle_soc.h").is_none());
+    }
+}
@@ -0,0 +1,264 @@
+//! IP-XACT (IEEE 1685-2014) component description of the model, for EDA and verification tools
+//! which don't read svd. Every peripheral instance becomes an `addressBlock` of a single memory
+//! map.
+
+use crate::svd::insert_comment;
+use crate::{BitField, Peripheral, Register, Type};
+use std::collections::BTreeMap;
+use std::io::Write;
+use xmltree::{Element, Namespace};
+
+const NAMESPACE: &str = "http://www.accellera.org/XMLSchema/IPXACT/1685-2014";
+const XSI_NAMESPACE: &str = "http://www.w3.org/2001/XMLSchema-instance";
+const PREFIX: &str = "ipxact";
+
+fn element(name: &str) -> Element {
+    let mut element = Element::new(name);
+    element.prefix = Some(PREFIX.to_string());
+    element
+}
+
+fn text(name: &str, text: String) -> Element {
+    let mut element = element(name);
+    element.text = Some(text);
+    element
+}
+
+/// A SystemVerilog hex literal, the number format of IP-XACT 2014
+fn hex(value: u32) -> String {
+    format!("'h{:x}", value)
+}
+
+fn access(type_: Type) -> &'static str {
+    match type_ {
+        Type::ReadOnly => "read-only",
+        Type::ReadWrite => "read-write",
+        Type::WriteOnly => "write-only",
+    }
+}
+
+/// `description` element, `None` for empty descriptions or ones only repeating the name
+fn description(name: &str, description: &str) -> Option<Element> {
+    let description = description.trim();
+    if description.is_empty() || description == name {
+        None
+    } else {
+        Some(text("description", description.to_string()))
+    }
+}
+
+fn field(f: &BitField) -> Element {
+    let mut field = element("field");
+    field.children.push(text("name", f.name.clone()));
+    field.children.extend(description(&f.name, &f.description));
+    field
+        .children
+        .push(text("bitOffset", f.bits.lsb().to_string()));
+    let mut reset = element("reset");
+    reset.children.push(text("value", hex(f.reset_value)));
+    let mut resets = element("resets");
+    resets.children.push(reset);
+    field.children.push(resets);
+    let width = f.bits.msb() - f.bits.lsb() + 1;
+    field.children.push(text("bitWidth", width.to_string()));
+    field
+        .children
+        .push(text("access", access(f.type_).to_string()));
+    field
+}
+
+fn register(r: &Register) -> Element {
+    let mut register = element("register");
+    register.children.push(text("name", r.name.clone()));
+    let note = match r.safety {
+        Some(safety) => format!("{}. {}", r.description.trim_end_matches('.'), safety.note()),
+        None => r.description.clone(),
+    };
+    register.children.extend(description(&r.name, &note));
+    register
+        .children
+        .push(text("addressOffset", hex(r.address)));
+    let size = match r.width {
+        0 => 32,
+        width => width,
+    };
+    register.children.push(text("size", size.to_string()));
+    let mut fields: Vec<&BitField> = r.bit_fields.iter().collect();
+    fields.sort_by_key(|f| f.bits.lsb());
+    register.children.extend(fields.into_iter().map(field));
+    register
+}
+
+fn address_block(name: &str, address: u32, p: &Peripheral) -> Element {
+    let mut block = element("addressBlock");
+    block.children.push(text("name", name.to_string()));
+    block.children.extend(description(name, &p.description));
+    block.children.push(text("baseAddress", hex(address)));
+    block
+        .children
+        .push(text("range", hex(p.address_block_size())));
+    block.children.push(text("width", "32".to_string()));
+    block.children.push(text("usage", "register".to_string()));
+    let mut registers: Vec<&Register> = p
+        .registers
+        .iter()
+        // IP-XACT only has alternate registers within alternate groups, which the model lacks
+        .filter(|r| r.alternate_register.is_none())
+        .collect();
+    registers.sort_by_key(|r| (r.address, r.name.clone()));
+    block.children.extend(registers.into_iter().map(register));
+    block
+}
+
+/// The `component` element describing all peripherals with registers, further instances get
+/// the same registers at their own base address
+pub fn ipxact_component(peripherals: &BTreeMap<String, Peripheral>) -> Element {
+    let mut component = element("component");
+    let mut namespaces = Namespace::empty();
+    namespaces.put(PREFIX, NAMESPACE);
+    namespaces.put("xsi", XSI_NAMESPACE);
+    component.namespaces = Some(namespaces);
+    component.attributes.insert(
+        "xsi:schemaLocation".to_string(),
+        format!("{0} {0}/index.xsd", NAMESPACE),
+    );
+    component
+        .children
+        .push(text("vendor", "espressif.com".to_string()));
+    component.children.push(text("library", "soc".to_string()));
+    component.children.push(text("name", "esp32".to_string()));
+    component.children.push(text("version", "1.0".to_string()));
+
+    let mut memory_map = element("memoryMap");
+    memory_map
+        .children
+        .push(text("name", "registers".to_string()));
+    // an address block needs a non-zero range
+    for (name, p) in peripherals.iter().filter(|(_, p)| !p.registers.is_empty()) {
+        memory_map.children.push(address_block(name, p.address, p));
+        for (i, address) in p.instance_addresses.iter().enumerate() {
+            let instance = format!("{}{}", name, i + 1);
+            memory_map
+                .children
+                .push(address_block(&instance, *address, p));
+        }
+    }
+    let mut memory_maps = element("memoryMaps");
+    memory_maps.children.push(memory_map);
+    component.children.push(memory_maps);
+    component
+}
+
+/// Write the IP-XACT component of `peripherals` into `out`, with `comment` as a comment after
+/// the xml declaration unless empty
+pub fn write_ipxact<W: Write>(
+    peripherals: &BTreeMap<String, Peripheral>,
+    comment: &str,
+    mut out: W,
+) -> Result<(), String> {
+    let mut encoded = vec![];
+    ipxact_component(peripherals)
+        .write(&mut encoded)
+        .map_err(|e| format!("Failed to write ip-xact: {}", e))?;
+    insert_comment(&mut encoded, comment);
+    out.write_all(&encoded)
+        .map_err(|e| format!("Failed to write ip-xact: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Bits;
+
+    #[test]
+    fn test_ipxact_component() {
+        let mut peripherals = BTreeMap::new();
+        peripherals.insert(
+            "UART".to_string(),
+            Peripheral {
+                description: "UART".to_string(),
+                address: 0x3ff4_0000,
+                instance_addresses: vec![0x3ff5_0000],
+                registers: vec![Register {
+                    name: "UART_CONF0".to_string(),
+                    description: "Configuration".to_string(),
+                    address: 0x20,
+                    bit_fields: vec![
+                        BitField {
+                            name: "UART_STOP_BIT_NUM".to_string(),
+                            bits: Bits::Range(4..=5),
+                            type_: Type::ReadWrite,
+                            reset_value: 1,
+                            ..Default::default()
+                        },
+                        BitField {
+                            name: "UART_PARITY".to_string(),
+                            bits: Bits::Single(0),
+                            type_: Type::ReadOnly,
+                            ..Default::default()
+                        },
+                    ],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+        );
+
+        let component = ipxact_component(&peripherals);
+        let blocks = &component.get_child("memoryMaps").unwrap().children[0].children;
+        let names: Vec<_> = blocks
+            .iter()
+            .filter_map(|b| b.get_child("name")?.text.clone())
+            .collect();
+        assert_eq!(names, vec!["UART", "UART1"]);
+        assert_eq!(
+            blocks[2].get_child("baseAddress").unwrap().text.as_deref(),
+            Some("'h3ff50000")
+        );
+        let register = blocks[1].get_child("register").unwrap();
+        assert_eq!(
+            register.get_child("addressOffset").unwrap().text.as_deref(),
+            Some("'h20")
+        );
+        let fields: Vec<_> = register
+            .children
+            .iter()
+            .filter(|c| c.name == "field")
+            .map(|f| {
+                let child = |name| f.get_child(name).unwrap().text.clone().unwrap();
+                (
+                    child("name"),
+                    child("bitOffset"),
+                    child("bitWidth"),
+                    child("access"),
+                )
+            })
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                (
+                    "UART_PARITY".into(),
+                    "0".into(),
+                    "1".into(),
+                    "read-only".into()
+                ),
+                (
+                    "UART_STOP_BIT_NUM".into(),
+                    "4".into(),
+                    "2".into(),
+                    "read-write".into()
+                ),
+            ]
+        );
+
+        let mut out = vec![];
+        write_ipxact(&peripherals, "banner\n", &mut out).unwrap();
+        let xml = String::from_utf8(out).unwrap();
+        assert!(xml.contains("?>\n<!--\nbanner\n-->\n<ipxact:component"));
+        assert!(
+            xml.contains("xmlns:ipxact=\"http://www.accellera.org/XMLSchema/IPXACT/1685-2014\"")
+        );
+        assert!(xml.contains("<ipxact:resets><ipxact:reset><ipxact:value>'h1</ipxact:value>"));
+    }
+}
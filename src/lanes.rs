@@ -0,0 +1,174 @@
+//! Byte access views of 32-bit registers, e.g. for writing single bytes to a FIFO register.
+//! Every selected register gets 8-bit alternate registers `<NAME>_BYTE<n>`, one per byte
+//! lane, carrying the fields that lie entirely within that byte.
+
+use crate::{svd, BitField, Bits, Peripheral, Register};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Byte lanes to emit for one register
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ByteLanes {
+    /// `PERIPHERAL.REGISTER`
+    pub path: String,
+    /// Lanes to emit, 0 being the least significant byte, all four when empty
+    #[serde(default)]
+    pub lanes: Vec<u8>,
+}
+
+/// Field `f` moved to byte `lane`, `None` if it isn't contained in that byte
+fn lane_field(f: &BitField, lane: u8) -> Option<BitField> {
+    let low = lane * 8;
+    if f.bits.lsb() < low || f.bits.msb() >= low + 8 {
+        return None;
+    }
+    let bits = match &f.bits {
+        Bits::Single(b) => Bits::Single(b - low),
+        Bits::Range(r) => Bits::Range(r.start() - low..=r.end() - low),
+    };
+    Some(BitField { bits, ..f.clone() })
+}
+
+/// The 8-bit alternate register for byte `lane` of `r`
+fn lane_register(r: &Register, lane: u8) -> Register {
+    Register {
+        name: format!("{}_BYTE{}", r.name, lane),
+        address: r.address + u32::from(lane),
+        width: 8,
+        access: r.access,
+        description: format!("Byte {} of {}", lane, r.name),
+        reset_value: (r.reset_value >> (lane * 8)) & 0xFF,
+        reset_mask: r.reset_mask.map(|mask| (mask >> (lane * 8)) & 0xFF),
+        detailed_description: None,
+        bit_fields: r
+            .bit_fields
+            .iter()
+            .filter_map(|f| lane_field(f, lane))
+            .collect(),
+        views: r.views.clone(),
+        provenance: r.provenance.clone(),
+        complete: false,
+        alternate_register: Some(r.name.clone()),
+        safety: r.safety,
+        strobes: None,
+        pair: None,
+        modified_write: r.modified_write,
+    }
+}
+
+/// Add the byte lane registers listed in `selected` to the model. Fields crossing a byte
+/// boundary are left out of the lanes. Returns the number of added registers
+pub fn add_byte_lanes(
+    peripherals: &mut BTreeMap<String, Peripheral>,
+    selected: &[ByteLanes],
+) -> Result<usize, String> {
+    let mut added = 0;
+    for s in selected {
+        let mut parts = s.path.splitn(2, '.');
+        let (pname, rname) = match (parts.next(), parts.next()) {
+            (Some(p), Some(r)) => (p, r),
+            _ => return Err(format!("{}: expected PERIPHERAL.REGISTER", s.path)),
+        };
+        let p = peripherals
+            .get_mut(pname)
+            .ok_or_else(|| format!("{}: no peripheral {}", s.path, pname))?;
+        let r = p
+            .registers
+            .iter()
+            .find(|r| r.name == rname)
+            .ok_or_else(|| format!("{}: no register {}", s.path, rname))?;
+        if r.width != 0 && u32::from(r.width) != svd::DEFAULT_SIZE {
+            return Err(format!("{}: only 32-bit registers can be split", s.path));
+        }
+        let lanes = if s.lanes.is_empty() {
+            vec![0, 1, 2, 3]
+        } else {
+            s.lanes.clone()
+        };
+        if let Some(lane) = lanes.iter().find(|l| **l > 3) {
+            return Err(format!("{}: invalid byte lane {}", s.path, lane));
+        }
+
+        let split: Vec<Register> = lanes.iter().map(|l| lane_register(r, *l)).collect();
+        let crossing: Vec<&str> = r
+            .bit_fields
+            .iter()
+            .filter(|f| f.bits.lsb() / 8 != f.bits.msb() / 8)
+            .map(|f| f.name.as_str())
+            .collect();
+        if !crossing.is_empty() {
+            println!(
+                "{}: fields {:?} cross a byte boundary and are left out of the byte lanes",
+                s.path, crossing
+            );
+        }
+        added += split.len();
+        p.registers.extend(split);
+        p.registers.sort_by_key(|r| r.address);
+    }
+    Ok(added)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_byte_lanes() {
+        let fifo = Register {
+            name: "UART_FIFO".to_string(),
+            address: 0x10,
+            reset_value: 0x1234,
+            bit_fields: vec![
+                BitField {
+                    name: "RXFIFO_RD_BYTE".to_string(),
+                    bits: Bits::Range(0..=7),
+                    ..Default::default()
+                },
+                BitField {
+                    name: "WIDE".to_string(),
+                    bits: Bits::Range(4..=11),
+                    ..Default::default()
+                },
+                BitField {
+                    name: "FLAG".to_string(),
+                    bits: Bits::Single(9),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let mut peripherals = BTreeMap::new();
+        peripherals.insert(
+            "UART".to_string(),
+            Peripheral {
+                registers: vec![fifo],
+                ..Default::default()
+            },
+        );
+        let selected = vec![ByteLanes {
+            path: "UART.UART_FIFO".to_string(),
+            lanes: vec![1, 0],
+        }];
+        assert_eq!(add_byte_lanes(&mut peripherals, &selected), Ok(2));
+
+        let registers = &peripherals["UART"].registers;
+        let names: Vec<&str> = registers.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["UART_FIFO", "UART_FIFO_BYTE0", "UART_FIFO_BYTE1"]
+        );
+        assert_eq!(registers[2].address, 0x11);
+        assert_eq!(registers[2].reset_value, 0x12);
+        assert_eq!(registers[2].bit_fields[0].name, "FLAG");
+        assert_eq!(registers[2].bit_fields[0].bits.lsb(), 1);
+        assert_eq!(registers[1].bit_fields.len(), 1);
+
+        let invalid = vec![ByteLanes {
+            path: "UART.UART_FIFO".to_string(),
+            lanes: vec![4],
+        }];
+        assert!(add_byte_lanes(&mut peripherals, &invalid).is_err());
+    }
+}
@@ -1,29 +1,137 @@
+use preprocess::preprocess;
 use regex::Regex;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::fmt;
 use std::fs::File;
 use std::io::prelude::*;
 use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
+pub mod aliases;
+pub mod append;
+pub mod arduino;
+pub mod arrays;
+pub mod baseline;
+pub mod browser;
+pub mod caps;
+pub mod changelog;
+pub mod chips;
+pub mod config;
+pub mod consts;
+pub mod diff;
+pub mod doc;
+pub mod efuse;
+pub mod enums;
+pub mod explain;
+pub mod expr;
+pub mod fetch;
+pub mod fixup;
+pub mod groups;
+pub mod headers;
+pub mod html;
+pub mod interrupts;
+pub mod iomux;
+pub mod ipxact;
+pub mod lanes;
+pub mod manifest;
+pub mod markdown;
+pub mod memmap;
+pub mod merge;
+pub mod metadata;
+pub mod metrics;
+pub mod nonos;
+pub mod pac;
+pub mod pairs;
+pub mod patch;
+pub mod pdf;
+pub mod preprocess;
+pub mod provenance;
+pub mod qemu;
+pub mod regmap;
+pub mod rename;
+pub mod renode;
+pub mod review;
+pub mod safety;
+pub mod sanitize;
+pub mod shorten;
+pub mod source;
+pub mod stats;
+pub mod strobes;
+pub mod structs;
+pub mod stub;
+pub mod svd;
+pub mod tables;
+pub mod unparsed;
+pub mod validate;
+pub mod view;
+pub mod writes;
+
+/// SoC header of the ESP8266 SDKs next to `soc.h`, with the watchdog and other registers
+/// defined by offset
+pub const EAGLE_SOC_H: &str = "eagle_soc.h";
+
 /* Regex's to find all the peripheral addresses */
 pub const REG_BASE: &'static str = r"\#define[\s*]+DR_REG_(.*)_BASE[\s*]+0x([0-9a-fA-F]+)";
+pub const PERIPHS_BASE: &str = r"\#define[\s*]+PERIPHS_(.*)_BASEADDR[\s*]+\(?0x([0-9a-fA-F]+)";
 pub const REG_DEF: &'static str = r"\#define[\s*]+([^\s*]+)_REG[\s*]+\(DR_REG_(.*)_BASE \+ (.*)\)";
 pub const REG_DEF_INDEX: &'static str =
-    r"\#define[\s*]+([^\s*]+)_REG\(i\)[\s*]+\(REG_([0-9A-Za-z_]+)_BASE[\s*]*\(i\) \+ (.*?)\)";
-pub const REG_BITS: &'static str =
-    r"\#define[\s*]+([^\s*]+)_(S|V)[\s*]+\(?(0x[0-9a-fA-F]+|[0-9]+)\)?";
+    r"\#define[\s*]+([^\s*]+)_REG\(i\)[\s*]+\(REG_([0-9A-Za-z_]+)_BASE[\s*]*\(i\) \+ (.*)\)[\s]*(?:/[*/].*)?$";
+pub const REG_DEF_ABS: &str = r"\#define[\s*]+([^\s*(]+)_REG[\s*]+\(?0x([0-9a-fA-F]+)\)?(?:\s|$)";
+pub const REG_BITS: &'static str = r"\#define[\s*]+([^\s*(]+)_(S|V|M)[\s*]+(.+?)[\s]*(?:/[*/].*)?$";
 pub const REG_BIT_INFO: &'static str =
     r"/\*[\s]+([0-9A-Za-z_]+)[\s]+:[\s]+([0-9A-Za-z_/]+)[\s]+;bitpos:\[(.*)\][\s];default:[\s]+(.*)[\s];[\s]\*/";
 pub const REG_DESC: &'static str = r"\*description:\s(.*[\n|\r|\r\n]?.*)\*/";
+pub const COMMENT: &str = r"(?:/\*+<?\s*(.*?)\s*\*+/|//+<?\s*(.*?))\s*$";
+pub const COUNT_DEF: &str = r"\#define[\s*]+([0-9A-Za-z_]+_NUM)[\s*]+(.+?)[\s]*(?:/[*/].*)?$";
 pub const INTERRUPTS: &'static str =
-    r"\#define[\s]ETS_([0-9A-Za-z_/]+)_SOURCE[\s]+([0-9]+)/\*\*<\s([0-9A-Za-z_/\s,]+)\*/";
+    r"\#define[\s]ETS_([0-9A-Za-z_/]+)_SOURCE[\s]+([0-9]+)[ \t]*/\*\*<((?s:.*?))\*/";
 
 #[derive(Debug, Default, Clone)]
 pub struct Peripheral {
     pub description: String,
+    /// Documented revision of the block
+    pub version: Option<String>,
     pub address: u32,
     pub registers: Vec<Register>,
+    /// Base addresses of further instances sharing this register block
+    pub instance_addresses: Vec<u32>,
+    /// Count macros such as `RMT_CHANNEL_NUM` defined for this peripheral
+    pub counts: BTreeMap<String, u32>,
+    pub interrupts: Vec<Interrupt>,
+    /// Where the base address was defined and changed
+    pub provenance: Vec<Provenance>,
+    /// Size of the address block when configured, instead of the span of the registers
+    pub block_size: Option<u32>,
+    /// Group of peripherals this one belongs to, e.g. `UART` for all UARTs
+    pub group_name: Option<String>,
+    /// Name of the C struct generated for it, when shared with other peripherals
+    pub header_struct_name: Option<String>,
+}
+impl Peripheral {
+    /// Size of the address block emitted for this peripheral, the configured size or the span
+    /// of its registers rounded up to whole words
+    pub fn address_block_size(&self) -> u32 {
+        if let Some(size) = self.block_size {
+            return size;
+        }
+        let end = self
+            .registers
+            .iter()
+            .map(|r| {
+                let width = match u32::from(r.width) {
+                    0 => svd::DEFAULT_SIZE,
+                    width => width,
+                };
+                r.address + width / 8
+            })
+            .max()
+            .unwrap_or(0);
+        (end + 3) & !3
+    }
 }
+
 #[derive(Clone, Debug, Default)]
 pub struct Interrupt {
     pub name: String,
@@ -39,13 +147,33 @@ pub struct Register {
     pub address: u32,
     /// Width
     pub width: u8,
+    /// Documented access of the whole register, otherwise taken from its fields
+    pub access: Option<Type>,
     /// Description
     pub description: String,
     /// Reset Value
     pub reset_value: u64,
+    /// Bits with a defined reset value, the device default when absent
+    pub reset_mask: Option<u32>,
     /// Detailed description
     pub detailed_description: Option<String>,
     pub bit_fields: Vec<BitField>,
+    /// Views this register is part of, empty for all views
+    pub views: Vec<String>,
+    /// Where the register was defined and changed
+    pub provenance: Vec<Provenance>,
+    /// Documented as fully described by its fields, so every bit should be covered
+    pub complete: bool,
+    /// Register this one is an alternate view of, e.g. a byte lane of a 32-bit register
+    pub alternate_register: Option<String>,
+    /// Set for registers that must not be accessed by ordinary code
+    pub safety: Option<safety::Safety>,
+    /// Registers setting and clearing the bits of this one
+    pub strobes: Option<strobes::Strobes>,
+    /// High half of the wider value this register holds the low half of
+    pub pair: Option<pairs::Pair>,
+    /// Effect of writing a one to the bits of its fields
+    pub modified_write: Option<writes::WriteValues>,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -60,6 +188,76 @@ pub struct BitField {
     pub reset_value: u32,
     /// Description
     pub description: String,
+    /// Where the field information was taken from
+    pub origin: Origin,
+    /// Views this field is part of, empty for all views
+    pub views: Vec<String>,
+    /// Where the field was defined and changed
+    pub provenance: Vec<Provenance>,
+    /// Effect of writing a one, the one of the register when absent
+    pub modified_write: Option<writes::WriteValues>,
+    /// Named values of the field, from a C enum of the SDK
+    pub enumerated_values: Vec<enums::EnumValue>,
+}
+
+/// Place an element of the model was defined or changed, for auditing the output
+#[derive(Debug, Clone, PartialEq)]
+pub enum Provenance {
+    /// A header, with the 1-based line when known
+    Header { file: String, line: Option<usize> },
+    /// A documentation file, doc JSON or register map
+    Doc { file: String },
+    /// A peripheral seed from the chip profile or config, with its documentation reference
+    Seed { reference: Option<String> },
+    /// Renamed by a patch from the config, from the given path
+    Patch { renamed_from: String },
+    /// Built in corrections for registers the headers describe incompletely
+    Curated,
+    /// A previously generated svd the run was appended to
+    Svd { file: String },
+}
+
+impl fmt::Display for Provenance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Provenance::Header {
+                file,
+                line: Some(line),
+            } => write!(f, "{}:{}", file, line),
+            Provenance::Header { file, line: None } => write!(f, "{}", file),
+            Provenance::Doc { file } => write!(f, "doc {}", file),
+            Provenance::Seed { reference: None } => write!(f, "seed"),
+            Provenance::Seed {
+                reference: Some(reference),
+            } => write!(f, "seed from {}", reference),
+            Provenance::Patch { renamed_from } => write!(f, "patch renaming {}", renamed_from),
+            Provenance::Curated => write!(f, "curated"),
+            Provenance::Svd { file } => write!(f, "svd {}", file),
+        }
+    }
+}
+
+/// Source of a bit field's position and width
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum Origin {
+    /// Stated by a `bitpos` annotation comment in the header
+    HeaderComment,
+    /// Derived from `_V`/`_S` or mask defines in the header
+    HeaderMask,
+    /// A single bit defined with `BIT(n)`
+    HeaderBit,
+    /// Taken from external documentation
+    Doc,
+    /// Not stated anywhere, e.g. a register treated as one full width field
+    #[default]
+    Assumed,
+}
+
+impl Origin {
+    /// Whether the width of the field was inferred rather than stated
+    pub fn is_inferred(self) -> bool {
+        self == Origin::Assumed
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -68,6 +266,28 @@ pub enum Bits {
     Range(RangeInclusive<u8>),
 }
 
+impl Bits {
+    /// Lowest bit covered
+    pub fn lsb(&self) -> u8 {
+        match self {
+            Bits::Single(b) => *b,
+            Bits::Range(r) => *r.start(),
+        }
+    }
+
+    /// Highest bit covered
+    pub fn msb(&self) -> u8 {
+        match self {
+            Bits::Single(b) => *b,
+            Bits::Range(r) => *r.end(),
+        }
+    }
+
+    pub fn overlaps(&self, other: &Bits) -> bool {
+        self.lsb() <= other.msb() && other.lsb() <= self.msb()
+    }
+}
+
 impl Default for Bits {
     fn default() -> Self {
         Bits::Single(0)
@@ -76,7 +296,7 @@ impl Default for Bits {
 
 use svd_parser::Access;
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum Type {
     // ReadAsZero,
     ReadOnly,
@@ -118,159 +338,507 @@ impl FromStr for Type {
     }
 }
 
+/// Callback deriving the emitted name of a bit field from its owning peripheral,
+/// register and the name found in the headers
+pub type FieldNamer = fn(&Peripheral, &Register, &str) -> String;
+
+/// Which spelling of a peripheral base address wins when both are defined
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum BasePrecedence {
+    /// `DR_REG_*_BASE`
+    #[default]
+    DrReg,
+    /// `PERIPHS_*_BASEADDR`
+    Periphs,
+}
+
+impl FromStr for BasePrecedence {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<BasePrecedence, Self::Err> {
+        Ok(match s {
+            "dr-reg" => BasePrecedence::DrReg,
+            "periphs" => BasePrecedence::Periphs,
+            _ => return Err(String::from("Invalid base precedence: ") + &String::from(s)),
+        })
+    }
+}
+
+/// Selection of the peripherals to parse and emit
+#[derive(Debug, Default, Clone)]
+pub struct PeripheralFilter {
+    /// Only keep these peripherals, all are kept when empty
+    pub only: Vec<String>,
+    /// Drop these peripherals, applied after `only`
+    pub exclude: Vec<String>,
+}
+
+impl PeripheralFilter {
+    pub fn includes(&self, peripheral: &str) -> bool {
+        (self.only.is_empty() || self.only.iter().any(|p| p == peripheral))
+            && !self.exclude.iter().any(|p| p == peripheral)
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct ParseOptions {
+    /// Optional hook used to rename every parsed bit field
+    pub field_namer: Option<FieldNamer>,
+    /// Preprocessor defines used to pick the active branch of `#if` blocks
+    pub defines: HashMap<String, String>,
+    /// Spelling used when a base address is defined twice
+    pub base_precedence: BasePrecedence,
+    /// Glob patterns selecting the register headers, all `*_reg.h` files next to `soc.h`
+    /// are used when empty
+    pub header_globs: Vec<String>,
+    /// Text replacements applied to the headers before parsing
+    pub replacements: Vec<config::Replacement>,
+    /// Peripherals added to the model before parsing the register headers
+    pub seeds: Vec<config::PeripheralSeed>,
+    /// Capability macros of `soc_caps.h`, their counts give the number of instances of seeded
+    /// peripherals without configured base addresses
+    pub capabilities: BTreeMap<String, i64>,
+    /// Peripherals to parse, registers of the others are skipped
+    pub filter: PeripheralFilter,
+    /// Interrupts not defined in `soc.h`, such as the NMI
+    pub interrupts: Vec<config::FixedInterrupt>,
+    /// Print every step of the register parser that matched a regex or changed its state
+    pub trace: bool,
+}
+
+/// What the header parser couldn't make sense of, also printed at the end of parsing
+#[derive(Debug, Clone, Default)]
+pub struct ParseReport {
+    /// Register headers without anything parsable
+    pub empty_files: Vec<String>,
+    /// Register headers that couldn't be read, e.g. binary files, with the error
+    pub skipped_files: Vec<String>,
+    /// Interrupts that belong to no peripheral
+    pub unassigned_interrupts: Vec<String>,
+    /// Peripherals without a base address, their registers are dropped
+    pub unresolved_peripherals: Vec<String>,
+    /// Registers whose definition couldn't be parsed
+    pub dropped_registers: Vec<String>,
+    /// Fields with an invalid `bitpos` annotation, with the annotation
+    pub invalid_bit_fields: Vec<(String, String)>,
+    /// Malformed register definitions that were kept
+    pub recovered: Vec<String>,
+    /// Fields whose `_M`, `_V` and `_S` defines disagree with each other or with the bits of
+    /// the field, usually a typo in the header
+    pub inconsistent_masks: Vec<String>,
+    /// Defines no pattern of the parser matched, by file
+    pub unparsed: unparsed::Unparsed,
+    /// Fields defined by a mask of zero, which aren't added to the model
+    pub zero_width_fields: Vec<validate::Issue>,
+}
+
 enum State {
     FindReg,
     FindBitFieldInfo(String, Register),
     FindDescription(String, Register, BitField),
     CheckEnd(String, Register),
+    /// Collecting the fields of a register without annotations from its `_S`/`_V` defines
+    Recover(String, Register),
 }
 
-pub fn parse_idf(path: &str) -> HashMap<String, Peripheral> {
-    let mut peripherals = HashMap::new();
+impl State {
+    fn name(&self) -> &'static str {
+        match self {
+            State::FindReg => "FindReg",
+            State::FindBitFieldInfo(..) => "FindBitFieldInfo",
+            State::FindDescription(..) => "FindDescription",
+            State::CheckEnd(..) => "CheckEnd",
+            State::Recover(..) => "Recover",
+        }
+    }
+}
+
+/// One line handled by the state machine, for `ParseOptions::trace`
+struct Step {
+    line: usize,
+    from: &'static str,
+    /// Name of the matched regex and its capture groups
+    matched: Option<(&'static str, Vec<String>)>,
+}
+
+/// Record the regex matched in this step, when tracing
+fn note(step: &mut Option<Step>, regex: &'static str, captures: Option<&regex::Captures>) {
+    if let Some(step) = step {
+        let groups = captures.map_or(vec![], |c| {
+            c.iter()
+                .skip(1)
+                .map(|g| g.map_or("", |g| g.as_str()).to_string())
+                .collect()
+        });
+        step.matched = Some((regex, groups));
+    }
+}
+
+/// Print a step that matched a regex or changed the state
+fn trace(file: &str, step: Step, to: &State) {
+    if step.matched.is_none() && step.from == to.name() {
+        return;
+    }
+    let matched = match step.matched {
+        Some((regex, groups)) if groups.is_empty() => format!(" {}", regex),
+        Some((regex, groups)) => format!(" {} {:?}", regex, groups),
+        None => String::new(),
+    };
+    println!(
+        "trace {}:{} {} -> {}{}",
+        file,
+        step.line + 1,
+        step.from,
+        to.name(),
+        matched
+    );
+}
+
+pub fn parse_idf(path: &str) -> BTreeMap<String, Peripheral> {
+    let options = ParseOptions {
+        seeds: config::default_seeds(),
+        ..Default::default()
+    };
+    parse_idf_with_options(path, &options)
+}
+
+pub fn parse_idf_with_options(path: &str, options: &ParseOptions) -> BTreeMap<String, Peripheral> {
+    parse_idf_with_report(path, options).0
+}
+
+/// Parse the headers like `parse_idf_with_options`, along with what couldn't be parsed
+pub fn parse_idf_with_report(
+    path: &str,
+    options: &ParseOptions,
+) -> (BTreeMap<String, Peripheral>, ParseReport) {
+    let mut peripherals = BTreeMap::new();
     let mut invalid_peripherals = vec![];
     let mut invalid_files = vec![];
+    let mut skipped_files = vec![];
     let mut invalid_registers = vec![];
     let mut invalid_bit_fields = vec![];
-
-    let mut interrupts = vec![];
+    let mut recovered = vec![];
+    let mut zero_width_fields = vec![];
+    let mut mask_defines: HashMap<String, MaskDefines> = HashMap::new();
+    // defines no regex matched, checked against the parsed fields once all files are read
+    let mut unmatched = vec![];
 
     let filname = path.to_owned() + "soc.h";
-    let re_base = Regex::new(REG_BASE).unwrap();
     let re_reg = Regex::new(REG_DEF).unwrap();
     let re_reg_index = Regex::new(REG_DEF_INDEX).unwrap();
+    let re_reg_abs = Regex::new(REG_DEF_ABS).unwrap();
     let re_reg_desc = Regex::new(REG_DESC).unwrap();
     let re_reg_bit_info = Regex::new(REG_BIT_INFO).unwrap();
-    let re_interrupts = Regex::new(INTERRUPTS).unwrap();
+    let re_comment = Regex::new(COMMENT).unwrap();
+    let re_count = Regex::new(COUNT_DEF).unwrap();
+    let re_reg_bits = Regex::new(REG_BITS).unwrap();
+    let is_reg_def = |line: &str| {
+        re_reg.is_match(line) || re_reg_index.is_match(line) || re_reg_abs.is_match(line)
+    };
 
-    let soc_h = file_to_string(&filname);
+    let soc_h = file_to_string(filname.as_ref()).unwrap();
+    let soc_h = config::apply_replacements(&options.replacements, filname.as_ref(), soc_h);
+    let mut soc_defines = options.defines.clone();
+    let soc_h = preprocess(&soc_h, &mut soc_defines)
+        .into_iter()
+        .map(|(_, line)| line.into_owned())
+        .collect::<Vec<_>>()
+        .join("\n");
 
-    for captures in re_interrupts.captures_iter(soc_h.as_str()) {
-        let name = &captures[1];
-        let index = &captures[2];
-        let desc = &captures[3];
-        let intr = Interrupt {
-            name: name.to_string(),
-            description: Some(desc.to_string()),
-            value: index.parse().unwrap(),
+    let interrupts = scrape_interrupts(&soc_h);
+
+    /* Peripheral base addresses */
+    let bases = base_addresses(&soc_h, options.base_precedence);
+    for (peripheral, address) in bases {
+        let p = Peripheral {
+            address,
+            description: peripheral.to_string(),
+            provenance: vec![Provenance::Header {
+                file: filname.clone(),
+                line: None,
+            }],
+            ..Default::default()
         };
-        interrupts.push(intr);
-        // println!("{:#?}", intr);
+
+        peripherals.insert(peripheral, p);
     }
 
-    /*
-       Theses are indexed, we seed these as they cannot be derived from the docs
-       These blocks are identical, so we need to do some post processing to properly index
-       and offset these
-    */
-    peripherals.insert("I2C".to_string(), Peripheral::default());
-    peripherals.insert("SPI".to_string(), Peripheral::default());
-    peripherals.insert("TIMG".to_string(), Peripheral::default());
-    peripherals.insert("MCPWM".to_string(), Peripheral::default());
-    peripherals.insert("UHCI".to_string(), Peripheral::default());
+    /* Seeded peripherals, for base addresses that cannot be derived from the headers */
+    for seed in &options.seeds {
+        let p = peripherals
+            .entry(seed.name.clone())
+            .or_insert_with(|| Peripheral {
+                description: seed.name.clone(),
+                ..Default::default()
+            });
+        if seed.block_size.is_some() {
+            p.block_size = seed.block_size;
+        }
+        if !seed.base_addresses.is_empty() && seed.base_addresses.len() != seed.instances {
+            println!(
+                "Seed for {} lists {} base addresses for {} instances",
+                seed.name,
+                seed.base_addresses.len(),
+                seed.instances
+            );
+        }
+        if let Some((&first, rest)) = seed.base_addresses.split_first() {
+            if p.address != 0 && p.address != first {
+                println!(
+                    "Seed overrides base address of {} ({:#x} -> {:#x})",
+                    seed.name, p.address, first
+                );
+            }
+            p.address = first;
+            p.instance_addresses = rest.to_vec();
+            p.provenance.push(Provenance::Seed {
+                reference: seed.reference.clone(),
+            });
+            continue;
+        }
+        if p.provenance.is_empty() {
+            p.provenance.push(Provenance::Seed {
+                reference: seed.reference.clone(),
+            });
+        }
+        // an indexed peripheral, instances from the capabilities unless configured
+        let count = if seed.instances > 1 {
+            Some(seed.instances)
+        } else {
+            caps::instance_count(&options.capabilities, &seed.name)
+        };
+        let bases =
+            count.and_then(|count| caps::instance_bases(&soc_h, &seed.name, count, &soc_defines));
+        if let Some((&first, rest)) = bases.as_ref().and_then(|b| b.split_first()) {
+            if p.address == 0 || p.address == first {
+                p.address = first;
+                p.instance_addresses = rest.to_vec();
+                p.provenance.push(Provenance::Header {
+                    file: filname.clone(),
+                    line: None,
+                });
+            }
+        }
+    }
 
-    /* Peripheral base addresses */
-    for captures in re_base.captures_iter(soc_h.as_str()) {
-        let peripheral = &captures[1];
-        let address = &captures[2];
-        let mut p = Peripheral::default();
-        p.address = u32::from_str_radix(address, 16).unwrap();
-        p.description = peripheral.to_string();
+    for name in options.filter.only.iter() {
+        if !peripherals.contains_key(name) {
+            println!("No peripheral called {} to parse", name);
+        }
+    }
+    peripherals.retain(|name, _| options.filter.includes(name));
 
-        peripherals.insert(peripheral.to_string(), p);
+    /* Interrupts belong to the peripheral with the longest name they start with */
+    let mut unassigned_interrupts = vec![];
+    let fixed = options.interrupts.iter().map(|i| {
+        let intr = Interrupt {
+            name: i.name.clone(),
+            description: i.description.clone(),
+            value: i.value,
+        };
+        (Some(i.peripheral.clone()), intr)
+    });
+    for (owner, intr) in interrupts.into_iter().map(|i| (None, i)).chain(fixed) {
+        let owner = owner.or_else(|| {
+            peripherals
+                .keys()
+                .filter(|p| intr.name.starts_with(p.as_str()))
+                .max_by_key(|p| p.len())
+                .cloned()
+        });
+        match owner.and_then(|owner| peripherals.get_mut(&owner)) {
+            Some(p) => p.interrupts.push(intr),
+            None => unassigned_interrupts.push(intr.name),
+        }
     }
 
-    std::fs::read_dir(path)
-        .unwrap()
-        .filter_map(Result::ok)
-        .filter(|f| f.path().to_str().unwrap().ends_with("_reg.h"))
+    header_files(path, &options.header_globs)
+        .into_iter()
         .for_each(|f| {
-            let name = f.path();
-            let name = name.to_str().unwrap();
+            let name = f.to_string_lossy();
+            let name: &str = &name;
             let mut buffer = vec![];
-            let file_data = file_to_string(name);
+            let file_data = match file_to_string(&f) {
+                Ok(data) => data,
+                Err(e) => {
+                    println!("Skipping {}", e);
+                    skipped_files.push(e);
+                    return;
+                }
+            };
+            let file_data = config::apply_replacements(&options.replacements, &f, file_data);
             // println!("Searching {}", name);
             let mut something_found = false;
             let mut state = State::FindReg;
-            for (i, line) in file_data.lines().enumerate() {
+            let mut last_comment = None;
+            // shift and value of the `_S`/`_V` defines seen while recovering a register
+            let mut masks: HashMap<String, (Option<u64>, Option<u64>)> = HashMap::new();
+            let mut symbols = soc_defines.clone();
+            let lines = preprocess(&file_data, &mut symbols);
+            // values like `(I2S_FOO_V << I2S_FOO_S)` refer to other defines
+            let define_value = |text: &str| {
+                expr::eval(text, &|n| preprocess::resolve(&symbols, n))
+                    .ok()
+                    .and_then(|v| u64::try_from(v).ok())
+            };
+            let here = |i: usize| Provenance::Header {
+                file: name.to_string(),
+                line: Some(i + 1),
+            };
+            // the state and matched regex of the last step, only kept when tracing
+            let mut step: Option<Step> = None;
+            for (i, line) in &lines {
+                let line: &str = line;
                 loop {
+                    if let Some(step) = step.take() {
+                        trace(name, step, &state);
+                    }
+                    if options.trace {
+                        step = Some(Step {
+                            line: *i,
+                            from: state.name(),
+                            matched: None,
+                        });
+                    }
                     match state {
                         State::FindReg => {
                             /* Normal register definitions */
                             if let Some(m) = re_reg.captures(line) {
+                                note(&mut step, "REG_DEF", Some(&m));
                                 let reg_name = &m[1];
                                 let pname = &m[2];
-                                let offset = &m[3].trim_start_matches("0x");
+                                let offset = &m[3];
                                 if reg_name.ends_with("(i)") {
                                     invalid_registers.push(reg_name.to_string());
                                     // some indexed still get through, ignore them
                                     break;
                                 }
-                                if let Ok(addr) = u32::from_str_radix(offset, 16) {
-                                    let mut r = Register::default();
-                                    r.description = reg_name.to_string();
-                                    r.name = reg_name.to_string();
-                                    r.address = addr;
+                                if let Some(addr) = parse_offset(offset, &symbols) {
+                                    let r = Register {
+                                        name: reg_name.to_string(),
+                                        description: reg_name.to_string(),
+                                        address: addr,
+                                        provenance: vec![here(*i)],
+                                        ..Default::default()
+                                    };
                                     state = State::FindBitFieldInfo(pname.to_string(), r);
                                 } else {
                                     invalid_registers.push(reg_name.to_string());
                                 }
                             } else if let Some(m) = re_reg_index.captures(line) {
+                                note(&mut step, "REG_DEF_INDEX", Some(&m));
                                 let reg_name = &m[1];
                                 let pname = &m[2];
-                                let offset = &m[3].trim_start_matches("0x");
+                                let offset = &m[3];
 
-                                if let Ok(addr) = u32::from_str_radix(offset, 16) {
-                                    let mut r = Register::default();
-                                    r.name = reg_name.to_string();
-                                    r.description = reg_name.to_string();
-                                    r.address = addr;
+                                if let Some(addr) = parse_offset(offset, &symbols) {
+                                    let r = Register {
+                                        name: reg_name.to_string(),
+                                        description: reg_name.to_string(),
+                                        address: addr,
+                                        provenance: vec![here(*i)],
+                                        ..Default::default()
+                                    };
                                     state = State::FindBitFieldInfo(pname.to_string(), r);
                                 } else {
                                     invalid_registers.push(reg_name.to_string());
                                 }
+                            } else if let Some(m) = re_reg_abs.captures(line) {
+                                note(&mut step, "REG_DEF_ABS", Some(&m));
+                                /* Absolute addresses, not relative to any base */
+                                let reg_name = &m[1];
+                                if let Ok(addr) = u32::from_str_radix(&m[2], 16) {
+                                    let pname =
+                                        peripheral_for_address(&mut peripherals, reg_name, addr);
+                                    let base = peripherals[&pname].address;
+
+                                    let r = Register {
+                                        name: reg_name.to_string(),
+                                        description: reg_name.to_string(),
+                                        address: addr - base,
+                                        provenance: vec![here(*i)],
+                                        ..Default::default()
+                                    };
+                                    state = State::FindBitFieldInfo(pname, r);
+                                } else {
+                                    invalid_registers.push(reg_name.to_string());
+                                }
+                            }
+
+                            /* Trailing or preceding comments describe the register */
+                            let comment = comment_text(&re_comment, line);
+                            if let State::FindBitFieldInfo(_, ref mut r) = state {
+                                if let Some(desc) = comment.or_else(|| last_comment.take()) {
+                                    r.description = desc;
+                                }
+                            } else if line.trim_start().starts_with('/') {
+                                last_comment = comment;
+                            } else {
+                                last_comment = None;
                             }
                             break; // next line
                         }
                         State::FindBitFieldInfo(ref mut pname, ref mut reg) => {
                             something_found = true;
-                            if let Some(m) = re_reg_bit_info.captures(line) {
-                                let bf_name = &m[1];
-                                let access_type = &m[2]; // TODO
-                                let bits = &mut m[3].split(':');
-                                let _default_val = &m[4]; // TODO
-                                let bits = match (bits.next(), bits.next()) {
-                                    (Some(h), Some(l)) => {
-                                        Bits::Range(l.parse().unwrap()..=h.parse().unwrap())
-                                    }
-                                    (Some(b), None) => Bits::Single(b.parse().unwrap()),
-                                    _ => {
-                                        // println!("Failed to parse bitpos {}", &m[3]);
-                                        invalid_bit_fields
-                                            .push((bf_name.to_string(), m[3].to_string()));
-                                        continue;
-                                    }
-                                };
-
-                                let bf = BitField {
-                                    name: bf_name.to_string(),
-                                    bits,
-                                    type_: Type::from_str(access_type).unwrap_or_else(|s| {
-                                        println!("{}", s);
-                                        Type::default()
-                                    }),
-                                    reset_value: 0,
-                                    ..Default::default()
-                                };
-                                state = State::FindDescription(pname.clone(), reg.clone(), bf);
-                            } else {
-                                println!("Failed to match reg info at {}:{}", name, i);
-                                state = State::FindReg;
-                            }
+                            let m = match re_reg_bit_info.captures(line) {
+                                Some(m) => m,
+                                None => {
+                                    recovered.push(format!(
+                                        "{}:{}: {} has no bitpos annotation, using its _S/_V defines",
+                                        name,
+                                        i + 1,
+                                        reg.name
+                                    ));
+                                    state = State::Recover(pname.clone(), reg.clone());
+                                    continue; // same line
+                                }
+                            };
+                            note(&mut step, "REG_BIT_INFO", Some(&m));
+                            let bf_name = &m[1];
+                            let access_type = &m[2]; // TODO
+                            let _default_val = &m[4]; // TODO
+                            let bits = match parse_bitpos(&m[3]) {
+                                Some(bits) => bits,
+                                None => {
+                                    invalid_bit_fields
+                                        .push((bf_name.to_string(), m[3].to_string()));
+                                    state = State::Recover(pname.clone(), reg.clone());
+                                    break; // next line
+                                }
+                            };
+
+                            let bf = BitField {
+                                name: bf_name.to_string(),
+                                bits,
+                                type_: Type::from_str(access_type).unwrap_or_else(|s| {
+                                    println!("{}", s);
+                                    Type::default()
+                                }),
+                                reset_value: 0,
+                                origin: Origin::HeaderComment,
+                                provenance: vec![here(*i)],
+                                ..Default::default()
+                            };
+                            state = State::FindDescription(pname.clone(), reg.clone(), bf);
                             break; // next line
                         }
                         State::FindDescription(ref mut pname, ref mut reg, ref mut bf) => {
+                            if buffer.is_empty() && line.trim_start().starts_with("#define") {
+                                // the description is missing, keep the field without one
+                                recovered.push(format!(
+                                    "{}:{}: {} has no description",
+                                    name,
+                                    i + 1,
+                                    bf.name
+                                ));
+                                reg.bit_fields.push(bf.clone());
+                                state = State::CheckEnd(pname.clone(), reg.clone());
+                                continue; // same line
+                            }
                             buffer.push(line);
-                            if let Some(_m) = re_reg_desc.captures(buffer.join("").as_str()) {
+                            if let Some(m) = re_reg_desc.captures(buffer.join(" ").as_str()) {
+                                note(&mut step, "REG_DESC", Some(&m));
+                                bf.description = clean_comment(&m[1]);
                                 buffer.clear();
                                 reg.bit_fields.push(bf.clone()); // add the bit field to the reg
                                 state = State::CheckEnd(pname.clone(), reg.clone());
@@ -281,19 +849,107 @@ pub fn parse_idf(path: &str) -> HashMap<String, Peripheral> {
                             if line.is_empty() {
                                 // println!("{} Adding {:#?}", pname, reg);
                                 // were done with this register
-                                if let Some(p) = peripherals.get_mut(&pname.to_string()) {
-                                    p.registers.push(reg.clone());
-                                } else {
-                                    // TODO indexed peripherals wont come up here
-                                    // println!("No periphal called {}", pname.to_string());
-                                    invalid_peripherals.push(pname.to_string());
-                                }
+                                push_register(
+                                    &mut peripherals,
+                                    &mut invalid_peripherals,
+                                    &options.filter,
+                                    pname,
+                                    reg.clone(),
+                                );
                                 state = State::FindReg;
                                 break; // next line
                             } else if re_reg_bit_info.is_match(line) {
+                                note(&mut step, "REG_BIT_INFO", None);
                                 // weve found the next bit field in the reg
                                 state = State::FindBitFieldInfo(pname.clone(), reg.clone());
+                            } else if is_reg_def(line) {
+                                note(&mut step, "register define", None);
+                                // the next register starts without a blank line in between
+                                push_register(
+                                    &mut peripherals,
+                                    &mut invalid_peripherals,
+                                    &options.filter,
+                                    pname,
+                                    reg.clone(),
+                                );
+                                state = State::FindReg;
                             } else {
+                                if let Some(m) = re_reg_bits.captures(line) {
+                                    note(&mut step, "REG_BITS", Some(&m));
+                                    mask_defines
+                                        .entry(m[1].to_string())
+                                        .or_insert_with(|| MaskDefines::at(name, *i))
+                                        .set(&m[2], define_value(&m[3]));
+                                }
+                                break; // next line
+                            }
+                        }
+                        State::Recover(ref mut pname, ref mut reg) => {
+                            if line.is_empty() || is_reg_def(line) {
+                                if reg.bit_fields.is_empty() {
+                                    recovered.push(format!(
+                                        "{}:{}: {} kept without fields",
+                                        name,
+                                        i + 1,
+                                        reg.name
+                                    ));
+                                }
+                                reg.bit_fields.sort_by_key(|f| f.bits.lsb());
+                                masks.clear();
+                                push_register(
+                                    &mut peripherals,
+                                    &mut invalid_peripherals,
+                                    &options.filter,
+                                    pname,
+                                    reg.clone(),
+                                );
+                                state = State::FindReg;
+                                if line.is_empty() {
+                                    break; // next line
+                                }
+                            } else if re_reg_bit_info.is_match(line) {
+                                note(&mut step, "REG_BIT_INFO", None);
+                                // back in sync with the annotations
+                                state = State::FindBitFieldInfo(pname.clone(), reg.clone());
+                            } else {
+                                if let Some(m) = re_reg_bits.captures(line) {
+                                    note(&mut step, "REG_BITS", Some(&m));
+                                    let value = define_value(&m[3]);
+                                    mask_defines
+                                        .entry(m[1].to_string())
+                                        .or_insert_with(|| MaskDefines::at(name, *i))
+                                        .set(&m[2], value);
+                                    if &m[2] != "S" && value == Some(0) {
+                                        let issue = validate::Issue::ZeroWidthField {
+                                            peripheral: pname.clone(),
+                                            register: reg.name.clone(),
+                                            field: m[1].to_string(),
+                                        };
+                                        if !zero_width_fields.contains(&issue) {
+                                            zero_width_fields.push(issue);
+                                        }
+                                    }
+                                    let field = masks.entry(m[1].to_string()).or_default();
+                                    match (&m[2], value) {
+                                        ("S", _) => field.0 = value,
+                                        ("V", _) => field.1 = value,
+                                        // a mask gives the shift and value not defined themselves
+                                        (_, Some(mask)) if mask != 0 => {
+                                            let shift = field
+                                                .0
+                                                .unwrap_or_else(|| u64::from(mask.trailing_zeros()));
+                                            field.0 = Some(shift);
+                                            field.1 = field.1.or(Some(mask >> shift));
+                                        }
+                                        _ => {}
+                                    }
+                                    if let Some(bf) = mask_field(&m[1], *field, here(*i)) {
+                                        if reg.bit_fields.iter().all(|f| !f.bits.overlaps(&bf.bits))
+                                        {
+                                            reg.bit_fields.push(bf);
+                                        }
+                                    }
+                                }
                                 break; // next line
                             }
                         }
@@ -301,12 +957,100 @@ pub fn parse_idf(path: &str) -> HashMap<String, Peripheral> {
                 }
             }
 
+            if let Some(step) = step.take() {
+                trace(name, step, &state);
+            }
+
+            /* Registers still open at the end of the file */
+            let unfinished = match state {
+                State::FindReg | State::FindBitFieldInfo(..) => None,
+                State::FindDescription(pname, mut reg, bf) => {
+                    reg.bit_fields.push(bf);
+                    Some((pname, reg))
+                }
+                State::CheckEnd(pname, reg) | State::Recover(pname, reg) => Some((pname, reg)),
+            };
+            if let Some((pname, reg)) = unfinished {
+                recovered.push(format!(
+                    "{}: {} ends at the end of the file",
+                    name, reg.name
+                ));
+                push_register(
+                    &mut peripherals,
+                    &mut invalid_peripherals,
+                    &options.filter,
+                    &pname,
+                    reg,
+                );
+            }
+
+            /* Count macros of channelised registers */
+            for (_, line) in &lines {
+                if let Some(m) = re_count.captures(line) {
+                    let count_name = &m[1];
+                    let owner = peripherals
+                        .keys()
+                        .filter(|p| {
+                            count_name.starts_with(&format!("{}_", p))
+                                || count_name.contains(&format!("_{}_", p))
+                        })
+                        .max_by_key(|p| p.len())
+                        .cloned();
+                    let count = expr::eval(&m[2], &|n| preprocess::resolve(&symbols, n))
+                        .ok()
+                        .and_then(|c| u32::try_from(c).ok());
+                    if let (Some(owner), Some(count)) = (owner, count) {
+                        peripherals
+                            .get_mut(&owner)
+                            .unwrap()
+                            .counts
+                            .insert(count_name.to_string(), count);
+                    }
+                }
+            }
+
+            for (i, line) in &lines {
+                let define = line.trim_start().starts_with("#define");
+                if define
+                    && !is_reg_def(line)
+                    && !re_count.is_match(line)
+                    && !re_reg_bits.is_match(line)
+                {
+                    unmatched.push((name.to_string(), i + 1, line.trim().to_string()));
+                }
+            }
+
             // log if nothing was parsed in this file
             if !something_found {
                 invalid_files.push(String::from(name))
             }
         });
 
+    /* Registers the SoC headers define by offset, like the watchdog of the ESP8266 SDKs */
+    let mut soc_headers = vec![(filname.clone(), soc_h.clone())];
+    let eagle_soc_h = PathBuf::from(format!("{}{}", path, EAGLE_SOC_H));
+    if eagle_soc_h.exists() {
+        match file_to_string(&eagle_soc_h) {
+            Ok(text) => soc_headers.push((
+                eagle_soc_h.display().to_string(),
+                config::apply_replacements(&options.replacements, &eagle_soc_h, text),
+            )),
+            Err(e) => {
+                println!("Skipping {}", e);
+                skipped_files.push(e);
+            }
+        }
+    }
+    add_offset_registers(
+        &mut peripherals,
+        &soc_headers,
+        &options.filter,
+        &mut zero_width_fields,
+    );
+
+    invalid_peripherals.sort();
+    invalid_peripherals.dedup();
+
     println!("Parsed idf for peripherals information.");
 
     if invalid_files.len() > 0 {
@@ -316,6 +1060,17 @@ pub fn parse_idf(path: &str) -> HashMap<String, Peripheral> {
         );
     }
 
+    if !skipped_files.is_empty() {
+        println!("The following files could not be read {:?}", skipped_files);
+    }
+
+    if !unassigned_interrupts.is_empty() {
+        println!(
+            "The following interrupts belong to no peripheral {:?}",
+            unassigned_interrupts
+        );
+    }
+
     if invalid_peripherals.len() > 0 {
         println!(
             "The following peripherals failed to parse {:?}",
@@ -337,14 +1092,433 @@ pub fn parse_idf(path: &str) -> HashMap<String, Peripheral> {
         );
     }
 
-    // println!("Interrupt information: {:#?}", interrupts);
+    if !recovered.is_empty() {
+        println!("Recovered from malformed register definitions:");
+        for r in &recovered {
+            println!("  {}", r);
+        }
+    }
+
+    // absolute registers can add peripherals while parsing
+    peripherals.retain(|name, _| options.filter.includes(name));
+
+    // keep the output stable between runs
+    for p in peripherals.values_mut() {
+        p.registers.sort_by_key(|r| r.address);
+        for r in p.registers.iter_mut() {
+            r.bit_fields.sort_by_key(|f| f.bits.lsb());
+        }
+    }
+
+    let mut inconsistent_masks = vec![];
+    for r in peripherals.values().flat_map(|p| p.registers.iter()) {
+        for f in &r.bit_fields {
+            let defines = match mask_defines.get(&f.name) {
+                Some(defines) => defines,
+                None => continue,
+            };
+            if let Some(problem) = defines.inconsistency(&f.bits) {
+                inconsistent_masks.push(format!("{}: {} {}", defines.location, f.name, problem));
+            }
+        }
+    }
+    if !inconsistent_masks.is_empty() {
+        println!("Fields whose _M/_V/_S defines disagree:");
+        for m in &inconsistent_masks {
+            println!("  {}", m);
+        }
+    }
+
+    let unparsed = unparsed::quarantine(unmatched, &peripherals);
+    if !unparsed.is_empty() {
+        println!(
+            "{} defines matched no pattern of the parser",
+            unparsed.values().map(Vec::len).sum::<usize>()
+        );
+    }
+
+    if let Some(namer) = options.field_namer {
+        rename_fields(&mut peripherals, namer);
+    }
+
+    let report = ParseReport {
+        empty_files: invalid_files,
+        skipped_files,
+        unassigned_interrupts,
+        unresolved_peripherals: invalid_peripherals,
+        dropped_registers: invalid_registers,
+        invalid_bit_fields,
+        recovered,
+        inconsistent_masks,
+        unparsed,
+        zero_width_fields,
+    };
+    (peripherals, report)
+}
+
+/// Add the registers `soc_headers` define by their offset from a base, as `WDT_CTL_ADDRESS`
+/// with `_MASK`/`_LSB` fields, to the peripherals without registers of their own. Peripherals
+/// only defined in these headers are added, along with the zero width masks of their registers
+fn add_offset_registers(
+    peripherals: &mut BTreeMap<String, Peripheral>,
+    soc_headers: &[(String, String)],
+    filter: &PeripheralFilter,
+    zero_width_fields: &mut Vec<validate::Issue>,
+) {
+    let (parsed, _, zero_width) = nonos::parse_nonos_texts(soc_headers);
+    let mut added = vec![];
+    for (name, p) in parsed {
+        if p.registers.is_empty() || !filter.includes(&name) {
+            continue;
+        }
+        match peripherals.get_mut(&name) {
+            Some(existing) if !existing.registers.is_empty() => {}
+            Some(existing) if existing.address != 0 && existing.address != p.address => println!(
+                "{} is at {:#x} but its registers at {:#x}, skipping them",
+                name, existing.address, p.address
+            ),
+            Some(existing) => {
+                existing.address = p.address;
+                existing.registers = p.registers;
+                added.push(name);
+            }
+            None => {
+                peripherals.insert(name.clone(), p);
+                added.push(name);
+            }
+        }
+    }
+    zero_width_fields.extend(zero_width.into_iter().filter(|issue| match issue {
+        validate::Issue::ZeroWidthField { peripheral, .. } => added.contains(peripheral),
+        _ => false,
+    }));
+}
+
+/// Add a parsed register to its peripheral, registers of unknown peripherals are recorded
+/// in `invalid_peripherals` unless filtered out
+fn push_register(
+    peripherals: &mut BTreeMap<String, Peripheral>,
+    invalid_peripherals: &mut Vec<String>,
+    filter: &PeripheralFilter,
+    pname: &str,
+    reg: Register,
+) {
+    if let Some(p) = peripherals.get_mut(pname) {
+        p.registers.push(reg);
+    } else if filter.includes(pname) {
+        // TODO indexed peripherals wont come up here
+        invalid_peripherals.push(pname.to_string());
+    }
+}
+
+/// Bits of a `bitpos` annotation, `[7:0]` or `[3]`
+fn parse_bitpos(bitpos: &str) -> Option<Bits> {
+    let mut bits = bitpos.split(':').map(|b| b.trim().parse::<u8>());
+    let bits = match (bits.next(), bits.next(), bits.next()) {
+        (Some(Ok(h)), Some(Ok(l)), None) if l <= h => Bits::Range(l..=h),
+        (Some(Ok(b)), None, None) => Bits::Single(b),
+        _ => return None,
+    };
+    if bits.msb() < 32 {
+        Some(bits)
+    } else {
+        None
+    }
+}
+
+/// Values of the `_S`, `_V` and `_M` defines of a field, with where the first of them is
+#[derive(Debug, Default)]
+struct MaskDefines {
+    location: String,
+    shift: Option<u64>,
+    value: Option<u64>,
+    mask: Option<u64>,
+}
+
+impl MaskDefines {
+    fn at(file: &str, line: usize) -> Self {
+        MaskDefines {
+            location: format!("{}:{}", file, line + 1),
+            ..Default::default()
+        }
+    }
+
+    fn set(&mut self, suffix: &str, value: Option<u64>) {
+        match suffix {
+            "S" => self.shift = value,
+            "V" => self.value = value,
+            _ => self.mask = value,
+        }
+    }
+
+    /// How the defines disagree with each other or with the `bits` of their field
+    fn inconsistency(&self, bits: &Bits) -> Option<String> {
+        let lsb = u64::from(bits.lsb());
+        let width = u64::from(bits.msb() - bits.lsb()) + 1;
+        let range = match bits {
+            Bits::Single(b) => format!("[{}]", b),
+            Bits::Range(r) => format!("[{}:{}]", r.end(), r.start()),
+        };
+        if let (Some(shift), Some(value), Some(mask)) = (self.shift, self.value, self.mask) {
+            if value.checked_shl(shift as u32) != Some(mask) {
+                return Some(format!(
+                    "_M {:#x} isn't _V {:#x} << _S {}",
+                    mask, value, shift
+                ));
+            }
+        }
+        match (self.shift, self.value, self.mask) {
+            (_, Some(value), _) if value != (1 << width) - 1 => Some(format!(
+                "_V {:#x} doesn't match the {} bit field {}",
+                value, width, range
+            )),
+            (Some(shift), _, _) if shift != lsb => {
+                Some(format!("_S {} doesn't match the field {}", shift, range))
+            }
+            (_, _, Some(mask)) if mask != ((1 << width) - 1) << lsb => {
+                Some(format!("_M {:#x} doesn't match the field {}", mask, range))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Field described by the `_S` (shift) and `_V` (value mask) defines of `name`, once both
+/// are known and form a contiguous mask within the register
+fn mask_field(
+    name: &str,
+    (shift, value): (Option<u64>, Option<u64>),
+    provenance: Provenance,
+) -> Option<BitField> {
+    let (shift, value) = (shift?, value?);
+    let width = value.count_ones() as u64;
+    if value == 0 || value.checked_add(1)? & value != 0 || shift + width > 32 {
+        return None;
+    }
+    let bits = if width == 1 {
+        Bits::Single(shift as u8)
+    } else {
+        Bits::Range(shift as u8..=(shift + width - 1) as u8)
+    };
+    Some(BitField {
+        name: name.to_string(),
+        bits,
+        origin: Origin::HeaderMask,
+        provenance: vec![provenance],
+        ..Default::default()
+    })
+}
+
+/// Rename all bit fields using `namer`
+pub fn rename_fields(peripherals: &mut BTreeMap<String, Peripheral>, namer: FieldNamer) {
+    for p in peripherals.values_mut() {
+        let names: Vec<Vec<String>> = p
+            .registers
+            .iter()
+            .map(|r| r.bit_fields.iter().map(|f| namer(p, r, &f.name)).collect())
+            .collect();
+        for (r, names) in p.registers.iter_mut().zip(names) {
+            for (f, name) in r.bit_fields.iter_mut().zip(names) {
+                f.name = name;
+            }
+        }
+    }
+}
+
+/// Drop fields whose layout was inferred rather than stated, and the registers and
+/// peripherals left without any fields
+pub fn retain_verified(peripherals: &mut BTreeMap<String, Peripheral>) {
+    for p in peripherals.values_mut() {
+        for r in p.registers.iter_mut() {
+            r.bit_fields.retain(|f| !f.origin.is_inferred());
+        }
+        p.registers.retain(|r| !r.bit_fields.is_empty());
+    }
+    peripherals.retain(|_, p| !p.registers.is_empty());
+}
+
+/// List the register headers to parse, entries that can't be read are skipped with a warning
+fn header_files(path: &str, globs: &[String]) -> Vec<PathBuf> {
+    if globs.is_empty() {
+        let entries = match std::fs::read_dir(path) {
+            Ok(entries) => entries,
+            Err(e) => {
+                println!("Failed to read {}: {}", path, e);
+                return vec![];
+            }
+        };
+        let mut files: Vec<PathBuf> = entries
+            .filter_map(|entry| match entry {
+                Ok(entry) => Some(entry.path()),
+                Err(e) => {
+                    println!("Skipping an entry of {}: {}", path, e);
+                    None
+                }
+            })
+            // names that aren't valid unicode still end in the ascii suffix
+            .filter(|f| f.to_string_lossy().ends_with("_reg.h") && !f.is_dir())
+            .collect();
+        files.sort();
+        return files;
+    }
+
+    let mut files = vec![];
+    for pattern in globs {
+        match glob::glob(pattern) {
+            Ok(paths) => files.extend(paths.filter_map(|path| match path {
+                Ok(path) => Some(path),
+                Err(e) => {
+                    println!("Skipping {}", e);
+                    None
+                }
+            })),
+            Err(e) => println!("Invalid header glob {}: {}", pattern, e),
+        }
+    }
+    files.sort();
+    files.dedup();
+    files
+}
+
+/// Collapse the whitespace of a (possibly multi line) comment
+fn clean_comment(comment: &str) -> String {
+    comment.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Text of the comment in `line`, if any
+fn comment_text(re_comment: &Regex, line: &str) -> Option<String> {
+    let m = re_comment.captures(line)?;
+    let text = clean_comment(m.get(1).or_else(|| m.get(2))?.as_str());
+    if text.is_empty() {
+        None
+    } else {
+        Some(text)
+    }
+}
+
+/// Text of an interrupt comment, which may span several lines continued with a leading `*`
+fn interrupt_description(comment: &str) -> String {
+    let lines: Vec<&str> = comment
+        .lines()
+        .map(|line| line.trim().trim_start_matches('*'))
+        .collect();
+    clean_comment(&lines.join(" "))
+}
+
+/// Interrupt sources defined in `soc.h` (`ETS_*_SOURCE`), named without the `_INTR` suffix
+pub fn scrape_interrupts(soc_h: &str) -> Vec<Interrupt> {
+    let re_interrupts = Regex::new(INTERRUPTS).unwrap();
+    re_interrupts
+        .captures_iter(soc_h)
+        .map(|captures| Interrupt {
+            name: captures[1].trim_end_matches("_INTR").to_string(),
+            description: Some(interrupt_description(&captures[3])),
+            value: captures[2].parse().unwrap(),
+        })
+        .collect()
+}
+
+/// Base addresses of the peripherals defined in `soc.h`, by peripheral name. Peripherals
+/// with both a `DR_REG_*_BASE` and a `PERIPHS_*_BASE` define get the address picked by
+/// `precedence`.
+pub fn base_addresses(soc_h: &str, precedence: BasePrecedence) -> BTreeMap<String, u32> {
+    let dr_reg_bases = collect_bases(&Regex::new(REG_BASE).unwrap(), soc_h);
+    let periphs_bases = collect_bases(&Regex::new(PERIPHS_BASE).unwrap(), soc_h);
+    let (mut bases, preferred) = match precedence {
+        BasePrecedence::DrReg => (periphs_bases, dr_reg_bases),
+        BasePrecedence::Periphs => (dr_reg_bases, periphs_bases),
+    };
+    for (peripheral, address) in preferred {
+        if let Some(discarded) = bases.insert(peripheral.clone(), address) {
+            if discarded != address {
+                println!(
+                    "Conflicting base addresses for {}, using {:#x} and discarding {:#x}",
+                    peripheral, address, discarded
+                );
+            }
+        }
+    }
+    bases
+}
+
+/// Collect the base addresses matched by `re`, the first definition of a peripheral wins
+fn collect_bases(re: &Regex, text: &str) -> BTreeMap<String, u32> {
+    let mut bases = BTreeMap::new();
+    for captures in re.captures_iter(text) {
+        let peripheral = &captures[1];
+        let address = match u32::from_str_radix(&captures[2], 16) {
+            Ok(address) => address,
+            Err(_) => continue,
+        };
+        match bases.get(peripheral) {
+            Some(&first) if first != address => println!(
+                "Base address of {} defined twice, discarding {:#x}",
+                peripheral, address
+            ),
+            Some(_) => {}
+            None => {
+                bases.insert(peripheral.to_string(), address);
+            }
+        }
+    }
+    bases
+}
+
+/// Evaluate a register offset expression. Plain numbers are hex even without a `0x` prefix,
+/// anything else is evaluated using the defines seen so far with index variables set to zero.
+fn parse_offset(offset: &str, symbols: &HashMap<String, String>) -> Option<u32> {
+    let offset = offset.trim();
+    if let Ok(addr) = u32::from_str_radix(offset.trim_start_matches("0x"), 16) {
+        return Some(addr);
+    }
+    let value = expr::eval(offset, &|name| match name {
+        "i" | "j" | "n" | "x" => Some(0),
+        _ => preprocess::resolve(symbols, name),
+    })
+    .ok()?;
+    u32::try_from(value).ok()
+}
+
+/// Size of the address window assumed to belong to a peripheral base address
+pub const PERIPHERAL_WINDOW: u32 = 0x1000;
+
+/// Find the peripheral whose address window contains `addr`, creating one named after the
+/// register prefix if none matches
+pub(crate) fn peripheral_for_address(
+    peripherals: &mut BTreeMap<String, Peripheral>,
+    reg_name: &str,
+    addr: u32,
+) -> String {
+    let enclosing = peripherals
+        .iter()
+        .filter(|(_, p)| p.address <= addr && addr - p.address < PERIPHERAL_WINDOW)
+        .max_by_key(|(_, p)| p.address)
+        .map(|(name, _)| name.clone());
+    if let Some(name) = enclosing {
+        return name;
+    }
 
-    peripherals
+    let base = addr & !(PERIPHERAL_WINDOW - 1);
+    let prefix = reg_name.split('_').next().unwrap_or(reg_name);
+    let name = if peripherals.contains_key(prefix) {
+        format!("{}_{:08X}", prefix, base)
+    } else {
+        prefix.to_string()
+    };
+    let p = Peripheral {
+        address: base,
+        description: name.clone(),
+        ..Default::default()
+    };
+    peripherals.insert(name.clone(), p);
+    name
 }
 
-fn file_to_string(fil: &str) -> String {
-    let mut soc = File::open(fil).unwrap();
+/// Contents of a header, failing for unreadable files and ones that aren't UTF-8 text
+fn file_to_string(fil: &Path) -> Result<String, String> {
     let mut data = String::new();
-    soc.read_to_string(&mut data).unwrap();
-    data
+    File::open(fil)
+        .and_then(|mut soc| soc.read_to_string(&mut data))
+        .map_err(|e| format!("{}: {}", fil.display(), e))?;
+    Ok(data)
 }
@@ -1,121 +1,1102 @@
 pub const SOC_BASE_PATH: &'static str = "esp-idf/components/soc/esp32/include/soc/";
+/// Checkout of the SDK `SOC_BASE_PATH` is in
+const SDK_DIR: &str = "esp-idf";
+/// Svd written unless `--output` is given
+const DEFAULT_OUTPUT: &str = "esp32.svd";
 
-use header2svd::{parse_idf, Bits, Peripheral};
+use header2svd::{
+    aliases::link_aliases,
+    baseline::check_baseline,
+    browser::browser_page,
+    caps::{capabilities_extension, parse_caps, CAPS_FILE},
+    changelog::changelog,
+    chips::DEFAULT_CHIP,
+    config::Config,
+    consts::generate_consts,
+    diff::{diff, read_svd},
+    doc::{parse_doc, DocFile, SCHEMA},
+    enums::apply_enums,
+    explain::explain,
+    fetch::{checkout, DEFAULT_REPOSITORY},
+    groups::{assign_groups, header_struct_names},
+    headers::write_c_headers,
+    html::extract_html,
+    interrupts::interrupt_header,
+    ipxact::write_ipxact,
+    lanes::add_byte_lanes,
+    manifest::{combined_report, ChipEntry, ChipReport, Manifest},
+    markdown::write_markdown,
+    memmap::{memory_map_yaml, merge_regions, parse_linker_script},
+    metadata::{CommentStyle, Metadata},
+    metrics::{metrics_report, peripheral_metrics},
+    pac::{check_svd2rust, write_pac_files},
+    pairs::{link_pairs, pairs_extension},
+    pdf::{extract_tables, pdf_text},
+    preprocess::parse_define,
+    provenance::provenance_json,
+    qemu::write_qemu_stubs,
+    rename::apply_rename_rules,
+    renode::platform_description,
+    retain_verified,
+    review::{findings, patch_entries, review},
+    safety::{omit_classified, safety_extension},
+    sanitize::{sanitize, Rename},
+    scrape_interrupts,
+    shorten::{shorten_names, ShortenRules},
+    source::{
+        apply_sources, ArduinoSource, DocSource, HeaderSource, NonosSource, PatchSource, Source,
+        SvdSource,
+    },
+    stats::{summary, summary_json, Summary},
+    strobes::{link_strobes, strobes_extension},
+    structs::cross_check,
+    stub::stub_json,
+    svd::{create_svd_with_options, write_svd_with_extensions, BitRangeStyle, SvdOptions},
+    tables::parse_value,
+    unparsed::{unparsed_json, unparsed_text},
+    validate::{issue_counts, validate, Issue, ValidateOptions},
+    view::{filter_view, views, DEFAULT_VIEW},
+    writes::{apply_write_overrides, detect_write_values},
+    BasePrecedence, ParseOptions, ParseReport, Peripheral, PeripheralFilter,
+};
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs::File;
-use std::io::BufWriter;
-use svd_parser::{
-    addressblock::AddressBlock, bitrange::BitRangeType, cpu::CpuBuilder, device::DeviceBuilder,
-    encode::Encode, endian::Endian, fieldinfo::FieldInfoBuilder, peripheral::PeripheralBuilder,
-    registerinfo::RegisterInfoBuilder, BitRange, Device as SvdDevice, Field,
-    Register as SvdRegister, RegisterCluster,
-};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use structopt::StructOpt;
 
-fn main() {
-    let peripherals = parse_idf(SOC_BASE_PATH);
-
-    let svd = create_svd(peripherals).unwrap();
-
-    let f = BufWriter::new(File::create("esp32.svd").unwrap());
-    svd.encode().unwrap().write(f).unwrap();
-}
-
-fn create_svd(peripherals: HashMap<String, Peripheral>) -> Result<SvdDevice, ()> {
-    let mut svd_peripherals = vec![];
-
-    for (name, p) in peripherals {
-        let mut registers = vec![];
-        for r in p.registers {
-            let mut fields = vec![];
-            for field in &r.bit_fields {
-                let description = if field.description.trim().is_empty() {
-                    None
-                } else {
-                    Some(field.description.clone())
-                };
-
-                let bit_range = match &field.bits {
-                    Bits::Single(bit) => BitRange {
-                        offset: u32::from(*bit),
-                        width: 1,
-                        range_type: BitRangeType::OffsetWidth,
-                    },
-                    Bits::Range(r) => BitRange {
-                        offset: u32::from(*r.start()),
-                        width: u32::from(r.end() - r.start() + 1),
-                        range_type: BitRangeType::OffsetWidth,
-                    },
-                };
-
-                let field_out = FieldInfoBuilder::default()
-                    .name(field.name.clone())
-                    .description(description)
-                    .bit_range(bit_range)
-                    .build()
-                    .unwrap();
-                fields.push(Field::Single(field_out));
+/// Exit codes of `--strict`, checked in this order, keep its help in sync
+const EXIT_UNRESOLVED_PERIPHERALS: i32 = 2;
+const EXIT_DROPPED_REGISTERS: i32 = 3;
+const EXIT_DOC_CONFLICTS: i32 = 4;
+const EXIT_SKIPPED_FILES: i32 = 5;
+const EXIT_SVD2RUST: i32 = 6;
+/// Exit code of `--baseline` regressions, also without `--strict`
+const EXIT_BASELINE: i32 = 7;
+
+/// Outputs selected with `--emit`
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Emit {
+    /// Rust module with address, offset and field constants, like `--consts`
+    RustConsts,
+    /// Directory of C headers, a `soc.h` and a `*_reg.h` per peripheral
+    CHeaders,
+    /// IP-XACT 1685-2014 component with a memory map of all peripherals
+    IpXact,
+    /// Renode platform description with a placeholder per peripheral
+    Renode,
+    /// Directory of QEMU device model skeletons, one per peripheral
+    QemuStubs,
+}
+
+impl Emit {
+    const NAMES: [&'static str; 5] = [
+        "rust-consts",
+        "c-headers",
+        "ip-xact",
+        "renode",
+        "qemu-stubs",
+    ];
+
+    /// Name of the output written next to the svd, the single file ones named after `chip`
+    fn file_name(self, chip: &str) -> String {
+        match self {
+            Emit::RustConsts => format!("{}_consts.rs", chip),
+            Emit::CHeaders => "include".to_string(),
+            Emit::IpXact => format!("{}.xml", chip),
+            Emit::Renode => format!("{}.repl", chip),
+            Emit::QemuStubs => "qemu".to_string(),
+        }
+    }
+}
+
+impl FromStr for Emit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rust-consts" => Ok(Emit::RustConsts),
+            "c-headers" => Ok(Emit::CHeaders),
+            "ip-xact" => Ok(Emit::IpXact),
+            "renode" => Ok(Emit::Renode),
+            "qemu-stubs" => Ok(Emit::QemuStubs),
+            _ => Err(format!("Unknown output {}", s)),
+        }
+    }
+}
+
+#[derive(StructOpt)]
+#[structopt(
+    name = "idf2svd",
+    about = "Generate an svd file from the esp-idf headers"
+)]
+struct Opt {
+    #[structopt(subcommand)]
+    command: Option<Command>,
+    /// TOML configuration file
+    #[structopt(long, parse(from_os_str))]
+    config: Option<PathBuf>,
+    /// Check out this revision of esp-idf before parsing, like the fetch subcommand
+    #[structopt(long)]
+    sdk_ref: Option<String>,
+    /// Checkout of the SDK, recorded in the outputs and used by `--sdk-ref`
+    #[structopt(long, parse(from_os_str), default_value = SDK_DIR)]
+    sdk_dir: PathBuf,
+    /// Directory of the soc headers, components/soc/esp32/include/soc of the SDK unless set
+    #[structopt(long)]
+    soc_dir: Option<String>,
+    /// Path of the svd, the outputs of `--emit` and the svds of other views are written next
+    /// to it
+    #[structopt(long, parse(from_os_str), default_value = DEFAULT_OUTPUT)]
+    output: PathBuf,
+    /// Preprocessor define used to evaluate `#if` blocks, as NAME or NAME=VALUE
+    #[structopt(short = "D", long = "define", number_of_values = 1)]
+    defines: Vec<String>,
+    /// Base address spelling that wins on conflicts, `dr-reg` or `periphs`
+    #[structopt(long, default_value = "dr-reg")]
+    base_precedence: BasePrecedence,
+    /// Representation of the field bit ranges in the svd
+    #[structopt(long, default_value = "offset-width", possible_values = &BitRangeStyle::NAMES)]
+    bit_range: BitRangeStyle,
+    /// Also write a Rust module with base address, register offset and field constants
+    #[structopt(long, parse(from_os_str))]
+    consts: Option<PathBuf>,
+    /// Additional output written next to the svd, can be repeated
+    #[structopt(long = "emit", number_of_values = 1, possible_values = &Emit::NAMES)]
+    emits: Vec<Emit>,
+    /// Also write a C header with the interrupt source numbers of soc.h and the config
+    #[structopt(long, parse(from_os_str))]
+    interrupt_header: Option<PathBuf>,
+    /// Also write a self-contained HTML page to browse the registers
+    #[structopt(long, parse(from_os_str))]
+    browser: Option<PathBuf>,
+    /// Also write a Markdown register reference into this directory, a page per peripheral
+    #[structopt(long, parse(from_os_str))]
+    markdown: Option<PathBuf>,
+    /// Also write a JSON description of the peripheral behaviour for simulators
+    #[structopt(long, parse(from_os_str))]
+    stub_json: Option<PathBuf>,
+    /// Also write the RAM and flash regions of the `--linker-script` files as a probe-rs
+    /// target description
+    #[structopt(long, parse(from_os_str))]
+    memory_map: Option<PathBuf>,
+    /// SDK linker script (e.g. eagle.app.v6.ld) to read the memory regions from, can be
+    /// repeated
+    #[structopt(long = "linker-script", parse(from_os_str), number_of_values = 1)]
+    linker_scripts: Vec<PathBuf>,
+    /// Also write a JSON file listing the header lines, doc files, seeds and patches every
+    /// peripheral, register and field came from
+    #[structopt(long, parse(from_os_str))]
+    provenance: Option<PathBuf>,
+    /// Device property of the svd, as KEY=VALUE with the keys of the `[device]` config (name,
+    /// vendor, series, version, description, license_text or width), can be repeated
+    #[structopt(long = "device", number_of_values = 1)]
+    device: Vec<String>,
+    /// Patch file written by `review`, its patches and merge rules are added to the config,
+    /// can be repeated
+    #[structopt(long = "patches", parse(from_os_str), number_of_values = 1)]
+    patch_files: Vec<PathBuf>,
+    /// Register alignment of a peripheral in bytes, as PERIPHERAL=BYTES
+    #[structopt(long = "alignment", number_of_values = 1)]
+    alignments: Vec<String>,
+    /// Strip the peripheral name prefix from register names
+    #[structopt(long)]
+    strip_register_prefix: bool,
+    /// Strip the peripheral name prefix from field names
+    #[structopt(long)]
+    strip_field_prefix: bool,
+    /// Suffix to strip from register names, e.g. _REG
+    #[structopt(long = "strip-register-suffix", number_of_values = 1)]
+    register_suffixes: Vec<String>,
+    /// Glob selecting register headers to parse instead of all *_reg.h files, can be repeated
+    #[structopt(long = "headers-glob", number_of_values = 1)]
+    header_globs: Vec<String>,
+    /// Drop registers and fields that fail validation
+    #[structopt(long)]
+    fix: bool,
+    /// Write rename and ignore lists for PAC tooling into this directory
+    #[structopt(long, parse(from_os_str))]
+    pac_files: Option<PathBuf>,
+    /// Cross-check the parsed registers against the *_struct.h layouts
+    #[structopt(long)]
+    check_structs: bool,
+    /// Log every step of the register parser that matches a regex or changes its state,
+    /// with the file, line and captured groups
+    #[structopt(long)]
+    trace_parser: bool,
+    /// Also write the header defines no pattern of the parser matched, grouped by file, as JSON
+    /// when the path ends in .json and as text otherwise
+    #[structopt(long, parse(from_os_str))]
+    unparsed: Option<PathBuf>,
+    /// Also write the summary counts printed at the end of the run as JSON
+    #[structopt(long, parse(from_os_str))]
+    summary_json: Option<PathBuf>,
+    /// Known good svd to compare the generated one with, exiting with 7 when peripherals or
+    /// registers were removed or moved that the `[baseline]` of the config doesn't allow
+    #[structopt(long, parse(from_os_str))]
+    baseline: Option<PathBuf>,
+    /// Fail when registers were dropped or conflicts resolved, exiting with the first of 2 for
+    /// peripherals without a base address, 3 for registers that failed to parse, 4 for
+    /// conflicts between the headers and docs, 5 for register headers that could not be read
+    /// and 6 for svd2rust failures with `--check-svd2rust`. Regressions against `--baseline`
+    /// exit with 7, also without `--strict`. The output files are written regardless
+    #[structopt(long)]
+    strict: bool,
+    /// Generate a PAC from the svd with svd2rust and report its errors, failing `--strict`
+    /// runs with 6 when it can't
+    #[structopt(long)]
+    check_svd2rust: bool,
+    /// Print the encoded size and generation time of every peripheral, largest first
+    #[structopt(long)]
+    metrics: bool,
+    /// Leave registers classified as debug-only, reserved or unsafe out of the output
+    #[structopt(long)]
+    omit_unsafe: bool,
+    /// Link registers holding the low and high half of a wider value, such as `_LO`/`_HI`
+    /// timer counters, in a vendor extension of the svd
+    #[structopt(long)]
+    link_register_pairs: bool,
+    /// Only emit fields stated by the headers or docs, dropping inferred ones and the
+    /// registers and peripherals left empty
+    #[structopt(long)]
+    only_verified: bool,
+    /// Arduino core esp8266_peri.h to merge registers missing from the SDK headers from
+    #[structopt(long, parse(from_os_str))]
+    peri_header: Option<PathBuf>,
+    /// Directory of the ESP8266 NONOS SDK headers to merge registers missing from the SDK headers from
+    #[structopt(long, parse(from_os_str))]
+    nonos_sdk: Option<PathBuf>,
+    /// Doc JSON file merged into the peripheral it describes, can be repeated
+    #[structopt(long = "doc", parse(from_os_str), number_of_values = 1)]
+    docs: Vec<PathBuf>,
+    /// CSV (or TSV) register map merged into the peripherals it describes, can be repeated
+    #[structopt(long = "regmap", parse(from_os_str), number_of_values = 1)]
+    regmaps: Vec<PathBuf>,
+    /// Previously generated svd to add the parsed peripherals and registers to, what it
+    /// already contains is kept. Docs and patches are applied to the combined device
+    #[structopt(long, parse(from_os_str))]
+    append: Option<PathBuf>,
+    /// Only parse and emit these peripherals, comma separated
+    #[structopt(long, use_delimiter = true)]
+    only: Vec<String>,
+    /// Skip these peripherals, comma separated
+    #[structopt(long, use_delimiter = true)]
+    exclude: Vec<String>,
+}
+
+#[derive(StructOpt)]
+enum Command {
+    /// Work with register documentation files
+    Doc(DocCommand),
+    /// Draft Markdown release notes from the changes between a previous and a regenerated svd
+    Changelog {
+        #[structopt(parse(from_os_str))]
+        old: PathBuf,
+        #[structopt(parse(from_os_str))]
+        new: PathBuf,
+        /// Write the notes to a file instead of stdout
+        #[structopt(short, long, parse(from_os_str))]
+        output: Option<PathBuf>,
+    },
+    /// Clone or update the SDK and check out a pinned revision
+    Fetch {
+        /// Tag, branch or commit to check out
+        rev: String,
+        #[structopt(long, default_value = DEFAULT_REPOSITORY)]
+        repository: String,
+        /// Directory of the checkout
+        #[structopt(long, parse(from_os_str), default_value = SDK_DIR)]
+        dir: PathBuf,
+    },
+    /// Show the header lines, matched regexes, doc files and patches a register was derived
+    /// from, using the same options as a normal run
+    Explain {
+        /// Register to explain, as PERIPHERAL.REGISTER with the names of the generated svd
+        path: String,
+    },
+    /// Walk through the registers assumed to be a full width value, full width mask fields and
+    /// resolved doc conflicts of a run, appending the decisions to a patch file
+    Review {
+        /// Patch file to append to, pass it with --patches to later runs
+        #[structopt(short, long, parse(from_os_str), default_value = "review.toml")]
+        output: PathBuf,
+    },
+    /// Generate the svds of all chips of a manifest, each like a normal run with the options
+    /// of its entry, and print a report combining them
+    GenerateAll {
+        #[structopt(parse(from_os_str))]
+        manifest: PathBuf,
+        /// Also write the report to this file
+        #[structopt(long, parse(from_os_str))]
+        report: Option<PathBuf>,
+    },
+}
+
+impl Opt {
+    /// Directory of the soc headers, ending in a separator as the parser expects
+    fn soc_path(&self) -> String {
+        match &self.soc_dir {
+            Some(dir) if dir.ends_with('/') => dir.clone(),
+            Some(dir) => format!("{}/", dir),
+            None if self.sdk_dir == Path::new(SDK_DIR) => SOC_BASE_PATH.to_string(),
+            None => format!(
+                "{}/{}",
+                self.sdk_dir.display(),
+                &SOC_BASE_PATH[SDK_DIR.len() + 1..]
+            ),
+        }
+    }
+
+    /// Where an output with a fixed file name is written, next to the svd
+    fn output_path(&self, file_name: &str) -> PathBuf {
+        self.output
+            .parent()
+            .unwrap_or_else(|| Path::new(""))
+            .join(file_name)
+    }
+}
+
+#[derive(StructOpt)]
+enum DocCommand {
+    /// Extract the register tables of a reference manual PDF into a doc JSON file
+    Extract {
+        #[structopt(parse(from_os_str))]
+        pdf: PathBuf,
+        /// Name of the documented peripheral
+        #[structopt(long)]
+        peripheral: String,
+        /// Pages describing the peripheral, as FIRST-LAST
+        #[structopt(long)]
+        pages: Option<String>,
+        /// Base address subtracted from absolute register addresses
+        #[structopt(long)]
+        base: Option<String>,
+        #[structopt(short, long, parse(from_os_str))]
+        output: PathBuf,
+    },
+    /// Scrape the register tables of saved html documentation pages into a doc JSON file
+    Scrape {
+        #[structopt(parse(from_os_str), required = true)]
+        pages: Vec<PathBuf>,
+        /// Name of the documented peripheral
+        #[structopt(long)]
+        peripheral: String,
+        /// Base address subtracted from absolute register addresses
+        #[structopt(long)]
+        base: Option<String>,
+        #[structopt(short, long, parse(from_os_str))]
+        output: PathBuf,
+    },
+    /// Check doc JSON files against the documentation format
+    Check {
+        #[structopt(parse(from_os_str), required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// Print the JSON schema of the documentation format
+    Schema,
+}
+
+fn parse_base(base: Option<String>) -> Result<Option<u32>, String> {
+    match base {
+        Some(base) => match parse_value(&base) {
+            Some(base) => Ok(Some(base as u32)),
+            None => Err(format!("Invalid base {}", base)),
+        },
+        None => Ok(None),
+    }
+}
+
+fn write_doc(doc: &DocFile, output: &Path) -> Result<(), String> {
+    println!(
+        "Extracted {} registers with {} fields",
+        doc.registers.len(),
+        doc.registers.iter().map(|r| r.fields.len()).sum::<usize>()
+    );
+    for error in doc.validate() {
+        println!("Needs manual fixing: {}", error);
+    }
+    let json = serde_json::to_string_pretty(doc).unwrap();
+    std::fs::write(output, json).map_err(|e| format!("Failed to write {}: {}", output.display(), e))
+}
+
+fn run_command(command: Command) -> Result<(), String> {
+    match command {
+        Command::Doc(DocCommand::Extract {
+            pdf,
+            peripheral,
+            pages,
+            base,
+            output,
+        }) => {
+            let pages = match pages {
+                Some(pages) => {
+                    let mut range = pages.splitn(2, '-').map(|p| p.trim().parse());
+                    match (range.next(), range.next()) {
+                        (Some(Ok(first)), Some(Ok(last))) => Some((first, last)),
+                        (Some(Ok(page)), None) => Some((page, page)),
+                        _ => return Err(format!("Invalid page range {}", pages)),
+                    }
+                }
+                None => None,
+            };
+            let doc = extract_tables(&pdf_text(&pdf, pages)?, &peripheral, parse_base(base)?);
+            write_doc(&doc, &output)
+        }
+        Command::Doc(DocCommand::Scrape {
+            pages,
+            peripheral,
+            base,
+            output,
+        }) => {
+            let mut html = vec![];
+            for page in &pages {
+                html.push(
+                    std::fs::read_to_string(page)
+                        .map_err(|e| format!("Failed to read {}: {}", page.display(), e))?,
+                );
+            }
+            let doc = extract_html(
+                html.iter().map(String::as_str),
+                &peripheral,
+                parse_base(base)?,
+            );
+            write_doc(&doc, &output)
+        }
+        Command::Doc(DocCommand::Check { files }) => {
+            let mut valid = true;
+            for file in &files {
+                if let Err(e) = parse_doc(file) {
+                    println!("{}", e);
+                    valid = false;
+                }
+            }
+            if valid {
+                Ok(())
+            } else {
+                Err(String::from("Invalid doc files found"))
+            }
+        }
+        Command::Doc(DocCommand::Schema) => {
+            print!("{}", SCHEMA);
+            Ok(())
+        }
+        Command::Changelog { old, new, output } => {
+            let notes = changelog(&diff(&read_svd(&old)?, &read_svd(&new)?));
+            match output {
+                Some(output) => std::fs::write(&output, notes)
+                    .map_err(|e| format!("Failed to write {}: {}", output.display(), e)),
+                None => {
+                    print!("{}", notes);
+                    Ok(())
+                }
+            }
+        }
+        Command::Fetch {
+            rev,
+            repository,
+            dir,
+        } => {
+            let commit = checkout(&repository, &rev, &dir)?;
+            println!("Checked out {} ({}) in {}", rev, commit, dir.display());
+            Ok(())
+        }
+        Command::Explain { .. } | Command::Review { .. } | Command::GenerateAll { .. } => {
+            unreachable!("handled in main")
+        }
+    }
+}
+
+/// The model generated by a run along with what happened to it on the way
+struct Generated {
+    peripherals: BTreeMap<String, Peripheral>,
+    renames: Vec<Rename>,
+    issues: Vec<Issue>,
+    report: ParseReport,
+    /// Differences between the headers and docs resolved by the merge strategies
+    conflicts: Vec<String>,
+    /// Capability macros of `soc_caps.h`, empty for SDKs without one
+    capabilities: BTreeMap<String, i64>,
+}
+
+/// Parse the headers and apply the merges, patches and passes selected by the options
+fn build(opt: &Opt, config: &Config) -> Result<Generated, String> {
+    let defines: HashMap<String, String> = opt.defines.iter().map(|d| parse_define(d)).collect();
+    let soc_path = opt.soc_path();
+    let capabilities = std::fs::read_to_string(format!("{}{}", soc_path, CAPS_FILE))
+        .map(|text| parse_caps(&text, &defines))
+        .unwrap_or_default();
+    let options = ParseOptions {
+        defines: defines.clone(),
+        base_precedence: opt.base_precedence,
+        header_globs: opt.header_globs.clone(),
+        replacements: config.replacements.clone(),
+        seeds: config.seeds.clone(),
+        capabilities: capabilities.clone(),
+        interrupts: config.interrupts.clone(),
+        filter: PeripheralFilter {
+            only: opt.only.clone(),
+            exclude: opt.exclude.clone(),
+        },
+        trace: opt.trace_parser,
+        ..Default::default()
+    };
+    let filter = options.filter.clone();
+    let mut headers = HeaderSource::new(&soc_path, options);
+    let mut nonos: Vec<NonosSource> = opt.nonos_sdk.iter().map(|d| NonosSource::new(d)).collect();
+    let mut arduino: Vec<ArduinoSource> = opt
+        .peri_header
+        .iter()
+        .map(|p| ArduinoSource::new(p))
+        .collect();
+    let mut svd: Vec<SvdSource> = opt.append.iter().map(|p| SvdSource::new(p)).collect();
+    let mut docs: Vec<DocSource> = opt
+        .docs
+        .iter()
+        .map(|p| DocSource::doc(p, &config.merge_rules))
+        .chain(
+            opt.regmaps
+                .iter()
+                .map(|p| DocSource::register_map(p, &config.merge_rules)),
+        )
+        .collect();
+    let mut patches = PatchSource::new(&config.patches);
+
+    let mut sources: Vec<&mut dyn Source> = vec![&mut headers, &mut patches];
+    sources.extend(nonos.iter_mut().map(|s| s as &mut dyn Source));
+    sources.extend(arduino.iter_mut().map(|s| s as &mut dyn Source));
+    sources.extend(svd.iter_mut().map(|s| s as &mut dyn Source));
+    sources.extend(docs.iter_mut().map(|s| s as &mut dyn Source));
+    let mut peripherals = BTreeMap::new();
+    let conflicts = apply_sources(&mut peripherals, &mut sources, &filter)?;
+    drop(sources);
+    let report = headers.report;
+    for conflict in &conflicts {
+        println!("Resolved by the merge strategy: {}", conflict);
+    }
+
+    if opt.check_structs {
+        for mismatch in cross_check(soc_path.as_ref(), &peripherals) {
+            println!("{}", mismatch);
+        }
+    }
+
+    if opt.omit_unsafe {
+        for path in omit_classified(&mut peripherals) {
+            println!("Omitted {}", path);
+        }
+    }
+
+    let rules = ShortenRules {
+        strip_register_prefix: opt.strip_register_prefix,
+        strip_field_prefix: opt.strip_field_prefix,
+        register_suffixes: opt.register_suffixes.clone(),
+    };
+    shorten_names(&mut peripherals, &rules);
+
+    let mut renames = apply_rename_rules(&mut peripherals, &config.rename_rules)
+        .map_err(|e| format!("Failed to apply the rename rules: {}", e))?;
+    renames.extend(sanitize(&mut peripherals));
+    for rename in &renames {
+        println!("Renamed {}", rename);
+    }
+
+    match link_aliases(&mut peripherals) {
+        0 => {}
+        linked => println!(
+            "Linked {} registers as alternates of a register at the same offset",
+            linked
+        ),
+    }
+
+    let validate_options = ValidateOptions {
+        alignment_overrides: opt
+            .alignments
+            .iter()
+            .filter_map(|a| {
+                let (peripheral, bytes) = parse_define(a);
+                match bytes.parse() {
+                    Ok(bytes) => Some((peripheral, bytes)),
+                    Err(_) => {
+                        println!("Ignoring invalid alignment {}", a);
+                        None
+                    }
+                }
+            })
+            .collect(),
+        fix: opt.fix,
+        ..Default::default()
+    };
+    let mut issues = validate(&mut peripherals, &validate_options);
+    // masks of zero never make it into the model, the parsers report them
+    issues.extend(report.zero_width_fields.iter().cloned());
+    for source in &nonos {
+        issues.extend(source.zero_width_fields.iter().cloned());
+    }
+    for issue in &issues {
+        println!("{}", issue);
+    }
+    for (kind, count) in issue_counts(&issues) {
+        println!("{:>5} {}", count, kind);
+    }
+
+    if opt.only_verified {
+        retain_verified(&mut peripherals);
+    }
+
+    match link_strobes(&mut peripherals) {
+        0 => {}
+        linked => println!(
+            "Linked {} registers to their set and clear registers",
+            linked
+        ),
+    }
+
+    match detect_write_values(&mut peripherals) {
+        0 => {}
+        detected => println!("Detected {} registers setting or clearing bits", detected),
+    }
+    apply_write_overrides(&mut peripherals, &config.writes)
+        .map_err(|e| format!("Failed to apply write overrides: {}", e))?;
+
+    match apply_enums(&mut peripherals, &opt.sdk_dir, &config.enums, &defines) {
+        Ok(0) => {}
+        Ok(applied) => println!("Added enumerated values to {} fields", applied),
+        Err(e) => return Err(format!("Failed to add enumerated values: {}", e)),
+    }
+
+    if opt.link_register_pairs {
+        let linked = link_pairs(&mut peripherals);
+        println!("Linked {} low and high register pairs", linked);
+    }
+
+    match assign_groups(&mut peripherals) {
+        0 => {}
+        shared => println!(
+            "{} peripherals share the header struct of their group",
+            shared
+        ),
+    }
+
+    match add_byte_lanes(&mut peripherals, &config.byte_lanes) {
+        Ok(0) => {}
+        Ok(added) => println!("Added {} byte lane registers", added),
+        Err(e) => return Err(format!("Failed to add byte lanes: {}", e)),
+    }
+
+    Ok(Generated {
+        peripherals,
+        renames,
+        issues,
+        report,
+        conflicts,
+        capabilities,
+    })
+}
+
+/// Load the config with the `--device` properties applied, and check out `--sdk-ref`
+fn prepare(opt: &Opt) -> Result<Config, String> {
+    let mut config = match &opt.config {
+        Some(path) => Config::load(path)?,
+        None => Config::default(),
+    };
+    for path in &opt.patch_files {
+        config.add_patch_file(path)?;
+    }
+    for property in &opt.device {
+        let (key, value) = parse_define(property);
+        config.device.set(&key, &value)?;
+    }
+
+    if let Some(rev) = &opt.sdk_ref {
+        checkout(DEFAULT_REPOSITORY, rev, &opt.sdk_dir)?;
+    }
+    Ok(config)
+}
+
+/// Write an output file, with its path in the error
+fn write_output(path: &Path, contents: impl AsRef<[u8]>) -> Result<(), String> {
+    std::fs::write(path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// What a run generated, for the report of `generate-all`
+struct Outcome {
+    summary: Summary,
+    issues: usize,
+    /// Exit code and reason when the strict mode check failed
+    failure: Option<(i32, String)>,
+}
+
+/// Build the model and write the svd and the other outputs selected by the options
+fn generate(opt: &Opt, config: &Config) -> Result<Outcome, String> {
+    let Generated {
+        peripherals,
+        renames,
+        issues,
+        report,
+        conflicts,
+        capabilities,
+    } = build(opt, config)?;
+
+    // record how the output was made so it can be reproduced, in every generated file
+    let inputs = opt
+        .config
+        .iter()
+        .chain(&opt.docs)
+        .chain(&opt.regmaps)
+        .chain(&opt.linker_scripts)
+        .map(PathBuf::as_path);
+    let metadata = Metadata::collect(&opt.sdk_dir, inputs, &config.banner)?;
+
+    if let Some(dir) = &opt.pac_files {
+        let pruned = if opt.fix { &issues[..] } else { &[] };
+        write_pac_files(dir, &renames, pruned)
+            .map_err(|e| format!("Failed to write {}: {}", dir.display(), e))?;
+    }
+
+    let chip = config.chip.as_deref().unwrap_or(DEFAULT_CHIP);
+    let chip_name = Path::new(chip)
+        .file_stem()
+        .ok_or_else(|| format!("Invalid chip name '{}'", chip))?
+        .to_string_lossy();
+    let emitted = opt
+        .emits
+        .iter()
+        .map(|emit| (*emit, opt.output_path(&emit.file_name(&chip_name))));
+    let outputs: Vec<(Emit, PathBuf)> = opt
+        .consts
+        .iter()
+        .map(|path| (Emit::RustConsts, path.clone()))
+        .chain(emitted)
+        .collect();
+    for (emit, path) in outputs {
+        match emit {
+            Emit::RustConsts => {
+                let text = metadata.banner(CommentStyle::Rust) + &generate_consts(&peripherals);
+                write_output(&path, text)?;
+            }
+            Emit::CHeaders => {
+                write_c_headers(&path, &peripherals, &metadata.banner(CommentStyle::C))
+                    .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+            }
+            Emit::IpXact => {
+                let out = File::create(&path)
+                    .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+                write_ipxact(&peripherals, &metadata.comment(), BufWriter::new(out))?;
             }
+            Emit::Renode => {
+                let text =
+                    metadata.banner(CommentStyle::Rust) + &platform_description(&peripherals);
+                write_output(&path, text)?;
+            }
+            Emit::QemuStubs => {
+                write_qemu_stubs(&path, &peripherals, &metadata.banner(CommentStyle::C))
+                    .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+            }
+        }
+    }
+
+    if let Some(path) = &opt.interrupt_header {
+        let soc_path = format!("{}soc.h", opt.soc_path());
+        let soc_h = std::fs::read_to_string(&soc_path)
+            .map_err(|e| format!("Failed to read {}: {}", soc_path, e))?;
+        let header = interrupt_header(&peripherals, &scrape_interrupts(&soc_h));
+        write_output(path, metadata.banner(CommentStyle::C) + &header)?;
+    }
+
+    if let Some(path) = &opt.browser {
+        let page = browser_page(&peripherals, &metadata.banner(CommentStyle::Html));
+        write_output(path, page)?;
+    }
+
+    if let Some(dir) = &opt.markdown {
+        write_markdown(dir, &peripherals, &metadata.banner(CommentStyle::Html))
+            .map_err(|e| format!("Failed to write {}: {}", dir.display(), e))?;
+    }
+
+    if let Some(path) = &opt.stub_json {
+        write_output(path, stub_json(&peripherals))?;
+    }
+
+    if let Some(path) = &opt.provenance {
+        write_output(path, provenance_json(&peripherals))?;
+    }
+
+    if let Some(path) = &opt.unparsed {
+        let text = if path.extension() == Some("json".as_ref()) {
+            unparsed_json(&report.unparsed)
+        } else {
+            unparsed_text(&report.unparsed)
+        };
+        write_output(path, text)?;
+    }
+
+    if let Some(path) = &opt.memory_map {
+        if opt.linker_scripts.is_empty() {
+            return Err("--memory-map needs at least one --linker-script".to_string());
+        }
+        let defines: HashMap<String, String> =
+            opt.defines.iter().map(|d| parse_define(d)).collect();
+        let mut regions = vec![];
+        for script in &opt.linker_scripts {
+            let parsed = std::fs::read_to_string(script)
+                .map_err(|e| e.to_string())
+                .and_then(|text| parse_linker_script(&text, &defines))
+                .map_err(|e| format!("Failed to read {}: {}", script.display(), e))?;
+            merge_regions(&mut regions, parsed);
+        }
+        let text = metadata.banner(CommentStyle::Hash) + &memory_map_yaml(&chip_name, &regions);
+        write_output(path, text)?;
+    }
 
-            let info = RegisterInfoBuilder::default()
-                .name(r.name.clone())
-                .description(Some(r.description.clone()))
-                .address_offset(r.address)
-                .size(Some(32))
-                .reset_value(Some(r.reset_value as u32))
-                .fields(Some(fields))
-                .build()
-                .unwrap();
-
-            registers.push(RegisterCluster::Register(SvdRegister::Single(info)));
-        }
-        let block_size = registers.iter().fold(0, |sum, reg| {
-            sum + match reg {
-                RegisterCluster::Register(r) => r.size.unwrap(),
-                _ => unimplemented!(),
+    if opt.metrics {
+        print!("{}", metrics_report(&peripheral_metrics(&peripherals)?));
+    }
+
+    let svd_options = SvdOptions {
+        bit_range: opt.bit_range,
+        cpu: config.cpu.clone(),
+        device: config.device.clone(),
+    };
+    for view in views(&peripherals) {
+        let view_peripherals = filter_view(&peripherals, &view);
+        let safety = safety_extension(&view_peripherals);
+        let strobes = strobes_extension(&view_peripherals);
+        let pairs = pairs_extension(&view_peripherals);
+        let header_structs = header_struct_names(&view_peripherals);
+        let svd = create_svd_with_options(view_peripherals, &svd_options)
+            .map_err(|e| format!("Failed to create the svd of the {} view: {}", view, e))?;
+
+        let filename = if view == DEFAULT_VIEW {
+            opt.output.clone()
+        } else {
+            let stem = opt
+                .output
+                .file_stem()
+                .ok_or_else(|| format!("Invalid output path '{}'", opt.output.display()))?
+                .to_string_lossy();
+            opt.output_path(&format!("{}-{}.svd", stem, view))
+        };
+        let f = File::create(&filename)
+            .map_err(|e| format!("Failed to create {}: {}", filename.display(), e))?;
+        let mut extensions = metadata.extensions();
+        if !capabilities.is_empty() {
+            extensions.push(capabilities_extension(&capabilities));
+        }
+        extensions.extend(safety);
+        extensions.extend(strobes);
+        extensions.extend(pairs);
+        write_svd_with_extensions(
+            &svd,
+            extensions,
+            &header_structs,
+            &config.device,
+            &metadata.comment(),
+            BufWriter::new(f),
+        )?;
+    }
+
+    let mut svd2rust_failed = false;
+    if opt.check_svd2rust {
+        let check = check_svd2rust(&opt.output)?;
+        for message in &check.messages {
+            println!("svd2rust: {}", message);
+        }
+        if check.success {
+            println!("svd2rust generated a PAC from {}", opt.output.display());
+        } else {
+            println!("svd2rust failed on {}", opt.output.display());
+            svd2rust_failed = true;
+        }
+    }
+
+    let mut regressions = vec![];
+    if let Some(path) = &opt.baseline {
+        regressions = check_baseline(&read_svd(path)?, &read_svd(&opt.output)?, &config.baseline);
+        for regression in &regressions {
+            println!("Regression against {}: {}", path.display(), regression);
+        }
+    }
+
+    let summary = summary(&peripherals);
+    print!("{}", summary);
+    if let Some(path) = &opt.summary_json {
+        write_output(path, summary_json(&summary))?;
+    }
+    let mut failure = None;
+    if opt.strict {
+        let failures = [
+            (
+                EXIT_UNRESOLVED_PERIPHERALS,
+                "peripherals without a base address",
+                report.unresolved_peripherals.len(),
+            ),
+            (
+                EXIT_DROPPED_REGISTERS,
+                "registers that failed to parse",
+                report.dropped_registers.len(),
+            ),
+            (
+                EXIT_DOC_CONFLICTS,
+                "conflicts between the headers and docs",
+                conflicts.len(),
+            ),
+            (
+                EXIT_SKIPPED_FILES,
+                "register headers that could not be read",
+                report.skipped_files.len(),
+            ),
+            (
+                EXIT_SVD2RUST,
+                "svd2rust failures",
+                usize::from(svd2rust_failed),
+            ),
+        ];
+        if let Some((code, what, count)) = failures.iter().find(|(_, _, count)| *count > 0) {
+            failure = Some((*code, format!("{} {}", count, what)));
+        }
+    }
+    if failure.is_none() && !regressions.is_empty() {
+        let reason = format!("{} regressions against the baseline", regressions.len());
+        failure = Some((EXIT_BASELINE, reason));
+    }
+    Ok(Outcome {
+        summary,
+        issues: issues.len(),
+        failure,
+    })
+}
+
+/// Run `generate` with the options of a chip of a manifest
+fn generate_chip(chip: &ChipEntry) -> Result<Outcome, String> {
+    let args = std::iter::once("idf2svd".to_string()).chain(chip.arguments()?);
+    let opt = Opt::from_iter_safe(args).map_err(|e| e.to_string())?;
+    if let Some(dir) = chip.output.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let config = prepare(&opt)?;
+    generate(&opt, &config)
+}
+
+/// Run `generate` for every chip of `manifest` and print the combined report, returning
+/// whether all runs succeeded
+fn generate_all(manifest: &Path, report: Option<&Path>) -> Result<bool, String> {
+    let manifest = Manifest::load(manifest)?;
+    let mut reports = vec![];
+    for chip in &manifest.chips {
+        println!("Generating {}", chip.name);
+        // a chip failing to generate is reported, the others are generated regardless
+        let (summary, issues, failure) = match generate_chip(chip) {
+            Ok(outcome) => (
+                outcome.summary,
+                outcome.issues,
+                outcome.failure.map(|(_, reason)| reason),
+            ),
+            Err(e) => {
+                println!("{}: {}", chip.name, e);
+                (Summary::default(), 0, Some(e))
             }
+        };
+        reports.push(ChipReport {
+            chip: chip.name.clone(),
+            output: chip.output.clone(),
+            summary,
+            issues,
+            failure,
         });
-        let out = PeripheralBuilder::default()
-            .name(name.to_owned())
-            .base_address(p.address)
-            .registers(Some(registers))
-            .address_block(Some(AddressBlock {
-                offset: 0x0,
-                size: block_size, // TODO what about derived peripherals?
-                usage: "registers".to_string(),
-            }))
-            .build()
-            .unwrap();
-
-        svd_peripherals.push(out);
-    }
-    println!("Len {}", svd_peripherals.len());
-
-    let cpu = CpuBuilder::default()
-        .name("Xtensa LX6".to_string())
-        .revision("1".to_string())
-        .endian(Endian::Little)
-        .mpu_present(false)
-        .fpu_present(true)
-        // according to https://docs.espressif.com/projects/esp-idf/en/latest/api-reference/system/intr_alloc.html#macros
-        // 7 levels so 3 bits? //TODO verify
-        .nvic_priority_bits(3)
-        .has_vendor_systick(false)
-        .build()
-        .unwrap();
-
-    let device = DeviceBuilder::default()
-        .name("Espressif".to_string())
-        .version(Some("1.0".to_string()))
-        .schema_version(Some("1.0".to_string()))
-        // broken see: https://github.com/rust-embedded/svd/pull/104
-        // .description(Some("ESP32".to_string()))
-        // .address_unit_bits(Some(8))
-        .width(Some(32))
-        .cpu(Some(cpu))
-        .peripherals(svd_peripherals)
-        .build()
-        .unwrap();
-
-    Ok(device)
+    }
+    let text = combined_report(&reports);
+    print!("{}", text);
+    if let Some(path) = report {
+        std::fs::write(path, text)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    }
+    Ok(reports.iter().all(|r| r.failure.is_none()))
+}
+
+/// The value of `result`, or exit after printing its error
+fn or_exit<T>(result: Result<T, String>) -> T {
+    match result {
+        Ok(value) => value,
+        Err(e) => {
+            println!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn main() {
+    let mut opt = Opt::from_args();
+    match opt.command.take() {
+        Some(Command::Explain { path }) => {
+            let config = or_exit(prepare(&opt));
+            let generated = or_exit(build(&opt, &config));
+            let peripherals = &generated.peripherals;
+            let text = explain(peripherals, &path, &generated.renames, &generated.issues);
+            print!("{}", or_exit(text));
+            return;
+        }
+        Some(Command::Review { output }) => {
+            let config = or_exit(prepare(&opt));
+            let generated = or_exit(build(&opt, &config));
+            let findings = findings(
+                &generated.peripherals,
+                &generated.issues,
+                &generated.conflicts,
+                &generated.renames,
+                &config.accepted,
+            );
+            println!("{} findings to review", findings.len());
+            let stdin = std::io::stdin();
+            let write_error =
+                |e: std::io::Error| format!("Failed to write {}: {}", output.display(), e);
+            let decisions = or_exit(
+                review(&findings, &mut stdin.lock(), &mut std::io::stdout()).map_err(write_error),
+            );
+            let mut file = or_exit(
+                std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&output)
+                    .map_err(write_error),
+            );
+            or_exit(
+                file.write_all(patch_entries(&decisions).as_bytes())
+                    .map_err(write_error),
+            );
+            println!(
+                "Wrote {} decisions to {}, pass it with --patches to apply them",
+                decisions.len(),
+                output.display()
+            );
+            return;
+        }
+        Some(Command::GenerateAll { manifest, report }) => {
+            match generate_all(&manifest, report.as_deref()) {
+                Ok(true) => {}
+                Ok(false) => std::process::exit(1),
+                Err(e) => {
+                    println!("{}", e);
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+        Some(command) => {
+            if let Err(e) = run_command(command) {
+                println!("{}", e);
+                std::process::exit(1);
+            }
+            return;
+        }
+        None => {}
+    }
+
+    let config = or_exit(prepare(&opt));
+    if let Some((code, reason)) = or_exit(generate(&opt, &config)).failure {
+        println!("Failed: {}", reason);
+        std::process::exit(code);
+    }
 }
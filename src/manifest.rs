@@ -0,0 +1,272 @@
+//! Manifest of the chips `generate-all` makes svds for in one run, each with its SDK, docs,
+//! config and output, followed by a report combining the runs
+//!
+//! ```toml
+//! [[chip]]
+//! name = "esp32"
+//! # optional, esp-idf unless set
+//! sdk = "esp-idf"
+//! # optional, the soc headers within the SDK, components/soc/<name>/include/soc unless set
+//! soc = "components/soc/esp32/include/soc"
+//! # optional, directory of doc JSON (*.json) and register map (*.csv, *.tsv) files
+//! docs = "docs/esp32"
+//! # optional
+//! config = "esp32.toml"
+//! output = "svd/esp32.svd"
+//! # optional, further options of a normal run
+//! args = ["--strict", "--emit", "rust-consts"]
+//!
+//! [[chip]]
+//! name = "esp8266"
+//! nonos_sdk = "ESP8266_NONOS_SDK/include"
+//! output = "svd/esp8266.svd"
+//! ```
+//!
+//! Relative paths are relative to the directory of the manifest.
+
+use crate::stats::Summary;
+use serde::Deserialize;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// SDK directory of chips without `sdk`
+const DEFAULT_SDK: &str = "esp-idf";
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    #[serde(rename = "chip")]
+    pub chips: Vec<ChipEntry>,
+}
+
+/// One chip of the manifest
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChipEntry {
+    pub name: String,
+    #[serde(default)]
+    pub sdk: Option<PathBuf>,
+    /// Directory of the soc headers, relative to the SDK
+    #[serde(default)]
+    pub soc: Option<PathBuf>,
+    #[serde(default)]
+    pub nonos_sdk: Option<PathBuf>,
+    #[serde(default)]
+    pub docs: Option<PathBuf>,
+    #[serde(default)]
+    pub config: Option<PathBuf>,
+    /// Path of the svd, the other outputs are written next to it
+    pub output: PathBuf,
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+impl Manifest {
+    /// Parse the text of a manifest, resolving its paths against `dir`
+    pub fn parse(text: &str, dir: &Path) -> Result<Manifest, String> {
+        let mut manifest: Manifest =
+            toml::from_str(text).map_err(|e| format!("Invalid manifest: {}", e))?;
+        for chip in manifest.chips.iter_mut() {
+            let sdk = chip
+                .sdk
+                .take()
+                .unwrap_or_else(|| PathBuf::from(DEFAULT_SDK));
+            chip.sdk = Some(dir.join(sdk));
+            for path in [&mut chip.nonos_sdk, &mut chip.docs, &mut chip.config].iter_mut() {
+                if let Some(path) = path.as_mut() {
+                    *path = dir.join(&path);
+                }
+            }
+            chip.output = dir.join(&chip.output);
+        }
+        if let Some(duplicate) = manifest
+            .chips
+            .iter()
+            .enumerate()
+            .find(|(i, c)| manifest.chips[..*i].iter().any(|o| o.output == c.output))
+        {
+            return Err(format!(
+                "Invalid manifest: {} is written twice",
+                duplicate.1.output.display()
+            ));
+        }
+        Ok(manifest)
+    }
+
+    pub fn load(path: &Path) -> Result<Manifest, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        Manifest::parse(&text, path.parent().unwrap_or_else(|| Path::new("")))
+            .map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+impl ChipEntry {
+    /// Command line options of the run generating this chip
+    pub fn arguments(&self) -> Result<Vec<String>, String> {
+        let path = |p: &Path| p.display().to_string();
+        let sdk = self
+            .sdk
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_SDK));
+        let soc = self.soc.clone().unwrap_or_else(|| {
+            ["components", "soc", &self.name, "include", "soc"]
+                .iter()
+                .collect()
+        });
+        let mut args = vec![
+            "--sdk-dir".to_string(),
+            path(&sdk),
+            "--soc-dir".to_string(),
+            path(&sdk.join(soc)),
+            "--output".to_string(),
+            path(&self.output),
+        ];
+        if let Some(config) = &self.config {
+            args.extend(vec!["--config".to_string(), path(config)]);
+        }
+        if let Some(nonos) = &self.nonos_sdk {
+            args.extend(vec!["--nonos-sdk".to_string(), path(nonos)]);
+        }
+        if let Some(docs) = &self.docs {
+            let entries = std::fs::read_dir(docs)
+                .map_err(|e| format!("Failed to read {}: {}", docs.display(), e))?;
+            let mut files: Vec<PathBuf> = entries.filter_map(|e| Some(e.ok()?.path())).collect();
+            files.sort();
+            for file in files {
+                let option = match file.extension().and_then(|e| e.to_str()) {
+                    Some("json") => "--doc",
+                    Some("csv") | Some("tsv") => "--regmap",
+                    _ => continue,
+                };
+                args.extend(vec![option.to_string(), path(&file)]);
+            }
+        }
+        args.extend(self.args.iter().cloned());
+        Ok(args)
+    }
+}
+
+/// Outcome of the run of one chip
+#[derive(Debug, Clone)]
+pub struct ChipReport {
+    pub chip: String,
+    pub output: PathBuf,
+    /// Counts of the generated svd
+    pub summary: Summary,
+    /// Validation issues found
+    pub issues: usize,
+    /// Why the run failed, e.g. the strict mode check
+    pub failure: Option<String>,
+}
+
+/// Table of all runs, followed by the number of failed ones
+pub fn combined_report(reports: &[ChipReport]) -> String {
+    let mut out = String::new();
+    writeln!(
+        out,
+        "{:<12} {:>11} {:>9} {:>6} {:>6}  output",
+        "chip", "peripherals", "registers", "fields", "issues"
+    )
+    .unwrap();
+    for r in reports {
+        let s = &r.summary;
+        write!(
+            out,
+            "{:<12} {:>11} {:>9} {:>6} {:>6}  {}",
+            r.chip,
+            s.peripherals,
+            s.registers,
+            s.fields,
+            r.issues,
+            r.output.display()
+        )
+        .unwrap();
+        match &r.failure {
+            Some(failure) => writeln!(out, " FAILED: {}", failure).unwrap(),
+            None => writeln!(out).unwrap(),
+        }
+    }
+    let failed = reports.iter().filter(|r| r.failure.is_some()).count();
+    writeln!(out, "{} of {} chips failed", failed, reports.len()).unwrap();
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest() {
+        let text = r#"
+[[chip]]
+name = "esp32s2"
+config = "esp32s2.toml"
+output = "svd/esp32s2.svd"
+args = ["--strict"]
+
+[[chip]]
+name = "esp8266"
+sdk = "/sdk/esp-idf"
+soc = "include"
+nonos_sdk = "nonos/include"
+output = "svd/esp8266.svd"
+"#;
+        let manifest = Manifest::parse(text, Path::new("release")).unwrap();
+        let args = manifest.chips[0].arguments().unwrap();
+        assert_eq!(
+            args,
+            vec![
+                "--sdk-dir",
+                "release/esp-idf",
+                "--soc-dir",
+                "release/esp-idf/components/soc/esp32s2/include/soc",
+                "--output",
+                "release/svd/esp32s2.svd",
+                "--config",
+                "release/esp32s2.toml",
+                "--strict",
+            ]
+        );
+        let args = manifest.chips[1].arguments().unwrap();
+        assert_eq!(
+            &args[..4],
+            &[
+                "--sdk-dir",
+                "/sdk/esp-idf",
+                "--soc-dir",
+                "/sdk/esp-idf/include"
+            ]
+        );
+        assert_eq!(&args[6..], &["--nonos-sdk", "release/nonos/include"]);
+
+        let twice = "[[chip]]\nname = \"a\"\noutput = \"x.svd\"\n\n[[chip]]\nname = \"b\"\noutput = \"x.svd\"\n";
+        assert!(Manifest::parse(twice, Path::new("")).is_err());
+
+        let reports = vec![
+            ChipReport {
+                chip: "esp32".to_string(),
+                output: PathBuf::from("esp32.svd"),
+                summary: Summary {
+                    peripherals: 2,
+                    registers: 10,
+                    fields: 40,
+                    ..Default::default()
+                },
+                issues: 1,
+                failure: None,
+            },
+            ChipReport {
+                chip: "esp8266".to_string(),
+                output: PathBuf::from("esp8266.svd"),
+                summary: Summary::default(),
+                issues: 0,
+                failure: Some("2 peripherals without a base address".to_string()),
+            },
+        ];
+        let report = combined_report(&reports);
+        assert!(report.contains("\nesp32                  2        10     40      1  esp32.svd\n"));
+        assert!(report.contains("esp8266.svd FAILED: 2 peripherals without a base address\n"));
+        assert!(report.ends_with("1 of 2 chips failed\n"));
+    }
+}
@@ -0,0 +1,285 @@
+//! Register reference in Markdown, a page per peripheral with its register map and a diagram
+//! of the fields of every register, for inclusion in documentation next to the svd.
+
+use crate::{BitField, Peripheral, Register, Type};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+
+/// Text of a table cell, which has to stay on one line and can't contain the column separator
+fn cell(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace('|', "\\|")
+}
+
+/// Heading anchor as generated by GitHub and mdBook for a register name
+fn anchor(name: &str) -> String {
+    name.to_lowercase()
+}
+
+fn access(type_: Type) -> &'static str {
+    match type_ {
+        Type::ReadOnly => "RO",
+        Type::ReadWrite => "R/W",
+        Type::WriteOnly => "WO",
+    }
+}
+
+fn bits(f: &BitField) -> String {
+    if f.bits.msb() == f.bits.lsb() {
+        f.bits.lsb().to_string()
+    } else {
+        format!("{}:{}", f.bits.msb(), f.bits.lsb())
+    }
+}
+
+/// Description of `r`, empty when it only repeats the name as the headers often do
+fn description(r: &Register) -> &str {
+    if r.description.trim() == r.name {
+        ""
+    } else {
+        r.description.trim()
+    }
+}
+
+fn register_width(r: &Register) -> u8 {
+    match r.width {
+        0 => 32,
+        width => width,
+    }
+}
+
+/// Diagram of the fields of `r` from the highest bit down, uncovered bits are shown as
+/// reserved. A field overlapping one already drawn is left out of the diagram
+pub fn bit_diagram(r: &Register) -> String {
+    let width = register_width(r);
+    let mut fields: Vec<&BitField> = r
+        .bit_fields
+        .iter()
+        .filter(|f| f.bits.msb() < width)
+        .collect();
+    fields.sort_by_key(|f| (Reverse(f.bits.msb()), f.bits.lsb()));
+
+    // (msb, lsb, label), `next` is the highest bit not drawn yet
+    let mut segments = vec![];
+    let mut next = i32::from(width) - 1;
+    for f in fields {
+        if i32::from(f.bits.msb()) > next {
+            continue;
+        }
+        if i32::from(f.bits.msb()) < next {
+            segments.push((next as u8, f.bits.msb() + 1, "reserved"));
+        }
+        segments.push((f.bits.msb(), f.bits.lsb(), f.name.as_str()));
+        next = i32::from(f.bits.lsb()) - 1;
+    }
+    if next >= 0 {
+        segments.push((next as u8, 0, "reserved"));
+    }
+
+    let mut numbers = String::from("|");
+    let mut names = String::from("|");
+    for (msb, lsb, label) in segments {
+        if msb == lsb {
+            let w = label.len().max(msb.to_string().len()) + 2;
+            write!(numbers, "{:^1$}|", msb, w).unwrap();
+            write!(names, "{:^1$}|", label, w).unwrap();
+        } else {
+            let range = format!("{} {}", msb, lsb);
+            let w = label.len().max(range.len()) + 2;
+            let lsb = lsb.to_string();
+            write!(numbers, "{:<1$}{2}|", msb, w - lsb.len(), lsb).unwrap();
+            write!(names, "{:^1$}|", label, w).unwrap();
+        }
+    }
+    format!("{}\n{}\n", numbers, names)
+}
+
+/// Markdown page describing peripheral `name`
+pub fn peripheral_page(name: &str, p: &Peripheral) -> String {
+    let mut out = String::new();
+    writeln!(out, "# {}\n", name).unwrap();
+    if !p.description.is_empty() && p.description != name {
+        writeln!(out, "{}\n", p.description.trim()).unwrap();
+    }
+    write!(out, "Base address: `{:#010X}`", p.address).unwrap();
+    for address in &p.instance_addresses {
+        write!(out, ", `{:#010X}`", address).unwrap();
+    }
+    writeln!(out).unwrap();
+    if let Some(version) = &p.version {
+        writeln!(out, "\nVersion: {}", version).unwrap();
+    }
+    if !p.interrupts.is_empty() {
+        let interrupts: Vec<String> = p
+            .interrupts
+            .iter()
+            .map(|i| format!("`{}` ({})", i.name, i.value))
+            .collect();
+        writeln!(out, "\nInterrupts: {}", interrupts.join(", ")).unwrap();
+    }
+
+    let mut registers: Vec<&Register> = p.registers.iter().collect();
+    registers.sort_by_key(|r| (r.address, r.name.clone()));
+
+    writeln!(out, "\n## Register map\n").unwrap();
+    writeln!(out, "| Offset | Register | Width | Reset | Description |").unwrap();
+    writeln!(out, "|-------:|----------|------:|------:|-------------|").unwrap();
+    for r in &registers {
+        writeln!(
+            out,
+            "| `{:#06X}` | [{}](#{}) | {} | `{:#010X}` | {} |",
+            r.address,
+            r.name,
+            anchor(&r.name),
+            register_width(r),
+            r.reset_value,
+            cell(description(r))
+        )
+        .unwrap();
+    }
+
+    for r in &registers {
+        writeln!(out, "\n## {}\n", r.name).unwrap();
+        writeln!(
+            out,
+            "Offset `{:#06X}`, address `{:#010X}`, reset value `{:#010X}`\n",
+            r.address,
+            p.address + r.address,
+            r.reset_value
+        )
+        .unwrap();
+        if let Some(safety) = r.safety {
+            writeln!(out, "**{}**\n", safety.note()).unwrap();
+        }
+        if !description(r).is_empty() {
+            writeln!(out, "{}\n", description(r)).unwrap();
+        }
+        if let Some(detailed) = &r.detailed_description {
+            writeln!(out, "{}\n", detailed.trim()).unwrap();
+        }
+        writeln!(out, "```text\n{}```\n", bit_diagram(r)).unwrap();
+
+        let mut fields: Vec<&BitField> = r.bit_fields.iter().collect();
+        fields.sort_by_key(|f| (Reverse(f.bits.msb()), f.bits.lsb()));
+        writeln!(out, "| Bits | Field | Access | Reset | Description |").unwrap();
+        writeln!(out, "|-----:|-------|--------|------:|-------------|").unwrap();
+        for f in fields {
+            writeln!(
+                out,
+                "| {} | {} | {} | `{:#X}` | {} |",
+                bits(f),
+                f.name,
+                access(f.type_),
+                f.reset_value,
+                cell(&f.description)
+            )
+            .unwrap();
+        }
+    }
+    out
+}
+
+/// Markdown page linking the page of every peripheral
+pub fn index_page(peripherals: &BTreeMap<String, Peripheral>) -> String {
+    let mut out = String::new();
+    writeln!(out, "# Peripherals\n").unwrap();
+    writeln!(
+        out,
+        "| Peripheral | Base address | Registers | Description |"
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "|------------|-------------:|----------:|-------------|"
+    )
+    .unwrap();
+    for (name, p) in peripherals {
+        let description = if p.description == *name {
+            ""
+        } else {
+            &p.description
+        };
+        writeln!(
+            out,
+            "| [{0}]({0}.md) | `{1:#010X}` | {2} | {3} |",
+            name,
+            p.address,
+            p.registers.len(),
+            cell(description)
+        )
+        .unwrap();
+    }
+    out
+}
+
+/// Write `index.md` and a `<PERIPHERAL>.md` page per peripheral into `dir`, starting every
+/// page with `banner`
+pub fn write_markdown(
+    dir: &Path,
+    peripherals: &BTreeMap<String, Peripheral>,
+    banner: &str,
+) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(
+        dir.join("index.md"),
+        format!("{}{}", banner, index_page(peripherals)),
+    )?;
+    for (name, p) in peripherals {
+        std::fs::write(
+            dir.join(format!("{}.md", name)),
+            format!("{}{}", banner, peripheral_page(name, p)),
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Bits;
+
+    #[test]
+    fn test_peripheral_page() {
+        let field = |name: &str, bits| BitField {
+            name: name.to_string(),
+            bits,
+            type_: Type::ReadWrite,
+            description: format!("{} | field", name),
+            ..Default::default()
+        };
+        let register = Register {
+            name: "UART_CONF0".to_string(),
+            address: 0x20,
+            width: 8,
+            description: "Configuration".to_string(),
+            bit_fields: vec![
+                field("PARITY_EN", Bits::Single(1)),
+                field("STOP_BIT_NUM", Bits::Range(4..=5)),
+                field("PARITY", Bits::Single(1)),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            bit_diagram(&register),
+            "|7        6|5            4|3        2|     1     |    0     |\n\
+             | reserved | STOP_BIT_NUM | reserved | PARITY_EN | reserved |\n"
+        );
+
+        let peripheral = Peripheral {
+            description: "UART controller".to_string(),
+            address: 0x3ff4_0000,
+            registers: vec![register],
+            ..Default::default()
+        };
+        let page = peripheral_page("UART", &peripheral);
+        assert!(page.contains(
+            "| `0x0020` | [UART_CONF0](#uart_conf0) | 8 | `0x00000000` | Configuration |"
+        ));
+        assert!(page.contains("| 5:4 | STOP_BIT_NUM | R/W | `0x0` | STOP_BIT_NUM \\| field |"));
+        assert!(page.contains("Offset `0x0020`, address `0x3FF40020`"));
+    }
+}
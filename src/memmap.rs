@@ -0,0 +1,191 @@
+//! Memory map of the chip from the `MEMORY` block of the SDK linker scripts (`eagle.*.ld` of
+//! the ESP8266 SDKs, `esp32.ld` of esp-idf), written as the target description YAML of
+//! probe-rs so debuggers and flash tools know the RAM and flash regions next to the svd.
+
+use crate::expr;
+use regex::Regex;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// What a region of the memory map is used for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RegionKind {
+    /// Instruction, data or RTC RAM
+    Ram,
+    /// Flash mapped into the address space through the cache
+    Nvm,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryRegion {
+    pub name: String,
+    pub start: u32,
+    pub length: u32,
+    pub kind: RegionKind,
+}
+
+/// Kind of a linker script segment by its name, `None` for segments which aren't memory such
+/// as the DPORT registers of the ESP8266 scripts
+fn region_kind(name: &str) -> Option<RegionKind> {
+    let name = name.to_lowercase();
+    if name.starts_with("dport") {
+        None
+    } else if name.contains("irom") || name.contains("drom") || name.contains("flash") {
+        Some(RegionKind::Nvm)
+    } else {
+        Some(RegionKind::Ram)
+    }
+}
+
+/// Evaluate an `org` or `len` expression, with the `K` and `M` suffixes of ld and the
+/// identifiers (e.g. `CONFIG_BT_RESERVE_DRAM`) taken from `defines`
+fn evaluate(value: &str, defines: &HashMap<String, String>) -> Result<u32, String> {
+    let re_suffix = Regex::new(r"\b([0-9]+)([KkMm])\b").unwrap();
+    let value = re_suffix.replace_all(value, |c: &regex::Captures| match &c[2] {
+        "K" | "k" => format!("({} * 1024)", &c[1]),
+        _ => format!("({} * 1024 * 1024)", &c[1]),
+    });
+    let resolve = |name: &str| {
+        defines
+            .get(name)
+            .and_then(|v| expr::eval(v, &|_| None).ok())
+    };
+    expr::eval(&value, &resolve).map(|v| v as u32)
+}
+
+/// Regions of the `MEMORY` block of a linker script, segments whose origin or length can't be
+/// evaluated are skipped with a warning
+pub fn parse_linker_script(
+    text: &str,
+    defines: &HashMap<String, String>,
+) -> Result<Vec<MemoryRegion>, String> {
+    let re_comment = Regex::new(r"(?s)/\*.*?\*/").unwrap();
+    let re_memory = Regex::new(r"(?s)\bMEMORY\s*\{(.*?)\}").unwrap();
+    let re_segment = Regex::new(
+        r"^\s*(\w+)\s*(?:\([A-Za-z!]*\))?\s*:\s*(?:ORIGIN|org|o)\s*=\s*([^,]+),\s*(?:LENGTH|len|l)\s*=\s*(.+?)\s*$",
+    )
+    .unwrap();
+
+    let text = re_comment.replace_all(text, "");
+    let memory = re_memory
+        .captures(&text)
+        .ok_or_else(|| "No MEMORY block".to_string())?;
+    let mut regions = vec![];
+    for c in memory[1]
+        .lines()
+        .filter_map(|line| re_segment.captures(line))
+    {
+        let kind = match region_kind(&c[1]) {
+            Some(kind) => kind,
+            None => continue,
+        };
+        match (evaluate(&c[2], defines), evaluate(&c[3], defines)) {
+            (Ok(start), Ok(length)) => regions.push(MemoryRegion {
+                name: c[1].to_string(),
+                start,
+                length,
+                kind,
+            }),
+            (Err(e), _) | (_, Err(e)) => println!("Skipping memory region {}: {}", &c[1], e),
+        }
+    }
+    Ok(regions)
+}
+
+/// Add `regions` from another linker script to `map`, regions already in it win
+pub fn merge_regions(map: &mut Vec<MemoryRegion>, regions: Vec<MemoryRegion>) {
+    for region in regions {
+        match map.iter().find(|r| r.name == region.name) {
+            Some(existing) if *existing != region => println!(
+                "Ignoring memory region {} at 0x{:08x}, already defined at 0x{:08x}",
+                region.name, region.start, existing.start
+            ),
+            Some(_) => {}
+            None => map.push(region),
+        }
+    }
+}
+
+/// probe-rs target description of `chip` with `regions` as its memory map
+pub fn memory_map_yaml(chip: &str, regions: &[MemoryRegion]) -> String {
+    let mut out = String::new();
+    writeln!(out, "name: {}", chip).unwrap();
+    writeln!(out, "variants:").unwrap();
+    writeln!(out, "  - name: {}", chip).unwrap();
+    writeln!(out, "    cores:").unwrap();
+    writeln!(out, "      - name: main").unwrap();
+    writeln!(out, "        type: xtensa").unwrap();
+    writeln!(out, "    memory_map:").unwrap();
+    let mut regions: Vec<&MemoryRegion> = regions.iter().collect();
+    regions.sort_by_key(|r| (r.start, r.name.clone()));
+    for r in regions {
+        let kind = match r.kind {
+            RegionKind::Ram => "Ram",
+            RegionKind::Nvm => "Nvm",
+        };
+        writeln!(out, "      - !{}", kind).unwrap();
+        writeln!(out, "        name: {}", r.name).unwrap();
+        writeln!(out, "        range:").unwrap();
+        writeln!(out, "          start: 0x{:x}", r.start).unwrap();
+        // the end is exclusive
+        let end = u64::from(r.start) + u64::from(r.length);
+        writeln!(out, "          end: 0x{:x}", end).unwrap();
+        writeln!(out, "        cores:").unwrap();
+        writeln!(out, "          - main").unwrap();
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_linker_script() {
+        let text = "/* This linker script generated from xt-genldscripts.tpp */
+MEMORY
+{
+  dport0_0_seg :                        org = 0x3FF00000, len = 0x10
+  dram0_0_seg :                         org = 0x3FFE8000, len = 80K
+  iram1_0_seg (RX) :                    org = 0x40100000, len = 0x8000
+  irom0_0_seg :                         org = 0x40210000, len = 0x5C000
+  rtc_seg :                             org = 0x60001200 + RESERVED, len = 0x200 - RESERVED
+  bad_seg :                             org = UNKNOWN, len = 0x10
+}
+
+PHDRS
+{
+  dport0_0_phdr PT_LOAD;
+}
+";
+        let mut defines = HashMap::new();
+        defines.insert("RESERVED".to_string(), "0x10".to_string());
+        let regions = parse_linker_script(text, &defines).unwrap();
+        let summary: Vec<(&str, u32, u32, RegionKind)> = regions
+            .iter()
+            .map(|r| (r.name.as_str(), r.start, r.length, r.kind))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("dram0_0_seg", 0x3ffe_8000, 0x14000, RegionKind::Ram),
+                ("iram1_0_seg", 0x4010_0000, 0x8000, RegionKind::Ram),
+                ("irom0_0_seg", 0x4021_0000, 0x5c000, RegionKind::Nvm),
+                ("rtc_seg", 0x6000_1210, 0x1f0, RegionKind::Ram),
+            ]
+        );
+        assert!(parse_linker_script("SECTIONS {}", &defines).is_err());
+
+        let mut map = regions[..1].to_vec();
+        let mut moved = regions[0].clone();
+        moved.start = 0;
+        merge_regions(&mut map, vec![moved, regions[2].clone()]);
+        assert_eq!(map, vec![regions[0].clone(), regions[2].clone()]);
+
+        let yaml = memory_map_yaml("esp8266", &map);
+        assert!(yaml.starts_with("name: esp8266\nvariants:\n  - name: esp8266\n"));
+        assert!(yaml.contains(
+            "      - !Nvm\n        name: irom0_0_seg\n        range:\n          start: 0x40210000\n          end: 0x4026c000\n"
+        ));
+    }
+}
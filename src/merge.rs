@@ -0,0 +1,304 @@
+//! Combining models parsed from different inputs into one.
+
+use crate::{Peripheral, Register};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Add the peripherals and registers of `source` missing from `target`. Registers are matched
+/// by offset, peripherals by name, `source_name` names the input in messages. Returns the
+/// number of added registers
+pub fn merge_union(
+    target: &mut BTreeMap<String, Peripheral>,
+    source: BTreeMap<String, Peripheral>,
+    source_name: &str,
+) -> usize {
+    let mut added = 0;
+    for (name, p) in source {
+        let existing = match target.get_mut(&name) {
+            Some(existing) => existing,
+            None => {
+                added += p.registers.len();
+                target.insert(name, p);
+                continue;
+            }
+        };
+        // an unknown (zero) base address on either side doesn't conflict
+        if existing.address != p.address && existing.address != 0 && p.address != 0 {
+            println!(
+                "{} is at {:#x} but at {:#x} in {}, skipping its registers",
+                name, existing.address, p.address, source_name
+            );
+            continue;
+        }
+        if p.address != 0 {
+            existing.address = p.address;
+        }
+        for r in p.registers {
+            if existing.registers.iter().any(|e| e.address == r.address) {
+                continue;
+            }
+            existing.registers.push(r);
+            added += 1;
+        }
+        existing.registers.sort_by_key(|r| r.address);
+    }
+    added
+}
+
+/// How registers described by both the headers and documentation are combined
+#[derive(Debug, Copy, Clone, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MergeStrategy {
+    /// Take the documented register, registers only found in the headers are kept and so are
+    /// the header fields of documented registers without fields
+    #[default]
+    PreferDoc,
+    /// Keep the header register, registers only found in the documentation are added
+    PreferHeader,
+    /// Keep the header register and add the documented fields on bits it doesn't cover
+    Union,
+    /// Fail when the documented register differs from the header register
+    Error,
+}
+
+/// Strategy for a peripheral, or for one of its registers when `register` is set
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MergeRule {
+    pub peripheral: String,
+    /// Register name, as found in either the headers or the documentation
+    #[serde(default)]
+    pub register: Option<String>,
+    pub strategy: MergeStrategy,
+}
+
+/// Strategy for `register` (by any of its names) of `peripheral`, register rules take
+/// precedence over peripheral rules and later rules over earlier ones
+pub fn strategy_for(rules: &[MergeRule], peripheral: &str, register: &[&str]) -> MergeStrategy {
+    let rule = |for_register: bool| {
+        rules.iter().rev().find(|r| {
+            r.peripheral == peripheral
+                && match &r.register {
+                    Some(name) => for_register && register.contains(&name.as_str()),
+                    None => !for_register,
+                }
+        })
+    };
+    rule(true)
+        .or_else(|| rule(false))
+        .map(|r| r.strategy)
+        .unwrap_or_default()
+}
+
+/// Register name without the peripheral prefix, docs usually leave it out
+fn short_name<'a>(peripheral: &str, register: &'a str) -> &'a str {
+    register
+        .strip_prefix(peripheral)
+        .and_then(|r| r.strip_prefix('_'))
+        .unwrap_or(register)
+}
+
+/// Describe how two registers at the same offset differ, `None` if they agree
+fn difference(peripheral: &str, header: &Register, doc: &Register) -> Option<String> {
+    if short_name(peripheral, &header.name) != short_name(peripheral, &doc.name) {
+        return Some(format!("named {} in the documentation", doc.name));
+    }
+    let layout = |r: &Register| {
+        let mut fields: Vec<(String, u8, u8)> = r
+            .bit_fields
+            .iter()
+            .map(|f| (f.name.clone(), f.bits.lsb(), f.bits.msb()))
+            .collect();
+        fields.sort();
+        fields
+    };
+    if layout(header) != layout(doc) {
+        return Some(String::from("fields differ from the documentation"));
+    }
+    None
+}
+
+/// Merge the documented peripheral `doc` into the header derived `existing` using the
+/// strategies of `rules`. Registers are matched by offset. Returns the differences between
+/// the headers and the documentation that were resolved by a strategy other than `error`
+pub fn merge_documented(
+    name: &str,
+    existing: &mut Peripheral,
+    doc: Peripheral,
+    rules: &[MergeRule],
+) -> Result<Vec<String>, String> {
+    let mut conflicts = vec![];
+    if doc.address != 0 && doc.address != existing.address {
+        let strategy = strategy_for(rules, name, &[]);
+        if existing.address != 0 && strategy != MergeStrategy::Error {
+            conflicts.push(format!(
+                "{}: base address {:#x} but {:#x} in the documentation",
+                name, existing.address, doc.address
+            ));
+        }
+        match strategy {
+            MergeStrategy::PreferDoc => {
+                existing.address = doc.address;
+                existing.provenance.extend(doc.provenance);
+            }
+            MergeStrategy::Error if existing.address != 0 => {
+                return Err(format!(
+                    "{}: base address {:#x} but {:#x} in the documentation",
+                    name, existing.address, doc.address
+                ))
+            }
+            _ if existing.address == 0 => existing.address = doc.address,
+            _ => {}
+        }
+    }
+    // the model falls back to the name when there is no description
+    if doc.description != name && strategy_for(rules, name, &[]) != MergeStrategy::PreferHeader {
+        existing.description = doc.description;
+    }
+    if doc.version.is_some() {
+        existing.version = doc.version;
+    }
+
+    for d in doc.registers {
+        let index = existing
+            .registers
+            .iter()
+            .position(|r| r.address == d.address);
+        let h = match index {
+            Some(index) => &mut existing.registers[index],
+            None => {
+                existing.registers.push(d);
+                continue;
+            }
+        };
+        let strategy = strategy_for(rules, name, &[&h.name, &d.name]);
+        // a classification isn't a difference, it is kept whatever the strategy
+        let safety = d.safety.or(h.safety);
+        if strategy != MergeStrategy::Error {
+            if let Some(difference) = difference(name, h, &d) {
+                conflicts.push(format!("{}.{}: {}", name, h.name, difference));
+            }
+        }
+        match strategy {
+            MergeStrategy::PreferDoc => {
+                let header_fields = std::mem::take(&mut h.bit_fields);
+                *h = d;
+                if h.bit_fields.is_empty() {
+                    h.bit_fields = header_fields;
+                }
+            }
+            MergeStrategy::PreferHeader => {}
+            MergeStrategy::Union => {
+                for f in d.bit_fields {
+                    if h.bit_fields.iter().all(|hf| !hf.bits.overlaps(&f.bits)) {
+                        h.bit_fields.push(f);
+                    }
+                }
+                h.bit_fields.sort_by_key(|f| f.bits.lsb());
+                h.complete |= d.complete;
+                if d.width != 0 {
+                    h.width = d.width;
+                }
+                h.access = d.access.or(h.access);
+                h.provenance.extend(d.provenance);
+            }
+            MergeStrategy::Error => {
+                if let Some(difference) = difference(name, h, &d) {
+                    return Err(format!("{}.{}: {}", name, h.name, difference));
+                }
+            }
+        }
+        h.safety = safety;
+    }
+    existing.registers.sort_by_key(|r| r.address);
+    Ok(conflicts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BitField, Bits};
+
+    fn rule(register: Option<&str>, strategy: MergeStrategy) -> MergeRule {
+        MergeRule {
+            peripheral: "TIMER".to_string(),
+            register: register.map(str::to_string),
+            strategy,
+        }
+    }
+
+    fn register(name: &str, fields: &[(&str, u8)]) -> Register {
+        Register {
+            name: name.to_string(),
+            address: 8,
+            bit_fields: fields
+                .iter()
+                .map(|(name, bit)| BitField {
+                    name: name.to_string(),
+                    bits: Bits::Single(*bit),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn merge(strategy: MergeStrategy) -> Result<(Vec<String>, Vec<String>), String> {
+        let mut header = Peripheral {
+            registers: vec![register("TIMER_FRC1_CTRL", &[("ENABLE", 7)])],
+            ..Default::default()
+        };
+        let doc = Peripheral {
+            registers: vec![register("FRC1_CTRL", &[("EN", 7), ("INT", 8)])],
+            ..Default::default()
+        };
+        let conflicts = merge_documented("TIMER", &mut header, doc, &[rule(None, strategy)])?;
+        let fields = header.registers[0]
+            .bit_fields
+            .iter()
+            .map(|f| f.name.clone())
+            .collect();
+        Ok((fields, conflicts))
+    }
+
+    #[test]
+    fn test_strategy_for() {
+        let rules = vec![
+            rule(Some("FRC1_CTRL"), MergeStrategy::Union),
+            rule(None, MergeStrategy::PreferHeader),
+        ];
+        assert_eq!(
+            strategy_for(&rules, "TIMER", &["TIMER_FRC1_CTRL", "FRC1_CTRL"]),
+            MergeStrategy::Union
+        );
+        assert_eq!(
+            strategy_for(&rules, "TIMER", &["FRC1_LOAD"]),
+            MergeStrategy::PreferHeader
+        );
+        assert_eq!(strategy_for(&rules, "UART", &[]), MergeStrategy::PreferDoc);
+    }
+
+    #[test]
+    fn test_strategies() {
+        assert_eq!(
+            merge(MergeStrategy::PreferDoc).unwrap().0,
+            vec!["EN", "INT"]
+        );
+        assert_eq!(
+            merge(MergeStrategy::PreferHeader).unwrap().0,
+            vec!["ENABLE"]
+        );
+        let (fields, conflicts) = merge(MergeStrategy::Union).unwrap();
+        assert_eq!(fields, vec!["ENABLE", "INT"]);
+        assert_eq!(
+            conflicts,
+            vec!["TIMER.TIMER_FRC1_CTRL: fields differ from the documentation"]
+        );
+        assert_eq!(
+            merge(MergeStrategy::Error),
+            Err(String::from(
+                "TIMER.TIMER_FRC1_CTRL: fields differ from the documentation"
+            ))
+        );
+    }
+}
@@ -0,0 +1,245 @@
+//! How an svd was produced: the generator version, the SDK commit, the hashes of the other
+//! input files, the time of generation and the configured license. Written into the
+//! `vendorExtensions` of the device and as a comment banner at the top of every generated file.
+
+use crate::config::Banner;
+use crate::fetch::{sdk_extension, sdk_revision};
+use chrono::{DateTime, SecondsFormat};
+use sha2::{Digest, Sha256};
+use std::convert::TryFrom;
+use std::fmt::Write;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use xmltree::Element;
+
+/// SHA-256 of `data` as lowercase hex, the same as `sha256sum` prints
+pub fn sha256(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// `seconds` since the epoch as an RFC 3339 UTC timestamp
+fn timestamp(seconds: u64) -> String {
+    i64::try_from(seconds)
+        .ok()
+        .and_then(|seconds| DateTime::from_timestamp(seconds, 0))
+        .unwrap_or_default()
+        .to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Generation time, `SOURCE_DATE_EPOCH` takes precedence for reproducible builds
+fn generation_time() -> String {
+    let seconds = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0)
+        });
+    timestamp(seconds)
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Metadata {
+    pub tool_version: String,
+    /// Repository and commit of the SDK checkout
+    pub sdk: Option<(String, String)>,
+    /// Input files other than the SDK headers, with their SHA-256
+    pub inputs: Vec<(String, String)>,
+    pub timestamp: String,
+    /// SPDX identifier of the license of the generated content
+    pub license: Option<String>,
+    pub notice: Vec<String>,
+}
+
+/// Comment syntax of a generated file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CommentStyle {
+    C,
+    Rust,
+    /// An html comment, as used by Markdown
+    Html,
+    /// `#` line comments, as used by YAML
+    Hash,
+}
+
+impl Metadata {
+    /// Collect the metadata of a run reading the SDK checkout in `sdk_dir` and `inputs`, with
+    /// the license and notice of the configured `banner`
+    pub fn collect<'a>(
+        sdk_dir: &Path,
+        inputs: impl IntoIterator<Item = &'a Path>,
+        banner: &Banner,
+    ) -> Result<Metadata, String> {
+        let mut hashes = vec![];
+        for path in inputs {
+            let data = std::fs::read(path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            hashes.push((path.display().to_string(), sha256(&data)));
+        }
+        Ok(Metadata {
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            sdk: sdk_revision(sdk_dir),
+            inputs: hashes,
+            timestamp: generation_time(),
+            license: banner.license.clone(),
+            notice: banner.notice.clone(),
+        })
+    }
+
+    /// Entries of the device `vendorExtensions`
+    pub fn extensions(&self) -> Vec<Element> {
+        let text = |name: &str, text: &str| {
+            let mut element = Element::new(name);
+            element.text = Some(text.to_string());
+            element
+        };
+        let mut generator = Element::new("generator");
+        generator.children.push(text("name", "idf2svd"));
+        generator.children.push(text("version", &self.tool_version));
+        generator.children.push(text("timestamp", &self.timestamp));
+
+        let mut extensions = vec![generator];
+        if let Some(license) = &self.license {
+            extensions.push(text("license", license));
+        }
+        if let Some((repository, commit)) = &self.sdk {
+            extensions.push(sdk_extension(repository, commit));
+        }
+        if !self.inputs.is_empty() {
+            let mut inputs = Element::new("inputs");
+            for (file, hash) in &self.inputs {
+                let mut input = Element::new("input");
+                input.children.push(text("file", file));
+                input.children.push(text("sha256", hash));
+                inputs.children.push(input);
+            }
+            extensions.push(inputs);
+        }
+        extensions
+    }
+
+    /// The same information as plain text lines
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![format!(
+            "Generated by idf2svd {} at {}",
+            self.tool_version, self.timestamp
+        )];
+        lines.extend(self.notice.iter().cloned());
+        // the tag license scanners look for
+        if let Some(license) = &self.license {
+            lines.push(format!("SPDX-License-Identifier: {}", license));
+        }
+        match &self.sdk {
+            Some((repository, commit)) if repository.is_empty() => {
+                lines.push(format!("SDK: {}", commit))
+            }
+            Some((repository, commit)) => lines.push(format!("SDK: {} {}", repository, commit)),
+            None => {}
+        }
+        for (file, hash) in &self.inputs {
+            lines.push(format!("Input: {} sha256:{}", file, hash));
+        }
+        lines
+    }
+
+    /// XML comment text for the top of the svd, without the `<!--` and `-->` around it
+    pub fn comment(&self) -> String {
+        let mut comment = String::new();
+        for line in self.lines() {
+            // `--` can't appear in an XML comment
+            writeln!(comment, "  {}", line.replace("--", "-")).unwrap();
+        }
+        comment
+    }
+
+    /// Comment banner for the top of a generated source file
+    pub fn banner(&self, style: CommentStyle) -> String {
+        let mut banner = String::new();
+        match style {
+            CommentStyle::C => {
+                writeln!(banner, "/*").unwrap();
+                for line in self.lines() {
+                    writeln!(banner, " * {}", line.replace("*/", "* /")).unwrap();
+                }
+                writeln!(banner, " */").unwrap();
+            }
+            CommentStyle::Rust => {
+                for line in self.lines() {
+                    writeln!(banner, "// {}", line).unwrap();
+                }
+            }
+            CommentStyle::Html => {
+                writeln!(banner, "<!--\n{}-->", self.comment()).unwrap();
+            }
+            CommentStyle::Hash => {
+                for line in self.lines() {
+                    writeln!(banner, "# {}", line).unwrap();
+                }
+            }
+        }
+        banner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sha256() {
+        assert_eq!(
+            sha256(b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            sha256(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        let long = vec![b'a'; 1000];
+        assert_eq!(
+            sha256(&long),
+            "41edece42d63e8d9bf515a9ba6932e1c20cbc9f5a5d134645adb5db1b9737ea3"
+        );
+    }
+
+    #[test]
+    fn test_timestamp() {
+        assert_eq!(timestamp(0), "1970-01-01T00:00:00Z");
+        assert_eq!(timestamp(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(timestamp(1_700_000_000), "2023-11-14T22:13:20Z");
+        assert_eq!(timestamp(1_709_164_800), "2024-02-29T00:00:00Z");
+        // 2100 is not a leap year
+        assert_eq!(timestamp(4_107_456_000 + 86_400), "2100-03-01T00:00:00Z");
+    }
+
+    #[test]
+    fn test_banner() {
+        let metadata = Metadata {
+            tool_version: "0.1.0".to_string(),
+            sdk: Some((String::new(), "abc123".to_string())),
+            inputs: vec![("uart.json".to_string(), sha256(b""))],
+            timestamp: timestamp(0),
+            license: Some("Apache-2.0".to_string()),
+            notice: vec!["Derived from */ esp-idf".to_string()],
+        };
+        assert_eq!(
+            metadata.banner(CommentStyle::C),
+            "/*\n * Generated by idf2svd 0.1.0 at 1970-01-01T00:00:00Z\n \
+             * Derived from * / esp-idf\n * SPDX-License-Identifier: Apache-2.0\n \
+             * SDK: abc123\n * Input: uart.json sha256:e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855\n */\n"
+        );
+        assert!(metadata
+            .banner(CommentStyle::Rust)
+            .lines()
+            .all(|l| l.starts_with("// ")));
+        assert!(metadata
+            .extensions()
+            .iter()
+            .any(|e| e.name == "license" && e.text.as_deref() == Some("Apache-2.0")));
+    }
+}
@@ -0,0 +1,116 @@
+//! Size of the encoded svd and time spent generating it per peripheral, to spot the peripheral
+//! behind a regression of the generation time or output size, e.g. one that ballooned after a
+//! doc merge.
+
+use crate::svd::{svd_peripheral, SvdOptions};
+use crate::Peripheral;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+use svd_parser::encode::Encode;
+
+#[derive(Debug, Clone)]
+pub struct PeripheralMetrics {
+    pub name: String,
+    pub registers: usize,
+    pub fields: usize,
+    /// Size of the encoded `<peripheral>` element
+    pub bytes: usize,
+    /// Time spent building and encoding the svd peripheral
+    pub duration: Duration,
+}
+
+/// Encode every peripheral on its own and measure it, the largest peripherals first
+pub fn peripheral_metrics(
+    peripherals: &BTreeMap<String, Peripheral>,
+) -> Result<Vec<PeripheralMetrics>, String> {
+    let mut metrics = vec![];
+    for (name, p) in peripherals {
+        let start = Instant::now();
+        let mut xml = vec![];
+        svd_peripheral(name, p, &SvdOptions::default())
+            .encode()
+            .map_err(|e| format!("Failed to encode {}: {:?}", name, e))?
+            .write(&mut xml)
+            .map_err(|e| format!("Failed to encode {}: {}", name, e))?;
+        metrics.push(PeripheralMetrics {
+            name: name.clone(),
+            registers: p.registers.len(),
+            fields: p.registers.iter().map(|r| r.bit_fields.len()).sum(),
+            bytes: xml.len(),
+            duration: start.elapsed(),
+        });
+    }
+    metrics.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.name.cmp(&b.name)));
+    Ok(metrics)
+}
+
+/// Render the metrics as a table, with each peripheral's share of the total size
+pub fn metrics_report(metrics: &[PeripheralMetrics]) -> String {
+    let total: usize = metrics.iter().map(|m| m.bytes).sum();
+    let mut out = String::new();
+    writeln!(
+        out,
+        "{:>10} {:>6} {:>9} {:>9} {:>7} peripheral",
+        "bytes", "share", "ms", "registers", "fields"
+    )
+    .unwrap();
+    for m in metrics {
+        writeln!(
+            out,
+            "{:>10} {:>5.1}% {:>9.2} {:>9} {:>7} {}",
+            m.bytes,
+            m.bytes as f64 * 100.0 / total.max(1) as f64,
+            m.duration.as_secs_f64() * 1000.0,
+            m.registers,
+            m.fields,
+            m.name
+        )
+        .unwrap();
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BitField, Register};
+
+    #[test]
+    fn test_peripheral_metrics() {
+        let register = |name: &str, fields: usize| Register {
+            name: name.to_string(),
+            bit_fields: (0..fields)
+                .map(|i| BitField {
+                    name: format!("F{}", i),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let mut peripherals = BTreeMap::new();
+        peripherals.insert(
+            "SMALL".to_string(),
+            Peripheral {
+                registers: vec![register("A", 1)],
+                ..Default::default()
+            },
+        );
+        peripherals.insert(
+            "LARGE".to_string(),
+            Peripheral {
+                registers: vec![register("A", 4), register("B", 8)],
+                ..Default::default()
+            },
+        );
+
+        let metrics = peripheral_metrics(&peripherals).unwrap();
+        assert_eq!(metrics[0].name, "LARGE");
+        assert_eq!((metrics[0].registers, metrics[0].fields), (2, 12));
+        assert!(metrics[0].bytes > metrics[1].bytes);
+
+        let report = metrics_report(&metrics);
+        assert_eq!(report.lines().count(), 3);
+        assert!(report.lines().nth(2).unwrap().ends_with(" SMALL"));
+    }
+}
@@ -0,0 +1,724 @@
+//! Parser for the headers of the ESP8266 NONOS SDK (`eagle_soc.h`, `*_register.h`), which
+//! define registers as `UART_FIFO(i)` or `GPIO_OUT_ADDRESS` and fields as a mask followed by a
+//! `_S` shift (or a shifted `_MASK` followed by its `_LSB`, or a mask such as `(0x7 << 4)`
+//! stating its own offset), without the annotation comments of the esp-idf headers. Registers
+//! of a single instance peripheral are defined relative to a `REG_RTC_BASE` alias of its base
+//! as `RTC_STORE0 (REG_RTC_BASE + 0x030)`. The DPORT registers are defined relative to
+//! `PERIPHS_DPORT_BASEADDR` or its address in `ets_sys.h`, or by their offset alone. The timer
+//! (FRC1/FRC2), watchdog, DPORT and RTC fields `eagle_soc.h` leaves out are filled in from the
+//! Technical Reference Manual, the pin multiplexer is parsed by [`crate::iomux`] and the efuse
+//! words added from [`crate::efuse`].
+
+use crate::efuse::{efuse_peripheral, DPORT_ADDRESS, PERIPHERAL as EFUSE};
+use crate::iomux::{parse_io_mux, PAD_REGISTER, PERIPHERAL as IO_MUX};
+use crate::validate::Issue;
+use crate::{expr, BitField, Bits, Origin, Peripheral, Provenance, Register, Type};
+use regex::Regex;
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+/// Source name recorded on the merged registers
+pub const SOURCE: &str = "nonos-sdk";
+
+/// All headers below `dir`
+fn headers(dir: &Path, files: &mut Vec<PathBuf>) {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            println!("Failed to read {}: {}", dir.display(), e);
+            return;
+        }
+    };
+    for path in entries.filter_map(Result::ok).map(|e| e.path()) {
+        if path.is_dir() {
+            headers(&path, files);
+        } else if path.extension() == Some(OsStr::new("h")) {
+            files.push(path);
+        }
+    }
+}
+
+fn number(value: &str) -> Option<u32> {
+    expr::eval(value, &|_| None).ok().map(|v| v as u32)
+}
+
+/// Register stems of peripherals whose registers aren't prefixed with the peripheral name,
+/// such as the `FRC1_LOAD_ADDRESS` timer registers of `eagle_soc.h`
+const STEM_ALIASES: &[(&str, &str)] = &[("FRC1", "TIMER"), ("FRC2", "TIMER")];
+
+/// Registers whose define only states their offset, such as `HOST_INF_SEL (0x28)` of the
+/// DPORT block: peripheral, register
+const OFFSET_REGISTERS: &[(&str, &str)] = &[("DPORT", "HOST_INF_SEL")];
+
+/// Fields of the timer, watchdog, DPORT and RTC registers `eagle_soc.h` doesn't define, from the
+/// ESP8266 Technical Reference Manual: peripheral, register, field, lsb, msb, access
+const CURATED_FIELDS: &[(&str, &str, &str, u8, u8, Type)] = &[
+    (
+        "TIMER",
+        "FRC1_LOAD",
+        "FRC1_LOAD_VALUE",
+        0,
+        22,
+        Type::ReadWrite,
+    ),
+    (
+        "TIMER",
+        "FRC1_COUNT",
+        "FRC1_COUNT_DATA",
+        0,
+        22,
+        Type::ReadOnly,
+    ),
+    ("TIMER", "FRC1_CTRL", "FRC1_INT_TYPE", 0, 0, Type::ReadWrite),
+    (
+        "TIMER",
+        "FRC1_CTRL",
+        "FRC1_PRESCALE_DIV",
+        2,
+        3,
+        Type::ReadWrite,
+    ),
+    (
+        "TIMER",
+        "FRC1_CTRL",
+        "FRC1_AUTO_LOAD",
+        6,
+        6,
+        Type::ReadWrite,
+    ),
+    ("TIMER", "FRC1_CTRL", "FRC1_ENABLE", 7, 7, Type::ReadWrite),
+    (
+        "TIMER",
+        "FRC1_CTRL",
+        "FRC1_INT_STATUS",
+        8,
+        8,
+        Type::ReadOnly,
+    ),
+    ("TIMER", "FRC1_INT", "FRC1_INT_CLR", 0, 0, Type::WriteOnly),
+    (
+        "TIMER",
+        "FRC2_LOAD",
+        "FRC2_LOAD_VALUE",
+        0,
+        31,
+        Type::ReadWrite,
+    ),
+    (
+        "TIMER",
+        "FRC2_COUNT",
+        "FRC2_COUNT_DATA",
+        0,
+        31,
+        Type::ReadOnly,
+    ),
+    ("TIMER", "FRC2_CTRL", "FRC2_INT_TYPE", 0, 0, Type::ReadWrite),
+    (
+        "TIMER",
+        "FRC2_CTRL",
+        "FRC2_PRESCALE_DIV",
+        2,
+        3,
+        Type::ReadWrite,
+    ),
+    (
+        "TIMER",
+        "FRC2_CTRL",
+        "FRC2_AUTO_LOAD",
+        6,
+        6,
+        Type::ReadWrite,
+    ),
+    ("TIMER", "FRC2_CTRL", "FRC2_ENABLE", 7, 7, Type::ReadWrite),
+    (
+        "TIMER",
+        "FRC2_CTRL",
+        "FRC2_INT_STATUS",
+        8,
+        8,
+        Type::ReadOnly,
+    ),
+    ("TIMER", "FRC2_INT", "FRC2_INT_CLR", 0, 0, Type::WriteOnly),
+    (
+        "TIMER",
+        "FRC2_ALARM",
+        "FRC2_ALARM_VALUE",
+        0,
+        31,
+        Type::ReadWrite,
+    ),
+    ("WDT", "WDT_OP", "WDT_OP_TIMEOUT", 0, 3, Type::ReadWrite),
+    (
+        "WDT",
+        "WDT_OP_ND",
+        "WDT_OP_ND_TIMEOUT",
+        0,
+        3,
+        Type::ReadWrite,
+    ),
+    ("WDT", "WDT_RST", "WDT_RST_FEED", 0, 7, Type::WriteOnly),
+    (
+        "DPORT",
+        "EDGE_INT_ENABLE",
+        "WDT_EDGE_INT_EN",
+        0,
+        0,
+        Type::ReadWrite,
+    ),
+    (
+        "DPORT",
+        "EDGE_INT_ENABLE",
+        "TM1_EDGE_INT_EN",
+        1,
+        1,
+        Type::ReadWrite,
+    ),
+    ("RTC", "RTC_SLP_VAL", "RTC_SLP_VAL", 0, 31, Type::ReadWrite),
+    (
+        "RTC",
+        "RTC_SLP_CNT_VAL",
+        "RTC_SLP_CNT_VAL",
+        0,
+        31,
+        Type::ReadOnly,
+    ),
+    ("RTC", "RTC_STORE0", "RTC_STORE0", 0, 31, Type::ReadWrite),
+    ("RTC", "RTC_STORE1", "RTC_STORE1", 0, 31, Type::ReadWrite),
+    ("RTC", "RTC_STORE2", "RTC_STORE2", 0, 31, Type::ReadWrite),
+    ("RTC", "RTC_STORE3", "RTC_STORE3", 0, 31, Type::ReadWrite),
+    (
+        "RTC",
+        "RTC_GPIO_OUT",
+        "RTC_GPIO_OUT_DATA",
+        0,
+        0,
+        Type::ReadWrite,
+    ),
+    (
+        "RTC",
+        "RTC_GPIO_ENABLE",
+        "RTC_GPIO_OUT_EN",
+        0,
+        0,
+        Type::ReadWrite,
+    ),
+    (
+        "RTC",
+        "RTC_GPIO_IN_DATA",
+        "RTC_GPIO_IN_DATA",
+        0,
+        0,
+        Type::ReadOnly,
+    ),
+];
+
+/// Bits covered by an unshifted `mask` starting at `shift`
+pub(crate) fn mask_bits(mask: u32, shift: u32) -> Option<Bits> {
+    let contiguous = mask >> mask.trailing_zeros();
+    if mask == 0 || contiguous & contiguous.wrapping_add(1) != 0 {
+        return None;
+    }
+    let lsb = (shift + mask.trailing_zeros()) as u8;
+    let msb = (shift + 31 - mask.leading_zeros()) as u8;
+    Some(if lsb == msb {
+        Bits::Single(lsb)
+    } else {
+        Bits::Range(lsb..=msb)
+    })
+}
+
+fn add_field(r: &mut Register, name: String, bits: Bits, origin: Origin, provenance: Provenance) {
+    if r.bit_fields.iter().all(|f| f.name != name) {
+        r.bit_fields.push(BitField {
+            name,
+            bits,
+            origin,
+            provenance: vec![provenance],
+            ..Default::default()
+        });
+    }
+}
+
+/// A shifted field mask such as `WDT_CTL_EN_MASK`, usually followed by its `_LSB`
+struct Shifted {
+    peripheral: String,
+    /// Index of the register the field belongs to
+    register: usize,
+    field: String,
+    mask: u32,
+    line: usize,
+}
+
+/// Add the [`CURATED_FIELDS`] to timer, watchdog, DPORT and RTC registers the headers give no
+/// fields
+fn curate(peripherals: &mut BTreeMap<String, Peripheral>) {
+    for (pname, rname, field, lsb, msb, type_) in CURATED_FIELDS {
+        let r = peripherals
+            .get_mut(*pname)
+            .and_then(|p| p.registers.iter_mut().find(|r| r.name == *rname));
+        let r = match r {
+            Some(r) => r,
+            None => continue,
+        };
+        if r.bit_fields.iter().any(|f| f.origin != Origin::Doc) {
+            continue;
+        }
+        r.bit_fields.push(BitField {
+            name: field.to_string(),
+            bits: if lsb == msb {
+                Bits::Single(*lsb)
+            } else {
+                Bits::Range(*lsb..=*msb)
+            },
+            type_: *type_,
+            origin: Origin::Doc,
+            provenance: vec![Provenance::Curated],
+            ..Default::default()
+        });
+    }
+}
+
+/// Parse the NONOS SDK headers below `dir`, returning the peripherals and the zero width masks
+/// left out of them
+pub fn parse_nonos(dir: &Path) -> (BTreeMap<String, Peripheral>, Vec<Issue>) {
+    let mut files = vec![];
+    headers(dir, &mut files);
+    files.sort();
+
+    let texts: Vec<(String, String)> = files
+        .iter()
+        .filter_map(|f| match std::fs::read_to_string(f) {
+            Ok(text) => Some((f.display().to_string(), text)),
+            Err(e) => {
+                println!("Skipping {}: {}", f.display(), e);
+                None
+            }
+        })
+        .collect();
+
+    let (peripherals, unparsed, zero_width) = parse_nonos_texts(&texts);
+    if !unparsed.is_empty() {
+        println!(
+            "The following NONOS SDK registers failed to parse {:?}",
+            unparsed
+        );
+    }
+    (peripherals, zero_width)
+}
+
+/// Parse headers in the NONOS SDK style given as (file name, text), returning the peripherals,
+/// the names of the register defines that failed to parse and the zero width masks
+pub fn parse_nonos_texts(
+    texts: &[(String, String)],
+) -> (BTreeMap<String, Peripheral>, Vec<String>, Vec<Issue>) {
+    let re_indexed_base =
+        Regex::new(r"^\s*#define\s+REG_(\w+)_BASE\(i\)\s+\(?\s*(0x[0-9a-fA-F]+)").unwrap();
+    let re_base = Regex::new(
+        r"^\s*#define\s+PERIPHS_(\w+)_BASE(?:ADDR|DDR)?\s+\(?\s*(0x[0-9a-fA-F]+)\)?\s*$",
+    )
+    .unwrap();
+    let re_indexed_reg =
+        Regex::new(r"^\s*#define\s+(\w+)\(i\)\s+\(REG_(\w+)_BASE\(i\)\s*\+\s*(.*)\)\s*$").unwrap();
+    let re_alias = Regex::new(
+        r"^\s*#define\s+REG_(\w+)_BASE\s+\(?\s*PERIPHS_(\w+)_BASE(?:ADDR|DDR)?\s*\)?\s*$",
+    )
+    .unwrap();
+    let re_based_reg = Regex::new(
+        r"^\s*#define\s+(\w+)\s+\(\s*REG_(\w+)_BASE\s*\+\s*([^()]+?)\s*\)\s*(?://\s*(.*?)|/\*\s*(.*?)\s*\*/)?\s*$",
+    )
+    .unwrap();
+    let re_absolute_reg = Regex::new(
+        r"^\s*#define\s+(\w+)\s+\(\s*(?:PERIPHS_(\w+?)_BASE(?:ADDR|DDR)?|(0x[0-9a-fA-F]+))\s*\+\s*([^()]+?)\s*\)\s*(?://.*|/\*.*)?$",
+    )
+    .unwrap();
+    let re_offset_reg =
+        Regex::new(r"^\s*#define\s+(\w+)\s+\(?\s*(0x[0-9a-fA-F]+)\s*\)?\s*(?://.*|/\*.*)?$")
+            .unwrap();
+    let re_address_reg = Regex::new(r"^\s*#define\s+(\w+)_ADDRESS\s+\(?([^\s)]+)\)?\s*$").unwrap();
+    let re_define = Regex::new(r"^\s*#define\s+(\w+)\s+(.+?)\s*(?://.*|/\*.*)?$").unwrap();
+    let re_bit = Regex::new(r"^\(?BIT\((\d+)\)\)?$").unwrap();
+    let re_shifted = Regex::new(r"^\(?\s*(0x[0-9a-fA-F]+|\d+)[uUlL]*\s*<<\s*(\d+)\s*\)?$").unwrap();
+    let re_pad = Regex::new(PAD_REGISTER).unwrap();
+
+    let mut peripherals = BTreeMap::new();
+    // `REG_*_BASE` aliases by their stem, to the peripheral of the base they alias
+    let mut aliases: HashMap<String, String> = HashMap::new();
+    for (_, text) in texts {
+        for line in text.lines() {
+            if let Some(c) = re_alias.captures(line) {
+                aliases.insert(c[1].to_string(), c[2].to_string());
+            }
+            let base = re_indexed_base
+                .captures(line)
+                .or_else(|| re_base.captures(line));
+            if let Some(c) = base {
+                if let Some(address) = number(&c[2]) {
+                    peripherals.entry(c[1].to_string()).or_insert(Peripheral {
+                        description: c[1].to_string(),
+                        address,
+                        ..Default::default()
+                    });
+                }
+            }
+        }
+    }
+
+    for (file, text) in texts {
+        if let Some(io_mux) = parse_io_mux(text, file) {
+            peripherals.entry(IO_MUX.to_string()).or_insert(io_mux);
+        }
+    }
+
+    let mut unparsed = vec![];
+    let mut zero_width = vec![];
+    for (file, text) in texts {
+        let here = |i: usize| Provenance::Header {
+            file: file.clone(),
+            line: Some(i + 1),
+        };
+        // register the following field defines belong to
+        let mut current: Option<(String, usize)> = None;
+        let mut masks: HashMap<String, u32> = HashMap::new();
+        // shifted `_MASK` defines waiting for their `_LSB`, with the peripheral and index of
+        // their register and their line
+        let mut shifted: Vec<Shifted> = vec![];
+
+        for (i, line) in text.lines().enumerate() {
+            // the pin multiplexer is parsed on its own, its defines belong to no register here
+            if re_pad.is_match(line) {
+                current = None;
+                continue;
+            }
+            let mut description = None;
+            let register = if let Some(c) = re_indexed_reg.captures(line) {
+                Some((c[1].to_string(), c[2].to_string(), c[3].to_string()))
+            } else if let Some(c) = re_based_reg.captures(line) {
+                description = c
+                    .get(4)
+                    .or_else(|| c.get(5))
+                    .map(|d| d.as_str().to_string());
+                let pname = aliases
+                    .get(&c[2])
+                    .cloned()
+                    .unwrap_or_else(|| c[2].to_string());
+                Some((c[1].to_string(), pname, c[3].to_string()))
+            } else if let Some(c) = re_absolute_reg.captures(line) {
+                // `(PERIPHS_DPORT_BASEADDR + 0x04)` or `(0x3ff00000 + 0x0c)`
+                let pname = match (c.get(2), c.get(3).and_then(|a| number(a.as_str()))) {
+                    (Some(stem), _) => Some(stem.as_str().to_string()),
+                    (None, Some(address)) => peripherals
+                        .iter()
+                        .find(|(_, p)| p.address == address)
+                        .map(|(name, _)| name.clone()),
+                    _ => None,
+                };
+                let name = c[1].trim_end_matches("_REG").to_string();
+                pname.map(|p| (name, p, c[4].to_string()))
+            } else if let Some(c) = re_offset_reg
+                .captures(line)
+                .filter(|c| OFFSET_REGISTERS.iter().any(|(_, r)| *r == &c[1]))
+            {
+                let (pname, _) = OFFSET_REGISTERS.iter().find(|(_, r)| *r == &c[1]).unwrap();
+                Some((c[1].to_string(), pname.to_string(), c[2].to_string()))
+            } else if let Some(c) = re_address_reg.captures(line) {
+                let name = &c[1];
+                let alias = STEM_ALIASES
+                    .iter()
+                    .find(|(stem, _)| name.starts_with(&format!("{}_", stem)))
+                    .map(|(_, p)| p.to_string())
+                    .filter(|p| peripherals.contains_key(p));
+                alias
+                    .or_else(|| {
+                        peripherals
+                            .keys()
+                            .filter(|p| name.starts_with(&format!("{}_", p)))
+                            .max_by_key(|p| p.len())
+                            .cloned()
+                    })
+                    .map(|p| (name.to_string(), p, c[2].to_string()))
+                    .or_else(|| {
+                        unparsed.push(name.to_string());
+                        None
+                    })
+            } else {
+                None
+            };
+
+            if let Some((name, pname, offset)) = register {
+                current = None;
+                let (p, offset) = match (peripherals.get_mut(&pname), number(&offset)) {
+                    (Some(p), Some(offset)) => (p, offset),
+                    _ => {
+                        unparsed.push(name);
+                        continue;
+                    }
+                };
+                p.registers.push(Register {
+                    description: description.unwrap_or_else(|| name.clone()),
+                    name,
+                    address: offset,
+                    provenance: vec![here(i)],
+                    ..Default::default()
+                });
+                current = Some((pname, p.registers.len() - 1));
+                masks.clear();
+                continue;
+            }
+
+            let (pname, index) = match &current {
+                Some(current) => current,
+                None => continue,
+            };
+            let c = match re_define.captures(line) {
+                Some(c) => c,
+                None => continue,
+            };
+            let (name, value) = (&c[1], &c[2]);
+            // register index and line of the mask an `_LSB` define completes
+            let mut shifted_from = None;
+            let field = if let Some(bit) = re_bit.captures(value) {
+                bit[1]
+                    .parse()
+                    .ok()
+                    .map(|bit| (name.to_string(), Bits::Single(bit), Origin::HeaderBit))
+            } else if let Some(field) = name.strip_suffix("_MASK") {
+                // masks such as `WDT_CTL_EN_MASK` can follow later registers of the peripheral
+                let register = peripherals[pname]
+                    .registers
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, r)| field.starts_with(&format!("{}_", r.name)))
+                    .max_by_key(|(_, r)| r.name.len())
+                    .map(|(index, _)| index);
+                if let (Some(register), Some(mask)) = (register, number(value)) {
+                    shifted.push(Shifted {
+                        peripheral: pname.clone(),
+                        register,
+                        field: field.to_string(),
+                        mask,
+                        line: i,
+                    });
+                }
+                None
+            } else if let Some(field) = name.strip_suffix("_LSB") {
+                let mask = shifted
+                    .iter()
+                    .position(|s| s.field == field && s.peripheral == *pname);
+                match (mask.map(|m| shifted.remove(m)), number(value)) {
+                    (Some(s), Some(lsb)) => {
+                        shifted_from = Some((s.register, s.line));
+                        mask_bits(s.mask, 0)
+                            .filter(|bits| u32::from(bits.lsb()) == lsb)
+                            .or_else(|| {
+                                println!("Mask of {} doesn't start at bit {}", field, lsb);
+                                None
+                            })
+                            .map(|bits| (field.to_string(), bits, Origin::HeaderMask))
+                    }
+                    _ => None,
+                }
+            } else if let Some(field) = name.strip_suffix("_S") {
+                match (masks.get(field), number(value)) {
+                    (Some(0), Some(_)) => {
+                        zero_width.push(Issue::ZeroWidthField {
+                            peripheral: pname.clone(),
+                            register: peripherals[pname].registers[*index].name.clone(),
+                            field: field.to_string(),
+                        });
+                        None
+                    }
+                    (Some(&mask), Some(shift)) => mask_bits(mask, shift)
+                        .map(|bits| (field.to_string(), bits, Origin::HeaderMask)),
+                    _ => None,
+                }
+            } else if let Some(c) = re_shifted.captures(value) {
+                // masks such as `(0x7 << 4)` state their offset themselves, without a `_S`
+                match (number(&c[1]), c[2].parse()) {
+                    (Some(mask), Ok(shift)) => mask_bits(mask, shift)
+                        .map(|bits| (name.to_string(), bits, Origin::HeaderMask)),
+                    _ => None,
+                }
+            } else {
+                if let Some(mask) = number(value) {
+                    masks.insert(name.trim_end_matches("_V").to_string(), mask);
+                }
+                None
+            };
+
+            if let Some((name, bits, origin)) = field {
+                let (index, line) = match shifted_from {
+                    Some((index, line)) => (index, line),
+                    None => (*index, i),
+                };
+                let r = &mut peripherals.get_mut(pname).unwrap().registers[index];
+                add_field(r, name, bits, origin, here(line));
+            }
+        }
+
+        // masks without `_LSB` are fields on their own
+        for s in shifted {
+            if let Some(bits) = mask_bits(s.mask, 0) {
+                let r = &mut peripherals.get_mut(&s.peripheral).unwrap().registers[s.register];
+                add_field(r, s.field, bits, Origin::HeaderMask, here(s.line));
+            }
+        }
+    }
+
+    curate(&mut peripherals);
+    if peripherals.get("DPORT").map(|p| p.address) == Some(DPORT_ADDRESS) {
+        peripherals
+            .entry(EFUSE.to_string())
+            .or_insert_with(efuse_peripheral);
+    }
+
+    for p in peripherals.values_mut() {
+        p.registers.sort_by_key(|r| r.address);
+        for r in p.registers.iter_mut() {
+            r.bit_fields.sort_by_key(|f| f.bits.lsb());
+        }
+    }
+    (peripherals, unparsed, zero_width)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EAGLE_SOC: &str = "#define PERIPHS_RTC_BASEADDR            0x60000700
+//RTC reg {{
+#define REG_RTC_BASE  PERIPHS_RTC_BASEADDR
+
+#define RTC_SLP_VAL                             (REG_RTC_BASE + 0x004)   // the target value of RTC_COUNTER for wakeup from light-sleep/deep-sleep
+#define RTC_SLP_CNT_VAL                         (REG_RTC_BASE + 0x01C)   // the current value of RTC_COUNTER
+#define RTC_STORE0                              (REG_RTC_BASE + 0x030)
+#define RTC_GPIO_OUT                            (REG_RTC_BASE + 0x068)
+#define RTC_GPIO_CONF                           (REG_RTC_BASE + 0x090)
+#define RTC_GPIO_CONF_MUX                       BIT(0)
+//}}
+
+#define PERIPHS_DPORT_BASEADDR          0x3ff00000
+//DPORT reg {{
+#define HOST_INF_SEL                            (0x28)
+#define DPORT_LINK_DEVICE_SEL                   0x000000FF
+#define DPORT_LINK_DEVICE_SEL_S                 8
+#define DPORT_UART_SEL                          (0x3 << 4)
+#define DPORT_UART_SEL_S                        4
+#define PERI_IO_UART_PORT_SWAP                  (BIT(0)) // swap two uart
+#define DPORT_UNUSED                            0x00000000
+#define DPORT_UNUSED_S                          12
+//}}
+
+//CACHE{{
+#define CACHE_FLASH_CTRL_REG                    (0x3ff00000 + 0x0c)
+#define CACHE_READ_EN_BIT                       BIT(8)
+//}}
+";
+
+    const ETS_SYS: &str = "#define EDGE_INT_ENABLE_REG  (PERIPHS_DPORT_BASEADDR+0x04)
+#define WDT_EDGE_INT_ENABLE() SET_PERI_REG_MASK(EDGE_INT_ENABLE_REG, BIT0)
+";
+
+    #[test]
+    fn test_parse_nonos() {
+        let dir = std::env::temp_dir().join(format!("header2svd-nonos-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("eagle_soc.h"), EAGLE_SOC).unwrap();
+        std::fs::write(dir.join("ets_sys.h"), ETS_SYS).unwrap();
+        let (peripherals, zero_width) = parse_nonos(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let rtc = &peripherals["RTC"];
+        assert_eq!(rtc.address, 0x6000_0700);
+        let registers: Vec<(&str, u32)> = rtc
+            .registers
+            .iter()
+            .map(|r| (r.name.as_str(), r.address))
+            .collect();
+        assert_eq!(
+            registers,
+            vec![
+                ("RTC_SLP_VAL", 0x04),
+                ("RTC_SLP_CNT_VAL", 0x1c),
+                ("RTC_STORE0", 0x30),
+                ("RTC_GPIO_OUT", 0x68),
+                ("RTC_GPIO_CONF", 0x90),
+            ]
+        );
+        assert_eq!(
+            rtc.registers[0].description,
+            "the target value of RTC_COUNTER for wakeup from light-sleep/deep-sleep"
+        );
+        assert_eq!(rtc.registers[2].description, "RTC_STORE0");
+
+        let field = |r: usize| {
+            let f = &rtc.registers[r].bit_fields[0];
+            (
+                f.name.as_str(),
+                f.bits.lsb(),
+                f.bits.msb(),
+                f.type_,
+                f.origin,
+            )
+        };
+        assert_eq!(
+            field(1),
+            ("RTC_SLP_CNT_VAL", 0, 31, Type::ReadOnly, Origin::Doc)
+        );
+        assert_eq!(
+            field(3),
+            ("RTC_GPIO_OUT_DATA", 0, 0, Type::ReadWrite, Origin::Doc)
+        );
+        assert_eq!(
+            field(4),
+            (
+                "RTC_GPIO_CONF_MUX",
+                0,
+                0,
+                Type::ReadWrite,
+                Origin::HeaderBit
+            )
+        );
+
+        assert_eq!(peripherals["EFUSE"].registers.len(), 4);
+        let dport = &peripherals["DPORT"];
+        let registers: Vec<(&str, u32, Vec<&str>)> = dport
+            .registers
+            .iter()
+            .map(|r| {
+                let fields = r.bit_fields.iter().map(|f| f.name.as_str()).collect();
+                (r.name.as_str(), r.address, fields)
+            })
+            .collect();
+        assert_eq!(
+            registers,
+            vec![
+                (
+                    "EDGE_INT_ENABLE",
+                    0x04,
+                    vec!["WDT_EDGE_INT_EN", "TM1_EDGE_INT_EN"]
+                ),
+                ("CACHE_FLASH_CTRL", 0x0c, vec!["CACHE_READ_EN_BIT"]),
+                (
+                    "HOST_INF_SEL",
+                    0x28,
+                    vec![
+                        "PERI_IO_UART_PORT_SWAP",
+                        "DPORT_UART_SEL",
+                        "DPORT_LINK_DEVICE_SEL"
+                    ]
+                ),
+            ]
+        );
+        let uart_sel = &dport.registers[2].bit_fields[1].bits;
+        assert_eq!((uart_sel.lsb(), uart_sel.msb()), (4, 5));
+        assert_eq!(
+            zero_width,
+            vec![Issue::ZeroWidthField {
+                peripheral: "DPORT".to_string(),
+                register: "HOST_INF_SEL".to_string(),
+                field: "DPORT_UNUSED".to_string(),
+            }]
+        );
+    }
+}
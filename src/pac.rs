@@ -0,0 +1,103 @@
+//! Companion files for PAC pipelines (svd2rust, form, svdtools), describing the renames and
+//! pruning done while generating the svd so downstream tooling can be configured to match.
+
+use crate::sanitize::Rename;
+use crate::validate::Issue;
+use std::path::Path;
+use std::process::Command;
+
+/// Target svd2rust generates the PAC for in `check_svd2rust`
+const SVD2RUST_TARGET: &str = "xtensa-lx";
+
+/// One `scope.from -> to` line per rename, scope being the dotted path of the parent
+pub fn rename_list(renames: &[Rename]) -> String {
+    renames.iter().map(|r| format!("{}\n", r)).collect()
+}
+
+/// One dotted path per element dropped from the model
+pub fn ignore_list(pruned: &[Issue]) -> String {
+    let mut paths: Vec<String> = pruned.iter().filter_map(Issue::path).collect();
+    paths.sort();
+    paths.dedup();
+    paths.iter().map(|p| format!("{}\n", p)).collect()
+}
+
+/// Write `renames.txt` and `ignore.txt` into `dir`
+pub fn write_pac_files(dir: &Path, renames: &[Rename], pruned: &[Issue]) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(dir.join("renames.txt"), rename_list(renames))?;
+    std::fs::write(dir.join("ignore.txt"), ignore_list(pruned))
+}
+
+/// Outcome of generating a PAC from an svd with svd2rust
+#[derive(Debug, Clone, PartialEq)]
+pub struct Svd2RustCheck {
+    pub success: bool,
+    /// Errors and warnings svd2rust logged, with the error it exited with
+    pub messages: Vec<String>,
+}
+
+/// Errors and warnings of the svd2rust output, without its info and debug logging
+fn svd2rust_messages(stderr: &str) -> Vec<String> {
+    stderr
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.trim().is_empty())
+        .filter(|line| {
+            !["[INFO", "[DEBUG", "[TRACE"]
+                .iter()
+                .any(|l| line.starts_with(l))
+        })
+        .map(str::to_string)
+        .collect()
+}
+
+/// Run svd2rust on `svd` in a scratch directory, to find what it can't handle before the PAC
+/// is rebuilt. An error means svd2rust couldn't be run at all
+pub fn check_svd2rust(svd: &Path) -> Result<Svd2RustCheck, String> {
+    let svd = svd
+        .canonicalize()
+        .map_err(|e| format!("Failed to read {}: {}", svd.display(), e))?;
+    // svd2rust writes lib.rs, build.rs and device.x into its working directory
+    let dir = std::env::temp_dir().join(format!("idf2svd-svd2rust-{}", std::process::id()));
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let output = Command::new("svd2rust")
+        .arg("--target")
+        .arg(SVD2RUST_TARGET)
+        .arg("-i")
+        .arg(&svd)
+        .current_dir(&dir)
+        .output();
+    std::fs::remove_dir_all(&dir).ok();
+    let output = output.map_err(|e| format!("Failed to run svd2rust, is it installed? {}", e))?;
+    Ok(Svd2RustCheck {
+        success: output.status.success(),
+        messages: svd2rust_messages(&String::from_utf8_lossy(&output.stderr)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_svd2rust_messages() {
+        let stderr = "[INFO  svd2rust] Parsing device from SVD file\n\
+                      [WARN  svd2rust::util] Missing description for register UART_FIFO\n\
+                      \n\
+                      Error: Error rendering device\n\
+                      \n\
+                      Caused by:\n    \
+                      0: Error rendering peripheral UART0\n";
+        assert_eq!(
+            svd2rust_messages(stderr),
+            vec![
+                "[WARN  svd2rust::util] Missing description for register UART_FIFO",
+                "Error: Error rendering device",
+                "Caused by:",
+                "    0: Error rendering peripheral UART0",
+            ]
+        );
+    }
+}
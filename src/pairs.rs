@@ -0,0 +1,130 @@
+//! Registers holding the low and high half of a wider value, such as the 64-bit timer counter
+//! `TIMG_T0LO` and `TIMG_T0HI`, linked so consumers know they form one logical value. The
+//! halves stay 32-bit registers, as the bus can't read both at once.
+
+use crate::Peripheral;
+use std::collections::BTreeMap;
+use xmltree::Element;
+
+/// Suffixes of the low and high half, longest first so `_LOW` isn't taken for `_LO`
+const SUFFIXES: &[(&str, &str)] = &[("_LOW", "_HIGH"), ("_LO", "_HI"), ("LO", "HI")];
+
+/// The high half of the value a register holds the low half of
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pair {
+    /// Name of the combined value, the low half without its suffix
+    pub name: String,
+    pub high: String,
+}
+
+/// Link every register ending in a low suffix to the register with the matching high suffix,
+/// returning the number of linked pairs
+pub fn link_pairs(peripherals: &mut BTreeMap<String, Peripheral>) -> usize {
+    let mut linked = 0;
+    for p in peripherals.values_mut() {
+        let names: Vec<String> = p.registers.iter().map(|r| r.name.clone()).collect();
+        for r in p.registers.iter_mut() {
+            let pair = SUFFIXES.iter().find_map(|(low, high)| {
+                let stem = r.name.strip_suffix(low)?;
+                let high = format!("{}{}", stem, high);
+                names.iter().find(|n| **n == high).map(|high| Pair {
+                    name: stem.to_string(),
+                    high: high.clone(),
+                })
+            });
+            if pair.is_some() {
+                linked += 1;
+            }
+            r.pair = pair;
+        }
+    }
+    linked
+}
+
+/// `vendorExtensions` entry listing the linked pairs, `None` if there are none. Pairs whose
+/// high half is missing from `peripherals`, e.g. left out of a view, are skipped
+pub fn pairs_extension(peripherals: &BTreeMap<String, Peripheral>) -> Option<Element> {
+    let text = |name: &str, text: String| {
+        let mut element = Element::new(name);
+        element.text = Some(text);
+        element
+    };
+    let mut pairs = Element::new("registerPairs");
+    for (name, p) in peripherals {
+        for r in &p.registers {
+            let pair = match &r.pair {
+                Some(pair) if p.registers.iter().any(|h| h.name == pair.high) => pair,
+                _ => continue,
+            };
+            let mut element = Element::new("pair");
+            element
+                .children
+                .push(text("path", format!("{}.{}", name, pair.name)));
+            element.children.push(text("low", r.name.clone()));
+            element.children.push(text("high", pair.high.clone()));
+            pairs.children.push(element);
+        }
+    }
+    if pairs.children.is_empty() {
+        None
+    } else {
+        Some(pairs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Register;
+
+    #[test]
+    fn test_link_pairs() {
+        let register = |name: &str| Register {
+            name: name.to_string(),
+            ..Default::default()
+        };
+        let mut peripherals = BTreeMap::new();
+        peripherals.insert(
+            "TIMG".to_string(),
+            Peripheral {
+                registers: vec![
+                    register("TIMG_T0LO"),
+                    register("TIMG_T0HI"),
+                    register("TIMG_T0ALARM_LOW"),
+                    register("TIMG_T0ALARM_HIGH"),
+                    register("TIMG_T0LOADLO"),
+                    register("TIMG_SOLO"),
+                ],
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(link_pairs(&mut peripherals), 2);
+        let timg = &peripherals["TIMG"];
+        assert_eq!(
+            timg.registers[0].pair,
+            Some(Pair {
+                name: "TIMG_T0".to_string(),
+                high: "TIMG_T0HI".to_string(),
+            })
+        );
+        assert_eq!(
+            timg.registers[2].pair.as_ref().unwrap().name,
+            "TIMG_T0ALARM"
+        );
+        assert!(timg.registers[4].pair.is_none());
+
+        peripherals
+            .get_mut("TIMG")
+            .unwrap()
+            .registers
+            .retain(|r| r.name != "TIMG_T0HI");
+        let extension = pairs_extension(&peripherals).unwrap();
+        assert_eq!(extension.children.len(), 1);
+        let pair = &extension.children[0];
+        assert_eq!(
+            pair.get_child("path").unwrap().text.as_deref(),
+            Some("TIMG.TIMG_T0ALARM")
+        );
+    }
+}
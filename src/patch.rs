@@ -0,0 +1,452 @@
+//! Model level fixes from the config file, renaming or deleting peripherals, registers and
+//! fields by their dotted path, or classifying registers as unsafe to access. Patches are
+//! idempotent: a rename whose source is gone but whose target exists, or a delete of a missing
+//! element, counts as already applied, so patch files can be re-applied to a model during
+//! incremental regeneration.
+
+use crate::safety::Safety;
+use crate::{BitField, Peripheral, Provenance, Register};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Patch {
+    /// `PERIPHERAL`, `PERIPHERAL.REGISTER` or `PERIPHERAL.REGISTER.FIELD`
+    pub path: String,
+    /// New name of the element
+    #[serde(default)]
+    pub rename: Option<String>,
+    /// Remove the element
+    #[serde(default)]
+    pub delete: bool,
+    /// Classify a register, applied after the renames and deletes so the path is the new name
+    #[serde(default)]
+    pub safety: Option<Safety>,
+}
+
+impl Patch {
+    /// Path of the element after applying this patch, `None` for deletes
+    fn target(&self) -> Option<String> {
+        let rename = self.rename.as_ref()?;
+        Some(match self.path.rfind('.') {
+            Some(i) => format!("{}.{}", &self.path[..i], rename),
+            None => rename.clone(),
+        })
+    }
+}
+
+/// Patches that can't be applied in a well defined, idempotent way
+#[derive(Debug, Clone, PartialEq)]
+pub enum Conflict {
+    /// A patch that doesn't do exactly one of rename, delete or classify, or that classifies
+    /// something other than a register
+    Invalid { path: String },
+    /// An element renamed or deleted by more than one patch
+    Duplicate { path: String },
+    /// Renames forming a cycle, e.g. `A -> B` and `B -> A`
+    Cycle { paths: Vec<String> },
+    /// A rename whose target is touched by another patch, which makes the result depend on
+    /// the order and number of applications
+    Chain { path: String, target: String },
+    /// A rename onto an element that already exists in the model
+    Collision { path: String, target: String },
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Conflict::Invalid { path } => {
+                write!(
+                    f,
+                    "patch for {} needs exactly one of rename, delete or a register safety",
+                    path
+                )
+            }
+            Conflict::Duplicate { path } => write!(f, "{} is patched more than once", path),
+            Conflict::Cycle { paths } => write!(f, "renames form a cycle: {}", paths.join(" -> ")),
+            Conflict::Chain { path, target } => write!(
+                f,
+                "{} is renamed to {}, which is patched as well",
+                path, target
+            ),
+            Conflict::Collision { path, target } => {
+                write!(f, "can't rename {}, {} already exists", path, target)
+            }
+        }
+    }
+}
+
+/// Check a list of patches for conflicts between its operations
+pub fn check_patches(patches: &[Patch]) -> Vec<Conflict> {
+    let mut conflicts = vec![];
+    let mut sources: BTreeMap<&str, &Patch> = BTreeMap::new();
+
+    for patch in patches {
+        let operations = [patch.delete, patch.rename.is_some(), patch.safety.is_some()];
+        let classifies_register = patch.path.split('.').count() == 2;
+        if operations.iter().filter(|o| **o).count() != 1
+            || (patch.safety.is_some() && !classifies_register)
+        {
+            conflicts.push(Conflict::Invalid {
+                path: patch.path.clone(),
+            });
+        } else if patch.safety.is_some() {
+            // refers to the final names, the order doesn't matter
+            continue;
+        } else if sources.insert(patch.path.as_str(), patch).is_some() {
+            conflicts.push(Conflict::Duplicate {
+                path: patch.path.clone(),
+            });
+        }
+    }
+
+    let mut in_cycle: Vec<&str> = vec![];
+    for patch in sources.values() {
+        let target = match patch.target() {
+            Some(target) => target,
+            None => continue,
+        };
+        if !sources.contains_key(target.as_str()) {
+            continue;
+        }
+
+        // follow the chain of renames to find out whether it leads back to the start
+        let mut paths = vec![patch.path.clone()];
+        let mut next = target;
+        while let Some(p) = sources.get(next.as_str()) {
+            if paths.contains(&next) {
+                break;
+            }
+            paths.push(next.clone());
+            next = match p.target() {
+                Some(target) => target,
+                None => break,
+            };
+        }
+        if next == patch.path {
+            if !in_cycle.contains(&patch.path.as_str()) {
+                in_cycle.extend(sources.keys().filter(|k| paths.iter().any(|p| p == *k)));
+                paths.push(next);
+                conflicts.push(Conflict::Cycle { paths });
+            }
+        } else {
+            conflicts.push(Conflict::Chain {
+                path: patch.path.clone(),
+                target: patch.target().unwrap(),
+            });
+        }
+    }
+
+    conflicts
+}
+
+fn rename_entry<T>(
+    items: &mut Vec<T>,
+    name: fn(&mut T) -> &mut String,
+    provenance: fn(&mut T) -> &mut Vec<Provenance>,
+    patch: &Patch,
+    rename: &str,
+) -> Result<(), Conflict> {
+    let from = patch.path.rsplit('.').next().unwrap_or_default();
+    let exists = |items: &mut Vec<T>, n: &str| items.iter_mut().any(|i| name(i) == n);
+    match (exists(items, from), exists(items, rename)) {
+        (true, true) => Err(Conflict::Collision {
+            path: patch.path.clone(),
+            target: patch.target().unwrap(),
+        }),
+        (true, false) => {
+            for item in items.iter_mut() {
+                if name(item) == from {
+                    *name(item) = rename.to_string();
+                    provenance(item).push(renamed(patch));
+                }
+            }
+            Ok(())
+        }
+        // already applied or nothing to rename
+        (false, _) => Ok(()),
+    }
+}
+
+fn renamed(patch: &Patch) -> Provenance {
+    Provenance::Patch {
+        renamed_from: patch.path.clone(),
+    }
+}
+
+fn register_name(r: &mut Register) -> &mut String {
+    &mut r.name
+}
+
+fn register_provenance(r: &mut Register) -> &mut Vec<Provenance> {
+    &mut r.provenance
+}
+
+fn field_name(f: &mut BitField) -> &mut String {
+    &mut f.name
+}
+
+fn field_provenance(f: &mut BitField) -> &mut Vec<Provenance> {
+    &mut f.provenance
+}
+
+/// Apply `patches` to the model, stopping at the first conflict
+pub fn apply_patches(
+    peripherals: &mut BTreeMap<String, Peripheral>,
+    patches: &[Patch],
+) -> Result<(), Conflict> {
+    if let Some(conflict) = check_patches(patches).into_iter().next() {
+        return Err(conflict);
+    }
+
+    for patch in patches.iter().filter(|p| p.safety.is_none()) {
+        let parts: Vec<&str> = patch.path.split('.').collect();
+        match parts[..] {
+            [peripheral] => match &patch.rename {
+                Some(rename) => {
+                    if peripherals.contains_key(peripheral) && peripherals.contains_key(rename) {
+                        return Err(Conflict::Collision {
+                            path: patch.path.clone(),
+                            target: rename.clone(),
+                        });
+                    }
+                    if let Some(mut p) = peripherals.remove(peripheral) {
+                        p.provenance.push(renamed(patch));
+                        peripherals.insert(rename.clone(), p);
+                    }
+                }
+                None => {
+                    peripherals.remove(peripheral);
+                }
+            },
+            [peripheral, register] => {
+                if let Some(p) = peripherals.get_mut(peripheral) {
+                    match &patch.rename {
+                        Some(rename) => rename_entry(
+                            &mut p.registers,
+                            register_name,
+                            register_provenance,
+                            patch,
+                            rename,
+                        )?,
+                        None => p.registers.retain(|r| r.name != register),
+                    }
+                }
+            }
+            [peripheral, register, field] => {
+                let r = peripherals
+                    .get_mut(peripheral)
+                    .and_then(|p| p.registers.iter_mut().find(|r| r.name == register));
+                if let Some(r) = r {
+                    match &patch.rename {
+                        Some(rename) => rename_entry(
+                            &mut r.bit_fields,
+                            field_name,
+                            field_provenance,
+                            patch,
+                            rename,
+                        )?,
+                        None => r.bit_fields.retain(|f| f.name != field),
+                    }
+                }
+            }
+            _ => {
+                return Err(Conflict::Invalid {
+                    path: patch.path.clone(),
+                })
+            }
+        }
+    }
+
+    for patch in patches {
+        let (safety, (peripheral, register)) = match (patch.safety, patch.path.split_once('.')) {
+            (Some(safety), Some(path)) => (safety, path),
+            _ => continue,
+        };
+        let r = peripherals
+            .get_mut(peripheral)
+            .and_then(|p| p.registers.iter_mut().find(|r| r.name == register));
+        if let Some(r) = r {
+            r.safety = Some(safety);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rename(path: &str, to: &str) -> Patch {
+        Patch {
+            path: path.to_string(),
+            rename: Some(to.to_string()),
+            delete: false,
+            safety: None,
+        }
+    }
+
+    fn delete(path: &str) -> Patch {
+        Patch {
+            path: path.to_string(),
+            rename: None,
+            delete: true,
+            safety: None,
+        }
+    }
+
+    fn model() -> BTreeMap<String, Peripheral> {
+        let field = |name: &str| BitField {
+            name: name.to_string(),
+            ..Default::default()
+        };
+        let register = |name: &str, address: u32| Register {
+            name: name.to_string(),
+            address,
+            bit_fields: vec![field("EN"), field("MODE")],
+            ..Default::default()
+        };
+        let mut peripherals = BTreeMap::new();
+        peripherals.insert(
+            "UART".to_string(),
+            Peripheral {
+                registers: vec![register("UART_CONF0", 0x20), register("UART_DATE", 0x78)],
+                ..Default::default()
+            },
+        );
+        peripherals.insert("SLC".to_string(), Peripheral::default());
+        peripherals
+    }
+
+    /// Names of all elements as dotted paths, for comparing models
+    fn paths(peripherals: &BTreeMap<String, Peripheral>) -> Vec<String> {
+        let mut paths = vec![];
+        for (name, p) in peripherals {
+            paths.push(name.clone());
+            for r in &p.registers {
+                paths.push(format!("{}.{}", name, r.name));
+                for f in &r.bit_fields {
+                    paths.push(format!("{}.{}.{}", name, r.name, f.name));
+                }
+            }
+        }
+        paths
+    }
+
+    #[test]
+    fn test_applying_twice_is_idempotent() {
+        let patches = vec![
+            rename("UART", "UART0"),
+            rename("UART0.UART_CONF0", "CONF0"),
+            rename("UART0.CONF0.MODE", "PARITY"),
+            delete("UART0.UART_DATE"),
+            delete("SLC"),
+        ];
+        let mut once = model();
+        apply_patches(&mut once, &patches).unwrap();
+        let mut twice = once.clone();
+        apply_patches(&mut twice, &patches).unwrap();
+
+        assert_eq!(paths(&once), paths(&twice));
+        assert_eq!(
+            paths(&once),
+            vec![
+                "UART0",
+                "UART0.CONF0",
+                "UART0.CONF0.EN",
+                "UART0.CONF0.PARITY"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rename_onto_existing_element() {
+        let mut peripherals = model();
+        let result = apply_patches(&mut peripherals, &[rename("UART.UART_CONF0", "UART_DATE")]);
+        assert_eq!(
+            result,
+            Err(Conflict::Collision {
+                path: "UART.UART_CONF0".to_string(),
+                target: "UART.UART_DATE".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_rename_and_delete_of_one_element() {
+        let conflicts = check_patches(&[rename("UART", "UART0"), delete("UART")]);
+        assert_eq!(
+            conflicts,
+            vec![Conflict::Duplicate {
+                path: "UART".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_rename_into_deleted_element() {
+        let conflicts = check_patches(&[rename("UART.A", "B"), delete("UART.B")]);
+        assert_eq!(
+            conflicts,
+            vec![Conflict::Chain {
+                path: "UART.A".to_string(),
+                target: "UART.B".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_rename_cycle() {
+        let conflicts = check_patches(&[rename("UART.A", "B"), rename("UART.B", "A")]);
+        assert_eq!(
+            conflicts,
+            vec![Conflict::Cycle {
+                paths: vec![
+                    "UART.A".to_string(),
+                    "UART.B".to_string(),
+                    "UART.A".to_string()
+                ]
+            }]
+        );
+    }
+
+    #[test]
+    fn test_safety_uses_renamed_path() {
+        let patches = vec![
+            Patch {
+                path: "UART.CONF0".to_string(),
+                rename: None,
+                delete: false,
+                safety: Some(Safety::Unsafe),
+            },
+            rename("UART.UART_CONF0", "CONF0"),
+        ];
+        let mut peripherals = model();
+        apply_patches(&mut peripherals, &patches).unwrap();
+        let safety: Vec<_> = peripherals["UART"]
+            .registers
+            .iter()
+            .map(|r| (r.name.as_str(), r.safety))
+            .collect();
+        assert_eq!(
+            safety,
+            vec![("CONF0", Some(Safety::Unsafe)), ("UART_DATE", None)]
+        );
+    }
+
+    #[test]
+    fn test_invalid_patch() {
+        let patch = Patch {
+            delete: true,
+            ..rename("UART", "UART0")
+        };
+        assert_eq!(
+            check_patches(&[patch]),
+            vec![Conflict::Invalid {
+                path: "UART".to_string()
+            }]
+        );
+    }
+}
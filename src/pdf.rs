@@ -0,0 +1,92 @@
+//! Extraction of register tables from the Technical Reference Manual PDFs into the
+//! documentation format of [`crate::doc`]. The PDF is converted to text with `pdftotext -layout`
+//! (poppler), which keeps table columns aligned.
+
+use crate::doc::DocFile;
+use crate::tables::{header_columns, Column, TableExtractor};
+use regex::Regex;
+use std::path::Path;
+use std::process::Command;
+
+/// Text of `pages` (first, last) of the PDF at `path`, laid out like the rendered pages
+pub fn pdf_text(path: &Path, pages: Option<(u32, u32)>) -> Result<String, String> {
+    let mut command = Command::new("pdftotext");
+    command.arg("-layout");
+    if let Some((first, last)) = pages {
+        command
+            .arg("-f")
+            .arg(first.to_string())
+            .arg("-l")
+            .arg(last.to_string());
+    }
+    let output = command
+        .arg(path)
+        .arg("-")
+        .output()
+        .map_err(|e| format!("Failed to run pdftotext, is poppler installed? {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "pdftotext failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Cells of a laid out row, split at runs of two or more spaces, with their start column
+fn cells(line: &str) -> Vec<(usize, &str)> {
+    let re_cell = Regex::new(r"\S+(?: \S+)*").unwrap();
+    re_cell
+        .find_iter(line)
+        .map(|m| (m.start(), m.as_str()))
+        .collect()
+}
+
+/// Find the register and field tables in the text of a manual. Table columns are found
+/// from the position of the titles in the header row. Addresses are made relative to `base`
+/// when given.
+pub fn extract_tables(text: &str, peripheral: &str, base: Option<u32>) -> DocFile {
+    let mut extractor = TableExtractor::new(peripheral, base);
+    let mut columns: Vec<(usize, Column)> = vec![];
+
+    for line in text.lines() {
+        if extractor.heading(line) {
+            columns.clear();
+            continue;
+        }
+        let row = cells(line);
+        if let Some(header) = header_columns(row.iter().map(|(_, title)| *title)) {
+            extractor.start_table(&header);
+            columns = row
+                .iter()
+                .zip(header)
+                .filter_map(|((start, _), column)| column.map(|c| (*start, c)))
+                .collect();
+            continue;
+        }
+        if columns.is_empty() || row.is_empty() {
+            continue;
+        }
+
+        // assign every cell to the closest column starting before it
+        let mut values: Vec<(Column, String)> = vec![];
+        for (start, text) in row {
+            let column = columns
+                .iter()
+                .rev()
+                .find(|(s, _)| *s <= start + 1)
+                .unwrap_or(&columns[0])
+                .1;
+            match values.iter_mut().find(|(c, _)| *c == column) {
+                Some((_, value)) => {
+                    value.push(' ');
+                    value.push_str(text);
+                }
+                None => values.push((column, text.to_string())),
+            }
+        }
+        extractor.row(&values);
+    }
+
+    extractor.finish()
+}
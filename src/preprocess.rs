@@ -0,0 +1,262 @@
+//! Minimal C preprocessor, only evaluating conditional blocks so that just the active
+//! branch of `#if`/`#ifdef` blocks is handed to the parser.
+
+use crate::expr;
+use regex::Regex;
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+struct Block {
+    /// Whether the enclosing block is active
+    parent_active: bool,
+    /// Whether any branch of this block has been taken yet
+    taken: bool,
+    active: bool,
+}
+
+/// Parse `NAME=VALUE` or `NAME` (defined as `1`) command line style defines
+pub fn parse_define(define: &str) -> (String, String) {
+    let mut parts = define.splitn(2, '=');
+    let name = parts.next().unwrap_or_default().trim().to_string();
+    let value = parts.next().unwrap_or("1").trim().to_string();
+    (name, value)
+}
+
+/// A `#define` split into its parts
+#[derive(Debug, Clone, PartialEq)]
+pub struct Define {
+    pub name: String,
+    /// Parameter names of a function like macro such as `REG_UART_BASE(i)`
+    pub params: Option<Vec<String>>,
+    /// Replacement text, without surrounding whitespace and trailing comment
+    pub value: String,
+}
+
+const DEFINE: &str =
+    r"^\s*#\s*define\s+([A-Za-z_][0-9A-Za-z_]*)(?:\(([^)]*)\))?\s*(.*?)\s*(?://.*|/\*.*)?$";
+
+/// Split a (continuation joined) `#define` line, `None` for any other line
+pub fn tokenize_define(line: &str) -> Option<Define> {
+    split_define(&Regex::new(DEFINE).unwrap(), line)
+}
+
+fn split_define(re_define: &Regex, line: &str) -> Option<Define> {
+    let c = re_define.captures(line)?;
+    Some(Define {
+        name: c[1].to_string(),
+        params: c.get(2).map(|params| {
+            params
+                .as_str()
+                .split(',')
+                .map(str::trim)
+                .filter(|p| !p.is_empty())
+                .map(str::to_string)
+                .collect()
+        }),
+        value: c[3].to_string(),
+    })
+}
+
+/// Evaluate the value of the define `name`, following references to other defines
+pub fn resolve(defines: &HashMap<String, String>, name: &str) -> Option<i64> {
+    resolve_nested(defines, name, 0)
+}
+
+fn resolve_nested(defines: &HashMap<String, String>, name: &str, depth: usize) -> Option<i64> {
+    let value = defines.get(name)?;
+    if depth > 16 {
+        return None;
+    }
+    expr::eval(value, &|n| resolve_nested(defines, n, depth + 1)).ok()
+}
+
+/// Evaluate the condition of an `#if`/`#elif`, undefined identifiers count as `0`
+fn condition(re_defined: &Regex, defines: &HashMap<String, String>, cond: &str) -> bool {
+    let cond = re_defined.replace_all(cond, |c: &regex::Captures| {
+        if defines.contains_key(&c[1]) {
+            "1"
+        } else {
+            "0"
+        }
+    });
+    match expr::eval(&cond, &|n| Some(resolve(defines, n).unwrap_or(0))) {
+        Ok(value) => value != 0,
+        Err(e) => {
+            println!("Failed to evaluate #if {}: {}", cond, e);
+            false
+        }
+    }
+}
+
+/// Join lines ending in a backslash with the following line, returning the logical lines
+/// together with the index of their first physical line
+pub fn join_continuations(text: &str) -> Vec<(usize, Cow<'_, str>)> {
+    let mut lines: Vec<(usize, Cow<'_, str>)> = vec![];
+    let mut continued = false;
+
+    for (i, line) in text.lines().enumerate() {
+        let trimmed = line.trim_end();
+        let continues = trimmed.ends_with('\\');
+        let content = if continues {
+            &trimmed[..trimmed.len() - 1]
+        } else {
+            line
+        };
+
+        match lines.last_mut() {
+            Some((_, last)) if continued => {
+                let last = last.to_mut();
+                last.push(' ');
+                last.push_str(content.trim_start());
+            }
+            _ => lines.push((i, Cow::Borrowed(content))),
+        }
+        continued = continues;
+    }
+
+    lines
+}
+
+/// Returns the active logical lines of `text` together with their original line index.
+/// `#define`s in active blocks are added to `defines`, preprocessor directives other
+/// than `#define` are dropped.
+pub fn preprocess<'a>(
+    text: &'a str,
+    defines: &mut HashMap<String, String>,
+) -> Vec<(usize, Cow<'a, str>)> {
+    let re_directive = Regex::new(r"^\s*#\s*([a-z]+)\s*(.*?)\s*(?://.*|/\*.*)?$").unwrap();
+    let re_define = Regex::new(DEFINE).unwrap();
+    let re_defined = Regex::new(r"defined\s*\(?\s*([A-Za-z_][0-9A-Za-z_]*)\s*\)?").unwrap();
+
+    let mut blocks: Vec<Block> = vec![];
+    let mut lines = vec![];
+
+    for (i, line) in join_continuations(text) {
+        let active = blocks.last().map(|b| b.active).unwrap_or(true);
+
+        let (directive, arg) = match re_directive.captures(&line) {
+            Some(c) => (c[1].to_string(), c[2].to_string()),
+            None => {
+                if active {
+                    lines.push((i, line));
+                }
+                continue;
+            }
+        };
+
+        let arg = arg.as_str();
+        match directive.as_str() {
+            "ifdef" | "ifndef" | "if" => {
+                let cond = active
+                    && match directive.as_str() {
+                        "ifdef" => defines.contains_key(arg),
+                        "ifndef" => !defines.contains_key(arg),
+                        _ => condition(&re_defined, defines, arg),
+                    };
+                blocks.push(Block {
+                    parent_active: active,
+                    taken: cond,
+                    active: cond,
+                });
+            }
+            "elif" => {
+                if let Some(b) = blocks.last_mut() {
+                    b.active = b.parent_active && !b.taken && condition(&re_defined, defines, arg);
+                    b.taken |= b.active;
+                }
+            }
+            "else" => {
+                if let Some(b) = blocks.last_mut() {
+                    b.active = b.parent_active && !b.taken;
+                    b.taken = true;
+                }
+            }
+            "endif" => {
+                blocks.pop();
+            }
+            "define" if active => {
+                if let Some(define) = split_define(&re_define, &line) {
+                    defines.insert(define.name, define.value);
+                }
+                lines.push((i, line));
+            }
+            "undef" if active => {
+                defines.remove(arg);
+            }
+            _ => {}
+        }
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn active(text: &str, defines: &[&str]) -> Vec<String> {
+        let mut defines = defines.iter().map(|d| parse_define(d)).collect();
+        preprocess(text, &mut defines)
+            .into_iter()
+            .map(|(_, line)| line.trim().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_if_chain() {
+        let text = "#if CHIP == 1\none\n#elif CHIP == 2\ntwo\n#elif CHIP == 2\nagain\n#else\nother\n#endif\nafter";
+        assert_eq!(active(text, &["CHIP=1"]), ["one", "after"]);
+        assert_eq!(active(text, &["CHIP=2"]), ["two", "after"]);
+        assert_eq!(active(text, &["CHIP=3"]), ["other", "after"]);
+        // undefined identifiers count as 0
+        assert_eq!(active("#if CHIP\nyes\n#else\nno\n#endif", &[]), ["no"]);
+    }
+
+    #[test]
+    fn test_nested_ifdef() {
+        let text = "#if 0\n#ifdef FOO\nfoo\n#else\nnot foo\n#endif\n#else\nouter\n#endif";
+        assert_eq!(active(text, &["FOO"]), ["outer"]);
+        assert_eq!(active(text, &[]), ["outer"]);
+        let text = "#ifndef FOO\n#ifdef BAR\nbar\n#endif\n#endif";
+        assert_eq!(active(text, &["BAR"]), ["bar"]);
+        assert!(active(text, &["FOO", "BAR"]).is_empty());
+    }
+
+    #[test]
+    fn test_defined() {
+        let text = "#if defined(FOO) && !defined BAR\nyes\n#endif";
+        assert_eq!(active(text, &["FOO"]), ["yes"]);
+        assert!(active(text, &["FOO", "BAR"]).is_empty());
+        assert!(active(text, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_undef() {
+        let text = "#define FOO 1\n#undef FOO\n#ifdef FOO\nfoo\n#endif";
+        assert_eq!(active(text, &[]), ["#define FOO 1"]);
+        // defines in inactive blocks are not seen
+        let mut defines = HashMap::new();
+        preprocess("#if 0\n#define FOO 1\n#endif", &mut defines);
+        assert!(!defines.contains_key("FOO"));
+    }
+
+    #[test]
+    fn test_resolve() {
+        let mut defines: HashMap<String, String> = HashMap::new();
+        defines.insert("BASE".to_string(), "0x3FF40000".to_string());
+        defines.insert("UART".to_string(), "(BASE + 0x1000)".to_string());
+        assert_eq!(resolve(&defines, "UART"), Some(0x3FF4_1000));
+        assert_eq!(resolve(&defines, "MISSING"), None);
+
+        // a chain of 17 references still resolves, one more is cut off
+        defines.insert("D0".to_string(), "1".to_string());
+        for i in 1..=18 {
+            defines.insert(format!("D{}", i), format!("D{}", i - 1));
+        }
+        assert_eq!(resolve(&defines, "D16"), Some(1));
+        assert_eq!(resolve(&defines, "D17"), None);
+        // and so is a define referring to itself
+        defines.insert("LOOP".to_string(), "LOOP + 1".to_string());
+        assert_eq!(resolve(&defines, "LOOP"), None);
+    }
+}
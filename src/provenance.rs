@@ -0,0 +1,35 @@
+//! Sidecar file recording where every peripheral, register and field of the generated svd came
+//! from, keyed by dotted path (`PERIPHERAL`, `PERIPHERAL.REGISTER`, `PERIPHERAL.REGISTER.FIELD`).
+//! The svd format only allows vendor extensions at the device level, so they are kept separate.
+
+use crate::{Peripheral, Provenance};
+use std::collections::BTreeMap;
+
+fn describe(provenance: &[Provenance]) -> Vec<String> {
+    provenance.iter().map(Provenance::to_string).collect()
+}
+
+/// Provenance of every element by dotted path, elements without any are left out
+pub fn provenance_map(peripherals: &BTreeMap<String, Peripheral>) -> BTreeMap<String, Vec<String>> {
+    let mut map = BTreeMap::new();
+    for (pname, p) in peripherals {
+        if !p.provenance.is_empty() {
+            map.insert(pname.clone(), describe(&p.provenance));
+        }
+        for r in &p.registers {
+            let path = format!("{}.{}", pname, r.name);
+            for f in r.bit_fields.iter().filter(|f| !f.provenance.is_empty()) {
+                map.insert(format!("{}.{}", path, f.name), describe(&f.provenance));
+            }
+            if !r.provenance.is_empty() {
+                map.insert(path, describe(&r.provenance));
+            }
+        }
+    }
+    map
+}
+
+/// Render the provenance of all elements as pretty printed JSON
+pub fn provenance_json(peripherals: &BTreeMap<String, Peripheral>) -> String {
+    serde_json::to_string_pretty(&provenance_map(peripherals)).unwrap()
+}
@@ -0,0 +1,312 @@
+//! C skeletons of QEMU device models, a `SysBusDevice` per peripheral with its MMIO region,
+//! `REG32`/`FIELD` definitions, reset values and a read/write switch over the registers. Writes
+//! to the set and clear registers linked to a register update its bits, everything else is
+//! plain storage to be replaced by the behaviour of the device.
+
+use crate::stub::register_access;
+use crate::{Peripheral, Register, Type};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+
+/// Prefix of the generated types and functions
+const PREFIX: &str = "esp32";
+
+/// Lower case C identifier of a peripheral, e.g. `esp32_uart`
+fn identifier(name: &str) -> String {
+    let name: String = name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    format!("{}_{}", PREFIX, name)
+}
+
+/// Name of the state struct, e.g. `Esp32UartState`
+fn state_type(ident: &str) -> String {
+    let mut name: String = ident
+        .split('_')
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect();
+    name.push_str("State");
+    name
+}
+
+/// File name of the skeleton of `peripheral`
+pub fn stub_file_name(peripheral: &str) -> String {
+    format!("{}.c", identifier(peripheral))
+}
+
+/// The registers backed by the register array: one per offset, without the narrower
+/// alternate registers
+fn mapped_registers(p: &Peripheral) -> Vec<&Register> {
+    let mut registers: Vec<&Register> = p
+        .registers
+        .iter()
+        .filter(|r| r.alternate_register.is_none())
+        .collect();
+    registers.sort_by_key(|r| (r.address, r.name.clone()));
+    registers.dedup_by_key(|r| r.address);
+    registers
+}
+
+fn write_case(out: &mut String, p: &Peripheral, r: &Register) {
+    writeln!(out, "    case A_{}:", r.name).unwrap();
+    // a set or clear register of another register
+    let target = p.registers.iter().find_map(|v| {
+        let strobes = v.strobes.as_ref()?;
+        if strobes.set.as_ref() == Some(&r.name) {
+            Some((v, "|= "))
+        } else if strobes.clear.as_ref() == Some(&r.name) {
+            Some((v, "&= ~"))
+        } else {
+            None
+        }
+    });
+    match (target, register_access(r)) {
+        (Some((v, op)), _) => {
+            writeln!(out, "        s->regs[R_{}] {}value;", v.name, op).unwrap();
+        }
+        (None, Type::ReadOnly) => {
+            writeln!(
+                out,
+                "        qemu_log_mask(LOG_GUEST_ERROR, \"%s: write to read-only register {}\\n\", __func__);",
+                r.name
+            )
+            .unwrap();
+        }
+        (None, _) => {
+            writeln!(out, "        s->regs[R_{}] = value;", r.name).unwrap();
+        }
+    }
+    writeln!(out, "        break;").unwrap();
+}
+
+/// The C source of the device model of peripheral `name`
+pub fn device_stub(name: &str, p: &Peripheral) -> String {
+    let ident = identifier(name);
+    let upper = ident.to_uppercase();
+    let state = state_type(&ident);
+    let registers = mapped_registers(p);
+    let mut out = String::new();
+
+    writeln!(out, "#include \"qemu/osdep.h\"").unwrap();
+    writeln!(out, "#include \"qemu/log.h\"").unwrap();
+    writeln!(out, "#include \"hw/irq.h\"").unwrap();
+    writeln!(out, "#include \"hw/registerfields.h\"").unwrap();
+    writeln!(out, "#include \"hw/sysbus.h\"").unwrap();
+    writeln!(out, "#include \"qom/object.h\"\n").unwrap();
+
+    writeln!(
+        out,
+        "#define TYPE_{} \"{}\"",
+        upper,
+        ident.replacen('_', ".", 1)
+    )
+    .unwrap();
+    writeln!(out, "OBJECT_DECLARE_SIMPLE_TYPE({}, {})\n", state, upper).unwrap();
+    writeln!(out, "#define {}_BASE 0x{:08x}", upper, p.address).unwrap();
+    for (i, address) in p.instance_addresses.iter().enumerate() {
+        writeln!(out, "#define {}{}_BASE 0x{:08x}", upper, i + 1, address).unwrap();
+    }
+    writeln!(
+        out,
+        "#define {}_SIZE 0x{:x}\n",
+        upper,
+        p.address_block_size()
+    )
+    .unwrap();
+
+    for r in &registers {
+        writeln!(out, "REG32({}, 0x{:x})", r.name, r.address).unwrap();
+        for f in &r.bit_fields {
+            let width = f.bits.msb() - f.bits.lsb() + 1;
+            writeln!(
+                out,
+                "    FIELD({}, {}, {}, {})",
+                r.name,
+                f.name,
+                f.bits.lsb(),
+                width
+            )
+            .unwrap();
+        }
+    }
+
+    writeln!(out, "\nstruct {} {{", state).unwrap();
+    writeln!(out, "    SysBusDevice parent_obj;\n").unwrap();
+    writeln!(out, "    MemoryRegion iomem;").unwrap();
+    writeln!(out, "    qemu_irq irq;").unwrap();
+    writeln!(out, "    uint32_t regs[{}_SIZE / 4];", upper).unwrap();
+    writeln!(out, "}};\n").unwrap();
+
+    writeln!(out, "static void {}_reset(DeviceState *dev)\n{{", ident).unwrap();
+    writeln!(out, "    {} *s = {}(dev);\n", state, upper).unwrap();
+    writeln!(out, "    memset(s->regs, 0, sizeof(s->regs));").unwrap();
+    for r in registers.iter().filter(|r| r.reset_value != 0) {
+        writeln!(out, "    s->regs[R_{}] = 0x{:08x};", r.name, r.reset_value).unwrap();
+    }
+    writeln!(out, "}}\n").unwrap();
+
+    writeln!(
+        out,
+        "static uint64_t {}_read(void *opaque, hwaddr addr, unsigned int size)\n{{",
+        ident
+    )
+    .unwrap();
+    writeln!(out, "    {} *s = opaque;\n", state).unwrap();
+    writeln!(out, "    switch (addr) {{").unwrap();
+    for r in registers
+        .iter()
+        .filter(|r| register_access(r) != Type::WriteOnly)
+    {
+        writeln!(out, "    case A_{}:", r.name).unwrap();
+    }
+    writeln!(out, "        return s->regs[addr / 4];").unwrap();
+    writeln!(out, "    default:").unwrap();
+    writeln!(
+        out,
+        "        qemu_log_mask(LOG_UNIMP, \"%s: unimplemented read at 0x%\" HWADDR_PRIx \"\\n\", __func__, addr);"
+    )
+    .unwrap();
+    writeln!(out, "        return 0;").unwrap();
+    writeln!(out, "    }}\n}}\n").unwrap();
+
+    writeln!(
+        out,
+        "static void {}_write(void *opaque, hwaddr addr, uint64_t value, unsigned int size)\n{{",
+        ident
+    )
+    .unwrap();
+    writeln!(out, "    {} *s = opaque;\n", state).unwrap();
+    writeln!(out, "    switch (addr) {{").unwrap();
+    for r in &registers {
+        write_case(&mut out, p, r);
+    }
+    writeln!(out, "    default:").unwrap();
+    writeln!(
+        out,
+        "        qemu_log_mask(LOG_UNIMP, \"%s: unimplemented write at 0x%\" HWADDR_PRIx \"\\n\", __func__, addr);"
+    )
+    .unwrap();
+    writeln!(out, "        break;").unwrap();
+    writeln!(out, "    }}\n}}\n").unwrap();
+
+    writeln!(out, "static const MemoryRegionOps {}_ops = {{", ident).unwrap();
+    writeln!(out, "    .read = {}_read,", ident).unwrap();
+    writeln!(out, "    .write = {}_write,", ident).unwrap();
+    writeln!(out, "    .endianness = DEVICE_LITTLE_ENDIAN,").unwrap();
+    writeln!(out, "    .valid.min_access_size = 4,").unwrap();
+    writeln!(out, "    .valid.max_access_size = 4,").unwrap();
+    writeln!(out, "}};\n").unwrap();
+
+    writeln!(out, "static void {}_init(Object *obj)\n{{", ident).unwrap();
+    writeln!(out, "    {} *s = {}(obj);\n", state, upper).unwrap();
+    writeln!(
+        out,
+        "    memory_region_init_io(&s->iomem, obj, &{0}_ops, s, TYPE_{1}, {1}_SIZE);",
+        ident, upper
+    )
+    .unwrap();
+    writeln!(out, "    sysbus_init_mmio(SYS_BUS_DEVICE(obj), &s->iomem);").unwrap();
+    writeln!(out, "    sysbus_init_irq(SYS_BUS_DEVICE(obj), &s->irq);").unwrap();
+    writeln!(out, "}}\n").unwrap();
+
+    writeln!(
+        out,
+        "static void {}_class_init(ObjectClass *klass, void *data)\n{{",
+        ident
+    )
+    .unwrap();
+    writeln!(out, "    DeviceClass *dc = DEVICE_CLASS(klass);\n").unwrap();
+    writeln!(out, "    dc->reset = {}_reset;", ident).unwrap();
+    writeln!(out, "}}\n").unwrap();
+
+    writeln!(out, "static const TypeInfo {}_info = {{", ident).unwrap();
+    writeln!(out, "    .name = TYPE_{},", upper).unwrap();
+    writeln!(out, "    .parent = TYPE_SYS_BUS_DEVICE,").unwrap();
+    writeln!(out, "    .instance_size = sizeof({}),", state).unwrap();
+    writeln!(out, "    .instance_init = {}_init,", ident).unwrap();
+    writeln!(out, "    .class_init = {}_class_init,", ident).unwrap();
+    writeln!(out, "}};\n").unwrap();
+
+    writeln!(out, "static void {}_register_types(void)\n{{", ident).unwrap();
+    writeln!(out, "    type_register_static(&{}_info);", ident).unwrap();
+    writeln!(out, "}}\n").unwrap();
+    writeln!(out, "type_init({}_register_types)", ident).unwrap();
+    out
+}
+
+/// Write the device model skeleton of every peripheral with registers into `dir`, starting
+/// every file with `banner`
+pub fn write_qemu_stubs(
+    dir: &Path,
+    peripherals: &BTreeMap<String, Peripheral>,
+    banner: &str,
+) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    for (name, p) in peripherals.iter().filter(|(_, p)| !p.registers.is_empty()) {
+        std::fs::write(
+            dir.join(stub_file_name(name)),
+            format!("{}{}", banner, device_stub(name, p)),
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strobes::Strobes;
+    use crate::{BitField, Bits};
+
+    #[test]
+    fn test_device_stub() {
+        let register = |name: &str, address, type_| Register {
+            name: name.to_string(),
+            address,
+            bit_fields: vec![BitField {
+                name: format!("{}_DATA", name),
+                bits: Bits::Range(0..=31),
+                type_,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let mut out = register("GPIO_OUT", 0x4, Type::ReadWrite);
+        out.reset_value = 0x10;
+        out.strobes = Some(Strobes {
+            set: Some("GPIO_OUT_W1TS".to_string()),
+            clear: Some("GPIO_OUT_W1TC".to_string()),
+        });
+        let p = Peripheral {
+            address: 0x3ff4_4000,
+            registers: vec![
+                out,
+                register("GPIO_OUT_W1TS", 0x8, Type::WriteOnly),
+                register("GPIO_OUT_W1TC", 0xc, Type::WriteOnly),
+                register("GPIO_IN", 0x3c, Type::ReadOnly),
+            ],
+            ..Default::default()
+        };
+
+        let stub = device_stub("GPIO", &p);
+        assert!(stub.contains("#define TYPE_ESP32_GPIO \"esp32.gpio\"\n"));
+        assert!(stub.contains("OBJECT_DECLARE_SIMPLE_TYPE(Esp32GpioState, ESP32_GPIO)"));
+        assert!(stub.contains("#define ESP32_GPIO_SIZE 0x40\n"));
+        assert!(stub.contains("REG32(GPIO_IN, 0x3c)\n    FIELD(GPIO_IN, GPIO_IN_DATA, 0, 32)\n"));
+        assert!(stub.contains("    s->regs[R_GPIO_OUT] = 0x00000010;\n"));
+        assert!(stub.contains("    case A_GPIO_OUT:\n    case A_GPIO_IN:\n        return"));
+        assert!(stub.contains("    case A_GPIO_OUT_W1TS:\n        s->regs[R_GPIO_OUT] |= value;\n"));
+        assert!(
+            stub.contains("    case A_GPIO_OUT_W1TC:\n        s->regs[R_GPIO_OUT] &= ~value;\n")
+        );
+        assert!(stub.contains("    case A_GPIO_IN:\n        qemu_log_mask(LOG_GUEST_ERROR"));
+    }
+}
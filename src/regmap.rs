@@ -0,0 +1,203 @@
+//! Import of hand transcribed register maps from CSV (or TSV) files, one row per field
+//!
+//! ```text
+//! peripheral,register,offset,field,bits,access,reset,description
+//! TIMER,FRC1_LOAD,0x0,,,,,the load value into the counter
+//! TIMER,FRC1_CTRL,0x8,TIMER_ENABLE,7,RW,0,timer enable
+//! TIMER,FRC1_CTRL,0x8,PRESCALE_DIV,3:2,RW,0,prescale divider
+//! ```
+//!
+//! Rows without field describe the register itself, the offset only needs to be given once
+//! per register.
+
+use crate::doc::{DocField, DocFile, DocRegister};
+use crate::tables::parse_value;
+use serde::Deserialize;
+use std::ffi::OsStr;
+use std::path::Path;
+
+#[derive(Debug, Deserialize)]
+struct Row {
+    peripheral: String,
+    register: String,
+    #[serde(default)]
+    offset: String,
+    #[serde(default)]
+    field: String,
+    #[serde(default)]
+    bits: String,
+    #[serde(default)]
+    access: String,
+    #[serde(default)]
+    reset: String,
+    #[serde(default)]
+    description: String,
+}
+
+fn optional(value: String) -> Option<String> {
+    if value.is_empty() {
+        None
+    } else {
+        Some(value)
+    }
+}
+
+/// Load a register map, `.tsv` files are tab separated. Returns one doc per peripheral
+pub fn parse_regmap(path: &Path) -> Result<Vec<DocFile>, String> {
+    let delimiter = if path.extension() == Some(OsStr::new("tsv")) {
+        b'\t'
+    } else {
+        b','
+    };
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .trim(csv::Trim::All)
+        .from_path(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    let mut docs: Vec<DocFile> = vec![];
+    for (i, row) in reader.deserialize::<Row>().enumerate() {
+        // the header is line 1
+        let line = i + 2;
+        let error = |e: String| format!("{}:{}: {}", path.display(), line, e);
+        let Row {
+            peripheral,
+            register: name,
+            offset,
+            field,
+            bits,
+            access,
+            reset,
+            description,
+        } = row.map_err(|e| error(e.to_string()))?;
+
+        let doc = match docs.iter().position(|d| d.peripheral == peripheral) {
+            Some(d) => &mut docs[d],
+            None => {
+                docs.push(DocFile {
+                    peripheral,
+                    ..Default::default()
+                });
+                docs.last_mut().unwrap()
+            }
+        };
+
+        let offset = match optional(offset) {
+            Some(offset) => Some(
+                parse_value(&offset).ok_or_else(|| error(format!("invalid offset {}", offset)))?
+                    as u32,
+            ),
+            None => None,
+        };
+        let reset = match optional(reset) {
+            Some(reset) => {
+                Some(parse_value(&reset).ok_or_else(|| error(format!("invalid reset {}", reset)))?)
+            }
+            None => None,
+        };
+
+        let register = match doc.registers.iter().position(|r| r.name == name) {
+            Some(r) => &mut doc.registers[r],
+            None => {
+                let offset = offset
+                    .ok_or_else(|| error(format!("no offset given for register {}", name)))?;
+                doc.registers.push(DocRegister {
+                    name: name.clone(),
+                    offset,
+                    ..Default::default()
+                });
+                doc.registers.last_mut().unwrap()
+            }
+        };
+        if let Some(offset) = offset {
+            if offset != register.offset {
+                return Err(error(format!(
+                    "register {} was given offset {:#x} before",
+                    name, register.offset
+                )));
+            }
+        }
+
+        if field.is_empty() {
+            register.description = description;
+            register.reset = reset;
+        } else {
+            if bits.is_empty() {
+                return Err(error(format!("no bits given for field {}", field)));
+            }
+            register.fields.push(DocField {
+                name: field,
+                bits,
+                access: optional(access),
+                reset: reset.map(|r| r as u32),
+                description,
+            });
+        }
+    }
+
+    for doc in docs.iter_mut() {
+        doc.registers.sort_by_key(|r| r.offset);
+        if let Some(e) = doc.validate().first() {
+            return Err(format!("{}: {}: {}", path.display(), doc.peripheral, e));
+        }
+    }
+    Ok(docs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_regmap() {
+        let dir = std::env::temp_dir().join(format!("header2svd-regmap-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("timer.csv");
+        std::fs::write(
+            &path,
+            "peripheral,register,offset,field,bits,access,reset,description
+TIMER,FRC1_LOAD,0x0,,,,,the load value into the counter
+TIMER,FRC1_CTRL,0x8,TIMER_ENABLE,7,RW,0,timer enable
+TIMER,FRC1_CTRL,,PRESCALE_DIV,3:2,RW,0x1,prescale divider
+",
+        )
+        .unwrap();
+        let docs = parse_regmap(&path);
+        std::fs::write(
+            &path,
+            "peripheral,register,offset,field,bits\nTIMER,FRC1_CTRL,0x8,TIMER_ENABLE,\n",
+        )
+        .unwrap();
+        let missing_bits = parse_regmap(&path);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let docs = docs.unwrap();
+        assert_eq!(docs.len(), 1);
+        assert_eq!(docs[0].peripheral, "TIMER");
+        let registers = &docs[0].registers;
+        assert_eq!(registers.len(), 2);
+        assert_eq!(registers[0].description, "the load value into the counter");
+        let ctrl = &registers[1];
+        assert_eq!((ctrl.name.as_str(), ctrl.offset), ("FRC1_CTRL", 0x8));
+        let fields: Vec<(&str, &str, Option<u32>)> = ctrl
+            .fields
+            .iter()
+            .map(|f| (f.name.as_str(), f.bits.as_str(), f.reset))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                ("TIMER_ENABLE", "7", Some(0)),
+                ("PRESCALE_DIV", "3:2", Some(1))
+            ]
+        );
+        assert_eq!(ctrl.fields[1].access.as_deref(), Some("RW"));
+
+        let error = missing_bits.unwrap_err();
+        assert!(
+            error.ends_with(":2: no bits given for field TIMER_ENABLE"),
+            "{}",
+            error
+        );
+    }
+}
@@ -0,0 +1,176 @@
+//! Naming policy from the config: regex rules renaming peripherals, registers or fields, e.g.
+//! collapsing `SPI_FLASH_` to `FLASH_`. Rules are applied in order after the name shortening,
+//! each one seeing the names left by the ones before it.
+
+use crate::sanitize::Rename;
+use crate::source::Model;
+use regex::Regex;
+use serde::Deserialize;
+
+/// Names a rule applies to
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RenameScope {
+    Peripheral,
+    Register,
+    Field,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RenameRule {
+    /// Regex replaced in every name of the scope
+    pub pattern: String,
+    /// Replacement, with `$1` or `${name}` for the groups of the pattern
+    pub replacement: String,
+    pub scope: RenameScope,
+    /// Only rename in this peripheral, by its name when the rule is applied
+    #[serde(default)]
+    pub peripheral: Option<String>,
+}
+
+/// Apply `rules` to the names of the model, returning the renames made. An invalid pattern or a
+/// peripheral renamed onto another one is an error
+pub fn apply_rename_rules(model: &mut Model, rules: &[RenameRule]) -> Result<Vec<Rename>, String> {
+    let mut renames = vec![];
+    for rule in rules {
+        let re = Regex::new(&rule.pattern)
+            .map_err(|e| format!("Invalid rename pattern {}: {}", rule.pattern, e))?;
+        let applies = |peripheral: &str| rule.peripheral.iter().all(|p| p == peripheral);
+        let rename = |name: &str| re.replace_all(name, rule.replacement.as_str()).into_owned();
+
+        if rule.scope == RenameScope::Peripheral {
+            let names: Vec<String> = model.keys().filter(|n| applies(n)).cloned().collect();
+            for from in names {
+                let to = rename(&from);
+                if to == from {
+                    continue;
+                }
+                if model.contains_key(&to) {
+                    return Err(format!(
+                        "Can't rename {} to {}, it already exists",
+                        from, to
+                    ));
+                }
+                let p = model.remove(&from).unwrap();
+                model.insert(to.clone(), p);
+                renames.push(Rename {
+                    scope: String::new(),
+                    from,
+                    to,
+                });
+            }
+            continue;
+        }
+        for (pname, p) in model.iter_mut().filter(|(n, _)| applies(n)) {
+            for r in p.registers.iter_mut() {
+                if rule.scope == RenameScope::Register {
+                    let to = rename(&r.name);
+                    if to != r.name {
+                        renames.push(Rename {
+                            scope: pname.clone(),
+                            from: std::mem::replace(&mut r.name, to),
+                            to: r.name.clone(),
+                        });
+                    }
+                    continue;
+                }
+                let scope = format!("{}.{}", pname, r.name);
+                for f in r.bit_fields.iter_mut() {
+                    let to = rename(&f.name);
+                    if to != f.name {
+                        renames.push(Rename {
+                            scope: scope.clone(),
+                            from: std::mem::replace(&mut f.name, to),
+                            to: f.name.clone(),
+                        });
+                    }
+                }
+            }
+        }
+    }
+    Ok(renames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BitField, Peripheral, Register};
+
+    #[test]
+    fn test_rename_rules() {
+        let register = |name: &str, field: &str| Register {
+            name: name.to_string(),
+            bit_fields: vec![BitField {
+                name: field.to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let mut model = Model::new();
+        model.insert(
+            "SPI_FLASH".to_string(),
+            Peripheral {
+                registers: vec![register("SPI_FLASH_CMD", "SPI_FLASH_READ")],
+                ..Default::default()
+            },
+        );
+        model.insert(
+            "SPI1".to_string(),
+            Peripheral {
+                registers: vec![register("SPI_FLASH_CMD", "SPI_USR")],
+                ..Default::default()
+            },
+        );
+        let rules: Vec<RenameRule> = toml::from_str::<std::collections::BTreeMap<String, _>>(
+            r#"
+[[rename]]
+pattern = "^SPI_FLASH$"
+replacement = "FLASH"
+scope = "peripheral"
+
+[[rename]]
+pattern = "^SPI_FLASH_"
+replacement = "FLASH_"
+scope = "register"
+peripheral = "FLASH"
+
+[[rename]]
+pattern = "^SPI_(\\w+)"
+replacement = "${1}_SPI"
+scope = "field"
+"#,
+        )
+        .unwrap()
+        .remove("rename")
+        .unwrap();
+
+        let renames = apply_rename_rules(&mut model, &rules).unwrap();
+        let renames: Vec<String> = renames.iter().map(Rename::to_string).collect();
+        assert_eq!(
+            renames,
+            vec![
+                "SPI_FLASH -> FLASH",
+                "FLASH.SPI_FLASH_CMD -> FLASH_CMD",
+                "FLASH.FLASH_CMD.SPI_FLASH_READ -> FLASH_READ_SPI",
+                "SPI1.SPI_FLASH_CMD.SPI_USR -> USR_SPI",
+            ]
+        );
+        assert_eq!(model["SPI1"].registers[0].name, "SPI_FLASH_CMD");
+
+        let invalid = RenameRule {
+            pattern: "(".to_string(),
+            replacement: String::new(),
+            scope: RenameScope::Field,
+            peripheral: None,
+        };
+        assert!(apply_rename_rules(&mut model, &[invalid]).is_err());
+        let collision = RenameRule {
+            pattern: "^FLASH$".to_string(),
+            replacement: "SPI1".to_string(),
+            scope: RenameScope::Peripheral,
+            peripheral: None,
+        };
+        assert!(apply_rename_rules(&mut model, &[collision]).is_err());
+    }
+}
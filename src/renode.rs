@@ -0,0 +1,106 @@
+//! Renode platform description (`.repl`) of the model, to start emulating a chip from the
+//! parsed data. There are no Renode models of the peripherals, so every peripheral instance is
+//! registered as a `Python.PythonPeripheral` reading as zero at its base address, to be replaced
+//! by a real model once there is one. The interrupts are listed as comments for the same reason.
+
+use crate::Peripheral;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Script of the placeholder peripherals, reads return zero and writes are ignored
+const SCRIPT: &str = "request.value = 0";
+
+/// Name usable as a Renode identifier
+fn identifier(name: &str) -> String {
+    name.to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+fn placeholder(out: &mut String, name: &str, address: u32, p: &Peripheral) {
+    writeln!(
+        out,
+        "{}: Python.PythonPeripheral @ sysbus 0x{:08x}",
+        identifier(name),
+        address
+    )
+    .unwrap();
+    writeln!(out, "    size: 0x{:x}", p.address_block_size()).unwrap();
+    writeln!(out, "    initable: false").unwrap();
+    writeln!(out, "    script: \"{}\"", SCRIPT).unwrap();
+}
+
+/// The `.repl` registering every peripheral with registers on the system bus
+pub fn platform_description(peripherals: &BTreeMap<String, Peripheral>) -> String {
+    let mut out = String::new();
+    writeln!(
+        out,
+        "// The cpu and interrupt controller are left to the including platform, connect the"
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "// listed interrupts once a placeholder is replaced by a model with interrupt outputs"
+    )
+    .unwrap();
+    for (name, p) in peripherals.iter().filter(|(_, p)| !p.registers.is_empty()) {
+        writeln!(out).unwrap();
+        placeholder(&mut out, name, p.address, p);
+        let mut interrupts: Vec<_> = p.interrupts.iter().collect();
+        interrupts.sort_by_key(|i| i.value);
+        for i in interrupts {
+            writeln!(out, "    // interrupt {} = {}", i.name, i.value).unwrap();
+        }
+        for (i, address) in p.instance_addresses.iter().enumerate() {
+            writeln!(out).unwrap();
+            placeholder(&mut out, &format!("{}{}", name, i + 1), *address, p);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Interrupt, Register};
+
+    #[test]
+    fn test_platform_description() {
+        let mut peripherals = BTreeMap::new();
+        peripherals.insert(
+            "UART".to_string(),
+            Peripheral {
+                address: 0x6000_0000,
+                instance_addresses: vec![0x6000_0f00],
+                interrupts: vec![Interrupt {
+                    name: "UART".to_string(),
+                    description: None,
+                    value: 5,
+                }],
+                registers: vec![
+                    Register::default(),
+                    Register {
+                        address: 0x1c,
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            },
+        );
+        peripherals.insert(
+            "RTC_IO".to_string(),
+            Peripheral {
+                address: 0x6000_0700,
+                ..Default::default()
+            },
+        );
+
+        let repl = platform_description(&peripherals);
+        assert!(repl.contains(
+            "\nuart: Python.PythonPeripheral @ sysbus 0x60000000\n    size: 0x20\n    initable: false\n    script: \"request.value = 0\"\n    // interrupt UART = 5\n"
+        ));
+        assert!(repl.contains("\nuart1: Python.PythonPeripheral @ sysbus 0x60000f00\n"));
+        assert!(!repl.contains("rtc_io"));
+    }
+}
@@ -0,0 +1,390 @@
+//! Interactive review of what the parser had to guess: registers assumed to be one full width
+//! value, fields derived from a mask covering the whole register and differences between the
+//! headers and docs resolved by a merge strategy. Every finding is accepted, fixed or skipped at
+//! a prompt, and the decisions are appended to a patch file that later runs read with
+//! `--patches`, so accepted findings aren't asked again.
+//!
+//! ```toml
+//! [[patch]]
+//! path = "UART.UART_DATE"
+//! delete = true
+//!
+//! [[merge]]
+//! peripheral = "TIMG"
+//! register = "TIMG_T0CONFIG"
+//! strategy = "union"
+//!
+//! [[accept]]
+//! finding = "UART.UART_FIFO: no fields, assumed to be a full width value"
+//! ```
+
+use crate::merge::MergeStrategy;
+use crate::sanitize::Rename;
+use crate::validate::Issue;
+use crate::{Origin, Peripheral};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt::{self, Write as _};
+use std::io::{self, BufRead, Write};
+
+/// Something the parser guessed, with the names the patches and merge rules see, from before
+/// sanitizing
+#[derive(Debug, Clone, PartialEq)]
+pub enum Finding {
+    /// A register without stated fields, emitted as a single full width value
+    AssumedRegister {
+        peripheral: String,
+        register: String,
+    },
+    /// A field derived from a mask define covering the whole register
+    FullWidthField {
+        peripheral: String,
+        register: String,
+        field: String,
+    },
+    /// A difference between the headers and docs, resolved by the merge strategy
+    Conflict {
+        peripheral: String,
+        register: Option<String>,
+        difference: String,
+    },
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Finding::AssumedRegister {
+                peripheral,
+                register,
+            } => write!(
+                f,
+                "{}.{}: no fields, assumed to be a full width value",
+                peripheral, register
+            ),
+            Finding::FullWidthField {
+                peripheral,
+                register,
+                field,
+            } => write!(
+                f,
+                "{}.{}.{}: field of a mask covering the whole register",
+                peripheral, register, field
+            ),
+            Finding::Conflict {
+                peripheral,
+                register: Some(register),
+                difference,
+            } => write!(f, "{}.{}: {}", peripheral, register, difference),
+            Finding::Conflict {
+                peripheral,
+                register: None,
+                difference,
+            } => write!(f, "{}: {}", peripheral, difference),
+        }
+    }
+}
+
+/// A finding the review shouldn't ask about again
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Accepted {
+    /// The finding as printed by the review
+    pub finding: String,
+}
+
+/// What to do about a finding, skipped findings have none
+#[derive(Debug, Clone, PartialEq)]
+pub enum Decision {
+    /// The guess is right
+    Accept,
+    Rename(String),
+    Delete,
+    /// Resolve the conflict with another merge strategy
+    Strategy(MergeStrategy),
+}
+
+/// Name before sanitizing of `name` in `scope`
+fn original(renames: &[Rename], scope: &str, name: &str) -> String {
+    renames
+        .iter()
+        .find(|r| r.scope == scope && r.to == name)
+        .map(|r| r.from.clone())
+        .unwrap_or_else(|| name.to_string())
+}
+
+/// Findings of the generated model, its validation issues and the conflicts of the merge,
+/// leaving out the `accepted` ones
+pub fn findings(
+    peripherals: &BTreeMap<String, Peripheral>,
+    issues: &[Issue],
+    conflicts: &[String],
+    renames: &[Rename],
+    accepted: &[Accepted],
+) -> Vec<Finding> {
+    let mut findings = vec![];
+    for (pname, p) in peripherals {
+        for r in &p.registers {
+            if r.bit_fields.iter().all(|f| f.origin == Origin::Assumed) {
+                findings.push(Finding::AssumedRegister {
+                    peripheral: original(renames, "", pname),
+                    register: original(renames, pname, &r.name),
+                });
+            }
+        }
+    }
+    for issue in issues {
+        if let Issue::FullWidthField {
+            peripheral,
+            register,
+            field,
+        } = issue
+        {
+            findings.push(Finding::FullWidthField {
+                peripheral: original(renames, "", peripheral),
+                register: original(renames, peripheral, register),
+                field: original(renames, &format!("{}.{}", peripheral, register), field),
+            });
+        }
+    }
+    // conflicts are found before sanitizing, as `PERIPHERAL[.REGISTER]: difference`
+    for conflict in conflicts {
+        let mut parts = conflict.splitn(2, ": ");
+        let (path, difference) = match (parts.next(), parts.next()) {
+            (Some(path), Some(difference)) => (path, difference.to_string()),
+            _ => continue,
+        };
+        let mut path = path.splitn(2, '.');
+        findings.push(Finding::Conflict {
+            peripheral: path.next().unwrap().to_string(),
+            register: path.next().map(str::to_string),
+            difference,
+        });
+    }
+    findings.retain(|f| {
+        let finding = f.to_string();
+        accepted.iter().all(|a| a.finding != finding)
+    });
+    findings
+}
+
+fn choices(finding: &Finding) -> &'static str {
+    match finding {
+        Finding::Conflict { .. } => {
+            "[a]ccept, [s]kip, prefer-[d]oc, prefer-[h]eader, [u]nion or [q]uit"
+        }
+        _ => "[a]ccept, [s]kip, [d]elete, [r]ename NAME or [q]uit",
+    }
+}
+
+/// Decision of an answer at the prompt, `None` to skip the finding
+fn parse_answer(finding: &Finding, answer: &str) -> Result<Option<Decision>, String> {
+    let conflict = matches!(finding, Finding::Conflict { .. });
+    let mut words = answer.split_whitespace();
+    let decision = match (words.next(), words.next(), conflict) {
+        (None, _, _) | (Some("s"), None, _) => return Ok(None),
+        (Some("a"), None, _) => Decision::Accept,
+        (Some("d"), None, false) => Decision::Delete,
+        (Some("r"), Some(name), false) => Decision::Rename(name.to_string()),
+        (Some("d"), None, true) => Decision::Strategy(MergeStrategy::PreferDoc),
+        (Some("h"), None, true) => Decision::Strategy(MergeStrategy::PreferHeader),
+        (Some("u"), None, true) => Decision::Strategy(MergeStrategy::Union),
+        _ => return Err(format!("Expected one of {}", choices(finding))),
+    };
+    if words.next().is_some() {
+        return Err(format!("Expected one of {}", choices(finding)));
+    }
+    Ok(Some(decision))
+}
+
+/// Ask about every finding on `output`, reading the answers from `input` until it ends or
+/// the review is quit. Returns the decisions made, skipped findings left out
+pub fn review<R: BufRead, W: Write>(
+    findings: &[Finding],
+    input: &mut R,
+    output: &mut W,
+) -> io::Result<Vec<(Finding, Decision)>> {
+    let mut decisions = vec![];
+    for (i, finding) in findings.iter().enumerate() {
+        writeln!(output, "[{}/{}] {}", i + 1, findings.len(), finding)?;
+        loop {
+            write!(output, "  {}? ", choices(finding))?;
+            output.flush()?;
+            let mut answer = String::new();
+            if input.read_line(&mut answer)? == 0 || answer.trim() == "q" {
+                return Ok(decisions);
+            }
+            match parse_answer(finding, &answer) {
+                Ok(Some(decision)) => {
+                    decisions.push((finding.clone(), decision));
+                    break;
+                }
+                Ok(None) => break,
+                Err(e) => writeln!(output, "  {}", e)?,
+            }
+        }
+    }
+    Ok(decisions)
+}
+
+fn quoted(text: &str) -> toml::Value {
+    toml::Value::String(text.to_string())
+}
+
+/// The decisions as patch file entries. A finding fixed by a rename is accepted under its new
+/// name and resolved conflicts are accepted, so neither is asked again
+pub fn patch_entries(decisions: &[(Finding, Decision)]) -> String {
+    let mut out = String::new();
+    for (finding, decision) in decisions {
+        let path = match finding {
+            Finding::AssumedRegister {
+                peripheral,
+                register,
+            } => format!("{}.{}", peripheral, register),
+            Finding::FullWidthField {
+                peripheral,
+                register,
+                field,
+            } => format!("{}.{}.{}", peripheral, register, field),
+            Finding::Conflict { .. } => String::new(),
+        };
+        let accepted = match decision {
+            Decision::Accept => Some(finding.to_string()),
+            Decision::Delete => {
+                writeln!(out, "[[patch]]\npath = {}\ndelete = true\n", quoted(&path)).unwrap();
+                None
+            }
+            Decision::Rename(name) => {
+                writeln!(
+                    out,
+                    "[[patch]]\npath = {}\nrename = {}\n",
+                    quoted(&path),
+                    quoted(name)
+                )
+                .unwrap();
+                let renamed = match finding.clone() {
+                    Finding::AssumedRegister { peripheral, .. } => Finding::AssumedRegister {
+                        peripheral,
+                        register: name.clone(),
+                    },
+                    Finding::FullWidthField {
+                        peripheral,
+                        register,
+                        ..
+                    } => Finding::FullWidthField {
+                        peripheral,
+                        register,
+                        field: name.clone(),
+                    },
+                    conflict => conflict,
+                };
+                Some(renamed.to_string())
+            }
+            Decision::Strategy(strategy) => {
+                if let Finding::Conflict {
+                    peripheral,
+                    register,
+                    ..
+                } = finding
+                {
+                    writeln!(out, "[[merge]]\nperipheral = {}", quoted(peripheral)).unwrap();
+                    if let Some(register) = register {
+                        writeln!(out, "register = {}", quoted(register)).unwrap();
+                    }
+                    let strategy = match strategy {
+                        MergeStrategy::PreferDoc => "prefer-doc",
+                        MergeStrategy::PreferHeader => "prefer-header",
+                        MergeStrategy::Union => "union",
+                        MergeStrategy::Error => "error",
+                    };
+                    writeln!(out, "strategy = {}\n", quoted(strategy)).unwrap();
+                }
+                Some(finding.to_string())
+            }
+        };
+        if let Some(accepted) = accepted {
+            writeln!(out, "[[accept]]\nfinding = {}\n", quoted(&accepted)).unwrap();
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BitField, Register};
+
+    #[test]
+    fn test_review() {
+        let register = |name: &str, origin| Register {
+            name: name.to_string(),
+            bit_fields: vec![BitField {
+                origin,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let mut peripherals = BTreeMap::new();
+        peripherals.insert(
+            "UART".to_string(),
+            Peripheral {
+                registers: vec![
+                    register("UART_FIFO", Origin::Assumed),
+                    register("UART_CONF0", Origin::HeaderMask),
+                    register("UART_DATE_", Origin::Assumed),
+                ],
+                ..Default::default()
+            },
+        );
+        let issues = vec![Issue::FullWidthField {
+            peripheral: "UART".to_string(),
+            register: "UART_CONF0".to_string(),
+            field: "UART_CONF0".to_string(),
+        }];
+        let conflicts = vec!["UART.UART_CONF1: fields differ from the documentation".to_string()];
+        let renames = vec![Rename {
+            scope: "UART".to_string(),
+            from: "UART_DATE".to_string(),
+            to: "UART_DATE_".to_string(),
+        }];
+        let accepted = vec![Accepted {
+            finding: "UART.UART_FIFO: no fields, assumed to be a full width value".to_string(),
+        }];
+        let findings = findings(&peripherals, &issues, &conflicts, &renames, &accepted);
+        let printed: Vec<String> = findings.iter().map(Finding::to_string).collect();
+        assert_eq!(
+            printed,
+            vec![
+                "UART.UART_DATE: no fields, assumed to be a full width value",
+                "UART.UART_CONF0.UART_CONF0: field of a mask covering the whole register",
+                "UART.UART_CONF1: fields differ from the documentation",
+            ]
+        );
+
+        let mut input = "d\nx\nr UART_VERSION\nu\n".as_bytes();
+        let mut output = vec![];
+        let decisions = review(&findings, &mut input, &mut output).unwrap();
+        assert_eq!(
+            decisions.iter().map(|(_, d)| d.clone()).collect::<Vec<_>>(),
+            vec![
+                Decision::Delete,
+                Decision::Rename("UART_VERSION".to_string()),
+                Decision::Strategy(MergeStrategy::Union),
+            ]
+        );
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("  Expected one of [a]ccept, [s]kip, [d]elete, [r]ename NAME"));
+
+        let mut input = "s\na\n".as_bytes();
+        let skipped = review(&findings, &mut input, &mut vec![]).unwrap();
+        assert_eq!(skipped, vec![(findings[1].clone(), Decision::Accept)]);
+
+        assert_eq!(
+            patch_entries(&decisions),
+            "[[patch]]\npath = \"UART.UART_DATE\"\ndelete = true\n\n\
+             [[patch]]\npath = \"UART.UART_CONF0.UART_CONF0\"\nrename = \"UART_VERSION\"\n\n\
+             [[accept]]\nfinding = \"UART.UART_CONF0.UART_VERSION: field of a mask covering the whole register\"\n\n\
+             [[merge]]\nperipheral = \"UART\"\nregister = \"UART_CONF1\"\nstrategy = \"union\"\n\n\
+             [[accept]]\nfinding = \"UART.UART_CONF1: fields differ from the documentation\"\n\n"
+        );
+    }
+}
@@ -0,0 +1,123 @@
+//! Safety classification of registers, for registers that must not be accessed by ordinary
+//! code, e.g. the flash controller while executing from flash. Set by patches or doc files,
+//! noted in the register description and `vendorExtensions` and optionally left out of the svd.
+
+use crate::Peripheral;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use xmltree::Element;
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Safety {
+    /// Only meant for debugging or factory test
+    DebugOnly,
+    /// Documented as reserved, not to be accessed
+    Reserved,
+    /// Accessing it can crash or brick the chip
+    Unsafe,
+}
+
+impl Safety {
+    /// Sentence appended to the register description
+    pub fn note(self) -> &'static str {
+        match self {
+            Safety::DebugOnly => "Debug only, not for use by applications.",
+            Safety::Reserved => "Reserved, do not access.",
+            Safety::Unsafe => "Unsafe, accessing it can crash or brick the chip.",
+        }
+    }
+}
+
+impl fmt::Display for Safety {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Safety::DebugOnly => write!(f, "debug-only"),
+            Safety::Reserved => write!(f, "reserved"),
+            Safety::Unsafe => write!(f, "unsafe"),
+        }
+    }
+}
+
+/// Remove the classified registers from the model, returning their dotted paths
+pub fn omit_classified(peripherals: &mut BTreeMap<String, Peripheral>) -> Vec<String> {
+    let mut omitted = vec![];
+    for (name, p) in peripherals.iter_mut() {
+        p.registers.retain(|r| match r.safety {
+            Some(safety) => {
+                omitted.push(format!("{}.{} ({})", name, r.name, safety));
+                false
+            }
+            None => true,
+        });
+    }
+    omitted
+}
+
+/// `vendorExtensions` entry listing the classified registers, `None` if there are none
+pub fn safety_extension(peripherals: &BTreeMap<String, Peripheral>) -> Option<Element> {
+    let text = |name: &str, text: String| {
+        let mut element = Element::new(name);
+        element.text = Some(text);
+        element
+    };
+    let mut registers = Element::new("registerSafety");
+    for (name, p) in peripherals {
+        for r in &p.registers {
+            if let Some(safety) = r.safety {
+                let mut register = Element::new("register");
+                register
+                    .children
+                    .push(text("path", format!("{}.{}", name, r.name)));
+                register.children.push(text("safety", safety.to_string()));
+                registers.children.push(register);
+            }
+        }
+    }
+    if registers.children.is_empty() {
+        None
+    } else {
+        Some(registers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Register;
+
+    #[test]
+    fn test_omit_classified() {
+        let register = |name: &str, safety| Register {
+            name: name.to_string(),
+            safety,
+            ..Default::default()
+        };
+        let mut peripherals = BTreeMap::new();
+        peripherals.insert(
+            "SPI0".to_string(),
+            Peripheral {
+                registers: vec![
+                    register("SPI_CMD", Some(Safety::Unsafe)),
+                    register("SPI_DATE", None),
+                ],
+                ..Default::default()
+            },
+        );
+
+        let extension = safety_extension(&peripherals).unwrap();
+        assert_eq!(extension.children.len(), 1);
+        assert_eq!(
+            extension.children[0].children[1].text.as_deref(),
+            Some("unsafe")
+        );
+
+        assert_eq!(
+            omit_classified(&mut peripherals),
+            vec!["SPI0.SPI_CMD (unsafe)"]
+        );
+        assert_eq!(peripherals["SPI0"].registers.len(), 1);
+        assert!(safety_extension(&peripherals).is_none());
+    }
+}
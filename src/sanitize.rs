@@ -0,0 +1,168 @@
+//! Make names acceptable to svd2rust: only identifier characters, no leading digit, no Rust
+//! keywords and no duplicate names among siblings.
+
+use crate::Peripheral;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "box", "break", "const", "continue", "crate", "do", "dyn", "else",
+    "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in", "let", "loop", "macro",
+    "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return", "self", "static",
+    "struct", "super", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+    "virtual", "where", "while", "yield",
+];
+
+/// A name changed by the sanitization pass
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rename {
+    /// Path of the parent, empty for peripherals
+    pub scope: String,
+    pub from: String,
+    pub to: String,
+}
+
+impl fmt::Display for Rename {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.scope.is_empty() {
+            write!(f, "{} -> {}", self.from, self.to)
+        } else {
+            write!(f, "{}.{} -> {}", self.scope, self.from, self.to)
+        }
+    }
+}
+
+/// Sanitize a single name, without deduplication
+pub fn sanitize_name(name: &str) -> String {
+    let mut out: String = name
+        .trim()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if out.is_empty() || out.starts_with(|c: char| c.is_ascii_digit()) {
+        out.insert(0, '_');
+    }
+    if KEYWORDS.contains(&out.to_lowercase().as_str()) {
+        out.push('_');
+    }
+    out
+}
+
+/// Sanitize the names of siblings, appending `_1`, `_2`, .. to duplicates
+pub(crate) fn sanitize_siblings<'a>(names: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    names
+        .map(|name| {
+            let base = sanitize_name(name);
+            let mut candidate = base.clone();
+            let mut i = 1;
+            while !seen.insert(candidate.clone()) {
+                candidate = format!("{}_{}", base, i);
+                i += 1;
+            }
+            candidate
+        })
+        .collect()
+}
+
+/// Sanitize all peripheral, register and field names, returning the changes made
+pub fn sanitize(peripherals: &mut BTreeMap<String, Peripheral>) -> Vec<Rename> {
+    let mut renames = vec![];
+
+    let names = sanitize_siblings(peripherals.keys().map(String::as_str));
+    let old = std::mem::take(peripherals);
+    for ((from, mut p), to) in old.into_iter().zip(names) {
+        let names = sanitize_siblings(p.registers.iter().map(|r| r.name.as_str()));
+        for (r, name) in p.registers.iter_mut().zip(names) {
+            let scope = format!("{}.{}", to, name);
+            let field_names = sanitize_siblings(r.bit_fields.iter().map(|f| f.name.as_str()));
+            for (f, field_name) in r.bit_fields.iter_mut().zip(field_names) {
+                if f.name != field_name {
+                    renames.push(Rename {
+                        scope: scope.clone(),
+                        from: std::mem::replace(&mut f.name, field_name),
+                        to: f.name.clone(),
+                    });
+                }
+            }
+            if r.name != name {
+                renames.push(Rename {
+                    scope: to.clone(),
+                    from: std::mem::replace(&mut r.name, name),
+                    to: r.name.clone(),
+                });
+            }
+        }
+        if from != to {
+            renames.push(Rename {
+                scope: String::new(),
+                from,
+                to: to.clone(),
+            });
+        }
+        peripherals.insert(to, p);
+    }
+
+    renames
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BitField, Register};
+
+    #[test]
+    fn test_invalid_characters() {
+        assert_eq!(sanitize_name("RX/TX_CNT"), "RX_TX_CNT");
+        assert_eq!(sanitize_name("FOO BAR"), "FOO_BAR");
+    }
+
+    #[test]
+    fn test_leading_digit() {
+        assert_eq!(sanitize_name("8BIT"), "_8BIT");
+        assert_eq!(sanitize_name(""), "_");
+    }
+
+    #[test]
+    fn test_keywords() {
+        assert_eq!(sanitize_name("TYPE"), "TYPE_");
+        assert_eq!(sanitize_name("loop"), "loop_");
+        assert_eq!(sanitize_name("LOOPBACK"), "LOOPBACK");
+    }
+
+    #[test]
+    fn test_duplicate_siblings() {
+        let names = sanitize_siblings(vec!["A/B", "A_B", "A_B", "C"].into_iter());
+        assert_eq!(names, vec!["A_B", "A_B_1", "A_B_2", "C"]);
+    }
+
+    #[test]
+    fn test_renames_model() {
+        let field = |name: &str| BitField {
+            name: name.to_string(),
+            ..Default::default()
+        };
+        let register = Register {
+            name: "CONF/0".to_string(),
+            bit_fields: vec![field("EN"), field("EN"), field("TYPE")],
+            ..Default::default()
+        };
+        let mut peripherals = BTreeMap::new();
+        peripherals.insert(
+            "2WIRE".to_string(),
+            Peripheral {
+                registers: vec![register],
+                ..Default::default()
+            },
+        );
+
+        let renames = sanitize(&mut peripherals);
+
+        let r = &peripherals["_2WIRE"].registers[0];
+        assert_eq!(r.name, "CONF_0");
+        let fields: Vec<_> = r.bit_fields.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(fields, vec!["EN", "EN_1", "TYPE_"]);
+        assert_eq!(renames.len(), 4);
+        assert_eq!(renames[0].to_string(), "_2WIRE.CONF_0.EN -> EN_1");
+    }
+}
@@ -0,0 +1,126 @@
+//! Name shortening rules, e.g. `UART_RXFIFO_CNT` in the `UART` peripheral becomes
+//! `RXFIFO_CNT` which avoids stuttering in generated PAC apis.
+
+use crate::Peripheral;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Default)]
+pub struct ShortenRules {
+    /// Strip the peripheral name prefix from register names
+    pub strip_register_prefix: bool,
+    /// Strip the peripheral name prefix from field names
+    pub strip_field_prefix: bool,
+    /// Suffixes stripped from register names, e.g. `_REG`
+    pub register_suffixes: Vec<String>,
+}
+
+/// Strip `<peripheral>_` (or the peripheral name without instance number) from `name`,
+/// unless that would leave an empty name or one starting with a digit
+fn strip_prefix(peripheral: &str, name: &str) -> String {
+    let base = peripheral.trim_end_matches(|c: char| c.is_ascii_digit());
+    for prefix in &[peripheral, base] {
+        let prefix = format!("{}_", prefix);
+        if name.starts_with(&prefix) {
+            let rest = &name[prefix.len()..];
+            if !rest.is_empty() && !rest.starts_with(|c: char| c.is_ascii_digit()) {
+                return rest.to_string();
+            }
+        }
+    }
+    name.to_string()
+}
+
+/// `names` shortened by `shorten`, names whose short form collides with another one are kept
+fn shorten_unique(names: Vec<&mut String>, shorten: impl Fn(&str) -> String) {
+    let short: Vec<String> = names.iter().map(|n| shorten(n)).collect();
+    for (name, s) in names.into_iter().zip(&short) {
+        if short.iter().filter(|other| *other == s).count() == 1 {
+            *name = s.clone();
+        }
+    }
+}
+
+/// Apply `rules` to all register and field names
+pub fn shorten_names(peripherals: &mut BTreeMap<String, Peripheral>, rules: &ShortenRules) {
+    for (pname, p) in peripherals.iter_mut() {
+        let names = p.registers.iter_mut().map(|r| &mut r.name).collect();
+        shorten_unique(names, |name| {
+            let mut name = name.to_string();
+            for suffix in &rules.register_suffixes {
+                if name.len() > suffix.len() && name.ends_with(suffix.as_str()) {
+                    name.truncate(name.len() - suffix.len());
+                }
+            }
+            if rules.strip_register_prefix {
+                name = strip_prefix(pname, &name);
+            }
+            name
+        });
+        if rules.strip_field_prefix {
+            for r in p.registers.iter_mut() {
+                let names = r.bit_fields.iter_mut().map(|f| &mut f.name).collect();
+                shorten_unique(names, |name| strip_prefix(pname, name));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BitField, Register};
+
+    #[test]
+    fn test_shorten_names() {
+        let register = |name: &str, fields: &[&str]| Register {
+            name: name.to_string(),
+            bit_fields: fields
+                .iter()
+                .map(|f| BitField {
+                    name: f.to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let mut peripherals = BTreeMap::new();
+        peripherals.insert(
+            "UART1".to_string(),
+            Peripheral {
+                registers: vec![
+                    register("UART_CONF0_REG", &["UART_PARITY", "UART_1_BIT", "UART_"]),
+                    // would both become `CLKDIV`
+                    register("UART_CLKDIV_REG", &["UART_CLKDIV", "CLKDIV"]),
+                    register("CLKDIV", &[]),
+                    register("_REG", &[]),
+                ],
+                ..Default::default()
+            },
+        );
+        let rules = ShortenRules {
+            strip_register_prefix: true,
+            strip_field_prefix: true,
+            register_suffixes: vec!["_REG".to_string()],
+        };
+        shorten_names(&mut peripherals, &rules);
+
+        let names: Vec<(&str, Vec<&str>)> = peripherals["UART1"]
+            .registers
+            .iter()
+            .map(|r| {
+                let fields = r.bit_fields.iter().map(|f| f.name.as_str()).collect();
+                (r.name.as_str(), fields)
+            })
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                // a digit or nothing left keeps the prefix
+                ("CONF0", vec!["PARITY", "UART_1_BIT", "UART_"]),
+                ("UART_CLKDIV_REG", vec!["UART_CLKDIV", "CLKDIV"]),
+                ("CLKDIV", vec![]),
+                ("_REG", vec![]),
+            ]
+        );
+    }
+}
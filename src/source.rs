@@ -0,0 +1,472 @@
+//! Inputs contributing to the model. Every input, from the esp-idf headers to the patches of the
+//! config, is a `Source` which is loaded first and then contributes to the model in a fixed
+//! order of precedence: the headers, the registers of other SDK headers missing from them, a
+//! previously generated svd, the doc files and register maps and finally the patches.
+
+use crate::append::read_svd_model;
+use crate::arduino::{merge_peri, parse_peri, PeriRegister};
+use crate::doc::{merge_doc, parse_doc, DocFile};
+use crate::merge::{merge_union, MergeRule};
+use crate::nonos::{parse_nonos, SOURCE as NONOS_SOURCE};
+use crate::patch::{apply_patches, Patch};
+use crate::regmap::parse_regmap;
+use crate::validate::Issue;
+use crate::{parse_idf_with_report, ParseOptions, ParseReport, Peripheral, PeripheralFilter};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+pub type Model = BTreeMap<String, Peripheral>;
+
+/// Kinds of sources, in the order they contribute to the model
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SourceKind {
+    /// The esp-idf `soc.h` and register headers
+    Headers,
+    /// Headers of the ESP8266 NONOS SDK, with the curated fields missing from them
+    NonosSdk,
+    /// `esp8266_peri.h` of the ESP8266 Arduino core
+    ArduinoHeader,
+    /// A previously generated svd, whose registers win over the ones of the run
+    Svd,
+    /// Doc JSON files, e.g. extracted from the technical reference manual
+    Doc,
+    /// CSV or TSV register maps
+    RegisterMap,
+    /// The patches of the config
+    Patches,
+}
+
+impl fmt::Display for SourceKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SourceKind::Headers => write!(f, "headers"),
+            SourceKind::NonosSdk => write!(f, "NONOS SDK"),
+            SourceKind::ArduinoHeader => write!(f, "Arduino header"),
+            SourceKind::Svd => write!(f, "svd"),
+            SourceKind::Doc => write!(f, "doc"),
+            SourceKind::RegisterMap => write!(f, "register map"),
+            SourceKind::Patches => write!(f, "patches"),
+        }
+    }
+}
+
+impl SourceKind {
+    /// Whether the peripherals of the source are limited to the peripheral filter. A previously
+    /// generated svd keeps all its peripherals, and docs and patches are written for the run
+    pub fn filtered(self) -> bool {
+        self <= SourceKind::ArduinoHeader
+    }
+}
+
+/// What a source changed in the model
+#[derive(Debug, Default)]
+pub struct Contribution {
+    /// Registers added to the model
+    pub added: usize,
+    /// Differences with the model resolved by the merge strategies
+    pub conflicts: Vec<String>,
+}
+
+pub trait Source {
+    /// Name of the source in messages, usually its path
+    fn name(&self) -> String;
+
+    fn kind(&self) -> SourceKind;
+
+    /// Read and parse the source, before anything is contributed to the model
+    fn load(&mut self) -> Result<(), String>;
+
+    /// Contribute the loaded source to the model
+    fn contribute(&mut self, model: &mut Model) -> Result<Contribution, String>;
+}
+
+/// Load all `sources`, then let them contribute to `model` in the order of their kind. The
+/// peripherals outside of `filter` are removed after the contribution of every filtered source.
+/// Returns the conflicts resolved by the merge strategies
+pub fn apply_sources(
+    model: &mut Model,
+    sources: &mut [&mut dyn Source],
+    filter: &PeripheralFilter,
+) -> Result<Vec<String>, String> {
+    // stable, sources of the same kind keep their order
+    sources.sort_by_key(|s| s.kind());
+    for source in sources.iter_mut() {
+        source
+            .load()
+            .map_err(|e| format!("Failed to load {} {}: {}", source.kind(), source.name(), e))?;
+    }
+    let mut conflicts = vec![];
+    for source in sources.iter_mut() {
+        let contribution = source
+            .contribute(model)
+            .map_err(|e| format!("Failed to apply {} {}: {}", source.kind(), source.name(), e))?;
+        if contribution.added > 0 && source.kind() != SourceKind::Headers {
+            println!(
+                "Added {} registers from {} {}",
+                contribution.added,
+                source.kind(),
+                source.name()
+            );
+        }
+        conflicts.extend(contribution.conflicts);
+        if source.kind().filtered() {
+            model.retain(|name, _| filter.includes(name));
+        }
+    }
+    Ok(conflicts)
+}
+
+/// The esp-idf headers in `path`, parsed with `options`
+pub struct HeaderSource {
+    path: String,
+    options: ParseOptions,
+    parsed: Option<Model>,
+    /// What went wrong while parsing, once loaded
+    pub report: ParseReport,
+}
+
+impl HeaderSource {
+    pub fn new(path: &str, options: ParseOptions) -> Self {
+        HeaderSource {
+            path: path.to_string(),
+            options,
+            parsed: None,
+            report: ParseReport::default(),
+        }
+    }
+}
+
+impl Source for HeaderSource {
+    fn name(&self) -> String {
+        self.path.clone()
+    }
+
+    fn kind(&self) -> SourceKind {
+        SourceKind::Headers
+    }
+
+    fn load(&mut self) -> Result<(), String> {
+        let (parsed, report) = parse_idf_with_report(&self.path, &self.options);
+        self.parsed = Some(parsed);
+        self.report = report;
+        Ok(())
+    }
+
+    fn contribute(&mut self, model: &mut Model) -> Result<Contribution, String> {
+        let parsed = self.parsed.take().unwrap_or_default();
+        let added = parsed.values().map(|p| p.registers.len()).sum();
+        if model.is_empty() {
+            *model = parsed;
+            Ok(Contribution {
+                added,
+                ..Default::default()
+            })
+        } else {
+            Ok(Contribution {
+                added: merge_union(model, parsed, &self.path),
+                ..Default::default()
+            })
+        }
+    }
+}
+
+/// Headers of the ESP8266 NONOS SDK below a directory
+pub struct NonosSource {
+    dir: PathBuf,
+    parsed: Option<Model>,
+    /// Zero width masks left out of the model, once loaded
+    pub zero_width_fields: Vec<Issue>,
+}
+
+impl NonosSource {
+    pub fn new(dir: &Path) -> Self {
+        NonosSource {
+            dir: dir.to_path_buf(),
+            parsed: None,
+            zero_width_fields: vec![],
+        }
+    }
+}
+
+impl Source for NonosSource {
+    fn name(&self) -> String {
+        self.dir.display().to_string()
+    }
+
+    fn kind(&self) -> SourceKind {
+        SourceKind::NonosSdk
+    }
+
+    fn load(&mut self) -> Result<(), String> {
+        let (parsed, zero_width_fields) = parse_nonos(&self.dir);
+        self.parsed = Some(parsed);
+        self.zero_width_fields = zero_width_fields;
+        Ok(())
+    }
+
+    fn contribute(&mut self, model: &mut Model) -> Result<Contribution, String> {
+        let parsed = self.parsed.take().unwrap_or_default();
+        Ok(Contribution {
+            added: merge_union(model, parsed, NONOS_SOURCE),
+            ..Default::default()
+        })
+    }
+}
+
+/// `esp8266_peri.h` of the ESP8266 Arduino core
+pub struct ArduinoSource {
+    path: PathBuf,
+    registers: Vec<PeriRegister>,
+}
+
+impl ArduinoSource {
+    pub fn new(path: &Path) -> Self {
+        ArduinoSource {
+            path: path.to_path_buf(),
+            registers: vec![],
+        }
+    }
+}
+
+impl Source for ArduinoSource {
+    fn name(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn kind(&self) -> SourceKind {
+        SourceKind::ArduinoHeader
+    }
+
+    fn load(&mut self) -> Result<(), String> {
+        let text = std::fs::read_to_string(&self.path)
+            .map_err(|e| format!("Failed to read {}: {}", self.path.display(), e))?;
+        self.registers = parse_peri(&text);
+        Ok(())
+    }
+
+    fn contribute(&mut self, model: &mut Model) -> Result<Contribution, String> {
+        let registers = std::mem::take(&mut self.registers);
+        Ok(Contribution {
+            added: merge_peri(model, registers),
+            ..Default::default()
+        })
+    }
+}
+
+/// A previously generated svd the run is appended to, the registers of the run are only
+/// added where it has none
+pub struct SvdSource {
+    path: PathBuf,
+    existing: Option<Model>,
+}
+
+impl SvdSource {
+    pub fn new(path: &Path) -> Self {
+        SvdSource {
+            path: path.to_path_buf(),
+            existing: None,
+        }
+    }
+}
+
+impl Source for SvdSource {
+    fn name(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn kind(&self) -> SourceKind {
+        SourceKind::Svd
+    }
+
+    fn load(&mut self) -> Result<(), String> {
+        self.existing = Some(read_svd_model(&self.path)?);
+        Ok(())
+    }
+
+    fn contribute(&mut self, model: &mut Model) -> Result<Contribution, String> {
+        let mut existing = self.existing.take().unwrap_or_default();
+        let added = merge_union(&mut existing, std::mem::take(model), "this run");
+        println!("Appended {} registers to {}", added, self.path.display());
+        *model = existing;
+        Ok(Contribution::default())
+    }
+}
+
+/// A doc JSON file or a register map, merged with the `rules` of the config
+pub struct DocSource {
+    path: PathBuf,
+    kind: SourceKind,
+    rules: Vec<MergeRule>,
+    docs: Vec<DocFile>,
+}
+
+impl DocSource {
+    pub fn doc(path: &Path, rules: &[MergeRule]) -> Self {
+        DocSource {
+            path: path.to_path_buf(),
+            kind: SourceKind::Doc,
+            rules: rules.to_vec(),
+            docs: vec![],
+        }
+    }
+
+    pub fn register_map(path: &Path, rules: &[MergeRule]) -> Self {
+        DocSource {
+            kind: SourceKind::RegisterMap,
+            ..DocSource::doc(path, rules)
+        }
+    }
+}
+
+impl Source for DocSource {
+    fn name(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn kind(&self) -> SourceKind {
+        self.kind
+    }
+
+    fn load(&mut self) -> Result<(), String> {
+        self.docs = match self.kind {
+            SourceKind::RegisterMap => parse_regmap(&self.path)?,
+            _ => vec![parse_doc(&self.path)?],
+        };
+        Ok(())
+    }
+
+    fn contribute(&mut self, model: &mut Model) -> Result<Contribution, String> {
+        let file = self.name();
+        let mut contribution = Contribution::default();
+        for doc in std::mem::take(&mut self.docs) {
+            let before: usize = model.values().map(|p| p.registers.len()).sum();
+            contribution
+                .conflicts
+                .extend(merge_doc(model, doc, &file, &self.rules)?);
+            let after: usize = model.values().map(|p| p.registers.len()).sum();
+            contribution.added += after.saturating_sub(before);
+        }
+        Ok(contribution)
+    }
+}
+
+/// The renames, deletions and classifications of the config
+pub struct PatchSource {
+    patches: Vec<Patch>,
+}
+
+impl PatchSource {
+    pub fn new(patches: &[Patch]) -> Self {
+        PatchSource {
+            patches: patches.to_vec(),
+        }
+    }
+}
+
+impl Source for PatchSource {
+    fn name(&self) -> String {
+        format!("{} patches", self.patches.len())
+    }
+
+    fn kind(&self) -> SourceKind {
+        SourceKind::Patches
+    }
+
+    fn load(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn contribute(&mut self, model: &mut Model) -> Result<Contribution, String> {
+        apply_patches(model, &self.patches).map_err(|conflict| conflict.to_string())?;
+        Ok(Contribution::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Adds a peripheral named after it, or fails to load
+    struct Fixed {
+        name: &'static str,
+        kind: SourceKind,
+        loads: bool,
+    }
+
+    impl Source for Fixed {
+        fn name(&self) -> String {
+            self.name.to_string()
+        }
+
+        fn kind(&self) -> SourceKind {
+            self.kind
+        }
+
+        fn load(&mut self) -> Result<(), String> {
+            if self.loads {
+                Ok(())
+            } else {
+                Err("unreadable".to_string())
+            }
+        }
+
+        fn contribute(&mut self, model: &mut Model) -> Result<Contribution, String> {
+            let order = model.len().to_string();
+            model.insert(
+                self.name.to_string(),
+                Peripheral {
+                    description: order,
+                    ..Default::default()
+                },
+            );
+            Ok(Contribution::default())
+        }
+    }
+
+    #[test]
+    fn test_apply_sources() {
+        let fixed = |name, kind| Fixed {
+            name,
+            kind,
+            loads: true,
+        };
+        let mut doc = fixed("DOC", SourceKind::Doc);
+        let mut headers = fixed("HEADERS", SourceKind::Headers);
+        let mut regmap = fixed("REGMAP", SourceKind::RegisterMap);
+        let mut nonos = fixed("EXCLUDED", SourceKind::NonosSdk);
+        let filter = PeripheralFilter {
+            only: vec![],
+            exclude: vec!["EXCLUDED".to_string()],
+        };
+
+        let mut model = Model::new();
+        apply_sources(
+            &mut model,
+            &mut [&mut doc, &mut regmap, &mut headers, &mut nonos],
+            &filter,
+        )
+        .unwrap();
+        let order: Vec<(&str, &str)> = model
+            .iter()
+            .map(|(name, p)| (name.as_str(), p.description.as_str()))
+            .collect();
+        assert_eq!(order, vec![("DOC", "1"), ("HEADERS", "0"), ("REGMAP", "2")]);
+
+        let mut broken = Fixed {
+            name: "broken.json",
+            kind: SourceKind::Doc,
+            loads: false,
+        };
+        let mut headers = fixed("OTHER", SourceKind::Headers);
+        let mut model = Model::new();
+        let error = apply_sources(
+            &mut model,
+            &mut [&mut headers, &mut broken],
+            &PeripheralFilter::default(),
+        )
+        .unwrap_err();
+        assert_eq!(error, "Failed to load doc broken.json: unreadable");
+        assert!(model.is_empty());
+    }
+}
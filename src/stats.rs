@@ -0,0 +1,109 @@
+//! Counts describing the completeness of the generated model, printed at the end of a run so
+//! the quality of the output can be tracked across SDK versions.
+
+use crate::{Origin, Peripheral};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Summary {
+    pub peripherals: usize,
+    pub registers: usize,
+    pub fields: usize,
+    /// Registers without stated fields, emitted as a single full width value
+    pub full_width_registers: usize,
+    pub fields_without_description: usize,
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{:>6} peripherals", self.peripherals)?;
+        writeln!(f, "{:>6} registers", self.registers)?;
+        writeln!(f, "{:>6} fields", self.fields)?;
+        writeln!(
+            f,
+            "{:>6} registers assumed to be a full width value",
+            self.full_width_registers
+        )?;
+        writeln!(
+            f,
+            "{:>6} fields without description",
+            self.fields_without_description
+        )
+    }
+}
+
+/// Count the elements of the model
+pub fn summary(peripherals: &BTreeMap<String, Peripheral>) -> Summary {
+    let mut summary = Summary {
+        peripherals: peripherals.len(),
+        ..Default::default()
+    };
+    for r in peripherals.values().flat_map(|p| &p.registers) {
+        summary.registers += 1;
+        summary.fields += r.bit_fields.len();
+        if r.bit_fields.iter().all(|f| f.origin == Origin::Assumed) {
+            summary.full_width_registers += 1;
+        }
+        summary.fields_without_description += r
+            .bit_fields
+            .iter()
+            .filter(|f| f.description.trim().is_empty())
+            .count();
+    }
+    summary
+}
+
+/// Render the summary as pretty printed JSON
+pub fn summary_json(summary: &Summary) -> String {
+    serde_json::to_string_pretty(summary).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BitField, Register};
+
+    #[test]
+    fn test_summary() {
+        let field = |origin, description: &str| BitField {
+            origin,
+            description: description.to_string(),
+            ..Default::default()
+        };
+        let mut peripherals = BTreeMap::new();
+        peripherals.insert(
+            "UART".to_string(),
+            Peripheral {
+                registers: vec![
+                    Register {
+                        bit_fields: vec![
+                            field(Origin::HeaderComment, "Parity"),
+                            field(Origin::HeaderMask, " "),
+                        ],
+                        ..Default::default()
+                    },
+                    Register::default(),
+                    Register {
+                        bit_fields: vec![field(Origin::Assumed, "")],
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            },
+        );
+        peripherals.insert("EMPTY".to_string(), Peripheral::default());
+
+        assert_eq!(
+            summary(&peripherals),
+            Summary {
+                peripherals: 2,
+                registers: 3,
+                fields: 3,
+                full_width_registers: 2,
+                fields_without_description: 2,
+            }
+        );
+    }
+}
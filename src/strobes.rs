@@ -0,0 +1,147 @@
+//! Registers with write-1-to-set and write-1-to-clear companions, such as `GPIO_OUT` with
+//! `GPIO_OUT_W1TS` and `GPIO_OUT_W1TC`, linked so HAL generators can offer atomic bit set and
+//! clear operations.
+
+use crate::Peripheral;
+use std::collections::BTreeMap;
+use xmltree::Element;
+
+/// Suffixes of the set and clear registers, in the spellings found in the SDKs
+const SUFFIXES: &[(&str, &str)] = &[("_W1TS", "_W1TC"), ("_SET", "_CLR")];
+
+/// Set and clear registers acting on the bits of a value register
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Strobes {
+    pub set: Option<String>,
+    pub clear: Option<String>,
+}
+
+/// Link every register to the set and clear registers named after it, returning the number
+/// of registers that got linked
+pub fn link_strobes(peripherals: &mut BTreeMap<String, Peripheral>) -> usize {
+    let mut linked = 0;
+    for p in peripherals.values_mut() {
+        let names: Vec<String> = p.registers.iter().map(|r| r.name.clone()).collect();
+        for r in p.registers.iter_mut() {
+            let find = |suffix: &str| {
+                let name = format!("{}{}", r.name, suffix);
+                names.iter().find(|n| **n == name).cloned()
+            };
+            let strobes = SUFFIXES
+                .iter()
+                .map(|(set, clear)| Strobes {
+                    set: find(set),
+                    clear: find(clear),
+                })
+                .find(|s| s.set.is_some() || s.clear.is_some());
+            if strobes.is_some() {
+                linked += 1;
+            }
+            r.strobes = strobes;
+        }
+    }
+    linked
+}
+
+/// `vendorExtensions` entry listing the linked registers, `None` if there are none. Links to
+/// registers missing from `peripherals`, e.g. left out of a view, are skipped
+pub fn strobes_extension(peripherals: &BTreeMap<String, Peripheral>) -> Option<Element> {
+    let text = |name: &str, text: String| {
+        let mut element = Element::new(name);
+        element.text = Some(text);
+        element
+    };
+    let mut registers = Element::new("registerStrobes");
+    for (name, p) in peripherals {
+        let exists = |n: &Option<String>| {
+            n.as_ref()
+                .filter(|n| p.registers.iter().any(|r| r.name == **n))
+                .cloned()
+        };
+        for r in &p.registers {
+            let strobes = match &r.strobes {
+                Some(strobes) => strobes,
+                None => continue,
+            };
+            let (set, clear) = (exists(&strobes.set), exists(&strobes.clear));
+            if set.is_none() && clear.is_none() {
+                continue;
+            }
+            let mut register = Element::new("register");
+            register
+                .children
+                .push(text("path", format!("{}.{}", name, r.name)));
+            register.children.extend(set.map(|set| text("set", set)));
+            register
+                .children
+                .extend(clear.map(|clear| text("clear", clear)));
+            registers.children.push(register);
+        }
+    }
+    if registers.children.is_empty() {
+        None
+    } else {
+        Some(registers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Register;
+
+    #[test]
+    fn test_link_strobes() {
+        let register = |name: &str| Register {
+            name: name.to_string(),
+            ..Default::default()
+        };
+        let mut peripherals = BTreeMap::new();
+        peripherals.insert(
+            "GPIO".to_string(),
+            Peripheral {
+                registers: vec![
+                    register("GPIO_OUT"),
+                    register("GPIO_OUT_W1TS"),
+                    register("GPIO_OUT_W1TC"),
+                    register("GPIO_STATUS"),
+                    register("GPIO_STATUS_W1TC"),
+                    register("GPIO_ENABLE_W1TS"),
+                    register("INT_ENA"),
+                    register("INT_ENA_SET"),
+                    register("INT_ENA_CLR"),
+                ],
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(link_strobes(&mut peripherals), 3);
+        let gpio = &peripherals["GPIO"];
+        assert_eq!(
+            gpio.registers[0].strobes,
+            Some(Strobes {
+                set: Some("GPIO_OUT_W1TS".to_string()),
+                clear: Some("GPIO_OUT_W1TC".to_string()),
+            })
+        );
+        assert_eq!(
+            gpio.registers[3].strobes,
+            Some(Strobes {
+                set: None,
+                clear: Some("GPIO_STATUS_W1TC".to_string()),
+            })
+        );
+        assert_eq!(
+            gpio.registers[6].strobes.as_ref().unwrap().set.as_deref(),
+            Some("INT_ENA_SET")
+        );
+
+        peripherals
+            .get_mut("GPIO")
+            .unwrap()
+            .registers
+            .retain(|r| r.name != "GPIO_STATUS_W1TC");
+        let extension = strobes_extension(&peripherals).unwrap();
+        assert_eq!(extension.children.len(), 2);
+    }
+}
@@ -0,0 +1,448 @@
+//! Parser for the `*_struct.h` headers shipped next to the `*_reg.h` files, which describe
+//! the same registers as `typedef volatile struct` layouts. The layouts are only used to
+//! cross-check the offsets and field widths found in the register headers.
+
+use crate::Peripheral;
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+#[derive(Debug, Clone)]
+pub struct StructField {
+    pub name: String,
+    pub lsb: u8,
+    pub width: u8,
+}
+
+#[derive(Debug, Clone)]
+pub struct StructRegister {
+    /// Member name, `name[i]` for the elements of arrays
+    pub name: String,
+    pub offset: u32,
+    pub fields: Vec<StructField>,
+}
+
+#[derive(Debug, Clone)]
+pub struct StructLayout {
+    /// Type name, e.g. `uart_dev_t`
+    pub name: String,
+    pub registers: Vec<StructRegister>,
+    /// Whether a member couldn't be parsed, offsets after it are unknown and were dropped
+    pub incomplete: bool,
+}
+
+impl StructLayout {
+    /// Peripheral name derived from the type name, `uart_dev_t` becomes `UART`
+    pub fn peripheral(&self) -> String {
+        self.name
+            .trim_end_matches("_t")
+            .trim_end_matches("_dev")
+            .to_uppercase()
+    }
+}
+
+enum Level {
+    Top,
+    Union,
+    Bits,
+    /// Skipping an unsupported member until the brace depth drops back to the top level
+    Skip(usize),
+}
+
+fn array_len(len: &str) -> Option<u32> {
+    crate::expr::parse_number(len).ok().map(|n| n as u32)
+}
+
+/// Parse all `typedef struct` register layouts in `text`
+pub fn parse_struct_header(text: &str) -> Vec<StructLayout> {
+    let re_comment = Regex::new(r"/\*.*?\*/|//.*$").unwrap();
+    let re_start = Regex::new(r"^typedef\s+(?:volatile\s+)?struct\s*(?:\w+\s*)?\{$").unwrap();
+    let re_union = Regex::new(r"^union\s*\{$").unwrap();
+    let re_struct = Regex::new(r"^struct\s*\{$").unwrap();
+    let re_bits = Regex::new(r"^(?:volatile\s+)?uint32_t\s+(\w+)\s*:\s*(\d+)\s*;$").unwrap();
+    let re_word =
+        Regex::new(r"^(?:volatile\s+)?uint32_t\s+(\w+)\s*(?:\[\s*(\w+)\s*\])?\s*;$").unwrap();
+    let re_close = Regex::new(r"^\}\s*(\w+)?\s*(?:\[\s*(\w+)\s*\])?\s*;$").unwrap();
+
+    let mut layouts = vec![];
+    let mut layout: Option<StructLayout> = None;
+    let mut level = Level::Top;
+    let mut offset = 0;
+    let mut fields = vec![];
+    let mut bit = 0u8;
+
+    for line in text.lines() {
+        let line = re_comment.replace_all(line, "");
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let current = match layout.as_mut() {
+            Some(current) => current,
+            None => {
+                if re_start.is_match(line) {
+                    layout = Some(StructLayout {
+                        name: String::new(),
+                        registers: vec![],
+                        incomplete: false,
+                    });
+                    level = Level::Top;
+                    offset = 0;
+                }
+                continue;
+            }
+        };
+
+        level = match level {
+            Level::Skip(depth) => {
+                let depth =
+                    depth + line.matches('{').count() - line.matches('}').count().min(depth);
+                if depth == 0 {
+                    Level::Top
+                } else {
+                    Level::Skip(depth)
+                }
+            }
+            Level::Top => {
+                if re_union.is_match(line) {
+                    fields = vec![];
+                    Level::Union
+                } else if let Some(c) = re_word.captures(line) {
+                    let len = c.get(2).map_or(Some(1), |len| array_len(len.as_str()));
+                    match len {
+                        Some(len) if !current.incomplete => {
+                            for i in 0..len {
+                                let name = match c.get(2) {
+                                    Some(_) => format!("{}[{}]", &c[1], i),
+                                    None => c[1].to_string(),
+                                };
+                                current.registers.push(StructRegister {
+                                    name,
+                                    offset: offset + i * 4,
+                                    fields: vec![],
+                                });
+                            }
+                            offset += len * 4;
+                        }
+                        _ => current.incomplete = true,
+                    }
+                    Level::Top
+                } else if let Some(c) = re_close.captures(line) {
+                    current.name = c.get(1).map_or("", |n| n.as_str()).to_string();
+                    layouts.push(layout.take().unwrap());
+                    Level::Top
+                } else {
+                    current.incomplete = true;
+                    let depth = line.matches('{').count();
+                    if depth > 0 {
+                        Level::Skip(depth)
+                    } else {
+                        Level::Top
+                    }
+                }
+            }
+            Level::Union => {
+                if re_struct.is_match(line) {
+                    bit = 0;
+                    Level::Bits
+                } else if re_word.is_match(line) {
+                    // the `val` member covering the whole register
+                    Level::Union
+                } else if let Some(c) = re_close.captures(line) {
+                    let len = c.get(2).map_or(Some(1), |len| array_len(len.as_str()));
+                    match (c.get(1), len) {
+                        (Some(name), Some(len)) if !current.incomplete => {
+                            for i in 0..len {
+                                let name = match c.get(2) {
+                                    Some(_) => format!("{}[{}]", name.as_str(), i),
+                                    None => name.as_str().to_string(),
+                                };
+                                current.registers.push(StructRegister {
+                                    name,
+                                    offset: offset + i * 4,
+                                    fields: fields.clone(),
+                                });
+                            }
+                            offset += len * 4;
+                        }
+                        _ => current.incomplete = true,
+                    }
+                    Level::Top
+                } else {
+                    current.incomplete = true;
+                    Level::Skip(1 + line.matches('{').count())
+                }
+            }
+            Level::Bits => {
+                if let Some(c) = re_bits.captures(line) {
+                    let width: u8 = c[2].parse().unwrap_or(0);
+                    if !c[1].starts_with("reserved") {
+                        fields.push(StructField {
+                            name: c[1].to_string(),
+                            lsb: bit,
+                            width,
+                        });
+                    }
+                    bit = bit.saturating_add(width);
+                    Level::Bits
+                } else if re_close.is_match(line) {
+                    Level::Union
+                } else {
+                    current.incomplete = true;
+                    Level::Skip(2 + line.matches('{').count())
+                }
+            }
+        };
+    }
+
+    layouts
+}
+
+/// Disagreement between a struct layout and the parsed register headers
+#[derive(Debug, Clone)]
+pub enum Mismatch {
+    /// A register at a different offset than its struct member
+    Offset {
+        peripheral: String,
+        register: String,
+        header: u32,
+        structure: u32,
+    },
+    /// A field starting at the same bit as a struct field, but with a different width
+    FieldWidth {
+        peripheral: String,
+        register: String,
+        field: String,
+        header: u8,
+        structure: u8,
+    },
+    /// A struct member without a register in the headers
+    MissingRegister {
+        peripheral: String,
+        member: String,
+        offset: u32,
+    },
+    /// A layout with members that couldn't be parsed, only the part before them is checked
+    Incomplete { layout: String },
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Mismatch::Offset {
+                peripheral,
+                register,
+                header,
+                structure,
+            } => write!(
+                f,
+                "{}.{}: offset {:#x} in the register header but {:#x} in the struct",
+                peripheral, register, header, structure
+            ),
+            Mismatch::FieldWidth {
+                peripheral,
+                register,
+                field,
+                header,
+                structure,
+            } => write!(
+                f,
+                "{}.{}.{}: {} bits wide in the register header but {} in the struct",
+                peripheral, register, field, header, structure
+            ),
+            Mismatch::MissingRegister {
+                peripheral,
+                member,
+                offset,
+            } => write!(
+                f,
+                "{}: struct member {} at {:#x} has no register",
+                peripheral, member, offset
+            ),
+            Mismatch::Incomplete { layout } => {
+                write!(f, "{}: struct layout only partially parsed", layout)
+            }
+        }
+    }
+}
+
+/// Compare the registers of `peripherals` with the layouts of the `*_struct.h` files in `path`
+pub fn cross_check(path: &Path, peripherals: &BTreeMap<String, Peripheral>) -> Vec<Mismatch> {
+    let mut files: Vec<_> = match std::fs::read_dir(path) {
+        Ok(dir) => dir
+            .filter_map(Result::ok)
+            .map(|f| f.path())
+            .filter(|f| f.to_string_lossy().ends_with("_struct.h"))
+            .collect(),
+        Err(e) => {
+            println!("Failed to read {}: {}", path.display(), e);
+            return vec![];
+        }
+    };
+    files.sort();
+
+    let mut mismatches = vec![];
+    for file in files {
+        let text = match std::fs::read_to_string(&file) {
+            Ok(text) => text,
+            Err(e) => {
+                println!("Failed to read {}: {}", file.display(), e);
+                continue;
+            }
+        };
+        for layout in parse_struct_header(&text) {
+            let pname = layout.peripheral();
+            let p = match peripherals.get(&pname) {
+                Some(p) => p,
+                None => continue,
+            };
+            if layout.incomplete {
+                mismatches.push(Mismatch::Incomplete {
+                    layout: layout.name.clone(),
+                });
+            }
+            check_layout(&pname, p, &layout, &mut mismatches);
+        }
+    }
+    mismatches
+}
+
+fn check_layout(
+    pname: &str,
+    p: &Peripheral,
+    layout: &StructLayout,
+    mismatches: &mut Vec<Mismatch>,
+) {
+    for member in &layout.registers {
+        if member.name.starts_with("reserved") || member.name.contains('[') {
+            continue;
+        }
+        let upper = member.name.to_uppercase();
+        let prefixed = format!("{}_{}", pname, upper);
+        let r = match p
+            .registers
+            .iter()
+            .find(|r| r.name == prefixed || r.name == upper)
+        {
+            Some(r) => r,
+            None => {
+                mismatches.push(Mismatch::MissingRegister {
+                    peripheral: pname.to_string(),
+                    member: member.name.clone(),
+                    offset: member.offset,
+                });
+                continue;
+            }
+        };
+
+        if r.address != member.offset {
+            mismatches.push(Mismatch::Offset {
+                peripheral: pname.to_string(),
+                register: r.name.clone(),
+                header: r.address,
+                structure: member.offset,
+            });
+        }
+
+        for bf in &r.bit_fields {
+            let width = bf.bits.msb() - bf.bits.lsb() + 1;
+            let field = member.fields.iter().find(|f| f.lsb == bf.bits.lsb());
+            if let Some(field) = field {
+                if field.width != width {
+                    mismatches.push(Mismatch::FieldWidth {
+                        peripheral: pname.to_string(),
+                        register: r.name.clone(),
+                        field: bf.name.clone(),
+                        header: width,
+                        structure: field.width,
+                    });
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BitField, Bits, Register};
+
+    const UART_STRUCT_H: &str = "typedef volatile struct {
+    uint32_t fifo;                                 /*UART FIFO*/
+    union {
+        struct {
+            uint32_t parity:        1;
+            uint32_t bit_num:       2;
+            uint32_t reserved3:    29;
+        };
+        uint32_t val;
+    } conf0;
+    uint32_t reserved_8[2];
+    uint32_t date;
+} uart_dev_t;
+";
+
+    #[test]
+    fn test_cross_check() {
+        let layouts = parse_struct_header(UART_STRUCT_H);
+        assert_eq!(layouts.len(), 1);
+        let layout = &layouts[0];
+        assert_eq!(layout.peripheral(), "UART");
+        assert!(!layout.incomplete);
+        let members: Vec<(&str, u32)> = layout
+            .registers
+            .iter()
+            .map(|r| (r.name.as_str(), r.offset))
+            .collect();
+        assert_eq!(
+            members,
+            vec![
+                ("fifo", 0x0),
+                ("conf0", 0x4),
+                ("reserved_8[0]", 0x8),
+                ("reserved_8[1]", 0xc),
+                ("date", 0x10)
+            ]
+        );
+
+        let register = |name: &str, address, bit_fields| Register {
+            name: name.to_string(),
+            address,
+            bit_fields,
+            ..Default::default()
+        };
+        let field = |name: &str, bits| BitField {
+            name: name.to_string(),
+            bits,
+            ..Default::default()
+        };
+        let uart = Peripheral {
+            registers: vec![
+                register("UART_FIFO", 0x0, vec![]),
+                register(
+                    "UART_CONF0",
+                    0x4,
+                    vec![
+                        field("UART_PARITY", Bits::Single(0)),
+                        field("UART_BIT_NUM", Bits::Range(1..=3)),
+                    ],
+                ),
+                // the header has the date register a word further
+                register("UART_DATE", 0x14, vec![]),
+            ],
+            ..Default::default()
+        };
+        let mut mismatches = vec![];
+        check_layout("UART", &uart, layout, &mut mismatches);
+        let mismatches: Vec<String> = mismatches.iter().map(Mismatch::to_string).collect();
+        assert_eq!(
+            mismatches,
+            vec![
+                "UART.UART_CONF0.UART_BIT_NUM: 3 bits wide in the register header but 2 in the struct",
+                "UART.UART_DATE: offset 0x14 in the register header but 0x10 in the struct",
+            ]
+        );
+    }
+}
@@ -0,0 +1,94 @@
+//! Machine readable description of the peripheral behaviour (register list, access semantics
+//! and reset values), meant as a starting point for QEMU/Renode peripheral models.
+
+use crate::{svd::DEFAULT_SIZE, Peripheral, Register, Type};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Serialize)]
+pub struct PeripheralStub {
+    pub name: String,
+    pub base_address: u32,
+    pub size: u32,
+    pub registers: Vec<RegisterStub>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RegisterStub {
+    pub name: String,
+    pub offset: u32,
+    pub size: u32,
+    pub access: &'static str,
+    pub reset_value: u64,
+    pub fields: Vec<FieldStub>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FieldStub {
+    pub name: String,
+    pub lsb: u8,
+    pub width: u8,
+    pub access: &'static str,
+    pub reset_value: u32,
+}
+
+fn access(t: Type) -> &'static str {
+    match t {
+        Type::ReadOnly => "read-only",
+        Type::ReadWrite => "read-write",
+        Type::WriteOnly => "write-only",
+    }
+}
+
+/// Access of a register as a whole, only read or write only if all of its fields are
+pub fn register_access(r: &Register) -> Type {
+    match r.bit_fields.first() {
+        Some(first) if r.bit_fields.iter().all(|f| f.type_ == first.type_) => first.type_,
+        _ => Type::ReadWrite,
+    }
+}
+
+/// Build the stub description of all peripherals
+pub fn peripheral_stubs(peripherals: &BTreeMap<String, Peripheral>) -> Vec<PeripheralStub> {
+    peripherals
+        .iter()
+        .map(|(name, p)| PeripheralStub {
+            name: name.clone(),
+            base_address: p.address,
+            size: p.address_block_size(),
+            registers: p
+                .registers
+                .iter()
+                .map(|r| {
+                    let access = register_access(r);
+                    RegisterStub {
+                        name: r.name.clone(),
+                        offset: r.address,
+                        size: match r.width {
+                            0 => DEFAULT_SIZE,
+                            width => u32::from(width),
+                        },
+                        access: self::access(access),
+                        reset_value: r.reset_value,
+                        fields: r
+                            .bit_fields
+                            .iter()
+                            .map(|f| FieldStub {
+                                name: f.name.clone(),
+                                lsb: f.bits.lsb(),
+                                width: f.bits.msb() - f.bits.lsb() + 1,
+                                access: self::access(f.type_),
+                                reset_value: f.reset_value,
+                            })
+                            .collect(),
+                    }
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// Render the stub description as pretty printed JSON
+pub fn stub_json(peripherals: &BTreeMap<String, Peripheral>) -> String {
+    serde_json::to_string_pretty(&peripheral_stubs(peripherals)).unwrap()
+}
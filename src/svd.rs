@@ -0,0 +1,520 @@
+use crate::config::{CpuConfig, DeviceConfig, Endianness};
+use crate::groups::insert_header_struct_names;
+use crate::{Bits, Peripheral, Type};
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::str::FromStr;
+use svd_parser::{
+    addressblock::AddressBlock, bitrange::BitRangeType, cpu::CpuBuilder, device::DeviceBuilder,
+    encode::Encode, endian::Endian, enumeratedvalue::EnumeratedValueBuilder,
+    enumeratedvalues::EnumeratedValuesBuilder, fieldinfo::FieldInfoBuilder,
+    peripheral::PeripheralBuilder, registerinfo::RegisterInfoBuilder, Access, BitRange, Field,
+    Interrupt as SvdInterrupt, Peripheral as SvdPeripheral, Register as SvdRegister,
+    RegisterCluster, RegisterProperties,
+};
+use xmltree::Element;
+
+pub use svd_parser::Device as SvdDevice;
+
+/// Register size set at the device level, only differing registers repeat it
+pub const DEFAULT_SIZE: u32 = 32;
+/// Reset value set at the device level, only differing registers repeat it
+pub const DEFAULT_RESET_VALUE: u32 = 0;
+/// Access set at the device level, only differing fields repeat it
+pub const DEFAULT_ACCESS: Type = Type::ReadWrite;
+
+/// Representation of the field bit ranges, downstream tools differ in the ones they support
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum BitRangeStyle {
+    /// `<bitOffset>` and `<bitWidth>`
+    #[default]
+    OffsetWidth,
+    /// `<lsb>` and `<msb>`
+    LsbMsb,
+    /// `<bitRange>[msb:lsb]</bitRange>`
+    BitRange,
+}
+
+impl BitRangeStyle {
+    pub const NAMES: [&'static str; 3] = ["offset-width", "lsb-msb", "bit-range"];
+}
+
+impl FromStr for BitRangeStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "offset-width" => Ok(BitRangeStyle::OffsetWidth),
+            "lsb-msb" => Ok(BitRangeStyle::LsbMsb),
+            "bit-range" => Ok(BitRangeStyle::BitRange),
+            _ => Err(format!("Invalid bit range style: {}", s)),
+        }
+    }
+}
+
+impl From<BitRangeStyle> for BitRangeType {
+    fn from(style: BitRangeStyle) -> Self {
+        match style {
+            BitRangeStyle::OffsetWidth => BitRangeType::OffsetWidth,
+            BitRangeStyle::LsbMsb => BitRangeType::MsbLsb,
+            BitRangeStyle::BitRange => BitRangeType::BitRange,
+        }
+    }
+}
+
+/// Choices in how the model is represented in the svd
+#[derive(Debug, Clone, Default)]
+pub struct SvdOptions {
+    pub bit_range: BitRangeStyle,
+    /// The `cpu` element is left out without one
+    pub cpu: Option<CpuConfig>,
+    pub device: DeviceConfig,
+}
+
+fn default_register_properties() -> RegisterProperties {
+    RegisterProperties {
+        size: Some(DEFAULT_SIZE),
+        reset_value: Some(DEFAULT_RESET_VALUE),
+        reset_mask: Some(0xFFFF_FFFF),
+        access: Some(Access::from(DEFAULT_ACCESS)),
+    }
+}
+
+/// The svd peripheral of `p`, without its further instances
+pub fn svd_peripheral(name: &str, p: &Peripheral, options: &SvdOptions) -> SvdPeripheral {
+    let mut registers = vec![];
+    for r in &p.registers {
+        // documented or shared by all fields, the fields then only state a different access
+        let access = r.access.or_else(|| {
+            let first = r.bit_fields.first()?.type_;
+            if r.bit_fields.iter().all(|f| f.type_ == first) {
+                Some(first)
+            } else {
+                None
+            }
+        });
+        let inherited = access.unwrap_or(DEFAULT_ACCESS);
+        let mut fields = vec![];
+        for field in &r.bit_fields {
+            let description = if field.description.trim().is_empty() {
+                None
+            } else {
+                Some(field.description.clone())
+            };
+
+            let bit_range = match &field.bits {
+                Bits::Single(bit) => BitRange {
+                    offset: u32::from(*bit),
+                    width: 1,
+                    range_type: options.bit_range.into(),
+                },
+                Bits::Range(r) => BitRange {
+                    offset: u32::from(*r.start()),
+                    width: u32::from(r.end() - r.start() + 1),
+                    range_type: options.bit_range.into(),
+                },
+            };
+
+            let field_access = if field.type_ == inherited {
+                None
+            } else {
+                Some(Access::from(field.type_))
+            };
+
+            let mut enumerated_values = vec![];
+            if !field.enumerated_values.is_empty() {
+                let values = field
+                    .enumerated_values
+                    .iter()
+                    .map(|v| {
+                        EnumeratedValueBuilder::default()
+                            .name(v.name.clone())
+                            .description(v.description.clone())
+                            .value(Some(v.value))
+                            .build()
+                            .unwrap()
+                    })
+                    .collect();
+                enumerated_values.push(
+                    EnumeratedValuesBuilder::default()
+                        .values(values)
+                        .build()
+                        .unwrap(),
+                );
+            }
+
+            let field_out = FieldInfoBuilder::default()
+                .name(field.name.clone())
+                .description(description)
+                .bit_range(bit_range)
+                .access(field_access)
+                // svd2rust only reads the write semantics of fields, so repeat the register's
+                .modified_write_values(field.modified_write.or(r.modified_write).map(Into::into))
+                .enumerated_values(enumerated_values)
+                .build()
+                .unwrap();
+            fields.push(Field::Single(field_out));
+        }
+
+        let reset_value = r.reset_value as u32;
+        let reset_value = if reset_value == DEFAULT_RESET_VALUE {
+            None
+        } else {
+            Some(reset_value)
+        };
+
+        let size = match u32::from(r.width) {
+            0 | DEFAULT_SIZE => None,
+            width => Some(width),
+        };
+
+        let description = match r.safety {
+            Some(safety) => format!("{}. {}", r.description.trim_end_matches('.'), safety.note()),
+            None => r.description.clone(),
+        };
+
+        let info = RegisterInfoBuilder::default()
+            .name(r.name.clone())
+            // the register it aliases may have been left out of a view
+            .alternate_register(
+                r.alternate_register
+                    .clone()
+                    .filter(|a| p.registers.iter().any(|o| o.name == *a)),
+            )
+            .description(Some(description))
+            .address_offset(r.address)
+            .size(size)
+            .access(access.filter(|a| *a != DEFAULT_ACCESS).map(Access::from))
+            .reset_value(reset_value)
+            .reset_mask(r.reset_mask)
+            .modified_write_values(r.modified_write.map(Into::into))
+            .fields(Some(fields))
+            .build()
+            .unwrap();
+
+        registers.push(RegisterCluster::Register(SvdRegister::Single(info)));
+    }
+    let block_size = p.address_block_size();
+    let interrupts = p
+        .interrupts
+        .iter()
+        .map(|i| SvdInterrupt {
+            name: i.name.clone(),
+            description: i.description.clone(),
+            value: i.value,
+        })
+        .collect();
+    // like registers, peripherals only repeat their name as description in the model
+    let description = if p.description.is_empty() || p.description == name {
+        None
+    } else {
+        Some(p.description.clone())
+    };
+    PeripheralBuilder::default()
+        .name(name.to_owned())
+        .description(description)
+        .version(p.version.clone())
+        .group_name(p.group_name.clone())
+        .base_address(p.address)
+        .interrupt(interrupts)
+        .registers(Some(registers))
+        .address_block(Some(AddressBlock {
+            offset: 0x0,
+            size: block_size,
+            usage: "registers".to_string(),
+        }))
+        .build()
+        .unwrap()
+}
+
+pub fn create_svd(peripherals: BTreeMap<String, Peripheral>) -> Result<SvdDevice, String> {
+    create_svd_with_options(peripherals, &SvdOptions::default())
+}
+
+pub fn create_svd_with_options(
+    peripherals: BTreeMap<String, Peripheral>,
+    options: &SvdOptions,
+) -> Result<SvdDevice, String> {
+    let mut svd_peripherals = vec![];
+
+    for (name, p) in peripherals {
+        svd_peripherals.push(svd_peripheral(&name, &p, options));
+
+        for (i, address) in p.instance_addresses.iter().enumerate() {
+            let instance = PeripheralBuilder::default()
+                .name(format!("{}{}", name, i + 1))
+                .group_name(p.group_name.clone())
+                .base_address(*address)
+                .derived_from(Some(name.to_owned()))
+                .build()
+                .unwrap();
+            svd_peripherals.push(instance);
+        }
+    }
+
+    let cpu = options.cpu.as_ref().map(|cpu| {
+        CpuBuilder::default()
+            .name(cpu.name.clone())
+            .revision(cpu.revision.clone())
+            .endian(match cpu.endian {
+                Endianness::Little => Endian::Little,
+                Endianness::Big => Endian::Big,
+            })
+            .mpu_present(cpu.mpu_present)
+            .fpu_present(cpu.fpu_present)
+            .nvic_priority_bits(cpu.nvic_priority_bits)
+            .has_vendor_systick(cpu.vendor_systick)
+            .build()
+            .unwrap()
+    });
+
+    let device = DeviceBuilder::default()
+        .name(options.device.name.clone())
+        .version(Some(options.device.version.clone()))
+        .schema_version(Some("1.0".to_string()))
+        // broken see: https://github.com/rust-embedded/svd/pull/104, added by
+        // `insert_device_metadata` instead
+        // .description(Some("ESP32".to_string()))
+        // .address_unit_bits(Some(8))
+        .width(Some(options.device.width))
+        .default_register_properties(default_register_properties())
+        .cpu(cpu)
+        .peripherals(svd_peripherals)
+        .build()
+        .map_err(|e| format!("Failed to build the svd device: {:?}", e))?;
+
+    Ok(device)
+}
+
+/// Encode `device` as SVD xml into `out`
+pub fn write_svd<W: Write>(device: &SvdDevice, out: W) -> Result<(), String> {
+    write_svd_with_extensions(
+        device,
+        vec![],
+        &BTreeMap::new(),
+        &DeviceConfig::default(),
+        "",
+        out,
+    )
+}
+
+/// Insert `child` into `parent` after the last of its children named one of `after`, first when
+/// there is none
+fn insert_after(parent: &mut Element, after: &[&str], child: Element) {
+    let at = parent
+        .children
+        .iter()
+        .rposition(|c| after.contains(&c.name.as_str()))
+        .map_or(0, |i| i + 1);
+    parent.children.insert(at, child);
+}
+
+/// Add the device properties svd-parser can't encode, in the order of the schema
+fn insert_device_metadata(xml: &mut Element, device: &DeviceConfig) {
+    let properties = [
+        ("vendor", &device.vendor, &[][..]),
+        ("series", &device.series, &["vendor", "name"][..]),
+        (
+            "description",
+            &device.description,
+            &["vendor", "name", "series", "version"][..],
+        ),
+        (
+            "licenseText",
+            &device.license_text,
+            &["vendor", "name", "series", "version", "description"][..],
+        ),
+    ];
+    for (name, value, after) in properties.iter() {
+        if let Some(value) = value {
+            let mut element = Element::new(name);
+            element.text = Some(value.clone());
+            insert_after(xml, after, element);
+        }
+    }
+}
+
+/// Encode `device` like `write_svd`, with `extensions` as the children of its
+/// `vendorExtensions` element and `comment` as a comment before it. Both are left out when
+/// empty. `header_structs` are the `headerStructName` of peripherals by name, `metadata`
+/// the device properties svd-parser can't encode
+pub fn write_svd_with_extensions<W: Write>(
+    device: &SvdDevice,
+    extensions: Vec<Element>,
+    header_structs: &BTreeMap<String, String>,
+    metadata: &DeviceConfig,
+    comment: &str,
+    mut out: W,
+) -> Result<(), String> {
+    let mut xml = device
+        .encode()
+        .map_err(|e| format!("Failed to encode svd: {:?}", e))?;
+    insert_device_metadata(&mut xml, metadata);
+    insert_header_struct_names(&mut xml, header_structs);
+    if !extensions.is_empty() {
+        let mut vendor_extensions = Element::new("vendorExtensions");
+        vendor_extensions.children = extensions;
+        xml.children.push(vendor_extensions);
+    }
+    let mut encoded = vec![];
+    xml.write(&mut encoded)
+        .map_err(|e| format!("Failed to write svd: {}", e))?;
+    insert_comment(&mut encoded, comment);
+    out.write_all(&encoded)
+        .map_err(|e| format!("Failed to write svd: {}", e))
+}
+
+/// Insert `comment` into an encoded xml document, after the xml declaration which has to come
+/// first. Nothing is inserted for an empty comment
+pub(crate) fn insert_comment(encoded: &mut Vec<u8>, comment: &str) {
+    if comment.is_empty() {
+        return;
+    }
+    let at = encoded
+        .windows(2)
+        .position(|w| w == b"?>")
+        .map_or(0, |i| i + 2);
+    let comment = format!("\n<!--\n{}-->\n", comment);
+    encoded.splice(at..at, comment.bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BitField, Register};
+
+    #[test]
+    fn test_bit_range_style() {
+        let p = Peripheral {
+            registers: vec![Register {
+                name: "UART_CONF0".to_string(),
+                bit_fields: vec![BitField {
+                    name: "UART_STOP_BIT_NUM".to_string(),
+                    bits: Bits::Range(4..=5),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let options = SvdOptions {
+            bit_range: "lsb-msb".parse().unwrap(),
+            ..Default::default()
+        };
+        let svd = svd_peripheral("UART", &p, &options);
+        let register = match &svd.registers.as_ref().unwrap()[0] {
+            RegisterCluster::Register(register) => register,
+            RegisterCluster::Cluster(_) => unreachable!(),
+        };
+        let bit_range = register.fields.as_ref().unwrap()[0].bit_range;
+        assert_eq!(bit_range.range_type, BitRangeType::MsbLsb);
+        assert_eq!((bit_range.offset, bit_range.width), (4, 2));
+        assert!("msb-lsb".parse::<BitRangeStyle>().is_err());
+    }
+
+    #[test]
+    fn test_register_access() {
+        let field = |name: &str, bit, type_| BitField {
+            name: name.to_string(),
+            bits: Bits::Single(bit),
+            type_,
+            ..Default::default()
+        };
+        let register = |fields, access| Register {
+            name: "UART_STATUS".to_string(),
+            bit_fields: fields,
+            access,
+            ..Default::default()
+        };
+        let accesses = |r: Register| {
+            let p = Peripheral {
+                registers: vec![r],
+                ..Default::default()
+            };
+            let svd = svd_peripheral("UART", &p, &SvdOptions::default());
+            let register = match &svd.registers.as_ref().unwrap()[0] {
+                RegisterCluster::Register(register) => register.clone(),
+                RegisterCluster::Cluster(_) => unreachable!(),
+            };
+            let fields: Vec<Option<Access>> = register
+                .fields
+                .as_ref()
+                .unwrap()
+                .iter()
+                .map(|f| f.access)
+                .collect();
+            (register.access, fields)
+        };
+
+        let read_only = vec![
+            field("UART_RXFIFO_CNT", 0, Type::ReadOnly),
+            field("UART_TXFIFO_CNT", 1, Type::ReadOnly),
+        ];
+        assert_eq!(
+            accesses(register(read_only, None)),
+            (Some(Access::ReadOnly), vec![None, None])
+        );
+        let mixed = vec![
+            field("UART_RXFIFO_CNT", 0, Type::ReadOnly),
+            field("UART_TXFIFO_RST", 1, Type::ReadWrite),
+        ];
+        assert_eq!(
+            accesses(register(mixed.clone(), None)),
+            (None, vec![Some(Access::ReadOnly), None])
+        );
+        assert_eq!(
+            accesses(register(mixed, Some(Type::ReadOnly))),
+            (Some(Access::ReadOnly), vec![None, Some(Access::ReadWrite)])
+        );
+    }
+
+    #[test]
+    fn test_device_metadata() {
+        let mut device = DeviceConfig::default();
+        device.set("name", "ESP8266EX").unwrap();
+        device.set("vendor", "Espressif").unwrap();
+        device.set("series", "ESP8266").unwrap();
+        device.set("license_text", "Apache-2.0").unwrap();
+        assert!(device.set("width", "wide").is_err());
+        assert!(device.set("vendorID", "ESPR").is_err());
+
+        let mut xml = Element::parse(
+            "<device><name>ESP8266EX</name><version>1.0</version><width>32</width></device>"
+                .as_bytes(),
+        )
+        .unwrap();
+        insert_device_metadata(&mut xml, &device);
+        let children: Vec<&str> = xml.children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(
+            children,
+            vec![
+                "vendor",
+                "name",
+                "series",
+                "version",
+                "licenseText",
+                "width"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_address_block_size() {
+        let register = |address, width| Register {
+            address,
+            width,
+            ..Default::default()
+        };
+        let mut p = Peripheral {
+            registers: vec![register(0x0, 0), register(0x80, 0), register(0x85, 8)],
+            ..Default::default()
+        };
+        let size = |p: &Peripheral| svd_peripheral("UART", p, &SvdOptions::default()).address_block;
+        assert_eq!(size(&p).unwrap().size, 0x88);
+        p.registers.pop();
+        assert_eq!(size(&p).unwrap().size, 0x84);
+        p.block_size = Some(0x1000);
+        assert_eq!(size(&p).unwrap().size, 0x1000);
+    }
+}
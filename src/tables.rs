@@ -0,0 +1,248 @@
+//! Conversion of register and field tables found in documentation (PDF manuals, HTML pages)
+//! into the documentation format of [`crate::doc`]. The input adapters only find headings,
+//! table headers and rows, mapping the cells to columns is shared.
+
+use crate::doc::{parse_bits, DocField, DocFile, DocRegister};
+use regex::Regex;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Column {
+    Name,
+    Address,
+    Bits,
+    Access,
+    Reset,
+    Description,
+}
+
+impl Column {
+    pub fn from_title(title: &str) -> Option<Column> {
+        Some(match title.trim().to_lowercase().as_str() {
+            "name" | "field" | "register" | "register name" | "field name" => Column::Name,
+            "address" | "offset" | "addr" => Column::Address,
+            "bit" | "bits" | "bit(s)" | "position" => Column::Bits,
+            "access" | "type" | "r/w" | "rw" => Column::Access,
+            "reset" | "default" | "default value" | "reset value" => Column::Reset,
+            "description" | "function" => Column::Description,
+            _ => return None,
+        })
+    }
+}
+
+/// Columns of a table header, `None` unless it is a register table (with an address column)
+/// or a field table (with a bits column)
+pub fn header_columns<'a>(
+    titles: impl IntoIterator<Item = &'a str>,
+) -> Option<Vec<Option<Column>>> {
+    let columns: Vec<Option<Column>> = titles.into_iter().map(Column::from_title).collect();
+    let has = |c| columns.contains(&Some(c));
+    let known = columns.iter().filter(|c| c.is_some()).count();
+    if known >= 3 && has(Column::Name) && (has(Column::Bits) || has(Column::Address)) {
+        Some(columns)
+    } else {
+        None
+    }
+}
+
+/// Parse a documented number: decimal, hex or verilog style such as `8'h2` or `1'b0`
+pub fn parse_value(value: &str) -> Option<u64> {
+    let value = value.trim().replace('_', "");
+    if let Some(i) = value.find('\'') {
+        let digits = &value[i + 2..];
+        let radix = match value[i + 1..].chars().next()?.to_ascii_lowercase() {
+            'h' => 16,
+            'b' => 2,
+            'd' => 10,
+            'o' => 8,
+            _ => return None,
+        };
+        return u64::from_str_radix(digits, radix).ok();
+    }
+    crate::expr::parse_number(&value).ok().map(|v| v as u64)
+}
+
+/// Collects the registers and fields of the tables of one peripheral. Field tables belong to
+/// the last register heading (`NAME_REG (0x...)`) before them.
+pub struct TableExtractor {
+    doc: DocFile,
+    base: Option<u32>,
+    re_heading: Regex,
+    /// Whether the current table is a field table, `None` outside of tables
+    field_table: Option<bool>,
+    /// Index of the register field rows are added to
+    register: Option<usize>,
+}
+
+impl TableExtractor {
+    /// Addresses are made relative to `base` when given
+    pub fn new(peripheral: &str, base: Option<u32>) -> Self {
+        TableExtractor {
+            doc: DocFile {
+                peripheral: peripheral.to_string(),
+                address: base,
+                ..Default::default()
+            },
+            base,
+            re_heading: Regex::new(
+                r"^\s*(?:Register\s+[0-9.]+:\s*)?([A-Z][A-Z0-9_]*?)(?:_REG)?\s*\((0x[0-9A-Fa-f]+)\)",
+            )
+            .unwrap(),
+            field_table: None,
+            register: None,
+        }
+    }
+
+    fn relative(&self, address: u32) -> u32 {
+        match self.base {
+            Some(base) if address >= base => address - base,
+            _ => address,
+        }
+    }
+
+    /// Handle a heading or free text line, returns whether it named a register
+    pub fn heading(&mut self, text: &str) -> bool {
+        let c = match self.re_heading.captures(text) {
+            Some(c) => c,
+            None => return false,
+        };
+        let address = parse_value(&c[2]).unwrap_or(0) as u32;
+        let name = c[1].to_string();
+        let offset = self.relative(address);
+        let registers = &mut self.doc.registers;
+        self.register = match registers.iter().position(|r| r.name == name) {
+            Some(i) => Some(i),
+            None => {
+                registers.push(DocRegister {
+                    name,
+                    offset,
+                    ..Default::default()
+                });
+                Some(registers.len() - 1)
+            }
+        };
+        self.field_table = None;
+        true
+    }
+
+    /// Start a table with the columns from [`header_columns`]
+    pub fn start_table(&mut self, columns: &[Option<Column>]) {
+        self.field_table = Some(columns.contains(&Some(Column::Bits)));
+    }
+
+    pub fn end_table(&mut self) {
+        self.field_table = None;
+    }
+
+    /// Add a table row, a row without name continues the description of the previous one
+    pub fn row(&mut self, values: &[(Column, String)]) {
+        let field_table = match self.field_table {
+            Some(field_table) => field_table,
+            None => return,
+        };
+        let get = |column| {
+            values
+                .iter()
+                .find(|(c, v)| *c == column && !v.is_empty())
+                .map(|(_, v)| v.clone())
+        };
+
+        match get(Column::Name) {
+            None => {
+                let description = match get(Column::Description) {
+                    Some(description) => description,
+                    None => return,
+                };
+                let last = if field_table {
+                    let registers = &mut self.doc.registers;
+                    self.register
+                        .and_then(move |r| registers[r].fields.last_mut())
+                        .map(|f| &mut f.description)
+                } else {
+                    self.doc.registers.last_mut().map(|r| &mut r.description)
+                };
+                if let Some(last) = last {
+                    if !last.is_empty() {
+                        last.push(' ');
+                    }
+                    last.push_str(&description);
+                }
+            }
+            Some(name) if field_table => {
+                let r = match self.register {
+                    Some(r) => r,
+                    None => return,
+                };
+                let bits = get(Column::Bits).unwrap_or_default();
+                if parse_bits(&bits).is_none() || name.to_lowercase().starts_with("reserved") {
+                    return;
+                }
+                self.doc.registers[r].fields.push(DocField {
+                    name,
+                    bits: bits
+                        .trim_start_matches('[')
+                        .trim_end_matches(']')
+                        .to_string(),
+                    access: get(Column::Access).map(|a| a.to_uppercase()),
+                    reset: get(Column::Reset)
+                        .and_then(|v| parse_value(&v))
+                        .map(|v| v as u32),
+                    description: get(Column::Description).unwrap_or_default(),
+                });
+            }
+            Some(name) => {
+                let address = match get(Column::Address).and_then(|a| parse_value(&a)) {
+                    Some(address) => address as u32,
+                    None => return,
+                };
+                let name = name.trim_end_matches("_REG").to_string();
+                if self.doc.registers.iter().any(|r| r.name == name) {
+                    return;
+                }
+                let offset = self.relative(address);
+                self.doc.registers.push(DocRegister {
+                    name,
+                    offset,
+                    description: get(Column::Description).unwrap_or_default(),
+                    reset: get(Column::Reset).and_then(|v| parse_value(&v)),
+                    ..Default::default()
+                });
+            }
+        }
+    }
+
+    pub fn finish(mut self) -> DocFile {
+        self.doc.registers.sort_by_key(|r| r.offset);
+        self.doc
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_value() {
+        assert_eq!(parse_value("8'h2F"), Some(0x2f));
+        assert_eq!(parse_value("1'b1"), Some(1));
+        assert_eq!(parse_value("0x3FF4_0000"), Some(0x3ff4_0000));
+        assert_eq!(parse_value(" 12 "), Some(12));
+        assert_eq!(parse_value("2'x1"), None);
+        assert_eq!(parse_value("n/a"), None);
+    }
+
+    #[test]
+    fn test_header_columns() {
+        let columns = header_columns(vec!["Field", "Bit(s)", "Type", "Notes"]).unwrap();
+        assert_eq!(
+            columns,
+            vec![
+                Some(Column::Name),
+                Some(Column::Bits),
+                Some(Column::Access),
+                None
+            ]
+        );
+        // neither bits nor an address, not a register table
+        assert!(header_columns(vec!["Name", "Type", "Description"]).is_none());
+    }
+}
@@ -0,0 +1,115 @@
+//! Quarantine of the defines of the register headers no pattern of the parser understood, so
+//! systematic gaps in the regexes show up instead of their data being lost silently. Written
+//! with `--unparsed` as text or JSON, grouped per file.
+
+use crate::Peripheral;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UnparsedDefine {
+    pub line: usize,
+    pub text: String,
+}
+
+/// Unparsed defines by file, in the order of their lines
+pub type Unparsed = BTreeMap<String, Vec<UnparsedDefine>>;
+
+/// Name of the define of `line`, unless it defines no value such as an include guard
+fn define_name(line: &str) -> Option<&str> {
+    let mut parts = line
+        .trim_start()
+        .strip_prefix("#define")?
+        .split_whitespace();
+    let name = parts.next()?;
+    parts.next()?;
+    Some(name)
+}
+
+/// The defines of `candidates`, as file, line and text, that aren't the value define of a
+/// parsed field or register
+pub fn quarantine(
+    candidates: Vec<(String, usize, String)>,
+    peripherals: &BTreeMap<String, Peripheral>,
+) -> Unparsed {
+    let known: HashSet<&str> = peripherals
+        .values()
+        .flat_map(|p| p.registers.iter())
+        .flat_map(|r| {
+            std::iter::once(r.name.as_str()).chain(r.bit_fields.iter().map(|f| f.name.as_str()))
+        })
+        .collect();
+    let mut unparsed = Unparsed::new();
+    for (file, line, text) in candidates {
+        match define_name(&text) {
+            Some(name) if !known.contains(name) => unparsed
+                .entry(file)
+                .or_default()
+                .push(UnparsedDefine { line, text }),
+            _ => {}
+        }
+    }
+    unparsed
+}
+
+pub fn unparsed_text(unparsed: &Unparsed) -> String {
+    let mut out = String::new();
+    for (file, defines) in unparsed {
+        writeln!(out, "{}", file).unwrap();
+        for define in defines {
+            writeln!(out, "  {}: {}", define.line, define.text).unwrap();
+        }
+    }
+    out
+}
+
+pub fn unparsed_json(unparsed: &Unparsed) -> String {
+    serde_json::to_string_pretty(unparsed).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BitField, Register};
+
+    #[test]
+    fn test_quarantine() {
+        let mut peripherals = BTreeMap::new();
+        peripherals.insert(
+            "UART".to_string(),
+            Peripheral {
+                registers: vec![Register {
+                    name: "UART_CONF0".to_string(),
+                    bit_fields: vec![BitField {
+                        name: "UART_PARITY_EN".to_string(),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+        );
+        let candidate = |file: &str, line, text: &str| (file.to_string(), line, text.to_string());
+        let candidates = vec![
+            candidate("uart_reg.h", 1, "#define _SOC_UART_REG_H_"),
+            candidate("uart_reg.h", 5, "#define UART_PARITY_EN  (BIT(1))"),
+            candidate(
+                "uart_reg.h",
+                9,
+                "#define UART_INT_ST_FIFO  UART_INT_ST_REG(0)",
+            ),
+            candidate("uart_reg.h", 12, "#define UART_LINE_INV_MASK  (0x3F << 19)"),
+            candidate("i2s_reg.h", 3, "#define I2S_CLK_SEL  2"),
+        ];
+
+        let unparsed = quarantine(candidates, &peripherals);
+        assert_eq!(
+            unparsed_text(&unparsed),
+            "i2s_reg.h\n  3: #define I2S_CLK_SEL  2\n\
+             uart_reg.h\n  9: #define UART_INT_ST_FIFO  UART_INT_ST_REG(0)\n  \
+             12: #define UART_LINE_INV_MASK  (0x3F << 19)\n"
+        );
+        assert!(unparsed_json(&unparsed).contains("\"line\": 12"));
+    }
+}
@@ -0,0 +1,655 @@
+use crate::arrays::{counts_run, runs};
+use crate::svd::DEFAULT_SIZE;
+use crate::{Bits, Origin, Peripheral};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// Problems found in the parsed model before it is encoded
+#[derive(Debug, Clone, PartialEq)]
+pub enum Issue {
+    /// Two fields of one register share at least one bit
+    OverlappingFields {
+        peripheral: String,
+        register: String,
+        first: String,
+        second: String,
+    },
+    /// Two registers of one peripheral share an offset
+    DuplicateAddress {
+        peripheral: String,
+        address: u32,
+        first: String,
+        second: String,
+    },
+    /// A register that doesn't fit in the peripheral address block
+    OutsideAddressBlock {
+        peripheral: String,
+        register: String,
+        address: u32,
+    },
+    /// A field that extends past bit 31
+    FieldOutOfRange {
+        peripheral: String,
+        register: String,
+        field: String,
+    },
+    /// A register offset that isn't a multiple of the required alignment
+    Misaligned {
+        peripheral: String,
+        register: String,
+        address: u32,
+        alignment: u32,
+    },
+    /// A field derived from a mask define covering the whole register, more likely a version
+    /// number or magic constant than a field
+    FullWidthField {
+        peripheral: String,
+        register: String,
+        field: String,
+    },
+    /// A mask define of zero, left out of the model as it covers no bits. Reported by the
+    /// parsers rather than `validate`
+    ZeroWidthField {
+        peripheral: String,
+        register: String,
+        field: String,
+    },
+    /// Fields whose widths add up to more than the register width, because they overlap or
+    /// have the wrong width
+    FieldTotal {
+        peripheral: String,
+        register: String,
+        total: u32,
+        width: u32,
+    },
+    /// Bits not covered by any field of a register documented as completely described
+    MissingBits {
+        peripheral: String,
+        register: String,
+        mask: u32,
+    },
+    /// A run of channelised registers or fields disagreeing with its count macro
+    ArrayLength {
+        peripheral: String,
+        pattern: String,
+        count_macro: String,
+        expected: u32,
+        found: u32,
+    },
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Issue::OverlappingFields {
+                peripheral,
+                register,
+                first,
+                second,
+            } => write!(
+                f,
+                "{}.{}: fields {} and {} overlap",
+                peripheral, register, first, second
+            ),
+            Issue::DuplicateAddress {
+                peripheral,
+                address,
+                first,
+                second,
+            } => write!(
+                f,
+                "{}: registers {} and {} share offset {:#x}",
+                peripheral, first, second, address
+            ),
+            Issue::OutsideAddressBlock {
+                peripheral,
+                register,
+                address,
+            } => write!(
+                f,
+                "{}.{}: offset {:#x} is outside the address block",
+                peripheral, register, address
+            ),
+            Issue::FieldOutOfRange {
+                peripheral,
+                register,
+                field,
+            } => write!(
+                f,
+                "{}.{}: field {} exceeds bit 31",
+                peripheral, register, field
+            ),
+            Issue::Misaligned {
+                peripheral,
+                register,
+                address,
+                alignment,
+            } => write!(
+                f,
+                "{}.{}: offset {:#x} is not {} byte aligned",
+                peripheral, register, address, alignment
+            ),
+            Issue::FullWidthField {
+                peripheral,
+                register,
+                field,
+            } => write!(
+                f,
+                "{}.{}: field {} is a full width mask, delete it with a patch if it isn't a field",
+                peripheral, register, field
+            ),
+            Issue::ZeroWidthField {
+                peripheral,
+                register,
+                field,
+            } => write!(
+                f,
+                "{}.{}: field {} is a zero width mask, likely not a field",
+                peripheral, register, field
+            ),
+            Issue::FieldTotal {
+                peripheral,
+                register,
+                total,
+                width,
+            } => write!(
+                f,
+                "{}.{}: fields add up to {} bits in a {} bit register",
+                peripheral, register, total, width
+            ),
+            Issue::MissingBits {
+                peripheral,
+                register,
+                mask,
+            } => write!(
+                f,
+                "{}.{}: bits {:#010x} are documented but not covered by a field",
+                peripheral, register, mask
+            ),
+            Issue::ArrayLength {
+                peripheral,
+                pattern,
+                count_macro,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{}.{}: {} elements but {} is {}",
+                peripheral, pattern, found, count_macro, expected
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ValidateOptions {
+    /// Drop the offending registers and fields (the first of two conflicting entries is kept)
+    pub fix: bool,
+    /// Required register alignment in bytes
+    pub alignment: u32,
+    /// Per peripheral alignment, e.g. `1` for byte addressable FIFO windows
+    pub alignment_overrides: HashMap<String, u32>,
+}
+
+impl Default for ValidateOptions {
+    fn default() -> Self {
+        ValidateOptions {
+            fix: false,
+            alignment: 4,
+            alignment_overrides: HashMap::new(),
+        }
+    }
+}
+
+impl Issue {
+    /// Dotted path of the element that is dropped when fixing this issue, `None` for issues
+    /// that are only reported
+    pub fn path(&self) -> Option<String> {
+        Some(match self {
+            Issue::OverlappingFields {
+                peripheral,
+                register,
+                second,
+                ..
+            } => format!("{}.{}.{}", peripheral, register, second),
+            Issue::DuplicateAddress {
+                peripheral, second, ..
+            } => format!("{}.{}", peripheral, second),
+            Issue::OutsideAddressBlock {
+                peripheral,
+                register,
+                ..
+            }
+            | Issue::Misaligned {
+                peripheral,
+                register,
+                ..
+            } => format!("{}.{}", peripheral, register),
+            Issue::FieldOutOfRange {
+                peripheral,
+                register,
+                field,
+            }
+            | Issue::FullWidthField {
+                peripheral,
+                register,
+                field,
+            } => format!("{}.{}.{}", peripheral, register, field),
+            Issue::ZeroWidthField { .. }
+            | Issue::FieldTotal { .. }
+            | Issue::MissingBits { .. }
+            | Issue::ArrayLength { .. } => return None,
+        })
+    }
+
+    /// Short name of the kind of issue, for the summary
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Issue::OverlappingFields { .. } => "overlapping fields",
+            Issue::DuplicateAddress { .. } => "duplicate addresses",
+            Issue::OutsideAddressBlock { .. } => "outside address block",
+            Issue::FieldOutOfRange { .. } => "fields out of range",
+            Issue::Misaligned { .. } => "misaligned registers",
+            Issue::FullWidthField { .. } => "full width fields",
+            Issue::ZeroWidthField { .. } => "zero width fields",
+            Issue::FieldTotal { .. } => "field totals exceeding register width",
+            Issue::MissingBits { .. } => "incomplete documented registers",
+            Issue::ArrayLength { .. } => "array length mismatches",
+        }
+    }
+}
+
+/// Number of issues of each kind
+pub fn issue_counts(issues: &[Issue]) -> BTreeMap<&'static str, usize> {
+    let mut counts = BTreeMap::new();
+    for issue in issues {
+        *counts.entry(issue.kind()).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Check the model for inconsistencies
+pub fn validate(
+    peripherals: &mut BTreeMap<String, Peripheral>,
+    options: &ValidateOptions,
+) -> Vec<Issue> {
+    let mut issues = vec![];
+    let fix = options.fix;
+
+    for (pname, p) in peripherals.iter_mut() {
+        let block_size = p.address_block_size();
+        let alignment = options
+            .alignment_overrides
+            .get(pname)
+            .copied()
+            .unwrap_or(options.alignment)
+            .max(1);
+        let mut seen_addresses: HashMap<u32, String> = HashMap::new();
+
+        p.registers.retain(|r| {
+            if r.address % alignment != 0 {
+                issues.push(Issue::Misaligned {
+                    peripheral: pname.clone(),
+                    register: r.name.clone(),
+                    address: r.address,
+                    alignment,
+                });
+                return !fix;
+            }
+            if r.address >= block_size {
+                issues.push(Issue::OutsideAddressBlock {
+                    peripheral: pname.clone(),
+                    register: r.name.clone(),
+                    address: r.address,
+                });
+                return !fix;
+            }
+            // aliases sharing the offset on purpose, see `aliases`
+            if r.alternate_register.is_some() {
+                return true;
+            }
+            if let Some(first) = seen_addresses.get(&r.address) {
+                issues.push(Issue::DuplicateAddress {
+                    peripheral: pname.clone(),
+                    address: r.address,
+                    first: first.clone(),
+                    second: r.name.clone(),
+                });
+                return !fix;
+            }
+            seen_addresses.insert(r.address, r.name.clone());
+            true
+        });
+
+        for r in p.registers.iter_mut() {
+            let mut kept: Vec<(String, Bits)> = vec![];
+            let rname = r.name.clone();
+
+            let width = if r.width == 0 {
+                DEFAULT_SIZE
+            } else {
+                u32::from(r.width)
+            };
+            let total: u32 = r
+                .bit_fields
+                .iter()
+                .map(|bf| u32::from(bf.bits.msb() - bf.bits.lsb()) + 1)
+                .sum();
+            if total > width {
+                issues.push(Issue::FieldTotal {
+                    peripheral: pname.clone(),
+                    register: rname.clone(),
+                    total,
+                    width,
+                });
+            }
+            if r.complete {
+                let covered = r
+                    .bit_fields
+                    .iter()
+                    .flat_map(|bf| bf.bits.lsb()..=bf.bits.msb())
+                    .filter(|&bit| u32::from(bit) < width.min(32))
+                    .fold(0u32, |mask, bit| mask | 1 << bit);
+                let all = u32::MAX >> (32 - width.min(32));
+                let missing = all & !covered;
+                if missing != 0 {
+                    issues.push(Issue::MissingBits {
+                        peripheral: pname.clone(),
+                        register: rname.clone(),
+                        mask: missing,
+                    });
+                }
+            }
+
+            r.bit_fields.retain(|bf| {
+                if bf.bits.msb() > 31 {
+                    issues.push(Issue::FieldOutOfRange {
+                        peripheral: pname.clone(),
+                        register: rname.clone(),
+                        field: bf.name.clone(),
+                    });
+                    return !fix;
+                }
+                if bf.origin == Origin::HeaderMask && bf.bits.lsb() == 0 && bf.bits.msb() == 31 {
+                    issues.push(Issue::FullWidthField {
+                        peripheral: pname.clone(),
+                        register: rname.clone(),
+                        field: bf.name.clone(),
+                    });
+                    return !fix;
+                }
+                if let Some((first, _)) = kept.iter().find(|(_, bits)| bits.overlaps(&bf.bits)) {
+                    issues.push(Issue::OverlappingFields {
+                        peripheral: pname.clone(),
+                        register: rname.clone(),
+                        first: first.clone(),
+                        second: bf.name.clone(),
+                    });
+                    return !fix;
+                }
+                kept.push((bf.name.clone(), bf.bits.clone()));
+                true
+            });
+        }
+
+        let register_runs = runs(p.registers.iter().map(|r| (r.name.as_str(), r.address)));
+        let field_runs = p.registers.iter().flat_map(|r| {
+            runs(
+                r.bit_fields
+                    .iter()
+                    .map(|bf| (bf.name.as_str(), u32::from(bf.bits.lsb()))),
+            )
+        });
+        for run in register_runs.into_iter().chain(field_runs) {
+            for (count_macro, &expected) in &p.counts {
+                if counts_run(count_macro, &run) && run.len != expected {
+                    issues.push(Issue::ArrayLength {
+                        peripheral: pname.clone(),
+                        pattern: run.pattern.clone(),
+                        count_macro: count_macro.clone(),
+                        expected,
+                        found: run.len,
+                    });
+                }
+            }
+        }
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BitField, Register};
+
+    fn field(name: &str, bits: Bits, origin: Origin) -> BitField {
+        BitField {
+            name: name.to_string(),
+            bits,
+            origin,
+            ..Default::default()
+        }
+    }
+
+    fn register(name: &str, address: u32, bit_fields: Vec<BitField>) -> Register {
+        Register {
+            name: name.to_string(),
+            address,
+            bit_fields,
+            ..Default::default()
+        }
+    }
+
+    fn uart(registers: Vec<Register>) -> BTreeMap<String, Peripheral> {
+        let mut peripherals = BTreeMap::new();
+        peripherals.insert(
+            "UART".to_string(),
+            Peripheral {
+                registers,
+                ..Default::default()
+            },
+        );
+        peripherals
+    }
+
+    /// Issues of `peripherals` as displayed, which fixing reports the same way, and the model
+    /// left after the fix
+    fn check(
+        peripherals: BTreeMap<String, Peripheral>,
+        options: ValidateOptions,
+    ) -> (Vec<String>, BTreeMap<String, Peripheral>) {
+        let mut reported = peripherals.clone();
+        let issues = validate(&mut reported, &options);
+        let mut fixed = peripherals;
+        let fixed_issues = validate(
+            &mut fixed,
+            &ValidateOptions {
+                fix: true,
+                ..options
+            },
+        );
+        assert_eq!(issues.len(), fixed_issues.len());
+        (issues.iter().map(Issue::to_string).collect(), fixed)
+    }
+
+    fn names(peripherals: &BTreeMap<String, Peripheral>) -> Vec<String> {
+        peripherals["UART"]
+            .registers
+            .iter()
+            .flat_map(|r| {
+                std::iter::once(r.name.clone()).chain(r.bit_fields.iter().map(|f| f.name.clone()))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_overlapping_fields() {
+        let fields = vec![
+            field("A", Bits::Range(0..=3), Origin::HeaderComment),
+            field("B", Bits::Range(2..=5), Origin::HeaderComment),
+            field("C", Bits::Single(6), Origin::HeaderBit),
+        ];
+        let (issues, fixed) = check(uart(vec![register("CONF", 0, fields)]), Default::default());
+        assert_eq!(issues, ["UART.CONF: fields A and B overlap"]);
+        assert_eq!(names(&fixed), ["CONF", "A", "C"]);
+
+        let fields = vec![
+            field("A", Bits::Range(0..=3), Origin::HeaderComment),
+            field("B", Bits::Range(4..=5), Origin::HeaderComment),
+        ];
+        let (issues, _) = check(uart(vec![register("CONF", 0, fields)]), Default::default());
+        assert!(issues.is_empty());
+    }
+
+    #[test]
+    fn test_duplicate_address() {
+        let registers = vec![register("CONF", 4, vec![]), register("CTRL", 4, vec![])];
+        let (issues, fixed) = check(uart(registers), Default::default());
+        assert_eq!(issues, ["UART: registers CONF and CTRL share offset 0x4"]);
+        assert_eq!(names(&fixed), ["CONF"]);
+
+        // alternate registers share the offset on purpose
+        let mut alternate = register("CONF_BYTE", 4, vec![]);
+        alternate.alternate_register = Some("CONF".to_string());
+        let registers = vec![register("CONF", 4, vec![]), alternate];
+        let (issues, fixed) = check(uart(registers), Default::default());
+        assert!(issues.is_empty());
+        assert_eq!(names(&fixed), ["CONF", "CONF_BYTE"]);
+    }
+
+    #[test]
+    fn test_outside_address_block() {
+        let mut peripherals = uart(vec![
+            register("CONF", 0, vec![]),
+            register("FIFO", 8, vec![]),
+        ]);
+        peripherals.get_mut("UART").unwrap().block_size = Some(8);
+        let (issues, fixed) = check(peripherals, Default::default());
+        assert_eq!(
+            issues,
+            ["UART.FIFO: offset 0x8 is outside the address block"]
+        );
+        assert_eq!(names(&fixed), ["CONF"]);
+
+        // without a configured size the block spans all registers
+        let peripherals = uart(vec![
+            register("CONF", 0, vec![]),
+            register("FIFO", 8, vec![]),
+        ]);
+        let (issues, _) = check(peripherals, Default::default());
+        assert!(issues.is_empty());
+    }
+
+    #[test]
+    fn test_field_out_of_range() {
+        let fields = vec![
+            field("LOW", Bits::Range(0..=7), Origin::HeaderComment),
+            field("HIGH", Bits::Range(30..=32), Origin::HeaderComment),
+        ];
+        let (issues, fixed) = check(uart(vec![register("CONF", 0, fields)]), Default::default());
+        assert_eq!(issues, ["UART.CONF: field HIGH exceeds bit 31"]);
+        assert_eq!(names(&fixed), ["CONF", "LOW"]);
+
+        let fields = vec![field("HIGH", Bits::Range(29..=31), Origin::HeaderComment)];
+        let (issues, _) = check(uart(vec![register("CONF", 0, fields)]), Default::default());
+        assert!(issues.is_empty());
+    }
+
+    #[test]
+    fn test_misaligned() {
+        let registers = vec![register("CONF", 0, vec![]), register("FIFO", 2, vec![])];
+        let (issues, fixed) = check(uart(registers.clone()), Default::default());
+        assert_eq!(issues, ["UART.FIFO: offset 0x2 is not 4 byte aligned"]);
+        assert_eq!(names(&fixed), ["CONF"]);
+
+        let mut options = ValidateOptions::default();
+        options.alignment_overrides.insert("UART".to_string(), 1);
+        let (issues, fixed) = check(uart(registers), options);
+        assert!(issues.is_empty());
+        assert_eq!(names(&fixed), ["CONF", "FIFO"]);
+    }
+
+    #[test]
+    fn test_full_width_field() {
+        let fields = vec![field("DATE", Bits::Range(0..=31), Origin::HeaderMask)];
+        let (issues, fixed) = check(uart(vec![register("DATE", 0, fields)]), Default::default());
+        assert_eq!(
+            issues,
+            ["UART.DATE: field DATE is a full width mask, delete it with a patch if it isn't a field"]
+        );
+        assert_eq!(names(&fixed), ["DATE"]);
+
+        // only masks are suspicious, a register assumed to be one field is fine
+        let fields = vec![field("DATA", Bits::Range(0..=31), Origin::Assumed)];
+        let (issues, fixed) = check(uart(vec![register("DATA", 0, fields)]), Default::default());
+        assert!(issues.is_empty());
+        assert_eq!(names(&fixed), ["DATA", "DATA"]);
+    }
+
+    #[test]
+    fn test_field_total() {
+        let mut byte = register(
+            "CONF",
+            0,
+            vec![
+                field("LOW", Bits::Range(0..=7), Origin::HeaderComment),
+                field("EXTRA", Bits::Single(8), Origin::HeaderBit),
+            ],
+        );
+        byte.width = 8;
+        let (issues, fixed) = check(uart(vec![byte.clone()]), Default::default());
+        assert_eq!(
+            issues,
+            ["UART.CONF: fields add up to 9 bits in a 8 bit register"]
+        );
+        // only reported, nothing is dropped
+        assert_eq!(names(&fixed), ["CONF", "LOW", "EXTRA"]);
+
+        byte.bit_fields.pop();
+        let (issues, _) = check(uart(vec![byte]), Default::default());
+        assert!(issues.is_empty());
+    }
+
+    #[test]
+    fn test_missing_bits() {
+        let mut conf = register(
+            "CONF",
+            0,
+            vec![field("LOW", Bits::Range(0..=3), Origin::Doc)],
+        );
+        conf.width = 8;
+        let (issues, _) = check(uart(vec![conf.clone()]), Default::default());
+        assert!(issues.is_empty());
+
+        conf.complete = true;
+        let (issues, fixed) = check(uart(vec![conf]), Default::default());
+        assert_eq!(
+            issues,
+            ["UART.CONF: bits 0x000000f0 are documented but not covered by a field"]
+        );
+        assert_eq!(names(&fixed), ["CONF", "LOW"]);
+    }
+
+    #[test]
+    fn test_array_length() {
+        let registers = vec![
+            register("UART_CH0_CONF", 0, vec![]),
+            register("UART_CH1_CONF", 4, vec![]),
+        ];
+        let mut peripherals = uart(registers);
+        let counts = &mut peripherals.get_mut("UART").unwrap().counts;
+        counts.insert("UART_CHANNEL_NUM".to_string(), 3);
+        let (issues, fixed) = check(peripherals.clone(), Default::default());
+        assert_eq!(
+            issues,
+            ["UART.UART_CH%s_CONF: 2 elements but UART_CHANNEL_NUM is 3"]
+        );
+        assert_eq!(names(&fixed), ["UART_CH0_CONF", "UART_CH1_CONF"]);
+
+        let counts = &mut peripherals.get_mut("UART").unwrap().counts;
+        counts.insert("UART_CHANNEL_NUM".to_string(), 2);
+        let (issues, _) = check(peripherals, Default::default());
+        assert!(issues.is_empty());
+    }
+}
@@ -0,0 +1,42 @@
+//! Alternate views of one model, e.g. registers that behave differently while the ROM
+//! bootloader is running. Registers and fields without any view tags are part of every view.
+
+use crate::Peripheral;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The view used for the regular svd output
+pub const DEFAULT_VIEW: &str = "default";
+/// Registers as seen under the ROM bootloader
+pub const ROM_BOOT_VIEW: &str = "rom-boot";
+
+fn in_view(views: &[String], view: &str) -> bool {
+    views.is_empty() || views.iter().any(|v| v == view)
+}
+
+/// All views tagged anywhere in the model, always including the default view
+pub fn views(peripherals: &BTreeMap<String, Peripheral>) -> BTreeSet<String> {
+    let mut views = BTreeSet::new();
+    views.insert(DEFAULT_VIEW.to_string());
+    for r in peripherals.values().flat_map(|p| p.registers.iter()) {
+        views.extend(r.views.iter().cloned());
+        for f in &r.bit_fields {
+            views.extend(f.views.iter().cloned());
+        }
+    }
+    views
+}
+
+/// Copy of the model containing only the registers and fields that are part of `view`
+pub fn filter_view(
+    peripherals: &BTreeMap<String, Peripheral>,
+    view: &str,
+) -> BTreeMap<String, Peripheral> {
+    let mut filtered = peripherals.clone();
+    for p in filtered.values_mut() {
+        p.registers.retain(|r| in_view(&r.views, view));
+        for r in p.registers.iter_mut() {
+            r.bit_fields.retain(|f| in_view(&f.views, view));
+        }
+    }
+    filtered
+}
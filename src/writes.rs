@@ -0,0 +1,221 @@
+//! Registers where writing a one sets or clears a bit instead of storing it, such as
+//! `GPIO_OUT_W1TS`, `GPIO_OUT_W1TC` and the `*_INT_CLR` interrupt clear registers. They get the
+//! `modifiedWriteValues` of the svd, so PAC generators offer set and clear operations instead of
+//! plain writes that would seem to zero the other bits.
+
+use crate::{Peripheral, Type};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use svd_parser::ModifiedWriteValues;
+
+/// Effect of writing a one to a bit
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WriteValues {
+    OneToSet,
+    OneToClear,
+}
+
+impl From<WriteValues> for ModifiedWriteValues {
+    fn from(w: WriteValues) -> Self {
+        match w {
+            WriteValues::OneToSet => ModifiedWriteValues::OneToSet,
+            WriteValues::OneToClear => ModifiedWriteValues::OneToClear,
+        }
+    }
+}
+
+/// Write semantics of a register or field, replacing what the heuristics found
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WriteOverride {
+    /// `PERIPHERAL.REGISTER` or `PERIPHERAL.REGISTER.FIELD`
+    pub path: String,
+    /// Left out for bits storing what is written
+    #[serde(default)]
+    pub modified: Option<WriteValues>,
+    /// Access of the fields, `RO`, `RW` or `WO`, unchanged when left out
+    #[serde(default)]
+    pub access: Option<String>,
+}
+
+/// Name suffixes of registers and fields setting or clearing bits by writing a one
+const SUFFIXES: &[(&str, WriteValues)] = &[
+    ("_W1TS", WriteValues::OneToSet),
+    ("_W1TC", WriteValues::OneToClear),
+    ("INT_CLR", WriteValues::OneToClear),
+];
+
+fn by_name(name: &str) -> Option<WriteValues> {
+    SUFFIXES
+        .iter()
+        .find(|(suffix, _)| name.ends_with(suffix))
+        .map(|(_, w)| *w)
+}
+
+/// Set the write semantics of registers and fields named like set and clear registers, and of
+/// the registers `link_strobes` linked as the set or clear register of another. Their fields
+/// become write-only. Returns the number of registers given semantics, fields included
+pub fn detect_write_values(peripherals: &mut BTreeMap<String, Peripheral>) -> usize {
+    let mut detected = 0;
+    for p in peripherals.values_mut() {
+        let mut linked: Vec<(String, WriteValues)> = vec![];
+        for strobes in p.registers.iter().filter_map(|r| r.strobes.as_ref()) {
+            linked.extend(strobes.set.clone().map(|s| (s, WriteValues::OneToSet)));
+            linked.extend(strobes.clear.clone().map(|c| (c, WriteValues::OneToClear)));
+        }
+        for r in p.registers.iter_mut() {
+            let register = linked
+                .iter()
+                .find(|(name, _)| *name == r.name)
+                .map(|(_, w)| *w)
+                .or_else(|| by_name(&r.name));
+            let mut found = register.is_some();
+            r.modified_write = register;
+            for f in r.bit_fields.iter_mut() {
+                if register.is_some() {
+                    f.type_ = Type::WriteOnly;
+                } else if let Some(w) = by_name(&f.name) {
+                    f.modified_write = Some(w);
+                    f.type_ = Type::WriteOnly;
+                    found = true;
+                }
+            }
+            if found {
+                detected += 1;
+            }
+        }
+    }
+    detected
+}
+
+/// Apply the write semantics of the config, returning the number of applied overrides. Paths
+/// missing from the model are an error
+pub fn apply_write_overrides(
+    peripherals: &mut BTreeMap<String, Peripheral>,
+    overrides: &[WriteOverride],
+) -> Result<usize, String> {
+    for o in overrides {
+        let access = o.access.as_deref().map(Type::from_str).transpose()?;
+        let parts: Vec<&str> = o.path.split('.').collect();
+        let (peripheral, register, field) = match parts.as_slice() {
+            [p, r] => (p, r, None),
+            [p, r, f] => (p, r, Some(f)),
+            _ => return Err(format!("Invalid write override path {}", o.path)),
+        };
+        let r = peripherals
+            .get_mut(*peripheral)
+            .and_then(|p| p.registers.iter_mut().find(|r| r.name == *register))
+            .ok_or_else(|| format!("No register {}.{} to override", peripheral, register))?;
+        match field {
+            Some(field) => {
+                let f = r
+                    .bit_fields
+                    .iter_mut()
+                    .find(|f| f.name == **field)
+                    .ok_or_else(|| format!("No field {} to override", o.path))?;
+                f.modified_write = o.modified;
+                f.type_ = access.unwrap_or(f.type_);
+            }
+            None => {
+                r.modified_write = o.modified;
+                for f in r.bit_fields.iter_mut() {
+                    f.modified_write = None;
+                    f.type_ = access.unwrap_or(f.type_);
+                }
+            }
+        }
+    }
+    Ok(overrides.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strobes::link_strobes;
+    use crate::{BitField, Register};
+
+    #[test]
+    fn test_write_values() {
+        let field = |name: &str| BitField {
+            name: name.to_string(),
+            ..Default::default()
+        };
+        let register = |name: &str, fields: Vec<BitField>| Register {
+            name: name.to_string(),
+            bit_fields: fields,
+            ..Default::default()
+        };
+        let mut peripherals = BTreeMap::new();
+        peripherals.insert(
+            "GPIO".to_string(),
+            Peripheral {
+                registers: vec![
+                    register("GPIO_OUT", vec![field("GPIO_OUT_DATA")]),
+                    register("GPIO_OUT_W1TS", vec![field("GPIO_OUT_DATA_W1TS")]),
+                    register("GPIO_ENABLE_CLR", vec![]),
+                    register("GPIO_ENABLE", vec![]),
+                    register("GPIO_INT_CLR", vec![field("GPIO_INT_CLR")]),
+                    register("GPIO_CONF", vec![field("GPIO_FRC_INT_CLR")]),
+                ],
+                ..Default::default()
+            },
+        );
+        link_strobes(&mut peripherals);
+
+        assert_eq!(detect_write_values(&mut peripherals), 4);
+        let gpio = &peripherals["GPIO"];
+        let modified: Vec<Option<WriteValues>> =
+            gpio.registers.iter().map(|r| r.modified_write).collect();
+        assert_eq!(
+            modified,
+            vec![
+                None,
+                Some(WriteValues::OneToSet),
+                Some(WriteValues::OneToClear),
+                None,
+                Some(WriteValues::OneToClear),
+                None,
+            ]
+        );
+        assert_eq!(gpio.registers[0].bit_fields[0].type_, Type::ReadWrite);
+        let w1ts = &gpio.registers[1].bit_fields[0];
+        assert_eq!((w1ts.type_, w1ts.modified_write), (Type::WriteOnly, None));
+        let conf = &gpio.registers[5].bit_fields[0];
+        assert_eq!(
+            (conf.type_, conf.modified_write),
+            (Type::WriteOnly, Some(WriteValues::OneToClear))
+        );
+
+        let overrides: Vec<WriteOverride> = toml::from_str::<BTreeMap<String, _>>(
+            r#"
+[[write]]
+path = "GPIO.GPIO_CONF.GPIO_FRC_INT_CLR"
+access = "RW"
+
+[[write]]
+path = "GPIO.GPIO_OUT"
+modified = "one-to-clear"
+"#,
+        )
+        .unwrap()
+        .remove("write")
+        .unwrap();
+        assert_eq!(apply_write_overrides(&mut peripherals, &overrides), Ok(2));
+        let gpio = &peripherals["GPIO"];
+        let conf = &gpio.registers[5].bit_fields[0];
+        assert_eq!((conf.type_, conf.modified_write), (Type::ReadWrite, None));
+        assert_eq!(
+            gpio.registers[0].modified_write,
+            Some(WriteValues::OneToClear)
+        );
+
+        let missing = WriteOverride {
+            path: "GPIO.GPIO_MISSING".to_string(),
+            modified: None,
+            access: None,
+        };
+        assert!(apply_write_overrides(&mut peripherals, &[missing]).is_err());
+    }
+}
@@ -0,0 +1,127 @@
+//! Corpus of header excerpts in the styles found in the SDKs, each `tests/corpus/<name>.h`
+//! paired with `<name>.json`, the model parsed from it. The excerpts are parsed one at a
+//! time next to the shared `tests/corpus/soc.h`, with the default peripheral seeds.
+//!
+//! After an intended change of the parser output, regenerate the expected models with
+//!
+//! ```text
+//! $ UPDATE_CORPUS=1 cargo test --test corpus
+//! ```
+
+use header2svd::{config, parse_idf_with_options, Bits, ParseOptions, Peripheral, Provenance};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::Path;
+
+/// The parsed model as JSON, peripherals without registers are left out
+fn model_json(peripherals: &BTreeMap<String, Peripheral>) -> Value {
+    let mut model = serde_json::Map::new();
+    for (name, p) in peripherals.iter().filter(|(_, p)| !p.registers.is_empty()) {
+        let registers: Vec<Value> = p
+            .registers
+            .iter()
+            .map(|r| {
+                let line = r.provenance.iter().find_map(|p| match p {
+                    Provenance::Header { line, .. } => *line,
+                    _ => None,
+                });
+                let fields: Vec<Value> = r
+                    .bit_fields
+                    .iter()
+                    .map(|f| {
+                        let bits = match &f.bits {
+                            Bits::Single(b) => format!("{}", b),
+                            Bits::Range(r) => format!("{}:{}", r.end(), r.start()),
+                        };
+                        json!({
+                            "name": f.name,
+                            "bits": bits,
+                            "access": format!("{:?}", f.type_),
+                            "origin": format!("{:?}", f.origin),
+                            "description": f.description,
+                        })
+                    })
+                    .collect();
+                json!({
+                    "name": r.name,
+                    "offset": format!("{:#x}", r.address),
+                    "line": line,
+                    "description": r.description,
+                    "fields": fields,
+                })
+            })
+            .collect();
+        let mut peripheral = json!({
+            "address": format!("{:#x}", p.address),
+            "registers": registers,
+        });
+        if !p.counts.is_empty() {
+            peripheral["counts"] = json!(p.counts);
+        }
+        model.insert(name.clone(), peripheral);
+    }
+    Value::Object(model)
+}
+
+/// Parse `header` as the only register header next to the corpus `soc.h`
+fn parse_excerpt(soc_h: &Path, header: &Path, work: &Path) -> Value {
+    fs::create_dir_all(work).unwrap();
+    fs::copy(soc_h, work.join("soc.h")).unwrap();
+    fs::copy(header, work.join("excerpt_reg.h")).unwrap();
+    let options = ParseOptions {
+        seeds: config::default_seeds(),
+        ..Default::default()
+    };
+    let model = model_json(&parse_idf_with_options(
+        &format!("{}/", work.display()),
+        &options,
+    ));
+    fs::remove_dir_all(work).unwrap();
+    model
+}
+
+#[test]
+fn corpus() {
+    let update = std::env::var_os("UPDATE_CORPUS").is_some();
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus");
+    let soc_h = root.join("soc.h");
+    let mut excerpts: Vec<_> = fs::read_dir(&root)
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .filter(|p| p.extension() == Some(OsStr::new("h")) && *p != soc_h)
+        .collect();
+    excerpts.sort();
+    assert!(!excerpts.is_empty(), "no excerpts in {}", root.display());
+
+    let work = std::env::temp_dir().join(format!("header2svd-corpus-{}", std::process::id()));
+    let mut mismatched = vec![];
+    for excerpt in excerpts {
+        let actual = parse_excerpt(&soc_h, &excerpt, &work);
+        let expected_path = excerpt.with_extension("json");
+        if update {
+            let text = serde_json::to_string_pretty(&actual).unwrap();
+            fs::write(&expected_path, text + "\n").unwrap();
+            continue;
+        }
+        let expected: Value = fs::read_to_string(&expected_path)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or(Value::Null);
+        if expected != actual {
+            println!(
+                "{}: expected\n{}\nparsed\n{}",
+                excerpt.display(),
+                serde_json::to_string_pretty(&expected).unwrap(),
+                serde_json::to_string_pretty(&actual).unwrap()
+            );
+            mismatched.push(excerpt.display().to_string());
+        }
+    }
+    assert!(
+        mismatched.is_empty(),
+        "models differ, rerun with UPDATE_CORPUS=1 if intended: {:?}",
+        mismatched
+    );
+}
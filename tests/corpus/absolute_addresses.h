@@ -0,0 +1,12 @@
+#define EFUSE_BLK0_RDATA0_REG 0x3ff5a000
+/* EFUSE_RD_WIFI_MAC_CRC_LOW : RO ;bitpos:[31:0] ;default: 32'h0 ; */
+/*description: read for MAC address*/
+
+#define EFUSE_BLK0_RDATA1_REG (0x3ff5a004)
+/* EFUSE_RD_WIFI_MAC_CRC_HIGH : RO ;bitpos:[23:0] ;default: 24'h0 ; */
+/*description: read for MAC address*/
+
+#define SLC_CONF0_REG 0x3FF58000
+/* SLC_SLC0_RX_LINK_EN : R/W ;bitpos:[0] ;default: 1'b0 ; */
+/*description: outside of every known peripheral*/
+
@@ -0,0 +1,12 @@
+#define TIMG_T0CONFIG_REG(i)          (REG_TIMG_BASE(i) + 0x0000)
+/* TIMG_T0_EN : R/W ;bitpos:[31] ;default: 1'h0 ; */
+/*description: When set  timer 0 time-base counter is enabled*/
+/* TIMG_T0_INCREASE : RW ;bitpos:[30] ;default: 1'h1 ; */
+/*description: spelled without slash*/
+/* TIMG_T0_LO : RO ;bitpos:[29] ;default: 32'h0 ; */
+/*description: read only*/
+/* TIMG_T0_UPDATE : WO ;bitpos:[28] ;default: 32'h0 ; */
+/*description: write only*/
+/* TIMG_T0_ALARM_EN : R/W/SC ;bitpos:[10] ;default: 1'h0 ; */
+/*description: self clearing spelling*/
+
@@ -0,0 +1,11 @@
+#define RTC_CNTL_STATE0_REG          (DR_REG_RTCCNTL_BASE + 0x18)
+/*  RTC_CNTL_SLEEP_EN  :  R/W  ;bitpos:[31] ;default: 1'd0 ; */
+/*description: extra spaces around the name and access*/
+/* RTC_CNTL_SLP_REJECT : R/W ;bitpos:[30];default: 1'd0 ; */
+/*description: no space before default*/
+
+#define RTC_CNTL_TIMER1_REG          (DR_REG_RTCCNTL_BASE + 0x1c)
+/* RTC_CNTL_PLL_BUF_WAIT : R/W ;bitpos:[31:24] ;default: 8'd40 ; */
+/*description:
+ description text on its own line*/
+
@@ -0,0 +1,10 @@
+#define RMT_CH0DATA_REG          (DR_REG_RMT_BASE + 0x0000)
+/* RMT_CH0DATA : RO ;bitpos:[31:0] ;default: 32'h0 ; */
+/*description: The R/W ram address for channel0 by apb fifo access*/
+
+#define RMT_CHNCONF0_REG(n)          (DR_REG_RMT_BASE + 0x0020 + (n) * 8)
+/* RMT_CLK_EN : R/W ;bitpos:[31] ;default: 1'h0 ; */
+/*description: This bit  is used  to control clock.*/
+
+#define RMT_CHANNEL_NUM 8
+
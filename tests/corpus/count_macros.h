@@ -0,0 +1,10 @@
+#define LEDC_CHANNEL_NUM 8
+#define LEDC_TIMER_NUM (4) /* per speed mode */
+#define SOC_LEDC_SPEED_NUM 2
+#define PCNT_UNIT_NUM 8
+#define EFUSE_BLK_NUM  4 // key blocks
+
+#define LEDC_HSCH0_CONF0_REG          (DR_REG_LEDC_BASE + 0x0000)
+/* LEDC_IDLE_LV_HSCH0 : R/W ;bitpos:[3] ;default: 1'h0 ; */
+/*description: This bit is used to control the output value when high speed channel0 is off.*/
+
@@ -0,0 +1,10 @@
+#define DPORT_PRO_CACHE_CTRL_REG          (DR_REG_DPORT_BASE + 0x040)
+/* DPORT_PRO_DRAM_HL : R/W ;bitpos:[16] ;default: 1'b0 ; */
+/*description: description spread over
+ three lines of the
+ header comment*/
+#define DPORT_PRO_DRAM_HL  (BIT(16))
+/* DPORT_PRO_CACHE_ENABLE : R/W ;bitpos:[3] ;default: 1'b0 ; */
+/*description: */
+#define DPORT_PRO_CACHE_ENABLE  (BIT(3))
+
@@ -0,0 +1,14 @@
+#define DPORT_APP_INTR_STATUS_0_REG          (DR_REG_DPORT_BASE + 0x1F0)
+/* DPORT_APP_INTR_STATUS_0 : RO ;bitpos:[31:0] ;default: 32'h0 ; */
+/*description: */
+
+#define DPORT_DATE_REG          (DR_REG_DPORT_BASE + 0xFFC)
+/* DPORT_DATE : R/W ;bitpos:[27:0] ;default: 28'h1605190 ; */
+/*description: */
+#define DPORT_DPORT_DATE  0x0FFFFFFF
+#define DPORT_DATE_VERSION 0x1605190
+
+#define DPORT_PRO_BOOT_REMAP_CTRL_REG          (DR_REG_DPORT_BASE + 0x000)
+/* DPORT_PRO_BOOT_REMAP : R/W ;bitpos:[0] ;default: 1'b0 ; */
+/*description: registers are sorted by offset*/
+
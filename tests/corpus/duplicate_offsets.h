@@ -0,0 +1,12 @@
+#define SPI_ENCRYPT_MODE_REG          (DR_REG_SPI_ENCRYPT_BASE + 0x20)
+/* SPI_ENCRYPT_MODE : R/W ;bitpos:[2:0] ;default: 3'b0 ; */
+/*description: peripheral base only known as PERIPHS_*_BASEADDR*/
+
+#define TIMG_RTCCALICFG_REG(i)          (REG_TIMG_BASE(i) + 0x0068)
+/* TIMG_RTC_CALI_START : R/W ;bitpos:[31] ;default: 1'h0 ; */
+/*description: */
+
+#define TIMG_RTCCALICFG_ALIAS_REG(i)          (REG_TIMG_BASE(i) + 0x0068)
+/* TIMG_RTC_CALI_START_CYCLING : R/W ;bitpos:[12] ;default: 1'd1 ; */
+/*description: same offset under another name*/
+
@@ -0,0 +1,7 @@
+#define FE_GEN_CTRL          (DR_REG_FE_BASE + 0x0090)
+#define FE_IQEST_CONF1_REG          (DR_REG_FE_BASE + 0x00A0)
+/* FE_IQEST_EN : R/W ;bitpos:[0] ;default: 1'b0 ; */
+#define FE_IQEST_EN  (BIT(0))
+/* FE_IQEST_STEP : R/W ;bitpos:[7:1] ;default: 7'h0 ; */
+/*description: only the second field is described*/
+
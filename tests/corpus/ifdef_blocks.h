@@ -0,0 +1,26 @@
+#ifdef CONFIG_ESP32_REV_MIN_3
+#define APB_CTRL_DATE_REG          (DR_REG_APB_CTRL_BASE + 0x7C)
+/* APB_CTRL_DATE : R/W ;bitpos:[31:0] ;default: 32'h16111700 ; */
+/*description: rev 3 version*/
+
+#else
+#define APB_CTRL_DATE_REG          (DR_REG_APB_CTRL_BASE + 0x3FC)
+/* APB_CTRL_DATE : R/W ;bitpos:[31:0] ;default: 32'h16042000 ; */
+/*description: */
+
+#endif
+
+#if 0
+#define APB_CTRL_UNUSED_REG          (DR_REG_APB_CTRL_BASE + 0x100)
+/* APB_CTRL_UNUSED : R/W ;bitpos:[0] ;default: 1'b0 ; */
+/*description: never compiled*/
+
+#endif
+
+#ifndef APB_CTRL_SYSCLK_CONF_OFFSET
+#define APB_CTRL_SYSCLK_CONF_OFFSET 0x0
+#endif
+#define APB_CTRL_SYSCLK_CONF_REG          (DR_REG_APB_CTRL_BASE + APB_CTRL_SYSCLK_CONF_OFFSET)
+/* APB_CTRL_QUICK_CLK_CHNG : R/W ;bitpos:[13] ;default: 1'b1 ; */
+/*description: */
+
@@ -0,0 +1,15 @@
+#define REG_SPI_BASE(i)     (DR_REG_SPI1_BASE - (i-1) * 0x1000 + ( i == 0 ? 0x1000 : 0 ) )
+
+#define SPI_W0_REG(i)          (REG_SPI_BASE(i) + 0x80)
+/* SPI_BUF0 : R/W ;bitpos:[31:0] ;default: 32'h0 ; */
+/*description: data buffer*/
+#define SPI_BUF0  0xFFFFFFFF
+#define SPI_BUF0_M  ((SPI_BUF0_V)<<(SPI_BUF0_S))
+#define SPI_BUF0_V  0xFFFFFFFF
+#define SPI_BUF0_S  0
+
+#define SPI_W1_REG(i)          (REG_SPI_BASE(i) + 0x80 + 4 * 1)
+/* SPI_BUF1 : R/W ;bitpos:[31:0] ;default: 32'h0 ; */
+/*description: data buffer*/
+#define SPI_BUF1  0xFFFFFFFF
+
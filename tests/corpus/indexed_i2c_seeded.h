@@ -0,0 +1,25 @@
+#define REG_I2C_BASE(i)     (DR_REG_I2C_EXT_BASE + (i) * 0x14000 )
+
+#define I2C_SCL_LOW_PERIOD_REG(i)          (REG_I2C_BASE(i) + 0x0000)
+/* I2C_SCL_LOW_PERIOD : R/W ;bitpos:[13:0] ;default: 14'b0 ; */
+/*description: This register is used to configure the  low level width of SCL clock.*/
+#define I2C_SCL_LOW_PERIOD  0x00003FFF
+#define I2C_SCL_LOW_PERIOD_M  ((I2C_SCL_LOW_PERIOD_V)<<(I2C_SCL_LOW_PERIOD_S))
+#define I2C_SCL_LOW_PERIOD_V  0x3FFF
+#define I2C_SCL_LOW_PERIOD_S  0
+
+#define I2C_CTR_REG(i)          (REG_I2C_BASE(i) + 0x0004)
+/* I2C_CLK_EN : R/W ;bitpos:[6] ;default: 1'b0 ; */
+/*description: This is the clock gating control bit for reading or writing registers.*/
+#define I2C_CLK_EN  (BIT(6))
+#define I2C_CLK_EN_M  (BIT(6))
+#define I2C_CLK_EN_V  0x1
+#define I2C_CLK_EN_S  6
+/* I2C_MS_MODE : R/W ;bitpos:[4] ;default: 1'b0 ; */
+/*description: Set this bit to configure the module as i2c master  clear this bit
+ to configure the module as i2c slave.*/
+#define I2C_MS_MODE  (BIT(4))
+#define I2C_MS_MODE_M  (BIT(4))
+#define I2C_MS_MODE_V  0x1
+#define I2C_MS_MODE_S  4
+
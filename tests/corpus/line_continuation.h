@@ -0,0 +1,10 @@
+#define RTC_IO_TOUCH_PAD0_REG          (DR_REG_RTCIO_BASE + \
+                                        0x94)
+/* RTC_IO_TOUCH_PAD0_HOLD : R/W ;bitpos:[31] ;default: 1'd0 ; */
+/*description: hold the current value of the output when setting the hold to Ò1Ó*/
+
+#define RTC_IO_TOUCH_CFG_REG \
+    (DR_REG_RTCIO_BASE + 0x90)
+/* RTC_IO_TOUCH_XPD_BIAS : R/W ;bitpos:[31] ;default: 1'd0 ; */
+/*description: touch XPD_BIAS*/
+
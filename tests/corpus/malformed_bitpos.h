@@ -0,0 +1,11 @@
+#define IO_MUX_PIN_CTRL          (DR_REG_IO_MUX_BASE + 0x00)
+#define IO_MUX_GPIO36_REG          (DR_REG_IO_MUX_BASE + 0x04)
+/* MCU_SEL : R/W ;bitpos:[14:12] ;default: x ; */
+/*description: configures IO_MUX function*/
+/* FUN_DRV : R/W ;bitpos:[11:10] ;default: 2'b10 ; */
+/*description: configures drive strength*/
+/* FUN_IE : R/W ;bitpos:[9:x] ;default: 1'b0 ; */
+/*description: malformed bit position*/
+#define FUN_IE_V 0x1
+#define FUN_IE_S 9
+
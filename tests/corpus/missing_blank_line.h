@@ -0,0 +1,7 @@
+#define UHCI_CONF0_REG(i)          (REG_UHCI_BASE(i) + 0x0)
+/* UHCI_UART0_CE : R/W ;bitpos:[9] ;default: 1'b0 ; */
+/*description: Set this bit to use UART to transmit or receive data.*/
+#define UHCI_INT_RAW_REG(i)          (REG_UHCI_BASE(i) + 0x4)
+/* UHCI_RX_START_INT_RAW : RO ;bitpos:[0] ;default: 1'b0 ; */
+/*description: When a separator char has been sent  it will produce uhci_rx_start_int interrupt.*/
+
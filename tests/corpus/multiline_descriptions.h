@@ -0,0 +1,12 @@
+#define SENS_SAR_START_FORCE_REG          (DR_REG_SENS_BASE + 0x002c)
+/* SENS_SAR2_PWDET_EN : R/W ;bitpos:[4] ;default: 1'b0 ; */
+/*description: N/A*/
+/* SENS_SAR1_BIT_WIDTH : R/W ;bitpos:[1:0] ;default: 2'b11 ; */
+/*description: 00: 9 bit  01: 10 bits  10: 11bits
+ 11: 12bits*/
+
+#define SENS_SAR_MEAS_WAIT1_REG          (DR_REG_SENS_BASE + 0x000c)
+/* SENS_SAR_AMP_WAIT1 : R/W ;bitpos:[15:0] ;default: 16'd10 ; */
+/*description:
+ wait until the amplifier settles*/
+
@@ -0,0 +1,17 @@
+#if defined(CONFIG_A) || !defined(CONFIG_B)
+#  if CONFIG_LEVEL > 2
+#define SENS_SAR_READ_CTRL_REG          (DR_REG_SENS_BASE + 0x0100)
+#  else
+#define SENS_SAR_READ_CTRL_REG          (DR_REG_SENS_BASE + 0x0000)
+#  endif
+/* SENS_SAR1_DATA_INV : R/W ;bitpos:[28] ;default: 1'd0 ; */
+/*description: Invert SAR ADC1 data*/
+
+#endif
+#ifdef CONFIG_B
+#define SENS_SAR_READ_STATUS1_REG          (DR_REG_SENS_BASE + 0x0004)
+/* SENS_SAR1_READER_STATUS : RO ;bitpos:[31:0] ;default: 32'h0 ; */
+/*description: */
+
+#endif
+
@@ -0,0 +1,12 @@
+#define FE2_TX_INTERP_CTRL_REG          (DR_REG_FE2_BASE + 0X00F0)
+/* FE2_TX_INF_FORCE_PD : R/W ;bitpos:[9] ;default: 1'b0 ; */
+/*description: upper case hex prefix*/
+
+#define FE2_TX_INTERP_STATE_REG          (DR_REG_FE2_BASE + 0x00F4u)
+/* FE2_TX_INF_STATE : RO ;bitpos:[3:0] ;default: 4'h0 ; */
+/*description: unsigned suffix*/
+
+#define FE2_DATE_REG          (DR_REG_FE2_BASE + 252)
+/* FE2_DATE : R/W ;bitpos:[31:0] ;default: 32'h0 ; */
+/*description: offset without a prefix, read as hex*/
+
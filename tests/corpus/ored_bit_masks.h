@@ -0,0 +1,24 @@
+#define GPIO_STATUS_W1TS_REG          (DR_REG_GPIO_BASE + 0x0048)
+/* GPIO_STATUS_INT_W1TS : WO ;bitpos:[31:0] ;default: x ; */
+/*description: writing 1 into a bit in this register will set the related bit in GPIO_STATUS_INTERRUPT*/
+#define GPIO_STATUS_INT_W1TS  0xFFFFFFFF
+#define GPIO_STATUS_INT_W1TS_M  ((GPIO_STATUS_INT_W1TS_V)<<(GPIO_STATUS_INT_W1TS_S))
+#define GPIO_STATUS_INT_W1TS_V  0xFFFFFFFF
+#define GPIO_STATUS_INT_W1TS_S  0
+
+#define GPIO_PIN_INT_MASK  (BIT(13)|BIT(14)|BIT(15)|BIT(16)|BIT(17))
+#define GPIO_PIN0_REG          (DR_REG_GPIO_BASE + 0x0088)
+/* GPIO_PIN0_INT_ENA : R/W ;bitpos:[17:13] ;default: x ; */
+/*description: bit0: APP CPU interrupt enable  bit1: APP CPU non-maskable interrupt enable
+  bit3: PRO CPU interrupt enable  bit4:  PRO CPU non-maskable interrupt enable  bit5: SDIO's extent interrupt enable*/
+#define GPIO_PIN0_INT_ENA  0x0000001F
+#define GPIO_PIN0_INT_ENA_M  ((GPIO_PIN0_INT_ENA_V)<<(GPIO_PIN0_INT_ENA_S))
+#define GPIO_PIN0_INT_ENA_V  0x1F
+#define GPIO_PIN0_INT_ENA_S  13
+/* GPIO_PIN0_PAD_DRIVER : R/W ;bitpos:[2] ;default: x ; */
+/*description: if set to 0: normal output  if set to 1: open drain*/
+#define GPIO_PIN0_PAD_DRIVER  (BIT(2))
+#define GPIO_PIN0_PAD_DRIVER_M  (BIT(2))
+#define GPIO_PIN0_PAD_DRIVER_V  0x1
+#define GPIO_PIN0_PAD_DRIVER_S  2
+
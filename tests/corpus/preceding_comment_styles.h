@@ -0,0 +1,21 @@
+/* Pulse counter unit 0 configuration */
+#define PCNT_U0_CONF0_REG          (DR_REG_PCNT_BASE + 0x0000)
+/* PCNT_FILTER_EN_U0 : R/W ;bitpos:[10] ;default: 1'b1 ; */
+/*description: This is the enable bit for filtering input signals for unit0.*/
+
+// Pulse counter unit 0 thresholds
+#define PCNT_U0_CONF1_REG          (DR_REG_PCNT_BASE + 0x0004)
+/* PCNT_CNT_THRES0_U0 : R/W ;bitpos:[15:0] ;default: 10'h0 ; */
+/*description: This register is used to configure thres0 value for unit0.*/
+
+/** Pulse counter unit 0 limits */
+#define PCNT_U0_CONF2_REG          (DR_REG_PCNT_BASE + 0x0008)
+/* PCNT_CNT_L_LIM_U0 : R/W ;bitpos:[31:16] ;default: 10'h0 ; */
+/*description: This register is used to confiugre thr_l_lim value for unit0.*/
+
+/* Not a description: a define follows */
+#define PCNT_UNIT_MAX 8
+#define PCNT_U0_STATUS_REG          (DR_REG_PCNT_BASE + 0x0090)
+/* PCNT_CORE_STATUS_U0 : RO ;bitpos:[31:0] ;default: 32'h0 ; */
+/*description: */
+
@@ -0,0 +1,24 @@
+#ifndef _SOC_SOC_H_
+#define _SOC_SOC_H_
+
+#define DR_REG_DPORT_BASE                       0x3ff00000
+#define DR_REG_UART_BASE                        0x3ff40000
+#define DR_REG_SPI1_BASE                        0x3ff42000
+#define DR_REG_GPIO_BASE                        0x3ff44000
+#define DR_REG_FE2_BASE                         0x3ff45000
+#define DR_REG_FE_BASE                          0x3ff46000
+#define DR_REG_RTCCNTL_BASE                     0x3ff48000
+#define DR_REG_RTCIO_BASE                       0x3ff48400
+#define DR_REG_SENS_BASE                        0x3ff48800
+#define DR_REG_IO_MUX_BASE                      0x3ff49000
+#define DR_REG_UHCI1_BASE                       0x3ff4C000
+#define DR_REG_I2S_BASE                         0x3ff4F000
+#define DR_REG_RMT_BASE                         0x3ff56000
+#define DR_REG_PCNT_BASE                        0x3ff57000
+#define DR_REG_LEDC_BASE                        0x3ff59000
+#define DR_REG_EFUSE_BASE                       0x3ff5A000
+#define DR_REG_TIMERGROUP0_BASE                 0x3ff5F000
+#define DR_REG_APB_CTRL_BASE                    0x3ff66000
+#define PERIPHS_SPI_ENCRYPT_BASEADDR            0x3ff5B000
+
+#endif /* _SOC_SOC_H_ */
@@ -0,0 +1,21 @@
+#ifndef _SOC_FE_STRUCT_H_
+#define _SOC_FE_STRUCT_H_
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef volatile struct {
+    union {
+        struct {
+            uint32_t iqest_en:    1;      /* enable */
+            uint32_t reserved1:   31;
+        };
+        uint32_t val;
+    } iqest_conf1;
+} fe_dev_t;
+extern fe_dev_t FE;
+
+#ifdef __cplusplus
+}
+#endif
+#endif
@@ -0,0 +1,12 @@
+#define LEDC_CONF_REG          (DR_REG_LEDC_BASE + 0x0190) /* LEDC global configuration */
+/* LEDC_APB_CLK_SEL : R/W ;bitpos:[0] ;default: 1'b0 ; */
+/*description: This bit is used to set the frequency of slow_clk.*/
+
+#define LEDC_INT_RAW_REG          (DR_REG_LEDC_BASE + 0x0180) // raw interrupt status
+/* LEDC_HSTIMER0_OVF_INT_RAW : RO ;bitpos:[0] ;default: 1'h0 ; */
+/*description: The interrupt raw bit for high speed channel0  counter overflow.*/
+
+#define LEDC_INT_ST_REG          (DR_REG_LEDC_BASE + 0x0184) /**< masked interrupt status */
+/* LEDC_HSTIMER0_OVF_INT_ST : RO ;bitpos:[0] ;default: 1'h0 ; */
+/*description: The interrupt status bit for high speed channel0  counter overflow event.*/
+
@@ -0,0 +1,15 @@
+#define I2S_CONF_REG          (DR_REG_I2S_BASE + 0x0008)
+#define I2S_TX_RESET  (BIT(0))
+#define I2S_TX_RESET_M  (BIT(0))
+#define I2S_TX_RESET_V  0x1
+#define I2S_TX_RESET_S  0
+#define I2S_TX_SLAVE_MOD  (BIT(8))
+#define I2S_TX_SLAVE_MOD_M  (BIT(8))
+#define I2S_TX_SLAVE_MOD_V  0x1
+#define I2S_TX_SLAVE_MOD_S  8
+
+#define I2S_TIMING_REG          (DR_REG_I2S_BASE + 0x001c)
+#define I2S_TX_BCK_IN_DELAY  0x00000003
+#define I2S_TX_BCK_IN_DELAY_S  0
+#define I2S_TX_BCK_IN_DELAY_V  0x3
+
@@ -0,0 +1,8 @@
+#define SDIO_SLC_CONF0_REG          (DR_REG_SLC_BASE + 0x0)
+/* SDIO_SLC0_TX_RST : R/W ;bitpos:[0] ;default: 1'b0 ; */
+/*description: peripheral without a base address in soc.h*/
+
+#define UART_ID_REG(i)          (REG_UART_BASE(i) + 0x78)
+/* UART_ID : R/W ;bitpos:[31:0] ;default: 32'h000500 ; */
+/*description: indexed registers of a known peripheral still parse*/
+
@@ -0,0 +1,13 @@
+#define EFUSE_BLK1_RDATA0_REG          (DR_REG_EFUSE_BASE + EFUSE_BLK1_OFFSET)
+/* EFUSE_BLK1_DOUT0 : RO ;bitpos:[31:0] ;default: 32'h0 ; */
+/*description: the offset is never defined*/
+
+#define EFUSE_BLK2_OFFSET 0x58
+#define EFUSE_BLK2_RDATA0_REG          (DR_REG_EFUSE_BASE + EFUSE_BLK2_OFFSET)
+/* EFUSE_BLK2_DOUT0 : RO ;bitpos:[31:0] ;default: 32'h0 ; */
+/*description: the offset is defined above*/
+
+#define EFUSE_BLK3_RDATA0_REG          (DR_REG_EFUSE_BASE + (EFUSE_BLK2_OFFSET + 0x20))
+/* EFUSE_BLK3_DOUT0 : RO ;bitpos:[31:0] ;default: 32'h0 ; */
+/*description: the offset is an expression of a define*/
+
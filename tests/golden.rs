@@ -0,0 +1,119 @@
+//! Snapshot tests of the header parser. Every directory in `tests/golden` holds a `soc.h`,
+//! register headers and `expected.txt`, a plain text dump of the model parsed from them. An
+//! optional `defines` file lists `NAME VALUE` preprocessor defines, one per line.
+//!
+//! After an intended change of the parser output, regenerate the snapshots with
+//!
+//! ```text
+//! $ UPDATE_GOLDEN=1 cargo test --test golden
+//! ```
+//! and review the changes to `expected.txt` like any other diff.
+
+use header2svd::{parse_idf_with_options, Bits, ParseOptions, Peripheral};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+/// Stable text rendering of the model, with the fixture directory left out of file names
+fn dump(peripherals: &BTreeMap<String, Peripheral>, dir: &str) -> String {
+    let mut out = String::new();
+    for (name, p) in peripherals {
+        writeln!(out, "{} @ {:#010x} \"{}\"", name, p.address, p.description).unwrap();
+        for intr in &p.interrupts {
+            writeln!(
+                out,
+                "  interrupt {} = {} {:?}",
+                intr.name, intr.value, intr.description
+            )
+            .unwrap();
+        }
+        for (count, value) in &p.counts {
+            writeln!(out, "  count {} = {}", count, value).unwrap();
+        }
+        for r in &p.registers {
+            let provenance: Vec<String> = r.provenance.iter().map(|p| p.to_string()).collect();
+            writeln!(
+                out,
+                "  {:#05x} {} \"{}\" ({})",
+                r.address,
+                r.name,
+                r.description,
+                provenance.join(", ").replace(dir, "")
+            )
+            .unwrap();
+            for f in &r.bit_fields {
+                let bits = match &f.bits {
+                    Bits::Single(b) => format!("{}", b),
+                    Bits::Range(r) => format!("{}:{}", r.end(), r.start()),
+                };
+                writeln!(
+                    out,
+                    "    [{}] {} {:?} {:?} \"{}\"",
+                    bits, f.name, f.type_, f.origin, f.description
+                )
+                .unwrap();
+            }
+        }
+    }
+    out
+}
+
+#[test]
+fn golden() {
+    let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let mut cases: Vec<_> = fs::read_dir(&root)
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .filter(|p| p.is_dir())
+        .collect();
+    cases.sort();
+    assert!(!cases.is_empty(), "no golden cases in {}", root.display());
+
+    let mut mismatched = vec![];
+    for case in cases {
+        let dir = format!("{}/", case.display());
+        let defines = fs::read_to_string(case.join("defines")).unwrap_or_default();
+        let options = ParseOptions {
+            defines: defines
+                .lines()
+                .filter_map(|l| {
+                    let mut parts = l.split_whitespace();
+                    Some((
+                        parts.next()?.to_string(),
+                        parts.next().unwrap_or("1").to_string(),
+                    ))
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let actual = dump(&parse_idf_with_options(&dir, &options), &dir);
+        let expected_path = case.join("expected.txt");
+        if update {
+            fs::write(&expected_path, &actual).unwrap();
+            continue;
+        }
+        let expected = fs::read_to_string(&expected_path).unwrap_or_default();
+        if expected != actual {
+            let line = expected
+                .lines()
+                .zip(actual.lines())
+                .position(|(e, a)| e != a)
+                .unwrap_or_else(|| expected.lines().count().min(actual.lines().count()));
+            println!(
+                "{}: differs from line {}\n--- expected\n{}\n--- actual\n{}",
+                expected_path.display(),
+                line + 1,
+                expected.lines().nth(line).unwrap_or("<end of file>"),
+                actual.lines().nth(line).unwrap_or("<end of file>")
+            );
+            mismatched.push(expected_path.display().to_string());
+        }
+    }
+    assert!(
+        mismatched.is_empty(),
+        "snapshots differ, rerun with UPDATE_GOLDEN=1 if intended: {:?}",
+        mismatched
+    );
+}
@@ -0,0 +1,69 @@
+#include "soc.h"
+
+#define GPIO_BLOCK_OFFSET 0x80
+
+/* Preceding comments describe the next register */
+#define GPIO_OUT_REG          (DR_REG_GPIO_BASE + 0x0004)
+/* GPIO_OUT_DATA : R/W ;bitpos:[31:0] ;default: x ; */
+/*description: GPIO0~31 output value*/
+#define GPIO_OUT_DATA  0xFFFFFFFF
+
+#define GPIO_OUT_W1TS_REG          (DR_REG_GPIO_BASE + 0x0008) /* write 1 to set */
+/* GPIO_OUT_DATA_W1TS : WO ;bitpos:[31:0] ;default: x ; */
+/*description: writing 1 into a bit in this register will
+ set the related bit in GPIO_OUT_DATA*/
+#define GPIO_OUT_DATA_W1TS  0xFFFFFFFF
+
+// line comment ignored, a define follows
+#define GPIO_UNUSED 1
+#define GPIO_ENABLE_REG          (DR_REG_GPIO_BASE + 0x0020)
+/* GPIO_ENABLE_DATA : R/W ;bitpos:[31:0] ;default: x ; */
+/*description: GPIO0~31 output enable*/
+#define GPIO_ENABLE_DATA  0xFFFFFFFF
+/* no blank line before the next field */
+/* GPIO_ENABLE_KEEP : RW ;bitpos:[0] ;default: 1'b0 ; */
+/*description: unused*/
+
+#if defined(CHIP_REV) && CHIP_REV >= 2
+#define GPIO_STRAP_REG          (DR_REG_GPIO_BASE + 0x0040)
+/* GPIO_STRAPPING : RO ;bitpos:[15:0] ;default:  ; */
+/*description: revision 2 strapping*/
+
+#else
+#define GPIO_STRAP_REG          (DR_REG_GPIO_BASE + 0x0038)
+/* GPIO_STRAPPING : RO ;bitpos:[15:0] ;default:  ; */
+/*description: GPIO strapping results*/
+
+#endif
+
+#define GPIO_BLOCK_REG          (DR_REG_GPIO_BASE + GPIO_BLOCK_OFFSET + 4)
+/* GPIO_BLOCK_DATA : BAD ;bitpos:[3:0] ;default: 4'h0 ; */
+/*description: offset from a symbol, unknown access type*/
+
+#define GPIO_UNKNOWN_REG          (DR_REG_GPIO_BASE + GPIO_NOT_DEFINED)
+/* GPIO_UNKNOWN : R/W ;bitpos:[0] ;default: 1'b0 ; */
+/*description: the offset can not be resolved*/
+
+#define GPIO_PIN_REG(i)          (DR_REG_GPIO_BASE + 0x0088 + (i) * 4)
+/* GPIO_PIN_INT_ENA : R/W ;bitpos:[17:13] ;default: x ; */
+/*description: indexed register spelled with the base*/
+
+#define GPIO_NO_INFO_REG          (DR_REG_GPIO_BASE + 0x0100)
+#define GPIO_NO_INFO_FIELD 1
+
+#define GPIO_ABS_REG 0x3ff44200
+/* GPIO_ABS : R/W ;bitpos:[1] ;default: 1'b0 ; */
+/*description: absolute address inside the GPIO block*/
+
+#define RTCIO_PAD_REG (0x3ff48400)
+/* RTCIO_PAD_HOLD : R/W ;bitpos:[7:4] ;default: 4'h0 ; */
+/*description: absolute address outside of any known block*/
+
+#define SDIO_CONF_REG          (DR_REG_SDIO_BASE + \
+                                0x0010)
+/* SDIO_CONF : R/W ;bitpos:[0] ;default: 1'b0 ; */
+/*description: unknown peripheral*/
+
+#define GPIO_LAST_REG          (DR_REG_GPIO_BASE + 0x0204)
+/* GPIO_LAST : R/W ;bitpos:[0] ;default: 1'b0 ; */
+/*description: last register without a blank line after it*/
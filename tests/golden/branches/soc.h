@@ -0,0 +1,15 @@
+#ifndef _SOC_H_
+#define _SOC_H_
+
+#define DR_REG_GPIO_BASE                        0x3ff44000
+#define PERIPHS_GPIO_BASEADDR                   0x3ff44800
+#define PERIPHS_SPI_ENCRYPT_BASEADDR            0x3ff46000
+
+#ifdef CONFIG_HAS_TIMER
+#define DR_REG_TIMER_BASE                       0x3ff5f000
+#endif
+
+#define ETS_GPIO_INTR_SOURCE                    22/**< interrupt of GPIO, level*/
+#define ETS_GPIO_NMI_SOURCE                     23/**< interrupt of GPIO, NMI*/
+
+#endif
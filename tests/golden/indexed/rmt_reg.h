@@ -0,0 +1,20 @@
+#define RMT_CHANNEL_NUM 8
+#define RMT_CH_MEM_NUM (64)  /* words per channel */
+
+#define RMT_CH0CONF0_REG          (DR_REG_RMT_BASE + 0x0020)
+/* RMT_CLK_EN : R/W ;bitpos:[31] ;default: 1'h0 ; */
+/*description: This bit  is used  to control clock.when software config
+ RMT internal registers  it controls the register clock.*/
+#define RMT_CLK_EN  (BIT(31))
+/* RMT_MEM_SIZE_CH0 : R/W ;bitpos:[27:24] ;default: 4'h1 ; */
+/*description: This register is used to configure the the amount of memory blocks allocated to channel0.*/
+#define RMT_MEM_SIZE_CH0  0x0000000F
+/* RMT_DIV_CNT_CH0 : R/W ;bitpos:[7:0] ;default: 8'h2 ; */
+/*description: This register is used to configure the  frequency divider's factor in channel0.*/
+#define RMT_DIV_CNT_CH0  0x000000FF
+
+#define RMT_INT_RAW_REG          (DR_REG_RMT_BASE + 0x00a0)
+/* RMT_CH0_TX_END_INT_RAW : RO ;bitpos:[0] ;default: 1'b0 ; */
+/*description: The interrupt raw bit for channel 0 turns to high level when the transmit process is done.*/
+#define RMT_CH0_TX_END_INT_RAW  (BIT(0))
+
@@ -0,0 +1,13 @@
+#ifndef _SOC_H_
+#define _SOC_H_
+
+#define DR_REG_UART_BASE                        0x3ff40000
+#define DR_REG_RMT_BASE                         0x3ff56000
+#define DR_REG_UART1_BASE                       0x3ff50000
+
+#define ETS_UART0_INTR_SOURCE                   34/**< interrupt of UART0, level*/
+#define ETS_UART1_INTR_SOURCE                   35/**< interrupt of UART1, level*/
+#define ETS_RMT_INTR_SOURCE                     47/**< interrupt of remote controller, level*/
+#define ETS_CACHE_IA_INTR_SOURCE                68/**< interrupt of Cache Invalied Access, LEVEL*/
+
+#endif
@@ -0,0 +1,48 @@
+#ifndef __UART_REG_H__
+#define __UART_REG_H__
+
+#include "soc.h"
+#define REG_UART_BASE( i )  (DR_REG_UART_BASE + (i) * 0x10000 + ( i > 1 ? 0xe000 : 0 ) )
+
+#define UART_FIFO_REG(i)          (REG_UART_BASE(i) + 0x0)
+/* UART_RXFIFO_RD_BYTE : RO ;bitpos:[7:0] ;default: 8'b0 ; */
+/*description: This register stores one byte data  read by rx fifo.*/
+#define UART_RXFIFO_RD_BYTE  0x000000FF
+#define UART_RXFIFO_RD_BYTE_M  ((UART_RXFIFO_RD_BYTE_V)<<(UART_RXFIFO_RD_BYTE_S))
+#define UART_RXFIFO_RD_BYTE_V  0xFF
+#define UART_RXFIFO_RD_BYTE_S  0
+
+#define UART_INT_RAW_REG(i)          (REG_UART_BASE(i) + 0x4)
+/* UART_TX_DONE_INT_RAW : RO ;bitpos:[14] ;default: 1'b0 ; */
+/*description: This interrupt raw bit turns to high level when transmitter
+ has send all the data in fifo.*/
+#define UART_TX_DONE_INT_RAW  (BIT(14))
+#define UART_TX_DONE_INT_RAW_M  (BIT(14))
+#define UART_TX_DONE_INT_RAW_V  0x1
+#define UART_TX_DONE_INT_RAW_S  14
+/* UART_RXFIFO_FULL_INT_RAW : RO ;bitpos:[0] ;default: 1'b0 ; */
+/*description: */
+#define UART_RXFIFO_FULL_INT_RAW  (BIT(0))
+#define UART_RXFIFO_FULL_INT_RAW_M  (BIT(0))
+#define UART_RXFIFO_FULL_INT_RAW_V  0x1
+#define UART_RXFIFO_FULL_INT_RAW_S  0
+
+#define UART_CONF0_REG(i)          (REG_UART_BASE(i) + 0x20)
+/* UART_TICK_REF_ALWAYS_ON : R/W ;bitpos:[27] ;default: 1'b1 ; */
+/*description: This register is used to select the clock.*/
+#define UART_TICK_REF_ALWAYS_ON  (BIT(27))
+#define UART_TICK_REF_ALWAYS_ON_M  (BIT(27))
+#define UART_TICK_REF_ALWAYS_ON_V  0x1
+#define UART_TICK_REF_ALWAYS_ON_S  27
+
+#endif
+#define UART_MEM_BASE 0x100
+#define UART_MEM_REG(i)          (REG_UART_BASE(i) + UART_MEM_BASE + (i * 4))  /* memory (x) */
+/* UART_MEM : R/W ;bitpos:[31:0] ;default: x ; */
+/*description: NA*/
+
+#define UART_CONT_REG(i)          (REG_UART_BASE(i) + \
+                                   0x200)
+/* UART_CONT : R/W ;bitpos:[31:0] ;default: x ; */
+/*description: NA*/
+
@@ -0,0 +1,43 @@
+#include "soc.h"
+
+/* fields only described by their shift and value defines */
+#define I2S_CONF_REG          (DR_REG_I2S_BASE + 0x0008)
+#define I2S_TX_RESET_V  0x1
+#define I2S_TX_RESET_S  0
+#define I2S_RX_FIFO_MOD_S  16
+#define I2S_RX_FIFO_MOD_V  0x7
+#define I2S_NOT_A_MASK_V  0x5
+#define I2S_NOT_A_MASK_S  4
+
+#define I2S_INT_RAW_REG          (DR_REG_I2S_BASE + 0x000c)
+/* I2S_TX_DONE_INT_RAW : RO ;bitpos:[1] ;default: 1'b0 ; */
+#define I2S_TX_DONE_INT_RAW  (BIT(1))
+/* I2S_RX_DONE_INT_RAW : RO ;bitpos:[0] ;default: 1'b0 ; */
+/*description: described field after one without a description*/
+#define I2S_RX_DONE_INT_RAW  (BIT(0))
+#define I2S_INT_ENA_REG          (DR_REG_I2S_BASE + 0x0010)
+/* I2S_TX_DONE_INT_ENA : R/W ;bitpos:[1] ;default: 1'b0 ; */
+/*description: follows the previous register without a blank line*/
+
+#define I2S_TIMING_REG          (DR_REG_I2S_BASE + 0x001c)
+/* I2S_TX_BCK_IN_DELAY : R/W ;bitpos:[x:0] ;default: 2'h0 ; */
+/*description: malformed bitpos*/
+#define I2S_TX_BCK_IN_DELAY_V  0x3
+#define I2S_TX_BCK_IN_DELAY_S  0
+/* I2S_RX_DSYNC_SW : R/W ;bitpos:[21] ;default: 1'b0 ; */
+/*description: annotations resume after the malformed one*/
+
+#define I2S_FIFO_CONF_REG          (DR_REG_I2S_BASE + 0x0020)
+
+/* fields whose defines refer to other defines */
+#define I2S_CONF1_REG          (DR_REG_I2S_BASE + 0x0024)
+#define I2S_TX_PCM_CONF_V  0x7
+#define I2S_TX_PCM_CONF_S  1
+#define I2S_TX_PCM_CONF_M  ((I2S_TX_PCM_CONF_V)<<(I2S_TX_PCM_CONF_S))
+#define I2S_RX_PCM_CONF_V  I2S_TX_PCM_CONF_V
+#define I2S_RX_PCM_CONF_S  (I2S_TX_PCM_CONF_S + 3)  /* after the tx one */
+#define I2S_TX_STOP_EN_M  (BIT(8))
+
+#define I2S_DATE_REG          (DR_REG_I2S_BASE + 0x00fc)
+/* I2S_I2SDATE : R/W ;bitpos:[31:0] ;default: 32'h1604201 ; */
+/*description: last register without a blank line after it*/
@@ -0,0 +1 @@
+#define DR_REG_I2S_BASE                         0x3ff4f000
@@ -0,0 +1,103 @@
+//! The `_M`, `_V` and `_S` defines of a field are checked against each other and the bits of
+//! the field, a disagreement usually being a typo in the header. Masks of zero are reported
+//! instead of becoming fields.
+
+use header2svd::validate::Issue;
+use header2svd::{parse_idf_with_report, ParseOptions};
+use std::fs;
+
+const UART_REG_H: &str = "#define UART_CONF0_REG          (DR_REG_UART_BASE + 0x20)\n\
+/* UART_PARITY_EN : R/W ;bitpos:[1] ;default: 1'h0 ; */\n\
+/*description: Set this bit to enable uart parity check.*/\n\
+#define UART_PARITY_EN  (BIT(1))\n\
+#define UART_PARITY_EN_M  (BIT(1))\n\
+#define UART_PARITY_EN_V  0x1\n\
+#define UART_PARITY_EN_S  1\n\
+/* UART_BIT_NUM : R/W ;bitpos:[3:2] ;default: 2'd3 ; */\n\
+/*description: Set this register to set the length of data.*/\n\
+#define UART_BIT_NUM  0x00000003\n\
+#define UART_BIT_NUM_M  ((UART_BIT_NUM_V)<<(UART_BIT_NUM_S))\n\
+#define UART_BIT_NUM_V  0x3\n\
+#define UART_BIT_NUM_S  1\n\
+/* UART_STOP_BIT_NUM : R/W ;bitpos:[5:4] ;default: 2'd1 ; */\n\
+/*description: This register is used to set the length of stop bit.*/\n\
+#define UART_STOP_BIT_NUM  0x00000003\n\
+#define UART_STOP_BIT_NUM_M  ((UART_STOP_BIT_NUM_V)<<(UART_STOP_BIT_NUM_S))\n\
+#define UART_STOP_BIT_NUM_V  0x7\n\
+#define UART_STOP_BIT_NUM_S  4\n\
+/* UART_TXFIFO_RST : R/W ;bitpos:[18] ;default: 1'h0 ; */\n\
+/*description: Set this bit to reset the uart transmit-fifo.*/\n\
+#define UART_TXFIFO_RST  (BIT(18))\n\
+#define UART_TXFIFO_RST_M  (BIT(17))\n\
+#define UART_TXFIFO_RST_V  0x1\n\
+#define UART_TXFIFO_RST_S  18\n\
+\n";
+
+#[test]
+fn inconsistent_mask_defines_are_reported() {
+    let dir = std::env::temp_dir().join(format!("header2svd-masks-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("soc.h"),
+        "#define DR_REG_UART_BASE                        0x3ff40000\n",
+    )
+    .unwrap();
+    fs::write(dir.join("uart_reg.h"), UART_REG_H).unwrap();
+
+    let path = format!("{}/", dir.display());
+    let (peripherals, report) = parse_idf_with_report(&path, &ParseOptions::default());
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(peripherals["UART"].registers[0].bit_fields.len(), 4);
+    let inconsistent: Vec<&str> = report
+        .inconsistent_masks
+        .iter()
+        .map(|m| m.trim_start_matches(&path))
+        .collect();
+    assert_eq!(
+        inconsistent,
+        vec![
+            "uart_reg.h:11: UART_BIT_NUM _S 1 doesn't match the field [3:2]",
+            "uart_reg.h:17: UART_STOP_BIT_NUM _V 0x7 doesn't match the 2 bit field [5:4]",
+            "uart_reg.h:23: UART_TXFIFO_RST _M 0x20000 isn't _V 0x1 << _S 18",
+        ]
+    );
+}
+
+const UART_MEM_REG_H: &str = "#define UART_MEM_CONF_REG          (DR_REG_UART_BASE + 0x58)\n\
+#define UART_RESERVED_V  0x0\n\
+#define UART_RESERVED_S  4\n\
+#define UART_RX_SIZE_V  0xF\n\
+#define UART_RX_SIZE_S  0\n\
+\n";
+
+#[test]
+fn zero_width_masks_are_reported() {
+    let dir = std::env::temp_dir().join(format!("header2svd-zero-masks-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("soc.h"),
+        "#define DR_REG_UART_BASE                        0x3ff40000\n",
+    )
+    .unwrap();
+    fs::write(dir.join("uart_reg.h"), UART_MEM_REG_H).unwrap();
+
+    let path = format!("{}/", dir.display());
+    let (peripherals, report) = parse_idf_with_report(&path, &ParseOptions::default());
+    fs::remove_dir_all(&dir).unwrap();
+
+    let fields: Vec<&str> = peripherals["UART"].registers[0]
+        .bit_fields
+        .iter()
+        .map(|f| f.name.as_str())
+        .collect();
+    assert_eq!(fields, vec!["UART_RX_SIZE"]);
+    assert_eq!(
+        report.zero_width_fields,
+        vec![Issue::ZeroWidthField {
+            peripheral: "UART".to_string(),
+            register: "UART_MEM_CONF".to_string(),
+            field: "UART_RESERVED".to_string(),
+        }]
+    );
+}
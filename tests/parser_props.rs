@@ -0,0 +1,208 @@
+//! Property tests of the register state machine: randomized but well-formed register headers
+//! are parsed and every generated register must come out with exactly its fields.
+
+use header2svd::{parse_idf_with_options, ParseOptions, Peripheral};
+use proptest::prelude::*;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+const BASE: u32 = 0x3ff4_0000;
+/// Defined while parsing, `#ifdef GEN_DISABLED` blocks are skipped
+const ENABLED: &str = "GEN_ENABLED";
+
+#[derive(Debug, Clone)]
+struct GenField {
+    width: u8,
+    /// Unused bits below the field
+    gap: u8,
+    access: &'static str,
+    multiline: bool,
+    /// Which of the `_M`, `_V` and `_S` defines follow the field
+    defines: u8,
+}
+
+#[derive(Debug, Clone)]
+struct GenRegister {
+    fields: Vec<GenField>,
+    /// Wrapped in `#ifdef`, of a defined symbol when `true`
+    guard: Option<bool>,
+    comment: bool,
+    blank_lines: usize,
+}
+
+/// A register as it should be parsed
+#[derive(Debug, PartialEq)]
+struct Expected {
+    name: String,
+    offset: u32,
+    description: String,
+    /// Name, lsb and msb of every field
+    fields: Vec<(String, u8, u8)>,
+}
+
+fn field() -> impl Strategy<Value = GenField> {
+    (
+        1u8..=8,
+        0u8..4,
+        prop::sample::select(vec!["RO", "R/W", "WO"]),
+        any::<bool>(),
+        0u8..8,
+    )
+        .prop_map(|(width, gap, access, multiline, defines)| GenField {
+            width,
+            gap,
+            access,
+            multiline,
+            defines,
+        })
+}
+
+fn register() -> impl Strategy<Value = GenRegister> {
+    (
+        prop::collection::vec(field(), 1..6),
+        prop::option::of(any::<bool>()),
+        any::<bool>(),
+        1usize..3,
+    )
+        .prop_map(|(fields, guard, comment, blank_lines)| GenRegister {
+            fields,
+            guard,
+            comment,
+            blank_lines,
+        })
+}
+
+/// Render the registers as a header, along with the registers the parser should find in it
+fn render(registers: &[GenRegister]) -> (String, Vec<Expected>) {
+    let mut header = String::from("#include \"soc.h\"\n\n");
+    let mut expected = vec![];
+    for (i, r) in registers.iter().enumerate() {
+        let name = format!("GEN_R{}", i);
+        let offset = i as u32 * 4;
+        if let Some(enabled) = r.guard {
+            let symbol = if enabled { ENABLED } else { "GEN_DISABLED" };
+            header += &format!("#ifdef {}\n", symbol);
+        }
+        if r.comment {
+            header += &format!("/* register number {} */\n", i);
+        }
+        header += &format!(
+            "#define {}_REG          (DR_REG_GEN_BASE + {:#x})\n",
+            name, offset
+        );
+
+        let mut fields = vec![];
+        let mut lsb = 0u8;
+        for (j, f) in r.fields.iter().enumerate() {
+            lsb += f.gap;
+            let msb = lsb + f.width - 1;
+            if msb > 31 {
+                break;
+            }
+            let fname = format!("{}_F{}", name, j);
+            let bitpos = if f.width == 1 {
+                format!("{}", lsb)
+            } else {
+                format!("{}:{}", msb, lsb)
+            };
+            header += &format!(
+                "/* {} : {} ;bitpos:[{}] ;default: 1'b0 ; */\n",
+                fname, f.access, bitpos
+            );
+            if f.multiline {
+                header += &format!("/*description: field {}\n of register {}*/\n", j, i);
+            } else {
+                header += &format!("/*description: field {} of register {}*/\n", j, i);
+            }
+            let mask = (u32::MAX >> (32 - u32::from(f.width))) << lsb;
+            header += &format!("#define {}  {:#010x}\n", fname, mask);
+            if f.defines & 1 != 0 {
+                header += &format!("#define {0}_M  (({0}_V)<<({0}_S))\n", fname);
+            }
+            if f.defines & 2 != 0 {
+                header += &format!("#define {}_V  {:#x}\n", fname, mask >> lsb);
+            }
+            if f.defines & 4 != 0 {
+                header += &format!("#define {}_S  {}\n", fname, lsb);
+            }
+            fields.push((fname, lsb, msb));
+            lsb = msb + 1;
+        }
+        header += &"\n".repeat(r.blank_lines);
+        if r.guard.is_some() {
+            header += "#endif\n\n";
+        }
+
+        if r.guard != Some(false) {
+            expected.push(Expected {
+                description: if r.comment {
+                    format!("register number {}", i)
+                } else {
+                    name.clone()
+                },
+                name,
+                offset,
+                fields,
+            });
+        }
+    }
+    (header, expected)
+}
+
+/// Parse `header` as the only register header of a chip with a `GEN` peripheral
+fn parse(dir: &Path, header: &str) -> BTreeMap<String, Peripheral> {
+    fs::create_dir_all(dir).unwrap();
+    fs::write(
+        dir.join("soc.h"),
+        format!(
+            "#define DR_REG_GEN_BASE                        {:#x}\n",
+            BASE
+        ),
+    )
+    .unwrap();
+    fs::write(dir.join("gen_reg.h"), header).unwrap();
+
+    let mut options = ParseOptions::default();
+    options.defines.insert(ENABLED.to_string(), "1".to_string());
+    parse_idf_with_options(&format!("{}/", dir.display()), &options)
+}
+
+proptest! {
+    #[test]
+    fn parsed_registers_match_generated(registers in prop::collection::vec(register(), 1..8)) {
+        let dir = std::env::temp_dir().join(format!("header2svd-props-{}", std::process::id()));
+        let (header, expected) = render(&registers);
+        let peripherals = parse(&dir, &header);
+
+        let gen = &peripherals["GEN"];
+        prop_assert_eq!(gen.address, BASE);
+        for r in &gen.registers {
+            for (i, a) in r.bit_fields.iter().enumerate() {
+                for b in &r.bit_fields[i + 1..] {
+                    prop_assert!(!a.bits.overlaps(&b.bits), "{} overlaps {}", a.name, b.name);
+                }
+            }
+        }
+
+        let parsed: Vec<Expected> = gen
+            .registers
+            .iter()
+            .map(|r| Expected {
+                name: r.name.clone(),
+                offset: r.address,
+                description: r.description.clone(),
+                fields: r
+                    .bit_fields
+                    .iter()
+                    .map(|f| (f.name.clone(), f.bits.lsb(), f.bits.msb()))
+                    .collect(),
+            })
+            .collect();
+        prop_assert_eq!(parsed, expected);
+        for (r, f) in gen.registers.iter().flat_map(|r| r.bit_fields.iter().map(move |f| (r, f))) {
+            prop_assert!(f.description.starts_with("field "), "{}.{}", r.name, f.name);
+            prop_assert!(!f.description.contains('\n'));
+        }
+    }
+}
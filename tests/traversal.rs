@@ -0,0 +1,55 @@
+//! Register headers that can't be read, such as binary files or directories named like a
+//! header, are skipped with a warning instead of aborting the parse.
+
+use header2svd::{parse_idf_with_report, ParseOptions};
+use std::fs;
+
+const UART_REG_H: &str = "#define UART_CONF0_REG          (DR_REG_UART_BASE + 0x20)\n\
+/* UART_PARITY_EN : R/W ;bitpos:[1] ;default: 1'h0 ; */\n\
+/*description: Set this bit to enable uart parity check.*/\n\
+#define UART_PARITY_EN  (BIT(1))\n\
+\n";
+
+#[test]
+fn unreadable_headers_are_skipped() {
+    let dir = std::env::temp_dir().join(format!("header2svd-traversal-{}", std::process::id()));
+    fs::create_dir_all(dir.join("dir_reg.h")).unwrap();
+    fs::write(
+        dir.join("soc.h"),
+        "#define DR_REG_UART_BASE                        0x3ff40000\n",
+    )
+    .unwrap();
+    fs::write(dir.join("uart_reg.h"), UART_REG_H).unwrap();
+    fs::write(
+        dir.join("blob_reg.h"),
+        [0x7f, b'E', b'L', b'F', 0xff, 0xfe, 0x00],
+    )
+    .unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        let name = std::ffi::OsStr::from_bytes(b"latin1_\xe9_reg.h");
+        fs::write(
+            dir.join(name),
+            UART_REG_H.replace("CONF0", "CONF1").replace("0x20", "0x24"),
+        )
+        .unwrap();
+    }
+
+    let (peripherals, report) =
+        parse_idf_with_report(&format!("{}/", dir.display()), &ParseOptions::default());
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(report.skipped_files.len(), 1);
+    assert!(report.skipped_files[0].contains("blob_reg.h"));
+    let registers: Vec<&str> = peripherals["UART"]
+        .registers
+        .iter()
+        .map(|r| r.name.as_str())
+        .collect();
+    if cfg!(unix) {
+        assert_eq!(registers, vec!["UART_CONF0", "UART_CONF1"]);
+    } else {
+        assert_eq!(registers, vec!["UART_CONF0"]);
+    }
+}
@@ -0,0 +1,62 @@
+//! The watchdog of the ESP8266 SDKs is defined in `eagle_soc.h` by register offsets and
+//! `_MASK`/`_LSB` fields rather than in a `*_reg.h` header.
+
+use header2svd::{parse_idf_with_options, ParseOptions};
+use std::fs;
+
+const SOC_H: &str = "#define PERIPHS_WDT_BASEADDR                    0x60000900\n";
+
+const EAGLE_SOC_H: &str = "//Watchdog reg {{
+#define WDT_CTL_ADDRESS                         0
+#define WDT_OP_ADDRESS                          0x4
+#define WDT_OP_ND_ADDRESS                       0x8
+#define WDT_RST_ADDRESS                         0x14
+
+#define WDT_CTL_RSTLEN_MASK                     0x38
+#define WDT_CTL_RSPMOD_MASK                     0x6
+#define WDT_CTL_EN_MASK                         0x1
+
+#define WDT_CTL_RSTLEN_LSB                      0x3
+#define WDT_CTL_RSPMOD_LSB                      0x1
+#define WDT_CTL_EN_LSB                          0
+
+#define WDT_FEED_VALUE                          0x73
+#define WDT_FEED()                              WDT_REG_WRITE(WDT_RST_ADDRESS, WDT_FEED_VALUE)
+//}}
+";
+
+#[test]
+fn watchdog_from_eagle_soc() {
+    let dir = std::env::temp_dir().join(format!("header2svd-watchdog-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("soc.h"), SOC_H).unwrap();
+    fs::write(dir.join("eagle_soc.h"), EAGLE_SOC_H).unwrap();
+
+    let peripherals =
+        parse_idf_with_options(&format!("{}/", dir.display()), &ParseOptions::default());
+    fs::remove_dir_all(&dir).unwrap();
+
+    let wdt = &peripherals["WDT"];
+    assert_eq!(wdt.address, 0x6000_0900);
+    let registers: Vec<(&str, u32, Vec<&str>)> = wdt
+        .registers
+        .iter()
+        .map(|r| {
+            let fields = r.bit_fields.iter().map(|f| f.name.as_str()).collect();
+            (r.name.as_str(), r.address, fields)
+        })
+        .collect();
+    assert_eq!(
+        registers,
+        vec![
+            (
+                "WDT_CTL",
+                0x0,
+                vec!["WDT_CTL_EN", "WDT_CTL_RSPMOD", "WDT_CTL_RSTLEN"]
+            ),
+            ("WDT_OP", 0x4, vec!["WDT_OP_TIMEOUT"]),
+            ("WDT_OP_ND", 0x8, vec!["WDT_OP_ND_TIMEOUT"]),
+            ("WDT_RST", 0x14, vec!["WDT_RST_FEED"]),
+        ]
+    );
+}